@@ -1,3 +1,125 @@
+/// Every command the app registers; each gets an `allow-*` permission that a capability
+/// must grant before a window can call it.
+const COMMANDS: &[&str] = &[
+    "get_login_qr",
+    "check_login_status",
+    "get_config",
+    "update_config",
+    "add_task",
+    "update_task",
+    "delete_task",
+    "add_account",
+    "update_account",
+    "delete_account",
+    "run_task_now",
+    "search_tasks",
+    "get_task_stats",
+    "sync_attendance",
+    "validate_cookie",
+    "get_attendance_report",
+    "get_failure_analytics",
+    "get_timeline",
+    "get_anomalies",
+    "export_ics",
+    "export_history",
+    "get_receipt",
+    "import_ics",
+    "export_last_har",
+    "get_recent_logs",
+    "get_crash_reports",
+    "dismiss_crash_reports",
+    "get_storage_report",
+    "purge_storage",
+    "request_confirmation",
+    "parse_map_link",
+    "geocode_address",
+    "reverse_geocode",
+    "locate_by_ip",
+    "health_check",
+    "export_location_preset",
+    "import_location_preset",
+    "check_latest_version",
+    "report_issue",
+    "install_service",
+    "uninstall_service",
+    "refresh_site_profile",
+    "refresh_managed_tasks",
+    "take_task_drafts",
+    "debug_advance_time",
+    "check_for_updates",
+    "install_update",
+];
+
+/// Swift package of the iOS background plugin, named as in its `Package.swift`.
+#[cfg(target_os = "macos")]
+const IOS_PLUGIN: &str = "BackgroundPlugin";
+
 fn main() {
-    tauri_build::build()
+    // Only the capabilities in `app/` are granted to every build; `debug/` is added at
+    // runtime in debug builds.
+    tauri_build::try_build(
+        tauri_build::Attributes::new()
+            .capabilities_path_pattern("./capabilities/app/*.json")
+            .app_manifest(tauri_build::AppManifest::new().commands(COMMANDS)),
+    )
+    .expect("failed to run tauri-build");
+
+    // Build scripts run on the host, so the iOS target is read from Cargo's environment.
+    if std::env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("ios") {
+        link_ios_plugin();
+    }
+}
+
+/// Builds the Swift package in `ios/` and links it, so `init_plugin_background` resolves.
+///
+/// The package depends on the Tauri Swift API, which the `tauri` crate ships and is copied
+/// to `.tauri/tauri-api` first, as plugin crates do.
+#[cfg(target_os = "macos")]
+fn link_ios_plugin() {
+    use std::path::{Path, PathBuf};
+
+    let manifest_dir = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap());
+    let tauri_api = std::env::var("DEP_TAURI_IOS_LIBRARY_PATH")
+        .expect("missing `DEP_TAURI_IOS_LIBRARY_PATH`; is `tauri` a dependency?");
+    let target = manifest_dir.join(".tauri").join("tauri-api");
+    let _ = std::fs::remove_dir_all(&target);
+    copy_dir(Path::new(&tauri_api), &target).expect("failed to copy tauri-api");
+
+    println!("cargo:rerun-if-changed=ios/Package.swift");
+    println!("cargo:rerun-if-changed=ios/Sources");
+    tauri_utils::build::link_apple_library(IOS_PLUGIN, manifest_dir.join("ios"));
+}
+
+/// Copies a directory tree, leaving out Swift build output and tests.
+///
+/// # Arguments
+///
+/// * `source` - The directory to copy.
+/// * `target` - Where to copy it to.
+///
+/// # Returns
+///
+/// * `std::io::Result<()>` - An error if reading or writing a file failed.
+#[cfg(target_os = "macos")]
+fn copy_dir(source: &std::path::Path, target: &std::path::Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(target)?;
+    for entry in std::fs::read_dir(source)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if [".build", "Package.resolved", "Tests"].contains(&name.to_string_lossy().as_ref()) {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target.join(&name))?;
+        } else {
+            std::fs::copy(entry.path(), target.join(&name))?;
+        }
+    }
+    Ok(())
+}
+
+/// iOS apps are only built on macOS hosts.
+#[cfg(not(target_os = "macos"))]
+fn link_ios_plugin() {
+    panic!("the iOS background plugin can only be built on macOS");
 }
@@ -0,0 +1,67 @@
+package com.autocheckin.next
+
+import android.app.Activity
+import android.app.AlarmManager
+import android.app.PendingIntent
+import android.content.Context
+import android.content.Intent
+import android.os.Build
+import androidx.core.content.ContextCompat
+import app.tauri.annotation.Command
+import app.tauri.annotation.InvokeArg
+import app.tauri.annotation.TauriPlugin
+import app.tauri.plugin.Invoke
+import app.tauri.plugin.Plugin
+
+@InvokeArg
+class WakeArgs {
+    /** Wake-up time in milliseconds since the Unix epoch. */
+    var at: Long = 0
+}
+
+/**
+ * Keeps the scheduler alive in the background.
+ *
+ * Registered from `src/mobile.rs`. The permissions, `SchedulerService` and `WakeReceiver`
+ * it relies on are declared in `AndroidManifest.xml`.
+ */
+@TauriPlugin
+class BackgroundPlugin(private val activity: Activity) : Plugin(activity) {
+    /** Starts the foreground service that keeps the process running. */
+    @Command
+    fun startService(invoke: Invoke) {
+        SchedulerService.start(activity)
+        invoke.resolve()
+    }
+
+    /** Sets an exact alarm that restarts the service shortly before the next run. */
+    @Command
+    fun scheduleWake(invoke: Invoke) {
+        val args = invoke.parseArgs(WakeArgs::class.java)
+        val alarms = activity.getSystemService(Context.ALARM_SERVICE) as AlarmManager
+        val intent = PendingIntent.getBroadcast(
+            activity,
+            0,
+            Intent(activity, WakeReceiver::class.java),
+            PendingIntent.FLAG_UPDATE_CURRENT or PendingIntent.FLAG_IMMUTABLE
+        )
+        if (Build.VERSION.SDK_INT >= Build.VERSION_CODES.S && !alarms.canScheduleExactAlarms()) {
+            // Without the exact-alarm grant, an inexact alarm is the best the OS allows.
+            alarms.setAndAllowWhileIdle(AlarmManager.RTC_WAKEUP, args.at, intent)
+        } else {
+            alarms.setExactAndAllowWhileIdle(AlarmManager.RTC_WAKEUP, args.at, intent)
+        }
+        invoke.resolve()
+    }
+}
+
+/** Restarts the foreground service when the wake-up alarm fires. */
+class WakeReceiver : android.content.BroadcastReceiver() {
+    override fun onReceive(context: Context, intent: Intent) {
+        SchedulerService.start(context)
+    }
+}
+
+internal fun startForegroundCompat(context: Context, intent: Intent) {
+    ContextCompat.startForegroundService(context, intent)
+}
@@ -0,0 +1,73 @@
+package com.autocheckin.next
+
+import android.app.Notification
+import android.app.NotificationChannel
+import android.app.NotificationManager
+import android.app.PendingIntent
+import android.app.Service
+import android.content.Context
+import android.content.Intent
+import android.content.pm.ServiceInfo
+import android.os.Build
+import android.os.IBinder
+import androidx.core.app.NotificationCompat
+import androidx.core.app.ServiceCompat
+
+/**
+ * Foreground service whose only job is to keep the app process, and with it the Rust
+ * scheduler loop, running while the activity is in the background.
+ */
+class SchedulerService : Service() {
+    override fun onBind(intent: Intent?): IBinder? = null
+
+    override fun onStartCommand(intent: Intent?, flags: Int, startId: Int): Int {
+        ServiceCompat.startForeground(
+            this,
+            NOTIFICATION_ID,
+            notification(),
+            if (Build.VERSION.SDK_INT >= Build.VERSION_CODES.Q) {
+                ServiceInfo.FOREGROUND_SERVICE_TYPE_DATA_SYNC
+            } else {
+                0
+            }
+        )
+        // Restart after the OS kills the process for memory.
+        return START_STICKY
+    }
+
+    private fun notification(): Notification {
+        val manager = getSystemService(Context.NOTIFICATION_SERVICE) as NotificationManager
+        if (Build.VERSION.SDK_INT >= Build.VERSION_CODES.O) {
+            manager.createNotificationChannel(
+                NotificationChannel(
+                    CHANNEL_ID,
+                    "Scheduler",
+                    NotificationManager.IMPORTANCE_MIN
+                ).apply { description = "Keeps scheduled check-ins running" }
+            )
+        }
+        val open = PendingIntent.getActivity(
+            this,
+            0,
+            packageManager.getLaunchIntentForPackage(packageName),
+            PendingIntent.FLAG_IMMUTABLE
+        )
+        return NotificationCompat.Builder(this, CHANNEL_ID)
+            .setSmallIcon(applicationInfo.icon)
+            .setContentTitle("AutoCheckin-Next")
+            .setContentText("Scheduled check-ins are active")
+            .setContentIntent(open)
+            .setOngoing(true)
+            .setPriority(NotificationCompat.PRIORITY_MIN)
+            .build()
+    }
+
+    companion object {
+        private const val CHANNEL_ID = "scheduler"
+        private const val NOTIFICATION_ID = 1
+
+        fun start(context: Context) {
+            startForegroundCompat(context, Intent(context, SchedulerService::class.java))
+        }
+    }
+}
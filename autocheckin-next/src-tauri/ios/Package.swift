@@ -0,0 +1,27 @@
+// swift-tools-version:5.5
+
+import PackageDescription
+
+let package = Package(
+    name: "BackgroundPlugin",
+    platforms: [
+        .iOS(.v13),
+    ],
+    products: [
+        .library(
+            name: "BackgroundPlugin",
+            type: .static,
+            targets: ["BackgroundPlugin"]),
+    ],
+    dependencies: [
+        .package(name: "Tauri", path: "../.tauri/tauri-api"),
+    ],
+    targets: [
+        .target(
+            name: "BackgroundPlugin",
+            dependencies: [
+                .byName(name: "Tauri"),
+            ],
+            path: "Sources")
+    ]
+)
@@ -0,0 +1,96 @@
+import BackgroundTasks
+import Tauri
+import UIKit
+import UserNotifications
+import WebKit
+
+/// Identifier of the refresh task. Listed under `BGTaskSchedulerPermittedIdentifiers` in
+/// `Info.ios.plist`, which the Tauri CLI merges into the app's `Info.plist`.
+let refreshTaskIdentifier = "com.autocheckin.next.refresh"
+
+/// Identifier of the fallback notification, so each request replaces the previous one.
+let reminderIdentifier = "com.autocheckin.next.reminder"
+
+/// How long a refresh keeps the process running. iOS allows about 30 seconds.
+let refreshWindow: TimeInterval = 25
+
+class WakeArgs: Decodable {
+    /// Wake-up time in milliseconds since the Unix epoch.
+    let at: Int64
+    /// Scheduled run time in milliseconds since the Unix epoch.
+    let runAt: Int64
+    let reminderTitle: String
+    let reminderBody: String
+}
+
+/// Wakes the Rust scheduler near scheduled runs.
+///
+/// Registered from `src/mobile.rs`. iOS suspends the app shortly after it leaves the
+/// foreground, so the scheduler loop only advances while a background refresh is running.
+/// On resume it catches up on runs it missed. iOS decides when refreshes actually happen,
+/// so a local notification at the run time asks the user to open the app as a fallback.
+class BackgroundPlugin: Plugin {
+    override init() {
+        super.init()
+        // Launch handlers must be registered before the app finishes launching, which is
+        // when the plugin is created.
+        BGTaskScheduler.shared.register(forTaskWithIdentifier: refreshTaskIdentifier, using: nil) {
+            task in
+            self.handleRefresh(task as! BGAppRefreshTask)
+        }
+        UNUserNotificationCenter.current().requestAuthorization(options: [.alert, .sound]) {
+            _, _ in
+        }
+    }
+
+    /// Requests a background refresh before the next run and schedules the fallback
+    /// reminder at the run time.
+    @objc public func scheduleWake(_ invoke: Invoke) throws {
+        let args = try invoke.parseArgs(WakeArgs.self)
+        let request = BGAppRefreshTaskRequest(identifier: refreshTaskIdentifier)
+        request.earliestBeginDate = Date(timeIntervalSince1970: TimeInterval(args.at) / 1000)
+        do {
+            try BGTaskScheduler.shared.submit(request)
+        } catch {
+            Logger.error("Failed to submit the background refresh: \(error)")
+        }
+
+        let content = UNMutableNotificationContent()
+        content.title = args.reminderTitle
+        content.body = args.reminderBody
+        content.sound = .default
+        let runAt = Date(timeIntervalSince1970: TimeInterval(args.runAt) / 1000)
+        // Fire a little after the run. If the app was awake for it, the next request
+        // replaces this reminder before it is shown.
+        let delay = max(runAt.timeIntervalSinceNow + refreshWindow, 1)
+        let center = UNUserNotificationCenter.current()
+        center.removePendingNotificationRequests(withIdentifiers: [reminderIdentifier])
+        center.add(
+            UNNotificationRequest(
+                identifier: reminderIdentifier,
+                content: content,
+                trigger: UNTimeIntervalNotificationTrigger(timeInterval: delay, repeats: false)))
+        invoke.resolve()
+    }
+
+    /// Keeps the process running for the refresh window, then lets iOS suspend it again.
+    /// The scheduler tick submits the next request while it runs.
+    private func handleRefresh(_ task: BGAppRefreshTask) {
+        var finished = false
+        let finish = {
+            DispatchQueue.main.async {
+                if !finished {
+                    finished = true
+                    task.setTaskCompleted(success: true)
+                }
+            }
+        }
+        task.expirationHandler = finish
+        DispatchQueue.main.asyncAfter(deadline: .now() + refreshWindow, execute: finish)
+    }
+}
+
+@_cdecl("init_plugin_background")
+func initPlugin() -> Plugin {
+    return BackgroundPlugin()
+}
@@ -0,0 +1,169 @@
+use crate::history::{RunOutcome, RunRecord};
+use crate::profile;
+use crate::task::SITE_LAYOUT_CHANGED;
+use chrono::{Datelike, Duration, Local, Weekday};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Text of transport errors, as reported by the HTTP client.
+const NETWORK_MARKERS: &[&str] = &[
+    "error sending request",
+    "timed out",
+    "connection",
+    "dns error",
+    "tcp connect",
+    "network",
+];
+
+/// The root cause of a failed run or sign.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureCategory {
+    /// The site asked for a login.
+    CookieExpired,
+    /// The site rejected the location as too far from the classroom.
+    OutOfRange,
+    /// The session needs a password that was not sent or was wrong.
+    PasswordRequired,
+    /// The site could not be reached or did not answer in time.
+    Network,
+    /// A captcha, WAF or rate-limit page.
+    Blocked,
+    /// The response could not be read as a result.
+    Parse,
+    /// The punch listing no longer matches the site profile.
+    LayoutChanged,
+    /// The site reported an error not covered above.
+    SiteError,
+}
+
+/// Failure counts of one task.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TaskFailures {
+    /// ID of the task.
+    pub task_id: String,
+    /// Name of the task at its latest failure.
+    pub task_name: String,
+    /// Failures per category.
+    pub counts: HashMap<FailureCategory, usize>,
+}
+
+/// Failures in a time range, broken down by cause, weekday and task.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FailureAnalytics {
+    /// Number of failures.
+    pub total: usize,
+    /// Failures per category.
+    pub by_category: HashMap<FailureCategory, usize>,
+    /// Failures per weekday (`"Mon"` to `"Sun"`) and category.
+    pub by_weekday: HashMap<Weekday, HashMap<FailureCategory, usize>>,
+    /// Failures per task, most failures first.
+    pub by_task: Vec<TaskFailures>,
+    /// The most recent message of each category.
+    pub examples: HashMap<FailureCategory, String>,
+}
+
+/// Classifies the message of a failed sign or run.
+///
+/// # Arguments
+///
+/// * `outcome` - The outcome of the run.
+/// * `message` - The failure message.
+///
+/// # Returns
+///
+/// * `FailureCategory` - The most likely cause.
+pub fn classify(outcome: RunOutcome, message: &str) -> FailureCategory {
+    match outcome {
+        RunOutcome::CookieExpired => return FailureCategory::CookieExpired,
+        RunOutcome::Blocked => return FailureCategory::Blocked,
+        _ if message.starts_with(SITE_LAYOUT_CHANGED) => return FailureCategory::LayoutChanged,
+        _ => {}
+    }
+    let parser = profile::current();
+    let lower = message.to_lowercase();
+    if parser.is_out_of_range(message) {
+        FailureCategory::OutOfRange
+    } else if parser.is_password_error(message) {
+        FailureCategory::PasswordRequired
+    } else if NETWORK_MARKERS.iter().any(|m| lower.contains(m)) {
+        FailureCategory::Network
+    } else if outcome == RunOutcome::Failure && parser.is_error(message) {
+        FailureCategory::SiteError
+    } else if outcome == RunOutcome::Failure {
+        FailureCategory::Parse
+    } else {
+        FailureCategory::SiteError
+    }
+}
+
+/// Classifies the failures in the run history.
+///
+/// Each failed sign of a run counts once; runs that failed before signing (errors,
+/// expired cookies, anti-bot pages) count once with their run message.
+///
+/// # Arguments
+///
+/// * `records` - All run records.
+/// * `range_days` - Only consider runs from the last N days, or all runs if `None`.
+///
+/// # Returns
+///
+/// * `FailureAnalytics` - The breakdown.
+pub fn failure_analytics(records: &[RunRecord], range_days: Option<u32>) -> FailureAnalytics {
+    let since = range_days.map(|d| Local::now() - Duration::days(d as i64));
+    let mut analytics = FailureAnalytics {
+        total: 0,
+        by_category: HashMap::new(),
+        by_weekday: HashMap::new(),
+        by_task: Vec::new(),
+        examples: HashMap::new(),
+    };
+    let mut by_task: HashMap<&str, TaskFailures> = HashMap::new();
+
+    for run in records
+        .iter()
+        .filter(|r| r.outcome.is_failure())
+        .filter(|r| since.is_none_or(|s| r.started_at >= s))
+    {
+        let messages: Vec<&str> = match run.outcome {
+            RunOutcome::Failure => run
+                .signs
+                .iter()
+                .filter(|s| !s.success)
+                .map(|s| s.message.as_str())
+                .collect(),
+            _ => vec![run.message.as_str()],
+        };
+        for message in messages {
+            let category = classify(run.outcome, message);
+            analytics.total += 1;
+            *analytics.by_category.entry(category).or_insert(0) += 1;
+            *analytics
+                .by_weekday
+                .entry(run.started_at.weekday())
+                .or_default()
+                .entry(category)
+                .or_insert(0) += 1;
+            analytics.examples.insert(category, message.to_string());
+            let task = by_task
+                .entry(run.task_id.as_str())
+                .or_insert_with(|| TaskFailures {
+                    task_id: run.task_id.clone(),
+                    task_name: String::new(),
+                    counts: HashMap::new(),
+                });
+            task.task_name = run.task_name.clone();
+            *task.counts.entry(category).or_insert(0) += 1;
+        }
+    }
+
+    analytics.by_task = by_task.into_values().collect();
+    analytics.by_task.sort_by_key(|t| {
+        (
+            std::cmp::Reverse(t.counts.values().sum::<usize>()),
+            t.task_name.clone(),
+        )
+    });
+    analytics
+}
@@ -0,0 +1,81 @@
+use crate::config::Location;
+use chrono::{Datelike, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+
+/// When an anchor location applies.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AnchorRule {
+    /// Every day.
+    #[default]
+    Always,
+    /// Odd teaching weeks (1, 3, 5, ...).
+    OddWeeks,
+    /// Even teaching weeks (2, 4, 6, ...).
+    EvenWeeks,
+    /// Only on the listed weekdays.
+    Weekdays {
+        /// The weekdays, e.g. `["Mon", "Wed"]`.
+        days: Vec<Weekday>,
+    },
+}
+
+/// One of several locations a task's class meets at.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Anchor {
+    /// Label shown in the UI, e.g. "Building A".
+    #[serde(default)]
+    pub name: String,
+    /// The location used when the rule matches.
+    pub location: Location,
+    /// When this anchor applies.
+    #[serde(default)]
+    pub rule: AnchorRule,
+}
+
+/// Returns the teaching week of a date.
+///
+/// Weeks are counted from the term start (week 1 begins on that day). Without a term
+/// start, the ISO week number is used.
+///
+/// # Arguments
+///
+/// * `date` - The date to classify.
+/// * `term_start` - First day of the term, if configured.
+///
+/// # Returns
+///
+/// * `i64` - The week number.
+pub fn teaching_week(date: NaiveDate, term_start: Option<NaiveDate>) -> i64 {
+    match term_start {
+        Some(start) => (date - start).num_days().div_euclid(7) + 1,
+        None => date.iso_week().week() as i64,
+    }
+}
+
+/// Picks the anchor that applies on a date.
+///
+/// Anchors are checked in order and the first matching rule wins.
+///
+/// # Arguments
+///
+/// * `anchors` - The task's anchors.
+/// * `date` - The day of the sign.
+/// * `term_start` - First day of the term, used for odd/even weeks.
+///
+/// # Returns
+///
+/// * `Option<&Anchor>` - The matching anchor, or `None` if no rule matches.
+pub fn select_anchor(
+    anchors: &[Anchor],
+    date: NaiveDate,
+    term_start: Option<NaiveDate>,
+) -> Option<&Anchor> {
+    let week = teaching_week(date, term_start);
+    anchors.iter().find(|anchor| match &anchor.rule {
+        AnchorRule::Always => true,
+        AnchorRule::OddWeeks => week % 2 != 0,
+        AnchorRule::EvenWeeks => week % 2 == 0,
+        AnchorRule::Weekdays { days } => days.contains(&date.weekday()),
+    })
+}
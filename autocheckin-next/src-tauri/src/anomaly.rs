@@ -0,0 +1,247 @@
+use crate::config::{AnomalyConfig, Task};
+use crate::history::{RunOutcome, RunRecord};
+use crate::timeline::scheduled_slot;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveTime, Timelike, Weekday};
+use serde::{Deserialize, Serialize};
+
+/// Sessions a class needs in the history before its usual time is trusted.
+const MIN_SESSIONS: usize = 5;
+
+/// Times a class must have had a session on a weekday for the weekday to be usual.
+const MIN_WEEKDAY_SESSIONS: usize = 2;
+
+/// How far back `detect` looks for sessions at unusual times.
+const RECENT_DAYS: i64 = 14;
+
+/// When a class's check-in sessions usually open, learned from the run history.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClassPattern {
+    /// Class ID of the course.
+    pub class_id: String,
+    /// Number of runs that found a session.
+    pub sessions: usize,
+    /// The median time sessions were found at, once enough sessions were seen.
+    pub usual_time: Option<NaiveTime>,
+    /// The weekdays sessions are usually found on, Monday first.
+    pub weekdays: Vec<Weekday>,
+}
+
+/// What kind of change an anomaly points to.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyKind {
+    /// Scheduled runs keep finding no session; the class may have moved.
+    MissingSessions,
+    /// A session was found at a time no task covers and the class does not usually use.
+    UnusualTime,
+}
+
+/// A deviation from a class's usual check-in pattern.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Anomaly {
+    /// What kind of change was found.
+    pub kind: AnomalyKind,
+    /// ID of the task.
+    pub task_id: String,
+    /// Name of the task.
+    pub task_name: String,
+    /// Class ID of the course.
+    pub class_id: String,
+    /// Scheduled runs in a row without a session, for missing sessions.
+    pub count: usize,
+    /// When the class's sessions usually open, if known.
+    pub usual_time: Option<NaiveTime>,
+    /// When the unusual session was found, for unusual times.
+    pub seen_at: Option<DateTime<Local>>,
+}
+
+/// Learns when a class's sessions usually open from the runs that found one.
+///
+/// # Arguments
+///
+/// * `records` - All run records.
+/// * `class_id` - The class ID of the course.
+///
+/// # Returns
+///
+/// * `ClassPattern` - The usual time and weekdays; the time is only set once at least
+///   five sessions were seen.
+pub fn learn_pattern(records: &[RunRecord], class_id: &str) -> ClassPattern {
+    let sessions: Vec<&RunRecord> = records
+        .iter()
+        .filter(|r| r.class_id == class_id && !r.signs.is_empty())
+        .collect();
+    let mut minutes: Vec<u32> = sessions
+        .iter()
+        .map(|r| r.started_at.hour() * 60 + r.started_at.minute())
+        .collect();
+    minutes.sort_unstable();
+    let usual_time = minutes
+        .get(minutes.len() / 2)
+        .filter(|_| sessions.len() >= MIN_SESSIONS)
+        .and_then(|m| NaiveTime::from_hms_opt(m / 60, m % 60, 0));
+    let mut per_weekday = [0; 7];
+    for run in &sessions {
+        per_weekday[run.started_at.weekday().num_days_from_monday() as usize] += 1;
+    }
+    let weekdays = (0..7)
+        .filter(|&d| per_weekday[d as usize] >= MIN_WEEKDAY_SESSIONS)
+        .filter_map(|d| Weekday::try_from(d).ok())
+        .collect();
+    ClassPattern {
+        class_id: class_id.to_string(),
+        sessions: sessions.len(),
+        usual_time,
+        weekdays,
+    }
+}
+
+/// Counts the task's latest scheduled runs in a row that found no session.
+///
+/// Runs outside the schedule are ignored, as are runs that never saw the session list
+/// (errors, expired cookies, anti-bot pages and offline skips).
+///
+/// # Arguments
+///
+/// * `records` - All run records, oldest first.
+/// * `task` - The task to check.
+///
+/// # Returns
+///
+/// * `usize` - The number of empty scheduled runs since the last one that found a session.
+pub fn missing_streak(records: &[RunRecord], task: &Task) -> usize {
+    let mut streak = 0;
+    for run in records.iter().rev().filter(|r| r.task_id == task.id) {
+        if !run.signs.is_empty() {
+            break;
+        }
+        if run.outcome == RunOutcome::NoSession
+            && scheduled_slot(task, run.started_at.naive_local()).is_some()
+        {
+            streak += 1;
+        }
+    }
+    streak
+}
+
+/// Whether a run found a session at a time that no enabled task of its class covers and
+/// that is far from the class's usual time.
+fn is_unusual(
+    tasks: &[Task],
+    pattern: &ClassPattern,
+    run: &RunRecord,
+    config: &AnomalyConfig,
+) -> bool {
+    if run.signs.is_empty() {
+        return false;
+    }
+    let started_at = run.started_at.naive_local();
+    let covered = tasks
+        .iter()
+        .filter(|t| t.enable && t.class_id == run.class_id)
+        .any(|t| scheduled_slot(t, started_at).is_some());
+    let Some(usual) = pattern.usual_time else {
+        return false;
+    };
+    let off = (started_at.time() - usual).num_minutes().abs();
+    // A session just before midnight is close to a usual time just after it.
+    let off = off.min(24 * 60 - off);
+    !covered && off > config.unusual_minutes as i64
+}
+
+/// Lists the current anomalies of all enabled tasks.
+///
+/// # Arguments
+///
+/// * `tasks` - The configured tasks.
+/// * `records` - All run records, oldest first.
+/// * `config` - The anomaly thresholds.
+///
+/// # Returns
+///
+/// * `Vec<Anomaly>` - Tasks whose scheduled runs keep finding nothing, and sessions of the
+///   last two weeks found at unusual times.
+pub fn detect(tasks: &[Task], records: &[RunRecord], config: &AnomalyConfig) -> Vec<Anomaly> {
+    let since = Local::now() - Duration::days(RECENT_DAYS);
+    let mut anomalies = Vec::new();
+    for task in tasks.iter().filter(|t| t.enable) {
+        let pattern = learn_pattern(records, &task.class_id);
+        let streak = missing_streak(records, task);
+        if streak >= config.missing_runs.max(1) {
+            anomalies.push(missing(task, &pattern, streak));
+        }
+        anomalies.extend(
+            records
+                .iter()
+                .filter(|r| r.task_id == task.id && r.started_at >= since)
+                .filter(|r| is_unusual(tasks, &pattern, r, config))
+                .map(|r| unusual(task, &pattern, r)),
+        );
+    }
+    anomalies
+}
+
+/// Checks a finished run for anomalies worth an alert.
+///
+/// Missing sessions are reported once, by the run that reaches the threshold.
+///
+/// # Arguments
+///
+/// * `tasks` - The configured tasks.
+/// * `records` - All run records, including the finished run.
+/// * `task` - The task that ran.
+/// * `run` - The finished run.
+/// * `config` - The anomaly settings.
+///
+/// # Returns
+///
+/// * `Vec<Anomaly>` - The anomalies the run revealed; empty if alerts are off.
+pub fn check_run(
+    tasks: &[Task],
+    records: &[RunRecord],
+    task: &Task,
+    run: &RunRecord,
+    config: &AnomalyConfig,
+) -> Vec<Anomaly> {
+    if !config.enable {
+        return vec![];
+    }
+    let pattern = learn_pattern(records, &task.class_id);
+    let mut anomalies = Vec::new();
+    if run.outcome == RunOutcome::NoSession {
+        let streak = missing_streak(records, task);
+        if streak == config.missing_runs.max(1) {
+            anomalies.push(missing(task, &pattern, streak));
+        }
+    }
+    if is_unusual(tasks, &pattern, run, config) {
+        anomalies.push(unusual(task, &pattern, run));
+    }
+    anomalies
+}
+
+/// Builds a missing-sessions anomaly.
+fn missing(task: &Task, pattern: &ClassPattern, count: usize) -> Anomaly {
+    Anomaly {
+        kind: AnomalyKind::MissingSessions,
+        task_id: task.id.clone(),
+        task_name: task.name.clone(),
+        class_id: task.class_id.clone(),
+        count,
+        usual_time: pattern.usual_time,
+        seen_at: None,
+    }
+}
+
+/// Builds an unusual-time anomaly.
+fn unusual(task: &Task, pattern: &ClassPattern, run: &RunRecord) -> Anomaly {
+    Anomaly {
+        kind: AnomalyKind::UnusualTime,
+        task_id: task.id.clone(),
+        task_name: task.name.clone(),
+        class_id: task.class_id.clone(),
+        count: 0,
+        usual_time: pattern.usual_time,
+        seen_at: Some(run.started_at),
+    }
+}
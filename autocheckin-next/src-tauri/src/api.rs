@@ -0,0 +1,281 @@
+use crate::config::{ApiConfig, ConfigState, Task};
+use crate::events;
+use crate::history::{compute_stats, load_history, RunOutcome, RunRecord};
+use crate::httpd;
+use crate::i18n::{t, Msg};
+use crate::scheduler::{next_run, run_all_now, run_task_now};
+use crate::tray::set_task_enabled;
+use chrono::{DateTime, Local, NaiveDateTime};
+use log::{debug, info, warn};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::io::Write;
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::RecvTimeoutError;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// Most connections served at once, open event streams included.
+const MAX_CONNECTIONS: usize = 32;
+
+/// Interval of keep-alive comments on the event stream, so proxies keep it open.
+const KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+/// A task as exposed over the API, without its cookie or custom headers.
+#[derive(Debug, Serialize)]
+struct TaskSummary {
+    /// ID of the task.
+    id: String,
+    /// Name of the task.
+    name: String,
+    /// Scheduled time in HH:MM format, or the cron schedule if the task has one.
+    time: String,
+    /// ID of the class to check in.
+    class_id: String,
+    /// Whether the task is enabled.
+    enable: bool,
+    /// Labels of the task.
+    tags: Vec<String>,
+    /// Outcome of the most recent run, if any.
+    last_outcome: Option<RunOutcome>,
+    /// When the most recent run finished, if any.
+    last_run_at: Option<DateTime<Local>>,
+}
+
+/// Status snapshot returned by `GET /api/status`.
+#[derive(Debug, Serialize)]
+struct Status {
+    /// The app version.
+    version: &'static str,
+    /// All tasks.
+    tasks: Vec<TaskSummary>,
+    /// ID of the task that runs next, if any is enabled.
+    next_task_id: Option<String>,
+    /// When the next task runs.
+    next_run_at: Option<NaiveDateTime>,
+}
+
+/// A response status line and JSON body.
+type Reply = (&'static str, Value);
+
+/// Starts the REST API in a background thread.
+///
+/// The API exposes the task list, status and statistics, and can run, pause and resume
+/// tasks. `GET /api/events` streams scheduler ticks, run progress and results as
+/// server-sent events. Every request must carry `Authorization: Bearer <token>`; the
+/// event stream also accepts `?token=<token>`, since browsers' `EventSource` cannot set
+/// headers.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `config` - The API settings.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Ok once listening, or an error message if no token is set or
+///   the address cannot be bound.
+pub fn serve(app_handle: AppHandle, config: &ApiConfig) -> Result<(), String> {
+    if config.token.trim().is_empty() {
+        return Err(t(Msg::ApiTokenEmpty).to_string());
+    }
+    let listener = TcpListener::bind((config.bind_address.as_str(), config.port))
+        .map_err(|e| e.to_string())?;
+    info!(
+        "Serving REST API on http://{}:{}/api",
+        config.bind_address, config.port
+    );
+    let token = config.token.trim().to_string();
+    httpd::spawn(listener, MAX_CONNECTIONS, move |stream| {
+        if let Err(e) = respond(&app_handle, &token, stream) {
+            warn!("API request failed: {}", e);
+        }
+    });
+    Ok(())
+}
+
+/// Reads one request, checks its token and writes the response.
+fn respond(app_handle: &AppHandle, token: &str, stream: TcpStream) -> std::io::Result<()> {
+    let request = httpd::read_request(&stream, 0)?;
+    let authorized = request
+        .header("authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|given| constant_time_eq(given.trim(), token));
+    let method = request.method.as_str();
+    let (path, query) = request.path_and_query();
+    if method == "GET" && path == "/api/events" {
+        let query_token = query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("token="))
+            .is_some_and(|given| constant_time_eq(given, token));
+        if authorized || query_token {
+            return stream_events(&stream);
+        }
+    }
+    let (status, body) = if authorized {
+        route(app_handle, method, path)
+    } else {
+        (
+            "401 Unauthorized",
+            json!({ "error": "invalid or missing token" }),
+        )
+    };
+
+    httpd::write_response(&stream, status, "application/json", &body.to_string())
+}
+
+/// Writes published events to the client as server-sent events until it disconnects.
+fn stream_events(mut stream: &TcpStream) -> std::io::Result<()> {
+    let receiver = events::subscribe();
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n"
+    )?;
+    loop {
+        let chunk = match receiver.recv_timeout(KEEP_ALIVE) {
+            Ok(event) => format!("event: {}\ndata: {}\n\n", event.kind, event.data),
+            Err(RecvTimeoutError::Timeout) => ": keep-alive\n\n".to_string(),
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        };
+        // A failed write means the client went away, which ends the stream normally.
+        if stream
+            .write_all(chunk.as_bytes())
+            .and_then(|_| stream.flush())
+            .is_err()
+        {
+            debug!("Event stream client disconnected");
+            return Ok(());
+        }
+    }
+}
+
+/// Dispatches an authorized request to its handler.
+fn route(app_handle: &AppHandle, method: &str, path: &str) -> Reply {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    if let ["api", "tasks", id, _] = segments.as_slice() {
+        if !task_exists(app_handle, id) {
+            return not_found();
+        }
+    }
+    match (method, segments.as_slice()) {
+        ("GET", ["api", "status"]) => ok(status(app_handle)),
+        ("GET", ["api", "tasks"]) => ok(task_summaries(app_handle)),
+        ("GET", ["api", "tasks", id, "stats"]) => {
+            ok(compute_stats(&load_history(app_handle), id, None))
+        }
+        ("POST", ["api", "tasks", id, "run"]) => action(run_task_now(app_handle, id)),
+        ("POST", ["api", "tasks", id, "pause"]) => {
+            action(set_task_enabled(app_handle, id, Some(false)))
+        }
+        ("POST", ["api", "tasks", id, "resume"]) => {
+            action(set_task_enabled(app_handle, id, Some(true)))
+        }
+        ("POST", ["api", "run-all"]) => ok(json!({ "started": run_all_now(app_handle) })),
+        (_, ["api", ..]) if known_path(&segments) => (
+            "405 Method Not Allowed",
+            json!({ "error": "method not allowed" }),
+        ),
+        _ => not_found(),
+    }
+}
+
+/// Whether a path matches a route under another method.
+fn known_path(segments: &[&str]) -> bool {
+    matches!(
+        segments,
+        ["api", "status"]
+            | ["api", "tasks"]
+            | ["api", "events"]
+            | ["api", "run-all"]
+            | ["api", "tasks", _, "stats" | "run" | "pause" | "resume"]
+    )
+}
+
+/// Builds the status snapshot.
+fn status(app_handle: &AppHandle) -> Status {
+    let tasks = app_handle
+        .state::<ConfigState>()
+        .0
+        .lock()
+        .unwrap()
+        .tasks
+        .clone();
+    let next = next_run(&tasks, Local::now().naive_local()).map(|(t, at)| (t.id.clone(), at));
+    Status {
+        version: env!("CARGO_PKG_VERSION"),
+        tasks: summarize(&tasks, &load_history(app_handle)),
+        next_task_id: next.as_ref().map(|(id, _)| id.clone()),
+        next_run_at: next.map(|(_, at)| at),
+    }
+}
+
+/// Lists the tasks without their credentials.
+fn task_summaries(app_handle: &AppHandle) -> Vec<TaskSummary> {
+    let tasks = app_handle
+        .state::<ConfigState>()
+        .0
+        .lock()
+        .unwrap()
+        .tasks
+        .clone();
+    summarize(&tasks, &load_history(app_handle))
+}
+
+/// Pairs each task with its most recent run.
+fn summarize(tasks: &[Task], history: &[RunRecord]) -> Vec<TaskSummary> {
+    tasks
+        .iter()
+        .map(|task| {
+            let last = history.iter().rev().find(|r| r.task_id == task.id);
+            TaskSummary {
+                id: task.id.clone(),
+                name: task.name.clone(),
+                time: task.schedule_label(),
+                class_id: task.class_id.clone(),
+                enable: task.enable,
+                tags: task.tags.clone(),
+                last_outcome: last.map(|r| r.outcome),
+                last_run_at: last.map(|r| r.finished_at),
+            }
+        })
+        .collect()
+}
+
+/// Whether a task with the given ID exists.
+fn task_exists(app_handle: &AppHandle, task_id: &str) -> bool {
+    app_handle
+        .state::<ConfigState>()
+        .0
+        .lock()
+        .unwrap()
+        .tasks
+        .iter()
+        .any(|t| t.id == task_id)
+}
+
+/// A 200 reply with a serialized body.
+fn ok(body: impl Serialize) -> Reply {
+    ("200 OK", serde_json::to_value(body).unwrap_or_default())
+}
+
+/// A 404 reply.
+fn not_found() -> Reply {
+    ("404 Not Found", json!({ "error": "not found" }))
+}
+
+/// The reply to a task action.
+fn action(result: Result<(), String>) -> Reply {
+    match result {
+        Ok(()) => ok(json!({ "ok": true })),
+        Err(e) => ("409 Conflict", json!({ "error": e })),
+    }
+}
+
+/// Compares two strings in time independent of where they differ.
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
@@ -1,41 +1,165 @@
+use crate::config::TimeoutConfig;
+use crate::cookie::normalize_cookie;
+use crate::headers::WECHAT_ANDROID_UA;
+use crate::httplog;
+use crate::i18n::{t, Msg};
+use crate::tls;
 use base64::engine::general_purpose;
 use base64::Engine as _;
 use image::Luma;
 use qrcode::QrCode;
 use regex::Regex;
 use reqwest::blocking::Client;
+use reqwest::cookie::{CookieStore, Jar};
+use reqwest::Url;
 use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::io::Cursor;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
 
-/// User Agent string used for requests to simulate a mobile WeChat browser.
-const UA: &str = "Mozilla/5.0 (Linux; Android 12; PAL-AL00 Build/HUAWEIPAL-AL00; wv) AppleWebKit/537.36 (KHTML, like Gecko) Version/4.0 Chrome/116.0.0.0 Mobile Safari/537.36 XWEB/1160065 MMWEBSDK/20231202 MMWEBID/1136 MicroMessenger/8.0.47.2560(0x28002F35) WeChat/arm64 Weixin NetType/4G Language/zh_CN ABI/arm64";
+/// Selects the inline scripts of the login page.
+static SCRIPT_SELECTOR: LazyLock<Selector> = LazyLock::new(|| Selector::parse("script").unwrap());
+
+/// Finds the login URL in a script.
+static LOGIN_URL: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"https?://[^\s"']+"#).unwrap());
+
+/// Selects the links of the student home page.
+static LINK_SELECTOR: LazyLock<Selector> = LazyLock::new(|| Selector::parse("a[href]").unwrap());
+
+/// Finds the class ID in a link to a course page.
+static COURSE_LINK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"/student/course/([^/?#]+)/?(?:[?#]|$)").unwrap());
+
+/// QR login page of the login site.
+const QR_URL: &str = "https://login.b8n.cn/qr/weixin/student/2";
+
+/// Check-in site the login is completed on.
+const SITE_BASE: &str = "https://bj.k8n.cn";
+
+/// Extracts the session parameters from the login URL.
+static LOGIN_PARAM: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[?&](sess|tm|sign)=([^&]+)").unwrap());
+
+/// A completed QR login.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct LoginSession {
+    /// The cookies the check-in site set, as a `Cookie` header value.
+    pub cookie: String,
+    /// Class IDs of the courses linked from the student's home page, in page order.
+    pub class_ids: Vec<String>,
+}
+
+/// The QR login in progress, kept between fetching the QR code and polling for the scan.
+///
+/// The login server ties the scan to the session cookie it set with the QR page, so the
+/// poll must go through the same client and cookie jar.
+#[derive(Default)]
+pub struct LoginState(Mutex<Option<Arc<AuthHandler>>>);
+
+impl LoginState {
+    /// Fetches a QR code with a new handler and keeps the handler for polling.
+    ///
+    /// Replaces any login still in progress.
+    ///
+    /// # Arguments
+    ///
+    /// * `auth` - The handler to log in with.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(String, String), String>` - Base64 image and check URL, or an error message.
+    pub fn start(&self, auth: AuthHandler) -> Result<(String, String), String> {
+        let auth = Arc::new(auth);
+        *self.0.lock().unwrap() = Some(auth.clone());
+        auth.get_qr_code()
+    }
+
+    /// Polls the login in progress, and ends it once it completes.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The check URL returned by `start`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<LoginSession>, String>` - The session if login is successful, `None`
+    ///   if still waiting, or an error message if no login was started or it failed.
+    pub fn check(&self, url: &str) -> Result<Option<LoginSession>, String> {
+        let auth = self
+            .0
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| t(Msg::NoLoginInProgress).to_string())?;
+        let session = auth.check_login(url)?;
+        if session.is_some() {
+            let mut current = self.0.lock().unwrap();
+            if current.as_ref().is_some_and(|c| Arc::ptr_eq(c, &auth)) {
+                *current = None;
+            }
+        }
+        Ok(session)
+    }
+}
 
 /// Handles authentication-related operations, primarily fetching QR codes for login
 /// and checking login status.
 pub struct AuthHandler {
     /// The HTTP client used for making requests.
     client: Client,
+    /// The cookies the client received, read back once the login completes.
+    jar: Arc<Jar>,
     /// The base URL for fetching the login QR code.
     base_qr_url: String,
+    /// Scheme and host of the check-in site the login is completed on.
+    site_base: String,
 }
 
 impl AuthHandler {
     /// Creates a new instance of `AuthHandler`.
     ///
-    /// Initializes the HTTP client with a specific User Agent and cookie storage enabled.
+    /// Initializes the HTTP client with a specific User Agent, a shared cookie jar and
+    /// gzip/brotli decompression enabled, and the configured timeouts.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeouts` - The connect and request timeouts.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - A new instance of `AuthHandler`.
+    pub fn new(timeouts: &TimeoutConfig) -> Self {
+        Self::with_urls(timeouts, QR_URL, SITE_BASE)
+    }
+
+    /// Creates an `AuthHandler` that logs in through other hosts, such as the mock site.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeouts` - The connect and request timeouts.
+    /// * `qr_url` - The QR login page, polled with `?op=checklogin`.
+    /// * `site_base` - Scheme and host of the check-in site the login is completed on.
     ///
     /// # Returns
     ///
     /// * `Self` - A new instance of `AuthHandler`.
-    pub fn new() -> Self {
+    pub fn with_urls(timeouts: &TimeoutConfig, qr_url: &str, site_base: &str) -> Self {
+        let jar = Arc::new(Jar::default());
         Self {
-            client: Client::builder()
-                .user_agent(UA)
-                .cookie_store(true)
+            client: tls::client_builder()
+                .user_agent(WECHAT_ANDROID_UA)
+                .cookie_provider(jar.clone())
+                .gzip(true)
+                .brotli(true)
+                .connect_timeout(Duration::from_secs(timeouts.connect_secs))
+                .timeout(Duration::from_secs(timeouts.request_secs))
                 .build()
                 .unwrap(),
-            base_qr_url: "https://login.b8n.cn/qr/weixin/student/2".to_string(),
+            jar,
+            base_qr_url: qr_url.to_string(),
+            site_base: site_base.trim_end_matches('/').to_string(),
         }
     }
 
@@ -51,12 +175,9 @@ impl AuthHandler {
     ///   and the URL to check for login status, or an error message on failure.
     pub fn get_qr_code(&self) -> Result<(String, String), String> {
         // Returns (Base64 Image, Check URL)
-        let resp = self
-            .client
-            .get(&self.base_qr_url)
-            .send()
-            .map_err(|e| e.to_string())?;
+        let resp = httplog::send(self.client.get(&self.base_qr_url)).map_err(|e| e.to_string())?;
         let html = resp.text().map_err(|e| e.to_string())?;
+        httplog::log_body(&self.base_qr_url, &html);
 
         let params = self.extract_qr_params(&html)?;
 
@@ -101,18 +222,13 @@ impl AuthHandler {
         html: &str,
     ) -> Result<std::collections::HashMap<String, String>, String> {
         let document = Html::parse_document(html);
-        let script_selector = Selector::parse("script").unwrap();
-
-        for script in document.select(&script_selector) {
+        for script in document.select(&SCRIPT_SELECTOR) {
             let script_content = script.text().collect::<Vec<_>>().join("");
             if script_content.contains("login.b8n.cn") {
-                let re = Regex::new(r#"https?://[^\s"']+"#).unwrap();
-                if let Some(captures) = re.captures(&script_content) {
+                if let Some(captures) = LOGIN_URL.captures(&script_content) {
                     let url = captures.get(0).unwrap().as_str();
                     let mut params = std::collections::HashMap::new();
-
-                    let re_param = Regex::new(r"[?&](sess|tm|sign)=([^&]+)").unwrap();
-                    for cap in re_param.captures_iter(url) {
+                    for cap in LOGIN_PARAM.captures_iter(url) {
                         params.insert(cap[1].to_string(), cap[2].to_string());
                     }
                     return Ok(params);
@@ -120,48 +236,60 @@ impl AuthHandler {
             }
         }
 
-        Err("Could not extract QR params".to_string())
+        Err(t(Msg::QrParamsMissing).to_string())
     }
 
     /// Checks the login status by polling the server.
     ///
+    /// Once the QR code was scanned, follows the `uidlogin` redirect to the check-in site,
+    /// reads the session cookies it set from the cookie jar and collects the class IDs
+    /// linked from the student home page it lands on.
+    ///
     /// # Arguments
     ///
     /// * `_url` - The URL to check (currently unused in implementation, relies on `base_qr_url`).
     ///
     /// # Returns
     ///
-    /// * `Result<Option<(String, String)>, String>` - Returns `Some((cookie, class_id))` if login is successful,
-    ///   `None` if still waiting, or an error message.
-    pub fn check_login(&self, _url: &str) -> Result<Option<(String, String)>, String> {
-        let resp_json: Value = self
-            .client
-            .get(format!("{}?op=checklogin", self.base_qr_url))
-            .send()
-            .map_err(|e| e.to_string())?
-            .json()
-            .map_err(|e| e.to_string())?;
+    /// * `Result<Option<LoginSession>, String>` - The session if login is successful, `None`
+    ///   if still waiting, or an error message if the site set no cookie.
+    pub fn check_login(&self, _url: &str) -> Result<Option<LoginSession>, String> {
+        let resp_json: Value = httplog::json(
+            httplog::send(
+                self.client
+                    .get(format!("{}?op=checklogin", self.base_qr_url)),
+            )
+            .map_err(|e| e.to_string())?,
+        )?;
 
         if let Some(status) = resp_json.get("status") {
             if status.as_i64() == Some(1) {
                 if let Some(url) = resp_json.get("url") {
                     let redirect_url = url.as_str().unwrap();
                     let target = format!(
-                        "https://bj.k8n.cn/student/uidlogin?{}",
+                        "{}/student/uidlogin?{}",
+                        self.site_base,
                         redirect_url.split('?').nth(1).unwrap_or("")
                     );
 
-                    // Follow redirect to get cookies
-                    let _ = self.client.get(&target).send().map_err(|e| e.to_string())?;
+                    // The redirects are followed, so the response is the student home page.
+                    let resp =
+                        httplog::send(self.client.get(&target)).map_err(|e| e.to_string())?;
+                    let landing = resp.text().map_err(|e| e.to_string())?;
+                    httplog::log_body(&target, &landing);
 
-                    // To properly get cookies, we would need to inspect the cookie jar here.
-                    // But for this simple implementation, we'll return placeholders.
-                    // In a full implementation, we'd use a shared Arc<Jar> passed to the ClientBuilder.
+                    let site = Url::parse(&self.site_base).map_err(|e| e.to_string())?;
+                    let cookie = self
+                        .jar
+                        .cookies(&site)
+                        .and_then(|value| value.to_str().map(normalize_cookie).ok())
+                        .filter(|cookie| !cookie.is_empty())
+                        .ok_or_else(|| t(Msg::LoginNoCookie).to_string())?;
 
-                    return Ok(Some((
-                        "cookie_placeholder".to_string(),
-                        "class_id_placeholder".to_string(),
-                    )));
+                    return Ok(Some(LoginSession {
+                        cookie,
+                        class_ids: extract_class_ids(&landing),
+                    }));
                 }
             }
         }
@@ -169,3 +297,56 @@ impl AuthHandler {
         Ok(None)
     }
 }
+
+/// Collects the class IDs of the course links on the student home page.
+///
+/// # Arguments
+///
+/// * `html` - The HTML content of the page.
+///
+/// # Returns
+///
+/// * `Vec<String>` - The class IDs in page order, each once.
+fn extract_class_ids(html: &str) -> Vec<String> {
+    let document = Html::parse_document(html);
+    let mut class_ids: Vec<String> = Vec::new();
+    for link in document.select(&LINK_SELECTOR) {
+        let href = link.value().attr("href").unwrap_or_default();
+        if let Some(captures) = COURSE_LINK.captures(href) {
+            if !class_ids.iter().any(|id| *id == captures[1]) {
+                class_ids.push(captures[1].to_string());
+            }
+        }
+    }
+    class_ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn qr_params_come_from_the_login_script() {
+        let html = r#"<html><body>
+            <script>var x = 1;</script>
+            <script>
+                var url = "https://login.b8n.cn/qr/weixin/student/2?sess=ab12&tm=1760500000&sign=f00d";
+            </script>
+        </body></html>"#;
+        let params = AuthHandler::new(&TimeoutConfig::default())
+            .extract_qr_params(html)
+            .unwrap();
+        assert_eq!(params.len(), 3);
+        assert_eq!(params["sess"], "ab12");
+        assert_eq!(params["tm"], "1760500000");
+        assert_eq!(params["sign"], "f00d");
+    }
+
+    #[test]
+    fn qr_params_missing_is_an_error() {
+        let html = "<html><script>var url = 'https://example.com/?sess=1';</script></html>";
+        assert!(AuthHandler::new(&TimeoutConfig::default())
+            .extract_qr_params(html)
+            .is_err());
+    }
+}
@@ -0,0 +1,222 @@
+use crate::config::Task;
+use crate::i18n::{t, tf, Msg};
+use chrono::{Local, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use log::warn;
+use std::collections::HashSet;
+
+/// Product identifier written into exported calendars.
+const PRODID: &str = "-//AutoCheckin-Next//Schedule Export//EN";
+
+/// Length of each exported event in minutes.
+const EVENT_MINUTES: i64 = 10;
+
+/// Renders the schedules of enabled tasks as an iCalendar document.
+///
+/// Each task becomes a recurring event starting at its first run from today, repeating
+/// daily, on its weekdays, or as its cron schedule says. Tasks with an unparseable time or
+/// schedule are skipped, as are cron schedules a recurrence rule cannot express.
+///
+/// # Arguments
+///
+/// * `tasks` - The tasks to export.
+///
+/// # Returns
+///
+/// * `String` - The iCalendar document with CRLF line endings.
+pub fn render_ics(tasks: &[Task]) -> String {
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let today = Local::now().date_naive();
+
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:{}", PRODID),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+
+    for task in tasks.iter().filter(|t| t.enable) {
+        let (start, rule) = match task.cron() {
+            Some(Ok(schedule)) => {
+                let midnight = today.and_time(NaiveTime::MIN) - chrono::Duration::minutes(1);
+                let (Some(start), Some(rule)) =
+                    (schedule.next_after(midnight), schedule.to_rrule())
+                else {
+                    warn!(
+                        "Schedule of task {} cannot be exported to a calendar",
+                        task.name
+                    );
+                    continue;
+                };
+                (start, rule)
+            }
+            Some(Err(_)) => continue,
+            None => {
+                let Ok(time) = NaiveTime::parse_from_str(&task.time, "%H:%M") else {
+                    continue;
+                };
+                let Some(day) = today.iter_days().take(7).find(|d| task.runs_on(*d)) else {
+                    continue;
+                };
+                let rule = match task.days.is_empty() {
+                    true => "FREQ=DAILY".to_string(),
+                    false => {
+                        let codes: Vec<String> = task
+                            .days
+                            .iter()
+                            .map(|d| d.to_string()[..2].to_uppercase())
+                            .collect();
+                        format!("FREQ=WEEKLY;BYDAY={}", codes.join(","))
+                    }
+                };
+                (day.and_time(time), rule)
+            }
+        };
+        let end = start + chrono::Duration::minutes(EVENT_MINUTES);
+
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}@autocheckin-next", task.id));
+        lines.push(format!("DTSTAMP:{}", stamp));
+        lines.push(format!("DTSTART:{}", start.format("%Y%m%dT%H%M%S")));
+        lines.push(format!("DTEND:{}", end.format("%Y%m%dT%H%M%S")));
+        lines.push(format!("RRULE:{}", rule));
+        lines.push(format!("SUMMARY:{}", escape_text(&task.name)));
+        lines.push(format!(
+            "DESCRIPTION:{}",
+            escape_text(&tf(Msg::CalendarDescription, &[&task.class_id]))
+        ));
+        lines.push("END:VEVENT".to_string());
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+    lines
+        .iter()
+        .map(|l| fold_line(l))
+        .collect::<Vec<_>>()
+        .join("\r\n")
+        + "\r\n"
+}
+
+/// Escapes a value for use in an iCalendar TEXT property.
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Folds a content line at 75 octets as required by RFC 5545, without splitting characters.
+fn fold_line(line: &str) -> String {
+    let mut folded = String::new();
+    let mut width = 0;
+    for ch in line.chars() {
+        if width + ch.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(ch);
+        width += ch.len_utf8();
+    }
+    folded
+}
+
+/// Parses an iCalendar timetable into proposed tasks.
+///
+/// Each distinct event summary and start time becomes one disabled task named after the
+/// event. The class ID and cookie are left empty for the user to fill in.
+///
+/// # Arguments
+///
+/// * `content` - The iCalendar document.
+///
+/// # Returns
+///
+/// * `Result<Vec<Task>, String>` - The proposed tasks, or an error if no events were found.
+pub fn parse_ics(content: &str) -> Result<Vec<Task>, String> {
+    let mut proposals = Vec::new();
+    let mut seen = HashSet::new();
+    let mut summary: Option<String> = None;
+    let mut start: Option<NaiveTime> = None;
+    let mut in_event = false;
+
+    for line in unfold_lines(content) {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let (key, params) = name.split_once(';').unwrap_or((name, ""));
+        match key.to_ascii_uppercase().as_str() {
+            "BEGIN" if value.eq_ignore_ascii_case("VEVENT") => {
+                in_event = true;
+                summary = None;
+                start = None;
+            }
+            "END" if value.eq_ignore_ascii_case("VEVENT") => {
+                in_event = false;
+                if let (Some(name), Some(time)) = (summary.take(), start.take()) {
+                    let time = time.format("%H:%M").to_string();
+                    if seen.insert((name.clone(), time.clone())) {
+                        proposals.push(Task {
+                            name,
+                            time,
+                            ..Default::default()
+                        });
+                    }
+                }
+            }
+            "SUMMARY" if in_event => summary = Some(unescape_text(value)),
+            "DTSTART" if in_event && !params.contains("VALUE=DATE") => {
+                start = parse_start_time(value);
+            }
+            _ => {}
+        }
+    }
+
+    if proposals.is_empty() {
+        Err(t(Msg::NoCalendarEvents).to_string())
+    } else {
+        Ok(proposals)
+    }
+}
+
+/// Joins folded continuation lines and strips line endings.
+fn unfold_lines(content: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for raw in content.lines() {
+        match (raw.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(raw.to_string()),
+        }
+    }
+    lines
+}
+
+/// Parses a DTSTART value into a local wall-clock time.
+///
+/// UTC values (suffixed with `Z`) are converted to local time; all others are taken as-is.
+fn parse_start_time(value: &str) -> Option<NaiveTime> {
+    let value = value.trim();
+    if let Some(utc) = value.strip_suffix('Z') {
+        let dt = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some(Local.from_utc_datetime(&dt).time());
+    }
+    NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+        .ok()
+        .map(|dt| dt.time())
+}
+
+/// Reverses the escaping applied to iCalendar TEXT values.
+fn unescape_text(text: &str) -> String {
+    let mut out = String::new();
+    let mut chars = text.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\\' {
+            match chars.next() {
+                Some('n') | Some('N') => out.push('\n'),
+                Some(other) => out.push(other),
+                None => {}
+            }
+        } else {
+            out.push(ch);
+        }
+    }
+    out.trim().to_string()
+}
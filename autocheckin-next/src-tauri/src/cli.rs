@@ -0,0 +1,279 @@
+use crate::config::{get_config_path, AppConfig, ConfigState};
+use crate::deeplink;
+use crate::history::load_history;
+use crate::hooks;
+use crate::scheduler;
+use crate::service;
+use crate::tls;
+use chrono::NaiveTime;
+use std::collections::HashSet;
+use std::fs;
+use tauri::{AppHandle, Manager};
+
+/// Exit code for a command that completed.
+const EXIT_OK: i32 = 0;
+
+/// Exit code for a command that failed, e.g. a failed run or an invalid config.
+const EXIT_FAILURE: i32 = 1;
+
+/// Exit code for unknown commands or missing arguments.
+const EXIT_USAGE: i32 = 2;
+
+/// Flag of `install-service` that starts the tray instead of running headless.
+const TRAY_FLAG: &str = "--tray";
+
+/// Usage text printed for `help` and for invalid arguments.
+const USAGE: &str = "\
+Usage: autocheckin-next [--headless] [COMMAND]
+
+Without a command, the app starts normally.
+
+Commands:
+  list-tasks              List the configured tasks
+  run-task <id>           Run a task now and wait for the result
+  validate-config         Check the config file for errors
+  export-history [path]   Write the run history as JSON to a file or stdout
+  install-service         Start the app headless at boot (at login on macOS;
+                          add --tray there to keep the tray icon)
+  uninstall-service       Remove the boot registration
+  help                    Show this message";
+
+/// A command given on the command line.
+#[derive(Debug)]
+pub enum Command {
+    /// Print the configured tasks.
+    ListTasks,
+    /// Run one task and exit with its result.
+    RunTask(String),
+    /// Check the config file and report problems.
+    ValidateConfig,
+    /// Write the run history to a file, or stdout if `None`.
+    ExportHistory(Option<String>),
+    /// Register the app to start at boot; `false` keeps the tray on macOS.
+    InstallService(bool),
+    /// Remove the boot registration.
+    UninstallService,
+}
+
+/// Parses the command-line arguments.
+///
+/// Flags starting with `--` (such as `--headless`) are left to the caller, and opened
+/// `autocheckin:` links to the deep link handler. `help` and invalid arguments print the
+/// usage text and exit the process.
+///
+/// # Arguments
+///
+/// * `args` - The arguments after the program name.
+///
+/// # Returns
+///
+/// * `Option<Command>` - The command to run, or `None` to start the app normally.
+pub fn parse(args: &[String]) -> Option<Command> {
+    let link = format!("{}:", deeplink::SCHEME);
+    let mut words = args
+        .iter()
+        .filter(|arg| !arg.starts_with("--") && !arg.starts_with(&link));
+    let command = match words.next().map(String::as_str) {
+        None => return None,
+        Some("list-tasks") => Command::ListTasks,
+        Some("run-task") => match words.next() {
+            Some(id) => Command::RunTask(id.clone()),
+            None => usage_error("run-task needs a task ID"),
+        },
+        Some("validate-config") => Command::ValidateConfig,
+        Some("export-history") => Command::ExportHistory(words.next().cloned()),
+        Some("install-service") => {
+            Command::InstallService(!args.iter().any(|arg| arg == TRAY_FLAG))
+        }
+        Some("uninstall-service") => Command::UninstallService,
+        Some("help") => {
+            println!("{}", USAGE);
+            std::process::exit(EXIT_OK);
+        }
+        Some(other) => usage_error(&format!("Unknown command: {}", other)),
+    };
+    if let Some(extra) = words.next() {
+        usage_error(&format!("Unexpected argument: {}", extra));
+    }
+    Some(command)
+}
+
+/// Prints an argument error with the usage text and exits.
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}\n\n{}", message, USAGE);
+    std::process::exit(EXIT_USAGE);
+}
+
+/// Runs a command-line command once the app state is set up.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `command` - The command to run.
+///
+/// # Returns
+///
+/// * `i32` - The process exit code.
+pub fn execute(app_handle: &AppHandle, command: Command) -> i32 {
+    match command {
+        Command::ListTasks => list_tasks(app_handle),
+        Command::RunTask(id) => run_task(app_handle, &id),
+        Command::ValidateConfig => validate_config(app_handle),
+        Command::ExportHistory(path) => export_history(app_handle, path.as_deref()),
+        Command::InstallService(headless) => report(service::install_service(headless)),
+        Command::UninstallService => report(service::uninstall_service()),
+    }
+}
+
+/// Prints one line per task: ID, state, time, class and name.
+fn list_tasks(app_handle: &AppHandle) -> i32 {
+    let config = app_handle.state::<ConfigState>().0.lock().unwrap().clone();
+    if config.tasks.is_empty() {
+        println!("No tasks configured");
+    }
+    for task in &config.tasks {
+        println!(
+            "{}\t{}\t{}\t{}\t{}",
+            task.id,
+            if task.enable { "enabled" } else { "disabled" },
+            task.schedule_label(),
+            task.class_id,
+            task.name
+        );
+    }
+    EXIT_OK
+}
+
+/// Runs a task, prints its outcome and returns a failure code unless it succeeded.
+fn run_task(app_handle: &AppHandle, task_id: &str) -> i32 {
+    match scheduler::run_task_blocking(app_handle, task_id) {
+        Ok(Some(outcome)) => {
+            println!("{:?}", outcome);
+            if outcome.is_failure() {
+                EXIT_FAILURE
+            } else {
+                EXIT_OK
+            }
+        }
+        Ok(None) => {
+            println!("Skipped");
+            EXIT_OK
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            EXIT_FAILURE
+        }
+    }
+}
+
+/// Reads the config file strictly and prints every problem found.
+fn validate_config(app_handle: &AppHandle) -> i32 {
+    let path = get_config_path(app_handle);
+    let problems = match fs::read_to_string(&path) {
+        Ok(content) => match serde_json::from_str::<AppConfig>(&content) {
+            Ok(mut config) => {
+                config.link_accounts();
+                config_problems(&config)
+            }
+            Err(e) => vec![format!("Invalid JSON: {}", e)],
+        },
+        Err(e) => vec![format!("Cannot read {}: {}", path.display(), e)],
+    };
+    if problems.is_empty() {
+        println!("{}: OK", path.display());
+        return EXIT_OK;
+    }
+    eprintln!("{}:", path.display());
+    for problem in &problems {
+        eprintln!("  {}", problem);
+    }
+    EXIT_FAILURE
+}
+
+/// Lists settings that would stop tasks from running.
+fn config_problems(config: &AppConfig) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut ids = HashSet::new();
+    for task in &config.tasks {
+        let name = format!("Task \"{}\" ({})", task.name, task.id);
+        if !ids.insert(task.id.as_str()) {
+            problems.push(format!("{}: duplicate ID", name));
+        }
+        match task.cron() {
+            Some(Err(e)) => problems.push(format!("{}: {}", name, e)),
+            Some(Ok(_)) => {}
+            None if NaiveTime::parse_from_str(&task.time, "%H:%M").is_err() => {
+                problems.push(format!("{}: time \"{}\" is not HH:MM", name, task.time));
+            }
+            None => {}
+        }
+        if task.class_id.trim().is_empty() {
+            problems.push(format!("{}: class ID is empty", name));
+        }
+        if task.enable && task.cookie.trim().is_empty() {
+            problems.push(format!("{}: cookie is empty", name));
+        }
+        let lat = task.location.lat.parse::<f64>();
+        let lng = task.location.lng.parse::<f64>();
+        if !matches!(lat, Ok(lat) if (-90.0..=90.0).contains(&lat))
+            || !matches!(lng, Ok(lng) if (-180.0..=180.0).contains(&lng))
+        {
+            problems.push(format!("{}: location is not a valid coordinate", name));
+        }
+    }
+    let wecom = &config.global.wecom;
+    if wecom.enable
+        && [&wecom.corpid, &wecom.secret, &wecom.agentid]
+            .iter()
+            .any(|field| field.trim().is_empty())
+    {
+        problems.push("WeCom is enabled but corpid, secret or agentid is empty".to_string());
+    }
+    if let Err(e) = tls::configure(&config.global.tls) {
+        problems.push(format!("CA bundle cannot be loaded: {}", e));
+    }
+    if let Err(e) = hooks::validate(&config.global.scripting) {
+        problems.push(format!("Hook script cannot be compiled: {}", e));
+    }
+    problems
+}
+
+/// Prints the result of an action and returns its exit code.
+fn report(result: Result<String, String>) -> i32 {
+    match result {
+        Ok(message) => {
+            println!("{}", message);
+            EXIT_OK
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            EXIT_FAILURE
+        }
+    }
+}
+
+/// Writes the run history as pretty-printed JSON.
+fn export_history(app_handle: &AppHandle, path: Option<&str>) -> i32 {
+    let history = load_history(app_handle);
+    let content = match serde_json::to_string_pretty(&history) {
+        Ok(content) => content,
+        Err(e) => {
+            eprintln!("{}", e);
+            return EXIT_FAILURE;
+        }
+    };
+    let Some(path) = path else {
+        println!("{}", content);
+        return EXIT_OK;
+    };
+    match fs::write(path, content) {
+        Ok(()) => {
+            println!("Exported {} run(s) to {}", history.len(), path);
+            EXIT_OK
+        }
+        Err(e) => {
+            eprintln!("{}: {}", path, e);
+            EXIT_FAILURE
+        }
+    }
+}
@@ -1,4 +1,13 @@
+use crate::anchor::Anchor;
+use crate::cookie::normalize_cookie;
+use crate::cron::Schedule;
+use crate::geo::CoordSystem;
+use crate::headers::HeaderProfile;
+use crate::i18n::Locale;
+use chrono::{DateTime, Datelike, Local, NaiveDate, Weekday};
+use log::warn;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 use std::sync::Mutex;
@@ -6,7 +15,7 @@ use tauri::AppHandle;
 use tauri::Manager;
 
 /// Represents a geographical location.
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct Location {
     /// Latitude as a string.
     pub lat: String,
@@ -14,10 +23,20 @@ pub struct Location {
     pub lng: String,
     /// Accuracy of the location.
     pub acc: String,
+    /// The coordinate system `lat`/`lng` are given in; GCJ-02 when missing.
+    #[serde(default)]
+    pub coord_system: CoordSystem,
+}
+
+impl Location {
+    /// Returns whether no coordinates have been entered.
+    pub fn is_unset(&self) -> bool {
+        self.lat.trim().is_empty() && self.lng.trim().is_empty()
+    }
 }
 
 /// Represents a scheduled task for auto-checkin.
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct Task {
     /// Unique identifier for the task.
     pub id: String,
@@ -25,14 +44,189 @@ pub struct Task {
     pub name: String,
     /// Scheduled time in HH:MM format.
     pub time: String,
+    /// Cron expression (e.g. `0 30 8 * * MON-FRI`) run instead of the daily `time`.
+    #[serde(default)]
+    pub schedule: Option<String>,
+    /// Weekdays the daily `time` applies to; empty for every day. Cron schedules have
+    /// their own weekday field and ignore it.
+    #[serde(default)]
+    pub days: Vec<Weekday>,
     /// ID of the class to check in.
     pub class_id: String,
-    /// Authentication cookie for the session.
+    /// Authentication cookie for the session; filled in from the account when
+    /// `account_id` is set, and not saved then.
+    #[serde(default)]
     pub cookie: String,
+    /// Whether the task has a cookie. Set only in copies sent to the frontend, which get
+    /// the flag instead of the cookie.
+    #[serde(default, skip_deserializing, skip_serializing_if = "is_false")]
+    pub has_cookie: bool,
+    /// ID of the account whose session the task uses.
+    #[serde(default)]
+    pub account_id: Option<String>,
     /// Location data for the check-in.
     pub location: Location,
     /// Whether the task is enabled.
     pub enable: bool,
+    /// When the task was last switched back on; failed runs before it do not count
+    /// towards `disable_after_failures`.
+    #[serde(default)]
+    pub enabled_at: Option<DateTime<Local>>,
+    /// Extra HTTP headers merged into every request (e.g. `X-Requested-With`).
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Sign form fields merged over the defaults (e.g. `acc`, `res`, `gps_addr`).
+    #[serde(default)]
+    pub form_params: HashMap<String, String>,
+    /// Free-form labels used to group and filter tasks.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Disable the task after this many consecutive failed runs (0 = never).
+    #[serde(default)]
+    pub disable_after_failures: u32,
+    /// Maximum distance in meters between the configured and the submitted location
+    /// (default 15 m).
+    #[serde(default)]
+    pub drift_radius_m: Option<f64>,
+    /// Sign at the location required by the check-in card, when the card lists one,
+    /// instead of the configured location.
+    #[serde(default)]
+    pub use_site_location: bool,
+    /// Alternative locations with rotation rules; the first matching one replaces
+    /// `location` for the day.
+    #[serde(default)]
+    pub anchors: Vec<Anchor>,
+    /// Request timeout in seconds for this task, overriding the global one.
+    #[serde(default)]
+    pub request_timeout_secs: Option<u64>,
+    /// Browser headers for this task, overriding the global profile.
+    #[serde(default)]
+    pub header_profile: Option<HeaderProfile>,
+    /// Whether to browse the course page before signing, overriding the global setting.
+    #[serde(default)]
+    pub warm_up: Option<bool>,
+    /// Password sent with the sign of password-protected sessions.
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+impl Task {
+    /// Replaces the cookie with `has_cookie`, for copies sent to the frontend.
+    pub fn redact(&mut self) {
+        self.has_cookie = !self.cookie.is_empty();
+        self.cookie.clear();
+    }
+
+    /// Switches the task on or off, recording when it is switched back on.
+    ///
+    /// # Arguments
+    ///
+    /// * `enable` - The new enable flag.
+    pub fn set_enable(&mut self, enable: bool) {
+        if enable && !self.enable {
+            self.enabled_at = Some(Local::now());
+        }
+        self.enable = enable;
+    }
+
+    /// Parses the task's cron schedule, if it has one.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Result<Schedule, String>>` - The schedule or an error message if the
+    ///   expression is invalid, or `None` if the task runs daily at `time`.
+    pub fn cron(&self) -> Option<Result<Schedule, String>> {
+        self.schedule
+            .as_deref()
+            .filter(|expr| !expr.trim().is_empty())
+            .map(Schedule::parse)
+    }
+
+    /// Whether the daily `time` applies on a day, according to `days`.
+    ///
+    /// # Arguments
+    ///
+    /// * `day` - The day.
+    pub fn runs_on(&self, day: NaiveDate) -> bool {
+        self.days.is_empty() || self.days.contains(&day.weekday())
+    }
+
+    /// The schedule as shown in listings: the cron expression, or else the daily time and
+    /// its weekdays, e.g. `08:00 Tue,Thu`.
+    pub fn schedule_label(&self) -> String {
+        match self
+            .schedule
+            .as_deref()
+            .filter(|expr| !expr.trim().is_empty())
+        {
+            Some(expr) => expr.to_string(),
+            None if self.days.is_empty() => self.time.clone(),
+            None => {
+                let days: Vec<String> = self.days.iter().map(Weekday::to_string).collect();
+                format!("{} {}", self.time, days.join(","))
+            }
+        }
+    }
+}
+
+/// A site account whose session is shared by the tasks referencing it.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Account {
+    /// Unique identifier for the account.
+    pub id: String,
+    /// Display name, e.g. the student's name.
+    pub name: String,
+    /// Authentication cookie for the session.
+    #[serde(default)]
+    pub cookie: String,
+    /// Whether the account has a cookie. Set only in copies sent to the frontend, which
+    /// get the flag instead of the cookie.
+    #[serde(default, skip_deserializing, skip_serializing_if = "is_false")]
+    pub has_cookie: bool,
+    /// When the session expires, if known.
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Local>>,
+    /// Host of the check-in site the session belongs to (e.g. `bj.k8n.cn`).
+    #[serde(default)]
+    pub base_domain: String,
+}
+
+impl Account {
+    /// Replaces the cookie with `has_cookie`, for copies sent to the frontend.
+    pub fn redact(&mut self) {
+        self.has_cookie = !self.cookie.is_empty();
+        self.cookie.clear();
+    }
+
+    /// Whether the session is known to have expired.
+    pub fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|at| at <= Local::now())
+    }
+
+    /// Replaces the cookie; a new cookie clears an expiry that has passed.
+    ///
+    /// # Arguments
+    ///
+    /// * `cookie` - The new, normalized cookie.
+    pub fn set_cookie(&mut self, cookie: String) {
+        if cookie != self.cookie && self.is_expired() {
+            self.expires_at = None;
+        }
+        self.cookie = cookie;
+    }
+}
+
+/// A single page of tasks returned by a search.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TaskPage {
+    /// Tasks on the requested page.
+    pub items: Vec<Task>,
+    /// Total number of tasks matching the filter.
+    pub total: usize,
+    /// The 1-based page number.
+    pub page: usize,
+    /// Maximum number of tasks per page.
+    pub page_size: usize,
 }
 
 /// Configuration for WeCom (Work WeChat) integration.
@@ -48,6 +242,570 @@ pub struct WeComConfig {
     pub agentid: String,
     /// The user(s) to send notifications to (e.g., "@all").
     pub touser: String,
+    /// Receiving messages and menu clicks from the WeCom application.
+    #[serde(default)]
+    pub callback: WeComCallbackConfig,
+}
+
+/// Receiver for the WeCom application's message callback, which turns replies such as
+/// `run 高数` into commands. Changes take effect after a restart.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct WeComCallbackConfig {
+    /// Listen for callbacks.
+    pub enable: bool,
+    /// The Token set for the callback in the WeCom console.
+    pub token: String,
+    /// The EncodingAESKey set for the callback in the WeCom console.
+    pub encoding_aes_key: String,
+    /// Address to listen on; WeCom must reach it, e.g. through a reverse proxy.
+    pub bind_address: String,
+    /// Port to listen on.
+    pub port: u16,
+    /// WeCom user IDs allowed to send commands; empty allows everyone who can see the app.
+    pub allowed_users: Vec<String>,
+}
+
+impl Default for WeComCallbackConfig {
+    /// Creates the default settings: disabled, listening on localhost port 9466.
+    fn default() -> Self {
+        Self {
+            enable: false,
+            token: String::new(),
+            encoding_aes_key: String::new(),
+            bind_address: "127.0.0.1".to_string(),
+            port: 9466,
+            allowed_users: vec![],
+        }
+    }
+}
+
+/// Base URL and path templates for the check-in site.
+///
+/// `{class}` is replaced with the class ID and `{id}` with the check-in session ID.
+/// Templates starting with `http` are used as absolute URLs.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct EndpointConfig {
+    /// Scheme and host the path templates are resolved against.
+    pub base_url: String,
+    /// Fall back to plain HTTP when the site cannot be reached over HTTPS.
+    pub allow_http_fallback: bool,
+    /// Course home page, also used as the Referer.
+    pub course: String,
+    /// Page listing the check-in sessions of a course.
+    pub punch_list: String,
+    /// Form target for submitting a check-in.
+    pub sign: String,
+    /// Pages of past check-in sessions; `{page}` is replaced with the 1-based page number.
+    pub punch_history: String,
+}
+
+impl EndpointConfig {
+    /// The host of the base URL, e.g. `k8n.cn`.
+    pub fn host(&self) -> String {
+        reqwest::Url::parse(&self.base_url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_default()
+    }
+}
+
+impl Default for EndpointConfig {
+    /// Creates the default endpoint templates used by k8n.cn, over HTTPS.
+    fn default() -> Self {
+        Self {
+            base_url: "https://k8n.cn".to_string(),
+            allow_http_fallback: true,
+            course: "/student/course/{class}".to_string(),
+            punch_list: "/student/course/{class}/punchs".to_string(),
+            sign: "/student/punchs/course/{class}/{id}".to_string(),
+            punch_history: "/student/course/{class}/punchs?page={page}".to_string(),
+        }
+    }
+}
+
+/// Output format of the log files.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    /// Human-readable lines in `<name>.log`.
+    #[default]
+    Text,
+    /// Structured JSON lines in `<name>.jsonl`.
+    Json,
+    /// Both text and JSON files.
+    Both,
+}
+
+/// Log file and verbosity settings.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// Minimum level written to the logs (`error`, `warn`, `info`, `debug`, `trace`).
+    pub level: String,
+    /// Size in KiB after which the log file is rotated.
+    pub max_file_size_kb: u64,
+    /// Number of log files (current plus rotated) to keep.
+    pub keep_files: usize,
+    /// Output format of the log files.
+    pub format: LogFormat,
+}
+
+impl Default for LoggingConfig {
+    /// Creates the default logging settings: `info` level, 1 MiB files, a week of history.
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            max_file_size_kb: 1024,
+            keep_files: 7,
+            format: LogFormat::Text,
+        }
+    }
+}
+
+/// Prometheus metrics endpoint settings. Changes take effect after a restart.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct MetricsConfig {
+    /// Serve `GET /metrics`.
+    pub enable: bool,
+    /// Address to listen on; `0.0.0.0` lets a Prometheus server on the LAN scrape it.
+    pub bind_address: String,
+    /// Port the endpoint listens on.
+    pub port: u16,
+}
+
+impl Default for MetricsConfig {
+    /// Creates the default settings: disabled, on `127.0.0.1:9464`.
+    fn default() -> Self {
+        Self {
+            enable: false,
+            bind_address: "127.0.0.1".to_string(),
+            port: 9464,
+        }
+    }
+}
+
+/// Local REST API settings. Changes take effect after a restart.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ApiConfig {
+    /// Serve the REST API. It is not started while `token` is empty.
+    pub enable: bool,
+    /// Address to listen on; `0.0.0.0` exposes the API to the LAN.
+    pub bind_address: String,
+    /// Port to listen on.
+    pub port: u16,
+    /// Bearer token every request must carry in its `Authorization` header.
+    pub token: String,
+}
+
+impl Default for ApiConfig {
+    /// Creates the default settings: disabled, on `127.0.0.1:9465` without a token.
+    fn default() -> Self {
+        Self {
+            enable: false,
+            bind_address: "127.0.0.1".to_string(),
+            port: 9465,
+            token: String::new(),
+        }
+    }
+}
+
+/// MQTT broker connection with Home Assistant discovery. Changes take effect after a
+/// restart.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct MqttConfig {
+    /// Connect to the broker and publish the tasks as Home Assistant entities.
+    pub enable: bool,
+    /// Broker host name or address.
+    pub host: String,
+    /// Broker port (plain TCP).
+    pub port: u16,
+    /// User name; empty connects anonymously.
+    pub username: String,
+    /// Password for `username`.
+    pub password: String,
+    /// Home Assistant discovery prefix.
+    pub discovery_prefix: String,
+    /// Topic under which task states and commands live.
+    pub base_topic: String,
+}
+
+impl Default for MqttConfig {
+    /// Creates the default settings: disabled, port 1883, Home Assistant's default prefix.
+    fn default() -> Self {
+        Self {
+            enable: false,
+            host: String::new(),
+            port: 1883,
+            username: String::new(),
+            password: String::new(),
+            discovery_prefix: "homeassistant".to_string(),
+            base_topic: "autocheckin".to_string(),
+        }
+    }
+}
+
+/// Telegram bot that answers commands such as `/status` and `/run`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct TelegramConfig {
+    /// Poll the bot for commands. Changes take effect after a restart.
+    pub enable: bool,
+    /// Token from @BotFather.
+    pub bot_token: String,
+    /// Chats allowed to send commands; messages from others are ignored.
+    pub allowed_chat_ids: Vec<i64>,
+}
+
+/// OpenTelemetry trace export settings.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct TracingConfig {
+    /// Record spans for scheduler ticks, task runs and HTTP requests and export them.
+    pub enable: bool,
+    /// OTLP/HTTP traces endpoint of the collector, accepting JSON.
+    pub endpoint: String,
+    /// The `service.name` resource attribute.
+    pub service_name: String,
+}
+
+impl Default for TracingConfig {
+    /// Creates the default settings: disabled, exporting to a local collector.
+    fn default() -> Self {
+        Self {
+            enable: false,
+            endpoint: "http://localhost:4318/v1/traces".to_string(),
+            service_name: "autocheckin-next".to_string(),
+        }
+    }
+}
+
+/// Dead-man's-switch pings to an external monitor such as healthchecks.io or Uptime Kuma.
+///
+/// `{task}` and `{outcome}` in the run URLs are replaced with the task name and outcome.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct HeartbeatConfig {
+    /// URL pinged after scheduler cycles to show the app is alive; empty disables.
+    pub cycle_url: String,
+    /// Minutes between cycle pings.
+    pub cycle_interval_minutes: u64,
+    /// URL pinged after each successful run; empty disables.
+    pub run_success_url: String,
+    /// URL pinged after each failed run; empty uses `<run_success_url>/fail`.
+    pub run_failure_url: String,
+}
+
+impl Default for HeartbeatConfig {
+    /// Creates the default settings: no pings, cycle pings every 5 minutes once set.
+    fn default() -> Self {
+        Self {
+            cycle_url: String::new(),
+            cycle_interval_minutes: 5,
+            run_success_url: String::new(),
+            run_failure_url: String::new(),
+        }
+    }
+}
+
+/// A task lifecycle event delivered to webhooks.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    /// A run is about to contact the site.
+    TaskStarted,
+    /// A run signed every active session.
+    TaskSucceeded,
+    /// A run failed or errored.
+    TaskFailed,
+    /// A task's cookie was found expired, during a run or a session check.
+    CookieExpired,
+    /// A task's rolling success rate dropped below the alert threshold.
+    SuccessRateLow,
+}
+
+/// An outgoing webhook that receives task events as JSON POST requests.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct WebhookConfig {
+    /// The URL to post to.
+    pub url: String,
+    /// Key for the `X-AutoCheckin-Signature: sha256=<hex>` HMAC of the body; empty
+    /// sends unsigned requests.
+    pub secret: String,
+    /// Events to deliver; empty delivers all.
+    pub events: Vec<WebhookEvent>,
+}
+
+/// Certificate settings for networks with TLS-intercepting proxies.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct TlsConfig {
+    /// PEM file with extra root certificates to trust, e.g. a campus proxy CA; empty for none.
+    pub ca_bundle_path: String,
+    /// Skip certificate validation entirely. Exposes the session cookie to anyone on the
+    /// network; prefer `ca_bundle_path`.
+    pub accept_invalid_certs: bool,
+}
+
+/// Timeouts applied to requests to the check-in and login sites.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[serde(default)]
+pub struct TimeoutConfig {
+    /// Seconds allowed to establish a connection.
+    pub connect_secs: u64,
+    /// Seconds allowed for a whole request, including reading the response.
+    pub request_secs: u64,
+}
+
+impl Default for TimeoutConfig {
+    /// Creates the default timeouts: 10 s to connect, 30 s per request.
+    fn default() -> Self {
+        Self {
+            connect_secs: 10,
+            request_secs: 30,
+        }
+    }
+}
+
+/// Request rate ceiling for each site account, shared by all tasks using it.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[serde(default)]
+pub struct ThrottleConfig {
+    /// Requests per minute allowed per account; `0` disables throttling.
+    pub requests_per_minute: u32,
+    /// Requests that may be sent back to back before the rate applies.
+    pub burst: u32,
+}
+
+impl Default for ThrottleConfig {
+    /// Creates the default ceiling: 20 requests per minute with bursts of 5.
+    fn default() -> Self {
+        Self {
+            requests_per_minute: 20,
+            burst: 5,
+        }
+    }
+}
+
+/// Navigation performed before the sign, imitating a user opening the course in WeChat.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[serde(default)]
+pub struct WarmUpConfig {
+    /// Visit the course page and wait before fetching the punch list.
+    pub enable: bool,
+    /// Shortest pause after a page load, in milliseconds.
+    pub min_delay_ms: u64,
+    /// Longest pause after a page load, in milliseconds.
+    pub max_delay_ms: u64,
+}
+
+impl Default for WarmUpConfig {
+    /// Creates the default warm-up: disabled, with pauses of 1.5 to 4 s.
+    fn default() -> Self {
+        Self {
+            enable: false,
+            min_delay_ms: 1500,
+            max_delay_ms: 4000,
+        }
+    }
+}
+
+/// Service used to look up coordinates for an address.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GeocodingProvider {
+    /// OpenStreetMap Nominatim. Free and keyless, but sparse for campus buildings.
+    #[default]
+    Nominatim,
+    /// Amap (Gaode) place search. Requires a Web service key.
+    Amap,
+    /// Tencent Maps place suggestions. Requires a WebService key.
+    Tencent,
+}
+
+/// Address lookup settings.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct GeocodingConfig {
+    /// The lookup service.
+    pub provider: GeocodingProvider,
+    /// API key for providers that need one.
+    pub api_key: String,
+    /// Fill the sign form's `gps_addr` field with the reverse-geocoded address.
+    pub fill_gps_addr: bool,
+}
+
+/// How drift offsets are distributed around the anchor.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftShape {
+    /// Evenly spread over the whole disc.
+    #[default]
+    Uniform,
+    /// Concentrated near the anchor and thinning out towards the edge, like real GPS error.
+    Gaussian,
+}
+
+/// What happens to an offset that falls outside the drift radius.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftClamp {
+    /// Pull it back onto the edge of the radius.
+    #[default]
+    Edge,
+    /// Draw a new offset until it lies inside the radius.
+    Resample,
+}
+
+/// Shape of the coordinate drift applied to each sign.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(default)]
+pub struct DriftConfig {
+    /// Distribution of the first offset of each day.
+    pub shape: DriftShape,
+    /// Handling of offsets outside the radius.
+    pub clamp: DriftClamp,
+}
+
+/// Optional GPS readings the sign form accepts, sent with per-sign variation.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct DeviceConfig {
+    /// Altitude of the check-in location in meters; sent as `altitude` when set.
+    pub altitude_m: Option<f64>,
+    /// Whether to send a `speed` field.
+    pub send_speed: bool,
+}
+
+/// Where to download updated site profiles from.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct SiteProfileConfig {
+    /// URL of the profile JSON; its minisign signature is read from `<url>.minisig`.
+    /// Empty uses the built-in profile only.
+    pub url: String,
+    /// Minisign public key the profile must be signed with.
+    pub public_key: String,
+    /// Hours between refreshes.
+    pub refresh_hours: u64,
+}
+
+impl Default for SiteProfileConfig {
+    /// Creates the default settings: no remote profile, refreshed daily once set.
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            public_key: String::new(),
+            refresh_hours: 24,
+        }
+    }
+}
+
+/// A central task list, published by an admin, that replaces the local tasks.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ManagedTasksConfig {
+    /// URL of the task list JSON; its minisign signature is read from `<url>.minisig`.
+    /// Empty keeps tasks local and editable.
+    pub url: String,
+    /// Minisign public key the task list must be signed with.
+    pub public_key: String,
+    /// Hours between refreshes.
+    pub refresh_hours: u64,
+}
+
+impl Default for ManagedTasksConfig {
+    /// Creates the default settings: local tasks, refreshed every six hours once set.
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            public_key: String::new(),
+            refresh_hours: 6,
+        }
+    }
+}
+
+/// Proof kept for each successful sign.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ReceiptConfig {
+    /// Save a receipt for each successful sign.
+    pub enable: bool,
+    /// Also keep the HTML page the site returned for the sign.
+    pub save_snapshot: bool,
+}
+
+impl Default for ReceiptConfig {
+    /// Creates the default settings: receipts on, without HTML snapshots.
+    fn default() -> Self {
+        Self {
+            enable: true,
+            save_snapshot: false,
+        }
+    }
+}
+
+/// Alert for a task whose success rate slowly degrades.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct SuccessRateAlertConfig {
+    /// Alert when a task's success rate drops below the threshold.
+    pub enable: bool,
+    /// The lowest acceptable success rate in percent.
+    pub threshold_percent: u32,
+    /// Number of days the rolling success rate covers.
+    pub window_days: u32,
+    /// Attempted runs needed in the window before the rate is judged.
+    pub min_attempts: usize,
+}
+
+impl Default for SuccessRateAlertConfig {
+    /// Creates the default settings: alert below 80% over two weeks of at least 5 attempts.
+    fn default() -> Self {
+        Self {
+            enable: true,
+            threshold_percent: 80,
+            window_days: 14,
+            min_attempts: 5,
+        }
+    }
+}
+
+/// Alerts about changes in a class's check-in pattern.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct AnomalyConfig {
+    /// Alert when a class's check-in pattern changes.
+    pub enable: bool,
+    /// Scheduled runs in a row that find no session before alerting.
+    pub missing_runs: usize,
+    /// Minutes a session may open away from the class's usual time before it is unusual.
+    pub unusual_minutes: u32,
+}
+
+impl Default for AnomalyConfig {
+    /// Creates the default settings: alerts on, after 3 empty runs or 90 minutes off.
+    fn default() -> Self {
+        Self {
+            enable: true,
+            missing_runs: 3,
+            unusual_minutes: 90,
+        }
+    }
+}
+
+/// A Rhai script with hooks called around each run.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ScriptConfig {
+    /// Path of a script defining `pre_run(task)` and/or `post_run(task, record)`; empty
+    /// for none.
+    pub path: String,
 }
 
 /// Global configuration settings for the application.
@@ -57,6 +815,105 @@ pub struct GlobalConfig {
     pub wecom: WeComConfig,
     /// Whether debug mode is enabled.
     pub debug: bool,
+    /// URL path templates for the check-in site.
+    #[serde(default)]
+    pub endpoints: EndpointConfig,
+    /// Log file and verbosity settings.
+    #[serde(default)]
+    pub logging: LoggingConfig,
+    /// URL of the updater manifest (`latest.json`).
+    #[serde(default = "default_update_endpoint")]
+    pub update_endpoint: String,
+    /// The coordinate system the check-in site expects (GCJ-02 by default); task locations
+    /// are converted to it.
+    #[serde(default)]
+    pub site_coord_system: CoordSystem,
+    /// First day of the term (`YYYY-MM-DD`), used to count odd and even teaching weeks.
+    #[serde(default)]
+    pub term_start: Option<NaiveDate>,
+    /// Use an approximate location from the public IP for tasks without a location.
+    #[serde(default)]
+    pub ip_location_fallback: bool,
+    /// Optional GPS readings sent with each sign.
+    #[serde(default)]
+    pub device: DeviceConfig,
+    /// Shape of the coordinate drift.
+    #[serde(default)]
+    pub drift: DriftConfig,
+    /// Address lookup settings.
+    #[serde(default)]
+    pub geocoding: GeocodingConfig,
+    /// Minutes a run waits for the network to come back before it is skipped as offline.
+    #[serde(default = "default_network_wait_minutes")]
+    pub network_wait_minutes: u64,
+    /// HTTP connect and request timeouts.
+    #[serde(default)]
+    pub timeouts: TimeoutConfig,
+    /// Per-account request rate ceiling.
+    #[serde(default)]
+    pub throttle: ThrottleConfig,
+    /// Navigation before the sign.
+    #[serde(default)]
+    pub warm_up: WarmUpConfig,
+    /// Prometheus metrics endpoint.
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+    /// Local REST API for remote control.
+    #[serde(default)]
+    pub api: ApiConfig,
+    /// MQTT broker connection for Home Assistant.
+    #[serde(default)]
+    pub mqtt: MqttConfig,
+    /// Telegram bot remote control.
+    #[serde(default)]
+    pub telegram: TelegramConfig,
+    /// OpenTelemetry trace export.
+    #[serde(default)]
+    pub tracing: TracingConfig,
+    /// Pings to an external uptime monitor.
+    #[serde(default)]
+    pub heartbeat: HeartbeatConfig,
+    /// Extra root certificates and certificate validation.
+    #[serde(default)]
+    pub tls: TlsConfig,
+    /// Outgoing webhooks for task events.
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
+    /// Evidence saved for successful signs.
+    #[serde(default)]
+    pub receipts: ReceiptConfig,
+    /// Alerts about missing or unusual check-in sessions.
+    #[serde(default)]
+    pub anomalies: AnomalyConfig,
+    /// Alert for tasks whose rolling success rate drops.
+    #[serde(default)]
+    pub success_rate_alert: SuccessRateAlertConfig,
+    /// Script hooks run before and after each run.
+    #[serde(default)]
+    pub scripting: ScriptConfig,
+    /// Source of updated parsing rules for the check-in site.
+    #[serde(default)]
+    pub site_profile: SiteProfileConfig,
+    /// Read-only task list downloaded from a central URL.
+    #[serde(default)]
+    pub managed_tasks: ManagedTasksConfig,
+    /// Browser headers sent to the check-in site.
+    #[serde(default)]
+    pub header_profile: HeaderProfile,
+    /// Language of backend messages, tray labels and notifications.
+    #[serde(default)]
+    pub locale: Locale,
+}
+
+/// The default updater manifest published with each GitHub release.
+fn default_update_endpoint() -> String {
+    "https://github.com/K2LinTeams/AutoCheckin-Next/releases/latest/download/latest.json"
+        .to_string()
+}
+
+/// Runs wait up to ten minutes for connectivity, enough for Wi-Fi to reconnect after wake.
+fn default_network_wait_minutes() -> u64 {
+    10
 }
 
 /// Root configuration structure for the application.
@@ -64,6 +921,9 @@ pub struct GlobalConfig {
 pub struct AppConfig {
     /// List of scheduled tasks.
     pub tasks: Vec<Task>,
+    /// Site accounts the tasks take their cookies from.
+    #[serde(default)]
+    pub accounts: Vec<Account>,
     /// Global application settings.
     pub global: GlobalConfig,
 }
@@ -73,6 +933,7 @@ impl Default for AppConfig {
     fn default() -> Self {
         Self {
             tasks: vec![],
+            accounts: vec![],
             global: GlobalConfig {
                 wecom: WeComConfig {
                     enable: false,
@@ -80,13 +941,133 @@ impl Default for AppConfig {
                     secret: "".to_string(),
                     agentid: "".to_string(),
                     touser: "@all".to_string(),
+                    callback: WeComCallbackConfig::default(),
                 },
                 debug: false,
+                endpoints: EndpointConfig::default(),
+                logging: LoggingConfig::default(),
+                update_endpoint: default_update_endpoint(),
+                site_coord_system: CoordSystem::default(),
+                term_start: None,
+                ip_location_fallback: false,
+                device: DeviceConfig::default(),
+                drift: DriftConfig::default(),
+                geocoding: GeocodingConfig::default(),
+                network_wait_minutes: default_network_wait_minutes(),
+                timeouts: TimeoutConfig::default(),
+                throttle: ThrottleConfig::default(),
+                warm_up: WarmUpConfig::default(),
+                metrics: MetricsConfig::default(),
+                api: ApiConfig::default(),
+                mqtt: MqttConfig::default(),
+                telegram: TelegramConfig::default(),
+                tracing: TracingConfig::default(),
+                heartbeat: HeartbeatConfig::default(),
+                tls: TlsConfig::default(),
+                webhooks: vec![],
+                receipts: ReceiptConfig::default(),
+                anomalies: AnomalyConfig::default(),
+                success_rate_alert: SuccessRateAlertConfig::default(),
+                scripting: ScriptConfig::default(),
+                site_profile: SiteProfileConfig::default(),
+                managed_tasks: ManagedTasksConfig::default(),
+                header_profile: HeaderProfile::default(),
+                locale: Locale::default(),
             },
         }
     }
 }
 
+impl AppConfig {
+    /// Moves cookies pasted into tasks into accounts and fills in the cookies of the
+    /// tasks that reference an account.
+    ///
+    /// A task with a cookie but no account is linked to the account holding the same
+    /// cookie, or to a new account named after the task.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether any task was linked to an account.
+    pub fn link_accounts(&mut self) -> bool {
+        let base_domain = self.global.endpoints.host();
+        let mut linked = false;
+        for task in &mut self.tasks {
+            if task.account_id.is_some() || task.cookie.trim().is_empty() {
+                continue;
+            }
+            let cookie = normalize_cookie(&task.cookie);
+            let id = match self.accounts.iter().find(|a| a.cookie == cookie) {
+                Some(account) => account.id.clone(),
+                None => {
+                    let id = uuid::Uuid::new_v4().to_string();
+                    self.accounts.push(Account {
+                        id: id.clone(),
+                        name: task.name.clone(),
+                        cookie,
+                        has_cookie: false,
+                        expires_at: None,
+                        base_domain: base_domain.clone(),
+                    });
+                    id
+                }
+            };
+            task.account_id = Some(id);
+            linked = true;
+        }
+        for task in &mut self.tasks {
+            if let Some(id) = &task.account_id {
+                task.cookie = self
+                    .accounts
+                    .iter()
+                    .find(|a| &a.id == id)
+                    .map(|a| a.cookie.clone())
+                    .unwrap_or_default();
+            }
+        }
+        linked
+    }
+
+    /// Returns a copy for the frontend, with every cookie replaced by `has_cookie`.
+    ///
+    /// # Returns
+    ///
+    /// * `AppConfig` - The configuration without cookies.
+    pub fn redacted(&self) -> AppConfig {
+        let mut config = self.clone();
+        for task in &mut config.tasks {
+            task.redact();
+        }
+        for account in &mut config.accounts {
+            account.redact();
+        }
+        config
+    }
+
+    /// Fills in the cookies a configuration from the frontend left empty from the
+    /// current one, matching tasks and accounts by ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `current` - The configuration the frontend's copy was redacted from.
+    pub fn restore_cookies(&mut self, current: &AppConfig) {
+        for task in self.tasks.iter_mut().filter(|t| t.cookie.is_empty()) {
+            if let Some(old) = current.tasks.iter().find(|t| t.id == task.id) {
+                task.cookie = old.cookie.clone();
+            }
+        }
+        for account in self.accounts.iter_mut().filter(|a| a.cookie.is_empty()) {
+            if let Some(old) = current.accounts.iter().find(|a| a.id == account.id) {
+                account.cookie = old.cookie.clone();
+            }
+        }
+    }
+}
+
+/// Whether a flag is unset, to leave it out of the saved configuration.
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
 /// State wrapper for `AppConfig` to allow sharing across threads safely using a Mutex.
 pub struct ConfigState(pub Mutex<AppConfig>);
 
@@ -110,7 +1091,8 @@ pub fn get_config_path(app_handle: &AppHandle) -> PathBuf {
 /// Loads the application configuration from the file system.
 ///
 /// If the configuration file exists, it reads and parses it.
-/// Otherwise, it returns the default configuration.
+/// Otherwise, it returns the default configuration. Cookies still stored in tasks are
+/// moved to accounts, and the tasks' cookies are filled in from their accounts.
 ///
 /// # Arguments
 ///
@@ -121,18 +1103,25 @@ pub fn get_config_path(app_handle: &AppHandle) -> PathBuf {
 /// * `AppConfig` - The loaded or default configuration.
 pub fn load_config(app_handle: &AppHandle) -> AppConfig {
     let config_path = get_config_path(app_handle);
-    if config_path.exists() {
+    let mut config: AppConfig = if config_path.exists() {
         let content = fs::read_to_string(config_path).unwrap_or_default();
         serde_json::from_str(&content).unwrap_or_default()
     } else {
         AppConfig::default()
+    };
+    // Cookies of older configurations live in the tasks; move them to accounts once.
+    if config.link_accounts() {
+        if let Err(e) = save_config(app_handle, &config) {
+            warn!("Failed to save tasks linked to accounts: {}", e);
+        }
     }
+    config
 }
 
 /// Saves the application configuration to the file system.
 ///
 /// Creates the parent directory if it doesn't exist, and writes the configuration
-/// as a pretty-printed JSON string.
+/// as a pretty-printed JSON string. Tasks with an account are saved without their cookie.
 ///
 /// # Arguments
 ///
@@ -147,7 +1136,43 @@ pub fn save_config(app_handle: &AppHandle, config: &AppConfig) -> Result<(), Str
     if let Some(parent) = config_path.parent() {
         fs::create_dir_all(parent).map_err(|e| e.to_string())?;
     }
-    let content = serde_json::to_string_pretty(config).map_err(|e| e.to_string())?;
+    // Cookies of tasks with an account are stored in the account only.
+    let mut stored = config.clone();
+    for task in stored.tasks.iter_mut().filter(|t| t.account_id.is_some()) {
+        task.cookie.clear();
+    }
+    let content = serde_json::to_string_pretty(&stored).map_err(|e| e.to_string())?;
     fs::write(config_path, content).map_err(|e| e.to_string())?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacted_config_keeps_the_stored_cookies_when_saved_back() {
+        let mut current = AppConfig::default();
+        current.accounts.push(Account {
+            id: "a".to_string(),
+            cookie: "remember_student=1".to_string(),
+            ..Account::default()
+        });
+        current.tasks.push(Task {
+            id: "t".to_string(),
+            cookie: "remember_student=1".to_string(),
+            account_id: Some("a".to_string()),
+            ..Task::default()
+        });
+
+        let mut sent = current.redacted();
+        assert!(sent.tasks[0].cookie.is_empty() && sent.tasks[0].has_cookie);
+        assert!(sent.accounts[0].cookie.is_empty() && sent.accounts[0].has_cookie);
+        let json = serde_json::to_string(&sent).unwrap();
+        assert!(!json.contains("remember_student"));
+
+        sent.restore_cookies(&current);
+        assert_eq!(sent.tasks[0].cookie, "remember_student=1");
+        assert_eq!(sent.accounts[0].cookie, "remember_student=1");
+    }
+}
@@ -0,0 +1,231 @@
+use serde_json::Value;
+
+/// `Set-Cookie` attributes that are not part of the cookie itself.
+const ATTRIBUTES: &[&str] = &[
+    "path",
+    "domain",
+    "expires",
+    "max-age",
+    "secure",
+    "httponly",
+    "samesite",
+    "priority",
+    "partitioned",
+    "version",
+    "comment",
+];
+
+/// Parses a pasted cookie string into name/value pairs.
+///
+/// Accepts a plain `Cookie` header value with or without the `Cookie:` prefix, one or
+/// more `Set-Cookie` lines, a Netscape `cookies.txt` export, a JSON array exported by
+/// browser extensions, and name/value rows copied from the DevTools cookie table.
+/// Attributes such as `Path` and `Expires` are dropped, and a cookie set more than once
+/// keeps its last value at the position of its first occurrence. The legacy
+/// `username=<cookie>` wrapping used by older configs is unwrapped.
+///
+/// # Arguments
+///
+/// * `raw` - The text as pasted by the user.
+///
+/// # Returns
+///
+/// * `Vec<(String, String)>` - The cookies in order.
+pub fn parse_cookie(raw: &str) -> Vec<(String, String)> {
+    let mut pairs: Vec<(String, String)> = Vec::new();
+    let mut add = |name: &str, value: &str| {
+        let (name, value) = (name.trim(), value.trim());
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return;
+        }
+        if name == "username" && value.contains('=') {
+            // Older versions stored the whole cookie behind a `username=` prefix.
+            for (name, value) in parse_cookie(value) {
+                upsert(&mut pairs, name, value);
+            }
+            return;
+        }
+        upsert(&mut pairs, name.to_string(), value.to_string());
+    };
+
+    let trimmed = raw.trim();
+    if trimmed.starts_with('[') || trimmed.starts_with('{') {
+        if let Ok(json) = serde_json::from_str::<Value>(trimmed) {
+            let items = match json {
+                Value::Array(items) => items,
+                single => vec![single],
+            };
+            for item in &items {
+                if let (Some(name), Some(value)) = (item["name"].as_str(), item["value"].as_str()) {
+                    add(name, value);
+                }
+            }
+            return pairs;
+        }
+    }
+
+    for line in trimmed.lines() {
+        let line = line.trim();
+        if line.is_empty() || (line.starts_with('#') && !line.starts_with("#HttpOnly_")) {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() == 7 && !fields[0].contains('=') {
+            // Netscape cookies.txt: domain, subdomains, path, secure, expiry, name, value.
+            add(fields[5], fields[6]);
+            continue;
+        }
+        if fields.len() >= 2 && !fields[0].contains('=') {
+            // DevTools table row: name, value, domain, path, ...
+            add(fields[0], fields[1]);
+            continue;
+        }
+
+        let (header, rest) = match line.split_once(':') {
+            Some((header, rest)) if !header.contains('=') => (header.trim(), rest),
+            _ => ("", line),
+        };
+        let is_set_cookie = header.eq_ignore_ascii_case("set-cookie");
+        for (i, part) in rest.split(';').enumerate() {
+            let (name, value) = part.split_once('=').unwrap_or((part, ""));
+            if is_set_cookie && i > 0 {
+                // Everything after the first pair of a Set-Cookie line is an attribute.
+                break;
+            }
+            if ATTRIBUTES.contains(&name.trim().to_ascii_lowercase().as_str()) {
+                continue;
+            }
+            if part.contains('=') {
+                add(name, value);
+            }
+        }
+    }
+    pairs
+}
+
+/// Normalizes a pasted cookie string into a valid `Cookie` header value.
+///
+/// # Arguments
+///
+/// * `raw` - The text as pasted by the user.
+///
+/// # Returns
+///
+/// * `String` - The cookies as `name=value` pairs joined with `; `.
+pub fn normalize_cookie(raw: &str) -> String {
+    parse_cookie(raw)
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Sets a cookie, replacing the value of an earlier one with the same name.
+fn upsert(pairs: &mut Vec<(String, String)>, name: String, value: String) {
+    match pairs.iter_mut().find(|(n, _)| *n == name) {
+        Some(entry) => entry.1 = value,
+        None => pairs.push((name, value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Owned pairs to compare `parse_cookie` output against.
+    fn expected(items: &[(&str, &str)]) -> Vec<(String, String)> {
+        items
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn devtools_table_rows() {
+        let raw = "remember_student_59ba\teyJpdiI6IjE=\tbj.k8n.cn\t/\t2026-11-15T08:00:00.000Z\t312\t✓\t✓\tLax\n\
+                   XSRF-TOKEN\tabc%3D\tbj.k8n.cn\t/\tSession\t20\t\t\tLax";
+        assert_eq!(
+            parse_cookie(raw),
+            expected(&[
+                ("remember_student_59ba", "eyJpdiI6IjE="),
+                ("XSRF-TOKEN", "abc%3D")
+            ])
+        );
+    }
+
+    #[test]
+    fn document_cookie() {
+        assert_eq!(
+            normalize_cookie(
+                "remember_student_59ba=eyJpdiI6IjE=;XSRF-TOKEN=abc%3D; k8n_session=xyz"
+            ),
+            "remember_student_59ba=eyJpdiI6IjE=; XSRF-TOKEN=abc%3D; k8n_session=xyz"
+        );
+    }
+
+    #[test]
+    fn fiddler_and_charles_cookie_header() {
+        assert_eq!(
+            parse_cookie("Cookie: remember_student_59ba=eyJpdiI6IjE=; k8n_session=xyz"),
+            expected(&[
+                ("remember_student_59ba", "eyJpdiI6IjE="),
+                ("k8n_session", "xyz")
+            ])
+        );
+        assert_eq!(
+            parse_cookie("cookie:k8n_session=xyz"),
+            expected(&[("k8n_session", "xyz")])
+        );
+    }
+
+    #[test]
+    fn set_cookie_lines_drop_attributes() {
+        let raw = "Set-Cookie: k8n_session=xyz; Path=/; HttpOnly; SameSite=Lax\n\
+                   Set-Cookie: remember_student_59ba=eyJpdiI6IjE=; Expires=Sun, 15 Nov 2026 08:00:00 GMT; Max-Age=2592000; Secure";
+        assert_eq!(
+            parse_cookie(raw),
+            expected(&[
+                ("k8n_session", "xyz"),
+                ("remember_student_59ba", "eyJpdiI6IjE=")
+            ])
+        );
+    }
+
+    #[test]
+    fn attributes_pasted_without_set_cookie_are_dropped() {
+        assert_eq!(
+            normalize_cookie("k8n_session=xyz; path=/; domain=bj.k8n.cn; secure"),
+            "k8n_session=xyz"
+        );
+    }
+
+    #[test]
+    fn duplicate_names_keep_the_last_value_in_first_position() {
+        assert_eq!(normalize_cookie("a=1; b=2; a=3"), "a=3; b=2");
+    }
+
+    #[test]
+    fn legacy_username_prefix_is_unwrapped() {
+        assert_eq!(
+            normalize_cookie("username=remember_student_59ba=eyJpdiI6IjE=; k8n_session=xyz"),
+            "remember_student_59ba=eyJpdiI6IjE=; k8n_session=xyz"
+        );
+        assert_eq!(normalize_cookie("username=alice"), "username=alice");
+    }
+
+    #[test]
+    fn extension_json_export() {
+        let raw = r#"[{"domain":"bj.k8n.cn","name":"k8n_session","value":"xyz","path":"/"},
+                      {"domain":"bj.k8n.cn","name":"a","value":"1"}]"#;
+        assert_eq!(normalize_cookie(raw), "k8n_session=xyz; a=1");
+    }
+
+    #[test]
+    fn netscape_cookies_txt() {
+        let raw = "# Netscape HTTP Cookie File\n\
+                   #HttpOnly_bj.k8n.cn\tFALSE\t/\tFALSE\t1794816000\tk8n_session\txyz\n\
+                   bj.k8n.cn\tFALSE\t/\tTRUE\t1794816000\ta\t1";
+        assert_eq!(normalize_cookie(raw), "k8n_session=xyz; a=1");
+    }
+}
@@ -0,0 +1,147 @@
+use crate::logs::snapshot_logs;
+use chrono::Local;
+use log::warn;
+use serde::Serialize;
+use std::backtrace::Backtrace;
+use std::fs;
+use std::panic;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+/// Number of recent log lines included in a crash report.
+const REPORT_LOG_LINES: usize = 50;
+
+/// A crash report written by a previous session.
+#[derive(Debug, Serialize, Clone)]
+pub struct CrashReport {
+    /// File name of the report.
+    pub file_name: String,
+    /// Full contents of the report.
+    pub content: String,
+}
+
+/// Retrieves the directory where crash reports are stored.
+///
+/// # Arguments
+///
+/// * `app_handle` - Handle to the Tauri application.
+///
+/// # Returns
+///
+/// * `PathBuf` - The `crashes` directory in the app's data directory.
+pub fn get_crash_dir(app_handle: &AppHandle) -> PathBuf {
+    app_handle
+        .path()
+        .app_data_dir()
+        .expect("failed to get app data dir")
+        .join("crashes")
+}
+
+/// Installs a panic hook that writes a crash report before the default hook runs.
+///
+/// Reports contain the panic message and location, the thread name, a backtrace,
+/// the app version and OS, and the most recent log lines.
+///
+/// # Arguments
+///
+/// * `app_handle` - Handle to the Tauri application.
+pub fn install_panic_hook(app_handle: &AppHandle) {
+    let dir = get_crash_dir(app_handle);
+    let version = app_handle.package_info().version.to_string();
+    let previous = panic::take_hook();
+
+    panic::set_hook(Box::new(move |info| {
+        let now = Local::now();
+        let thread = std::thread::current();
+        let logs: Vec<String> = snapshot_logs(REPORT_LOG_LINES)
+            .into_iter()
+            .map(|l| format!("{} [{}][{}] {}", l.timestamp, l.target, l.level, l.message))
+            .collect();
+        let report = format!(
+            "Crash report\n\
+             Time: {}\n\
+             Version: {}\n\
+             OS: {} ({})\n\
+             Thread: {}\n\
+             Panic: {}\n\n\
+             Backtrace:\n{}\n\n\
+             Recent logs:\n{}\n",
+            now.to_rfc3339(),
+            version,
+            std::env::consts::OS,
+            std::env::consts::ARCH,
+            thread.name().unwrap_or("<unnamed>"),
+            info,
+            Backtrace::force_capture(),
+            logs.join("\n")
+        );
+
+        let path = dir.join(format!("crash-{}.txt", now.format("%Y%m%d-%H%M%S%3f")));
+        let _ = fs::create_dir_all(&dir).and_then(|_| fs::write(path, report));
+
+        previous(info);
+    }));
+}
+
+/// Lists crash reports that have not been dismissed yet.
+///
+/// # Arguments
+///
+/// * `app_handle` - Handle to the Tauri application.
+///
+/// # Returns
+///
+/// * `Vec<CrashReport>` - The pending reports, oldest first.
+pub fn pending_crash_reports(app_handle: &AppHandle) -> Vec<CrashReport> {
+    let Ok(entries) = fs::read_dir(get_crash_dir(app_handle)) else {
+        return vec![];
+    };
+    let mut reports: Vec<CrashReport> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "txt"))
+        .filter_map(|e| {
+            Some(CrashReport {
+                file_name: e.file_name().to_string_lossy().into_owned(),
+                content: fs::read_to_string(e.path()).ok()?,
+            })
+        })
+        .collect();
+    reports.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+    reports
+}
+
+/// Marks all pending crash reports as seen by moving them into `crashes/seen`.
+///
+/// # Arguments
+///
+/// * `app_handle` - Handle to the Tauri application.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Ok on success, or an error message if a report cannot be moved.
+pub fn dismiss_crash_reports(app_handle: &AppHandle) -> Result<(), String> {
+    let dir = get_crash_dir(app_handle);
+    let seen = dir.join("seen");
+    fs::create_dir_all(&seen).map_err(|e| e.to_string())?;
+    for report in pending_crash_reports(app_handle) {
+        fs::rename(dir.join(&report.file_name), seen.join(&report.file_name))
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Logs a warning if the previous session left crash reports behind.
+///
+/// # Arguments
+///
+/// * `app_handle` - Handle to the Tauri application.
+pub fn warn_pending_reports(app_handle: &AppHandle) {
+    let count = pending_crash_reports(app_handle).len();
+    if count > 0 {
+        warn!(
+            "{} crash report(s) from a previous session in {}",
+            count,
+            get_crash_dir(app_handle).display()
+        );
+    }
+}
@@ -0,0 +1,357 @@
+use crate::i18n::{tf, Msg};
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime};
+
+/// How many days `next_after` looks ahead; enough for a schedule on 29 February.
+const SEARCH_DAYS: usize = 366 * 8;
+
+/// Month names accepted in the month field, from January.
+const MONTHS: [&str; 12] = [
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+
+/// Weekday names accepted in the weekday field, from Sunday.
+const WEEKDAYS: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+/// iCalendar weekday codes, from Sunday.
+const RRULE_WEEKDAYS: [&str; 7] = ["SU", "MO", "TU", "WE", "TH", "FR", "SA"];
+
+/// A parsed cron expression.
+///
+/// Takes five fields (`minute hour day month weekday`) or six with leading seconds, as in
+/// `0 30 8 * * MON-FRI`. Fields accept `*`, `?`, values, ranges, lists and `/` steps;
+/// months and weekdays also accept English abbreviations, and weekday 7 is Sunday. As in
+/// classic cron, a day matches if either the day of month or the weekday does when neither
+/// field starts with `*` or `?`, and must match both otherwise. The scheduler checks once a
+/// minute, so the seconds field must include second 0 and runs start at the top of the
+/// minute.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    /// One bit per minute, 0-59.
+    minutes: u64,
+    /// One bit per hour, 0-23.
+    hours: u64,
+    /// One bit per day of the month, 1-31.
+    days: u64,
+    /// One bit per month, 1-12.
+    months: u64,
+    /// One bit per weekday, Sunday = 0.
+    weekdays: u64,
+    /// Whether a day must match both day fields rather than either.
+    both_days: bool,
+}
+
+impl Schedule {
+    /// Parses a cron expression.
+    ///
+    /// # Arguments
+    ///
+    /// * `expr` - The expression, e.g. `0 30 8 * * MON-FRI` or `30 8,14 * * 1-5`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Schedule, String>` - The schedule, or an error message naming the invalid
+    ///   field.
+    pub fn parse(expr: &str) -> Result<Schedule, String> {
+        let invalid = |field: &str| tf(Msg::InvalidSchedule, &[&expr.trim(), &field]);
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let fields = match fields.len() {
+            5 => &fields[..],
+            6 => {
+                let seconds =
+                    parse_field(fields[0], 0, 59, &[]).ok_or_else(|| invalid(fields[0]))?;
+                if seconds & 1 == 0 {
+                    return Err(invalid(fields[0]));
+                }
+                &fields[1..]
+            }
+            _ => return Err(invalid(expr.trim())),
+        };
+        let field = |index: usize, min: u32, max: u32, names: &[&str]| {
+            parse_field(fields[index], min, max, names).ok_or_else(|| invalid(fields[index]))
+        };
+        let minutes = field(0, 0, 59, &[])?;
+        let hours = field(1, 0, 23, &[])?;
+        let days = field(2, 1, 31, &[])?;
+        let months = field(3, 1, 12, &MONTHS)?;
+        let weekdays = field(4, 0, 7, &WEEKDAYS)?;
+        let is_any = |index: usize| fields[index].starts_with(['*', '?']);
+        Ok(Schedule {
+            minutes,
+            hours,
+            days,
+            months,
+            // Fold Sunday as 7 onto Sunday as 0.
+            weekdays: (weekdays | weekdays >> 7) & 0x7f,
+            both_days: is_any(2) || is_any(4),
+        })
+    }
+
+    /// Whether the schedule runs on a day.
+    fn matches_day(&self, day: NaiveDate) -> bool {
+        let day_ok = self.days & 1 << day.day() != 0;
+        let weekday_ok = self.weekdays & 1 << day.weekday().num_days_from_sunday() != 0;
+        self.months & 1 << day.month() != 0
+            && match self.both_days {
+                true => day_ok && weekday_ok,
+                false => day_ok || weekday_ok,
+            }
+    }
+
+    /// Lists the times the schedule runs on a day.
+    ///
+    /// # Arguments
+    ///
+    /// * `day` - The day.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<NaiveDateTime>` - The run times in order; empty if it does not run that day.
+    pub fn times_on(&self, day: NaiveDate) -> Vec<NaiveDateTime> {
+        if !self.matches_day(day) {
+            return vec![];
+        }
+        bits(self.hours)
+            .flat_map(|hour| bits(self.minutes).map(move |minute| (hour, minute)))
+            .filter_map(|(hour, minute)| NaiveTime::from_hms_opt(hour, minute, 0))
+            .map(|time| day.and_time(time))
+            .collect()
+    }
+
+    /// Finds the first run after a time.
+    ///
+    /// # Arguments
+    ///
+    /// * `after` - The time to search from, exclusive.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<NaiveDateTime>` - The next run, or `None` if the schedule never matches a
+    ///   real date (e.g. `0 0 31 2 *`).
+    pub fn next_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        after
+            .date()
+            .iter_days()
+            .take(SEARCH_DAYS)
+            .flat_map(|day| self.times_on(day))
+            .find(|at| *at > after)
+    }
+
+    /// Renders the schedule as an iCalendar recurrence rule.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<String>` - The `RRULE` value, or `None` if a day may match either day field,
+    ///   which recurrence rules cannot express.
+    pub fn to_rrule(&self) -> Option<String> {
+        if !self.both_days {
+            return None;
+        }
+        let list = |mask: u64| {
+            bits(mask)
+                .map(|n| n.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        let mut rule = format!(
+            "FREQ=DAILY;BYHOUR={};BYMINUTE={}",
+            list(self.hours),
+            list(self.minutes)
+        );
+        if self.weekdays != full(0, 6) {
+            let days: Vec<&str> = bits(self.weekdays)
+                .map(|d| RRULE_WEEKDAYS[d as usize])
+                .collect();
+            rule.push_str(&format!(";BYDAY={}", days.join(",")));
+        }
+        if self.days != full(1, 31) {
+            rule.push_str(&format!(";BYMONTHDAY={}", list(self.days)));
+        }
+        if self.months != full(1, 12) {
+            rule.push_str(&format!(";BYMONTH={}", list(self.months)));
+        }
+        Some(rule)
+    }
+}
+
+/// Parses one cron field into a bit set of the values it selects.
+///
+/// # Arguments
+///
+/// * `field` - The field, e.g. `*/15`, `1-5` or `MON,WED`.
+/// * `min` - The smallest allowed value.
+/// * `max` - The largest allowed value.
+/// * `names` - Names accepted for `min`, `min + 1`, and so on; may be empty.
+///
+/// # Returns
+///
+/// * `Option<u64>` - The bit set, or `None` if the field is invalid.
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Option<u64> {
+    let value = |text: &str| -> Option<u32> {
+        let value = match names.iter().position(|n| n.eq_ignore_ascii_case(text)) {
+            Some(index) => min + index as u32,
+            None => text.parse().ok()?,
+        };
+        (min..=max).contains(&value).then_some(value)
+    };
+    let mut mask = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().ok().filter(|s| *s > 0)?),
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" | "?" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (value(start)?, value(end)?),
+                // `5/10` means every tenth value from 5.
+                None if part.contains('/') => (value(range)?, max),
+                None => (value(range)?, value(range)?),
+            },
+        };
+        if start > end {
+            return None;
+        }
+        for v in (start..=end).step_by(step as usize) {
+            mask |= 1 << v;
+        }
+    }
+    Some(mask)
+}
+
+/// The bit set selecting every value from `min` to `max`.
+fn full(min: u32, max: u32) -> u64 {
+    (min..=max).fold(0, |mask, v| mask | 1 << v)
+}
+
+/// The positions of the set bits, in ascending order.
+fn bits(mask: u64) -> impl Iterator<Item = u32> {
+    (0..64).filter(move |bit| mask & 1 << bit != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+
+    /// A time in October 2026, where the 12th is a Monday.
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    /// The first run after `after`, parsing `expr`.
+    fn next(expr: &str, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        Schedule::parse(expr).unwrap().next_after(after)
+    }
+
+    #[test]
+    fn next_runs() {
+        let friday_evening = at(16, 18, 0);
+        let cases = [
+            // Five and six fields, with names.
+            ("30 8 * * MON-FRI", friday_evening, at(19, 8, 30)),
+            ("0 30 8 * * MON-FRI", friday_evening, at(19, 8, 30)),
+            ("0 30 8 ? * mon-fri", friday_evening, at(19, 8, 30)),
+            ("30 8 * * 1-5", at(19, 8, 30), at(20, 8, 30)),
+            // 7 and 0 are both Sunday.
+            ("0 9 * * 7", friday_evening, at(18, 9, 0)),
+            ("0 9 * * SUN", friday_evening, at(18, 9, 0)),
+            // Steps, from a value and over a range.
+            ("5/10 8 * * *", at(12, 8, 5), at(12, 8, 15)),
+            ("*/15 * * * *", at(12, 8, 50), at(12, 9, 0)),
+            ("0 8-18/5 * * *", at(12, 9, 0), at(12, 13, 0)),
+            // Lists.
+            ("30 8,14 * * TUE,THU", at(13, 9, 0), at(13, 14, 30)),
+            (
+                "0 8 * JAN,NOV *",
+                friday_evening,
+                at(31, 8, 0) + TimeDelta::days(1),
+            ),
+        ];
+        for (expr, after, expected) in cases {
+            assert_eq!(next(expr, after), Some(expected), "{}", expr);
+        }
+    }
+
+    #[test]
+    fn day_fields_match_either_unless_one_is_a_wildcard() {
+        // Both restricted: the 1st of the month or any Monday.
+        assert_eq!(next("0 9 1 * MON", at(12, 10, 0)), Some(at(19, 9, 0)));
+        let november_first = NaiveDate::from_ymd_opt(2026, 11, 1).unwrap();
+        assert_eq!(
+            next("0 9 1 * MON", at(26, 10, 0)),
+            november_first.and_hms_opt(9, 0, 0)
+        );
+        // A wildcard day field: both must match, so only Mondays on odd days, not the
+        // odd 13th nor the even 26th.
+        assert_eq!(next("0 9 */2 * MON", at(12, 10, 0)), Some(at(19, 9, 0)));
+    }
+
+    #[test]
+    fn impossible_dates_never_run() {
+        assert_eq!(next("0 0 31 2 *", at(12, 0, 0)), None);
+        assert_eq!(next("0 0 30 2 *", at(12, 0, 0)), None);
+        let leap = next("0 0 29 2 *", at(12, 0, 0)).unwrap();
+        assert_eq!(leap.date(), NaiveDate::from_ymd_opt(2028, 2, 29).unwrap());
+    }
+
+    #[test]
+    fn runs_on_a_day_in_order() {
+        let schedule = Schedule::parse("0,30 8,14 * * *").unwrap();
+        assert_eq!(
+            schedule.times_on(at(12, 0, 0).date()),
+            [at(12, 8, 0), at(12, 8, 30), at(12, 14, 0), at(12, 14, 30)]
+        );
+    }
+
+    #[test]
+    fn recurrence_rules() {
+        let cases = [
+            (
+                "0 30 8 * * MON-FRI",
+                Some("FREQ=DAILY;BYHOUR=8;BYMINUTE=30;BYDAY=MO,TU,WE,TH,FR"),
+            ),
+            ("30 8,14 * * *", Some("FREQ=DAILY;BYHOUR=8,14;BYMINUTE=30")),
+            (
+                "0 9 1,15 * *",
+                Some("FREQ=DAILY;BYHOUR=9;BYMINUTE=0;BYMONTHDAY=1,15"),
+            ),
+            (
+                "0 9 * 9-12 SUN",
+                Some("FREQ=DAILY;BYHOUR=9;BYMINUTE=0;BYDAY=SU;BYMONTH=9,10,11,12"),
+            ),
+            // Either-or days cannot be expressed.
+            ("0 9 1 * MON", None),
+        ];
+        for (expr, expected) in cases {
+            let rule = Schedule::parse(expr).unwrap().to_rrule();
+            assert_eq!(rule.as_deref(), expected, "{}", expr);
+        }
+    }
+
+    #[test]
+    fn invalid_expressions_are_rejected() {
+        let cases = [
+            "",
+            "30 8 * *",
+            "0 0 30 8 * * *",
+            "60 8 * * *",
+            "30 24 * * *",
+            "30 8 0 * *",
+            "30 8 * 13 *",
+            "30 8 * * 8",
+            "30 8 * * FRI-MON",
+            "*/0 8 * * *",
+            "30 8 * * MON-",
+            "30 8 * * FOO",
+            "a b c d e",
+            // The scheduler only runs at second 0.
+            "15 30 8 * * *",
+        ];
+        for expr in cases {
+            assert!(Schedule::parse(expr).is_err(), "{:?} was accepted", expr);
+        }
+    }
+}
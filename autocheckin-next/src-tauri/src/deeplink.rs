@@ -0,0 +1,140 @@
+use crate::config::Location;
+use crate::geo::{validate_coordinates, CoordSystem};
+use crate::i18n::{t, tf, Msg};
+use chrono::NaiveTime;
+use log::{info, warn};
+use reqwest::Url;
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// URL scheme registered for the app.
+pub const SCHEME: &str = "autocheckin";
+
+/// Host of links that open the task form, as in `autocheckin://add-task?...`.
+const ADD_TASK: &str = "add-task";
+
+/// Event telling the frontend that drafts are waiting in `take_task_drafts`.
+const DRAFTS_EVENT: &str = "task-drafts";
+
+/// Drafts received from links and not yet shown in the task form.
+static PENDING: Mutex<Vec<TaskDraft>> = Mutex::new(Vec::new());
+
+/// A task pre-filled from a link, in the shape of the task form.
+///
+/// Never carries a cookie: links are shared in group chats, and each user logs in
+/// themselves.
+#[derive(Debug, Serialize, Clone)]
+pub struct TaskDraft {
+    /// Name of the task.
+    pub name: String,
+    /// Scheduled time in HH:MM format.
+    pub time: String,
+    /// ID of the class to check in.
+    pub class_id: String,
+    /// Always empty.
+    pub cookie: String,
+    /// Location of the classroom, empty if the link has none.
+    pub location: Location,
+    /// Always `true`.
+    pub enable: bool,
+}
+
+/// Reads an `autocheckin://add-task` link.
+///
+/// Query parameters: `class_id` (required), `name`, `time` (`HH:MM`), `lat`, `lng`,
+/// `acc` and `coord` (`wgs84`, `gcj02` or `bd09`, default `gcj02`). Unknown parameters
+/// are ignored.
+///
+/// # Arguments
+///
+/// * `url` - The opened link.
+///
+/// # Returns
+///
+/// * `Result<TaskDraft, String>` - The draft, or an error message if the link is not an
+///   add-task link or its values are invalid.
+pub fn parse_add_task(url: &Url) -> Result<TaskDraft, String> {
+    if url.scheme() != SCHEME || url.host_str() != Some(ADD_TASK) {
+        return Err(tf(Msg::UnsupportedLink, &[url]));
+    }
+    let param = |key: &str| {
+        url.query_pairs()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    let class_id = param("class_id").ok_or_else(|| t(Msg::LinkNoClassId).to_string())?;
+    let time = match param("time") {
+        Some(time) => NaiveTime::parse_from_str(&time, "%H:%M")
+            .map(|_| time.clone())
+            .map_err(|_| tf(Msg::LinkInvalidTime, &[&time]))?,
+        None => "08:00".to_string(),
+    };
+    let (lat, lng) = (
+        param("lat").unwrap_or_default(),
+        param("lng").unwrap_or_default(),
+    );
+    if !lat.is_empty() || !lng.is_empty() {
+        validate_coordinates(&lat, &lng)?;
+    }
+    let coord_system = match param("coord") {
+        Some(coord) => serde_json::from_value::<CoordSystem>(coord.to_lowercase().into())
+            .map_err(|_| tf(Msg::LinkInvalidCoordSystem, &[&coord]))?,
+        None => CoordSystem::default(),
+    };
+    Ok(TaskDraft {
+        name: param("name").unwrap_or_else(|| class_id.clone()),
+        time,
+        class_id,
+        cookie: String::new(),
+        location: Location {
+            lat,
+            lng,
+            acc: param("acc").unwrap_or_else(|| "10.0".to_string()),
+            coord_system,
+        },
+        enable: true,
+    })
+}
+
+/// Queues the tasks from opened links for the task form and brings the window up.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `urls` - The opened links.
+pub fn handle_urls(app_handle: &AppHandle, urls: Vec<Url>) {
+    let mut received = false;
+    for url in urls {
+        match parse_add_task(&url) {
+            Ok(draft) => {
+                info!("Received task link for class {}", draft.class_id);
+                PENDING.lock().unwrap().push(draft);
+                received = true;
+            }
+            Err(e) => warn!("Ignoring link: {}", e),
+        }
+    }
+    if !received {
+        return;
+    }
+    match app_handle.get_webview_window("main") {
+        Some(window) => {
+            let _ = window.show();
+            let _ = window.unminimize();
+            let _ = window.set_focus();
+            let _ = app_handle.emit(DRAFTS_EVENT, ());
+        }
+        None => warn!("Task link received, but there is no window to show it in"),
+    }
+}
+
+/// Removes and returns the drafts waiting for the task form.
+///
+/// # Returns
+///
+/// * `Vec<TaskDraft>` - The drafts, oldest first.
+pub fn take_drafts() -> Vec<TaskDraft> {
+    std::mem::take(&mut *PENDING.lock().unwrap())
+}
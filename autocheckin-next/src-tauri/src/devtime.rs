@@ -0,0 +1,175 @@
+use crate::i18n::{t, Msg};
+use crate::scheduler::{Clock, SystemClock};
+use chrono::{Local, NaiveDateTime, TimeDelta};
+use log::warn;
+use std::future::Future;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::time::sleep;
+
+/// Command-line flag enabling accelerated time, as `--dev-time=<scale>`.
+const FLAG_PREFIX: &str = "--dev-time=";
+
+/// Environment variable enabling accelerated time, set to the scale.
+const ENV_VAR: &str = "AUTOCHECKIN_DEV_TIME";
+
+/// Fastest supported scale: one simulated day per real minute.
+const MAX_SCALE: f64 = 1440.0;
+
+/// The simulated time, once accelerated time is enabled.
+static DEV_TIME: OnceLock<DevTime> = OnceLock::new();
+
+/// A simulated clock running `scale` times faster than real time from `origin`.
+struct DevTime {
+    /// How many simulated seconds pass per real second.
+    scale: f64,
+    /// The real instant the simulation started.
+    started: Instant,
+    /// The local time the simulation started at.
+    origin: NaiveDateTime,
+    /// Time added by `advance`.
+    offset: Mutex<TimeDelta>,
+}
+
+impl DevTime {
+    /// The current simulated time.
+    fn now(&self) -> NaiveDateTime {
+        let elapsed = TimeDelta::from_std(self.started.elapsed().mul_f64(self.scale))
+            .unwrap_or(TimeDelta::MAX);
+        self.origin + elapsed + *self.offset.lock().unwrap()
+    }
+}
+
+/// Reads the requested time scale from the command line or the environment.
+///
+/// Accelerated time is a development aid: release builds ignore the request.
+///
+/// # Arguments
+///
+/// * `args` - The command-line arguments, without the program name.
+///
+/// # Returns
+///
+/// * `Option<f64>` - The scale, between 1 and 1440, or `None` if not requested, invalid or
+///   in a release build.
+pub fn requested_scale(args: &[String]) -> Option<f64> {
+    let value = args
+        .iter()
+        .find_map(|arg| arg.strip_prefix(FLAG_PREFIX).map(str::to_string))
+        .or_else(|| std::env::var(ENV_VAR).ok())?;
+    if !cfg!(debug_assertions) {
+        warn!("Accelerated time is only available in debug builds; ignoring it");
+        return None;
+    }
+    match value.trim().parse::<f64>() {
+        Ok(scale) if scale.is_finite() && scale >= 1.0 => Some(scale.min(MAX_SCALE)),
+        _ => {
+            warn!(
+                "Invalid time scale {:?}; expected a number of at least 1",
+                value
+            );
+            None
+        }
+    }
+}
+
+/// Starts the simulated clock at the current local time.
+///
+/// # Arguments
+///
+/// * `scale` - Simulated seconds per real second; `1` for a clock that only moves faster
+///   through `advance`.
+///
+/// # Returns
+///
+/// * `DevClock` - The clock to drive the scheduler with.
+pub fn init(scale: f64) -> DevClock {
+    let dev_time = DEV_TIME.get_or_init(|| DevTime {
+        scale,
+        started: Instant::now(),
+        origin: Local::now().naive_local(),
+        offset: Mutex::new(TimeDelta::zero()),
+    });
+    warn!(
+        "Accelerated time enabled: {}x, starting at {}",
+        dev_time.scale,
+        dev_time.origin.format("%Y-%m-%d %H:%M:%S")
+    );
+    DevClock
+}
+
+/// Moves the simulated clock forward.
+///
+/// The scheduler sees the jump like a system resume: it runs the tasks scheduled in the
+/// skipped minutes and re-validates the other sessions.
+///
+/// # Arguments
+///
+/// * `minutes` - How far to move the clock.
+///
+/// # Returns
+///
+/// * `Result<NaiveDateTime, String>` - The new simulated time, or an error message if
+///   accelerated time is off.
+pub fn advance(minutes: u32) -> Result<NaiveDateTime, String> {
+    let dev_time = DEV_TIME
+        .get()
+        .ok_or_else(|| t(Msg::DevTimeDisabled).to_string())?;
+    *dev_time.offset.lock().unwrap() += TimeDelta::minutes(minutes as i64);
+    let now = dev_time.now();
+    warn!(
+        "Advanced simulated time by {} minute(s) to {}",
+        minutes, now
+    );
+    Ok(now)
+}
+
+/// Shortens a delay by the time scale, so waits such as retry backoff and jitter pass at
+/// the simulated pace.
+///
+/// # Arguments
+///
+/// * `duration` - The real-time delay.
+///
+/// # Returns
+///
+/// * `Duration` - The delay divided by the scale; unchanged while accelerated time is off.
+pub fn scaled(duration: Duration) -> Duration {
+    match DEV_TIME.get() {
+        Some(dev_time) => duration.div_f64(dev_time.scale),
+        None => duration,
+    }
+}
+
+/// The scheduler clock of the accelerated-time mode.
+pub struct DevClock;
+
+impl Clock for DevClock {
+    fn now(&self) -> NaiveDateTime {
+        DEV_TIME
+            .get()
+            .map(DevTime::now)
+            .unwrap_or_else(|| Local::now().naive_local())
+    }
+
+    fn sleep(&self, duration: Duration) -> impl Future<Output = ()> + Send {
+        sleep(scaled(duration))
+    }
+
+    fn pause(&self, duration: Duration) {
+        std::thread::sleep(scaled(duration));
+    }
+}
+
+/// The clock runs wait on: the simulated one while accelerated time is on, else the
+/// system clock.
+///
+/// # Returns
+///
+/// * `Arc<dyn Clock>` - The clock.
+pub fn clock() -> Arc<dyn Clock> {
+    match DEV_TIME.get() {
+        Some(_) => Arc::new(DevClock),
+        None => Arc::new(SystemClock),
+    }
+}
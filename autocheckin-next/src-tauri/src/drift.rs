@@ -0,0 +1,234 @@
+use crate::config::{DeviceConfig, DriftClamp, DriftConfig, DriftShape};
+use chrono::{Local, NaiveDate};
+use std::collections::BTreeMap;
+use std::f64::consts::TAU;
+use std::sync::Mutex;
+
+/// Standard deviation of a walk step, as a fraction of the drift radius.
+const STEP_FRACTION: f64 = 0.15;
+
+/// Standard deviation of per-fix GPS noise, as a fraction of the drift radius.
+const NOISE_FRACTION: f64 = 0.05;
+
+/// Standard deviation of the gaussian starting offset, as a fraction of the drift radius.
+const GAUSSIAN_FRACTION: f64 = 0.4;
+
+/// Draws attempted before a resampled offset falls back to the edge of the radius.
+const MAX_RESAMPLES: usize = 32;
+
+/// Median reported accuracy of a phone GPS fix in meters.
+const MEDIAN_ACCURACY_M: f64 = 10.0;
+
+/// Spread of the log-normal accuracy distribution.
+const ACCURACY_SIGMA: f64 = 0.45;
+
+/// Standard deviation of the reported altitude around the configured one, in meters.
+const ALTITUDE_SIGMA_M: f64 = 3.0;
+
+/// Share of fixes that report a speed of exactly zero, as stationary phones usually do.
+const ZERO_SPEED_SHARE: f64 = 0.6;
+
+/// Current walk position of each task, keyed by task ID.
+static WALKS: Mutex<BTreeMap<String, Walk>> = Mutex::new(BTreeMap::new());
+
+/// A simulated GPS fix submitted with a sign.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fix {
+    /// Latitude with six decimal places.
+    pub lat: String,
+    /// Longitude with six decimal places.
+    pub lng: String,
+    /// Reported horizontal accuracy in meters.
+    pub accuracy_m: f64,
+    /// Reported altitude in meters, if the form accepts one.
+    pub altitude_m: Option<f64>,
+    /// Reported speed in meters per second, if the form accepts one.
+    pub speed_mps: Option<f64>,
+    /// Street address of the position, sent as `gps_addr` when known.
+    pub address: Option<String>,
+}
+
+/// A task's position relative to its anchor for one day.
+#[derive(Debug, Clone, Copy)]
+struct Walk {
+    /// The day this walk belongs to; a new day starts a new walk.
+    date: NaiveDate,
+    /// Offset north of the anchor in meters.
+    north_m: f64,
+    /// Offset east of the anchor in meters.
+    east_m: f64,
+}
+
+/// Returns the next drift offset for a task.
+///
+/// The first sign of a day starts at a random point within the radius, spread evenly
+/// over the disc or concentrated around the anchor depending on the configured shape.
+/// Later signs on the same day take a small random step from there, so consecutive
+/// submissions look like a stationary person with GPS noise instead of jumping around
+/// the anchor. The walk never leaves the radius.
+///
+/// # Arguments
+///
+/// * `task_id` - The task being signed.
+/// * `radius_m` - The maximum distance from the anchor in meters.
+/// * `config` - The distribution shape and clamp behavior.
+///
+/// # Returns
+///
+/// * `(f64, f64)` - The offset north and east of the anchor in meters.
+pub fn next_offset(task_id: &str, radius_m: f64, config: &DriftConfig) -> (f64, f64) {
+    let radius_m = radius_m.max(0.0);
+    let today = Local::now().date_naive();
+    let mut walks = WALKS.lock().unwrap();
+
+    let (north_m, east_m) = match walks.get(task_id) {
+        Some(walk) if walk.date == today => {
+            let walk = *walk;
+            constrain(config.clamp, radius_m, || {
+                (
+                    walk.north_m + gaussian() * radius_m * STEP_FRACTION,
+                    walk.east_m + gaussian() * radius_m * STEP_FRACTION,
+                )
+            })
+        }
+        _ => match config.shape {
+            DriftShape::Uniform => {
+                let distance = radius_m * rand::random::<f64>().sqrt();
+                let bearing = rand::random::<f64>() * TAU;
+                (distance * bearing.cos(), distance * bearing.sin())
+            }
+            DriftShape::Gaussian => constrain(config.clamp, radius_m, || {
+                (
+                    gaussian() * radius_m * GAUSSIAN_FRACTION,
+                    gaussian() * radius_m * GAUSSIAN_FRACTION,
+                )
+            }),
+        },
+    };
+    let walk = Walk {
+        date: today,
+        north_m,
+        east_m,
+    };
+    walks.insert(task_id.to_string(), walk);
+
+    constrain(config.clamp, radius_m, || {
+        (
+            walk.north_m + gaussian() * radius_m * NOISE_FRACTION,
+            walk.east_m + gaussian() * radius_m * NOISE_FRACTION,
+        )
+    })
+}
+
+/// Completes a position with plausible phone GPS readings.
+///
+/// Accuracy follows a log-normal distribution around 10 m (3–65 m), altitude varies by a
+/// few meters around the configured value, and speed is mostly zero with occasional small
+/// values, as reported by a phone lying on a desk.
+///
+/// # Arguments
+///
+/// * `device` - Which optional readings the sign form accepts.
+/// * `lat` - The latitude to report.
+/// * `lng` - The longitude to report.
+///
+/// # Returns
+///
+/// * `Fix` - The position with accuracy, altitude and speed.
+pub fn make_fix(device: &DeviceConfig, lat: String, lng: String) -> Fix {
+    let accuracy_m = (MEDIAN_ACCURACY_M.ln() + gaussian() * ACCURACY_SIGMA)
+        .exp()
+        .clamp(3.0, 65.0);
+    let altitude_m = device
+        .altitude_m
+        .map(|base| base + gaussian() * ALTITUDE_SIGMA_M);
+    let speed_mps = device.send_speed.then(|| {
+        if rand::random::<f64>() < ZERO_SPEED_SHARE {
+            0.0
+        } else {
+            (gaussian() * 0.3).abs().min(1.5)
+        }
+    });
+    Fix {
+        lat,
+        lng,
+        accuracy_m,
+        altitude_m,
+        speed_mps,
+        address: None,
+    }
+}
+
+/// Draws an offset and keeps it within the radius according to the clamp behavior.
+fn constrain(mode: DriftClamp, radius_m: f64, mut draw: impl FnMut() -> (f64, f64)) -> (f64, f64) {
+    if mode == DriftClamp::Resample {
+        for _ in 0..MAX_RESAMPLES {
+            let (north_m, east_m) = draw();
+            if north_m.hypot(east_m) <= radius_m {
+                return (north_m, east_m);
+            }
+        }
+    }
+    let (north_m, east_m) = draw();
+    clamp(north_m, east_m, radius_m)
+}
+
+/// Pulls an offset back onto the edge of the radius if it lies outside.
+fn clamp(north_m: f64, east_m: f64, radius_m: f64) -> (f64, f64) {
+    let distance = north_m.hypot(east_m);
+    if distance <= radius_m || distance == 0.0 {
+        (north_m, east_m)
+    } else {
+        let scale = radius_m / distance;
+        (north_m * scale, east_m * scale)
+    }
+}
+
+/// Draws a standard normal sample using the Box-Muller transform.
+fn gaussian() -> f64 {
+    let u1 = 1.0 - rand::random::<f64>();
+    let u2 = rand::random::<f64>();
+    (-2.0 * u1.ln()).sqrt() * (TAU * u2).cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tolerance for floating-point error when scaling onto the edge.
+    const EPSILON_M: f64 = 1e-9;
+
+    #[test]
+    fn offsets_stay_within_the_radius() {
+        let radius_m = 20.0;
+        for shape in [DriftShape::Uniform, DriftShape::Gaussian] {
+            for clamp in [DriftClamp::Edge, DriftClamp::Resample] {
+                let config = DriftConfig { shape, clamp };
+                let task_id = format!("drift-{:?}-{:?}", shape, clamp);
+                for _ in 0..500 {
+                    let (north_m, east_m) = next_offset(&task_id, radius_m, &config);
+                    assert!(
+                        north_m.hypot(east_m) <= radius_m + EPSILON_M,
+                        "{:?}/{:?}: {} m",
+                        shape,
+                        clamp,
+                        north_m.hypot(east_m)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn constrain_keeps_draws_within_the_radius() {
+        for mode in [DriftClamp::Edge, DriftClamp::Resample] {
+            for _ in 0..500 {
+                let (north_m, east_m) =
+                    constrain(mode, 5.0, || (gaussian() * 10.0, gaussian() * 10.0));
+                assert!(north_m.hypot(east_m) <= 5.0 + EPSILON_M, "{:?}", mode);
+            }
+            let (north_m, east_m) = constrain(mode, 5.0, || (30.0, 40.0));
+            assert!((north_m - 3.0).abs() < EPSILON_M && (east_m - 4.0).abs() < EPSILON_M);
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use chrono::Local;
+use serde_json::{json, Value};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+
+/// Channels of the connected event stream clients.
+static SUBSCRIBERS: Mutex<Vec<Sender<Event>>> = Mutex::new(Vec::new());
+
+/// A state change published to event stream clients.
+#[derive(Debug, Clone)]
+pub struct Event {
+    /// The event name, e.g. `run_finished`.
+    pub kind: &'static str,
+    /// The event data, with a `timestamp` field added.
+    pub data: Value,
+}
+
+/// Publishes an event to every subscriber. Does nothing while no one is subscribed.
+///
+/// # Arguments
+///
+/// * `kind` - The event name.
+/// * `data` - A JSON object describing the event.
+pub fn publish(kind: &'static str, mut data: Value) {
+    let mut subscribers = SUBSCRIBERS.lock().unwrap();
+    if subscribers.is_empty() {
+        return;
+    }
+    if let Some(object) = data.as_object_mut() {
+        object.insert("timestamp".to_string(), json!(Local::now().to_rfc3339()));
+    }
+    let event = Event { kind, data };
+    subscribers.retain(|subscriber| subscriber.send(event.clone()).is_ok());
+}
+
+/// Subscribes to all events published from now on.
+///
+/// The subscription ends when the receiver is dropped.
+///
+/// # Returns
+///
+/// * `Receiver<Event>` - The channel the events arrive on.
+pub fn subscribe() -> Receiver<Event> {
+    let (sender, receiver) = mpsc::channel();
+    SUBSCRIBERS.lock().unwrap().push(sender);
+    receiver
+}
@@ -0,0 +1,284 @@
+use crate::history::{compute_attendance, RunRecord, SiteAttendance};
+use crate::i18n::{tf, Msg};
+use chrono::{Duration, Local};
+use rust_xlsxwriter::{Color, Format, FormatBorder, Workbook, Worksheet, XlsxError};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Column headers of the exported history.
+const HEADERS: [&str; 9] = [
+    "Date",
+    "Time",
+    "Task",
+    "Class",
+    "Outcome",
+    "Session",
+    "Message",
+    "Latitude",
+    "Longitude",
+];
+
+/// Longest sheet name Excel accepts.
+const MAX_SHEET_NAME: usize = 31;
+
+/// One exported line: a sign attempt, or a run that made none.
+struct Row {
+    date: String,
+    time: String,
+    task_name: String,
+    class_id: String,
+    outcome: String,
+    sign_id: String,
+    message: String,
+    lat: String,
+    lng: String,
+}
+
+impl Row {
+    /// The cells in `HEADERS` order.
+    fn cells(&self) -> [&str; 9] {
+        [
+            &self.date,
+            &self.time,
+            &self.task_name,
+            &self.class_id,
+            &self.outcome,
+            &self.sign_id,
+            &self.message,
+            &self.lat,
+            &self.lng,
+        ]
+    }
+}
+
+/// Exports the run history as CSV or Excel, chosen by the file extension.
+///
+/// A `.csv` file holds every run in one table. An `.xlsx` file starts with an attendance
+/// summary per class, followed by one sheet per class with its runs.
+///
+/// # Arguments
+///
+/// * `records` - All run records.
+/// * `site` - Attendance synced from the site, used in the Excel summary.
+/// * `path` - The destination file path, ending in `.csv` or `.xlsx`.
+/// * `range_days` - Only export runs from the last N days, or all runs if `None`.
+///
+/// # Returns
+///
+/// * `Result<usize, String>` - The number of exported rows, or an error message if the
+///   extension is not supported or the file cannot be written.
+pub fn export_history(
+    records: &[RunRecord],
+    site: &[SiteAttendance],
+    path: &str,
+    range_days: Option<u32>,
+) -> Result<usize, String> {
+    let since = range_days.map(|d| Local::now() - Duration::days(d as i64));
+    let runs: Vec<&RunRecord> = records
+        .iter()
+        .filter(|r| since.is_none_or(|s| r.started_at >= s))
+        .collect();
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    match extension.as_str() {
+        "csv" => write_csv(&runs, path),
+        "xlsx" => write_xlsx(&runs, records, site, path, range_days).map_err(|e| e.to_string()),
+        _ => Err(tf(Msg::UnsupportedExportFormat, &[&extension])),
+    }
+}
+
+/// Flattens runs into rows, one per sign attempt.
+fn rows(runs: &[&RunRecord]) -> Vec<Row> {
+    let mut rows = Vec::new();
+    for run in runs {
+        let outcome = serde_json::to_value(run.outcome)
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default();
+        let row = |sign_id: &str, message: &str, lat: &str, lng: &str| Row {
+            date: run.started_at.format("%Y-%m-%d").to_string(),
+            time: run.started_at.format("%H:%M:%S").to_string(),
+            task_name: run.task_name.clone(),
+            class_id: run.class_id.clone(),
+            outcome: outcome.clone(),
+            sign_id: sign_id.to_string(),
+            message: message.to_string(),
+            lat: lat.to_string(),
+            lng: lng.to_string(),
+        };
+        if run.signs.is_empty() {
+            rows.push(row("", &run.message, "", ""));
+        }
+        for sign in &run.signs {
+            rows.push(row(&sign.sign_id, &sign.message, &sign.lat, &sign.lng));
+        }
+    }
+    rows
+}
+
+/// Writes all runs to one CSV table, with a BOM so Excel reads it as UTF-8.
+fn write_csv(runs: &[&RunRecord], path: &str) -> Result<usize, String> {
+    let rows = rows(runs);
+    let line = |cells: &[&str]| {
+        cells
+            .iter()
+            .map(|cell| {
+                if cell.contains([',', '"', '\n', '\r']) {
+                    format!("\"{}\"", cell.replace('"', "\"\""))
+                } else {
+                    cell.to_string()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    let mut content = String::from("\u{feff}");
+    content.push_str(&line(&HEADERS));
+    content.push_str("\r\n");
+    for row in &rows {
+        content.push_str(&line(&row.cells()));
+        content.push_str("\r\n");
+    }
+    fs::write(path, content).map_err(|e| e.to_string())?;
+    Ok(rows.len())
+}
+
+/// Writes the summary sheet and one sheet per class.
+fn write_xlsx(
+    runs: &[&RunRecord],
+    records: &[RunRecord],
+    site: &[SiteAttendance],
+    path: &str,
+    range_days: Option<u32>,
+) -> Result<usize, XlsxError> {
+    let header = Format::new()
+        .set_bold()
+        .set_background_color(Color::RGB(0xD9E1F2))
+        .set_border_bottom(FormatBorder::Thin);
+    let percent = Format::new().set_num_format("0.0%");
+    let success = Format::new().set_font_color(Color::RGB(0x006100));
+    let failure = Format::new().set_font_color(Color::RGB(0x9C0006));
+
+    let mut classes: BTreeMap<&str, Vec<&RunRecord>> = BTreeMap::new();
+    for run in runs {
+        classes.entry(run.class_id.as_str()).or_default().push(run);
+    }
+    for record in site {
+        classes.entry(record.class_id.as_str()).or_default();
+    }
+
+    let mut workbook = Workbook::new();
+    let summary = workbook.add_worksheet();
+    summary.set_name("Summary")?;
+    let columns = [
+        "Class",
+        "Task",
+        "Present",
+        "Late",
+        "Absent",
+        "Attendance",
+        "Runs",
+    ];
+    write_header(summary, &columns, &header)?;
+    for (i, (class_id, class_runs)) in classes.iter().enumerate() {
+        let report = compute_attendance(records, site, class_id, range_days);
+        let row = i as u32 + 1;
+        summary.write_string(row, 0, *class_id)?;
+        summary.write_string(row, 1, course_name(class_runs))?;
+        summary.write_number(row, 2, report.present as f64)?;
+        summary.write_number(row, 3, report.late as f64)?;
+        summary.write_number(row, 4, report.absent as f64)?;
+        if let Some(rate) = report.attendance_rate {
+            summary.write_number_with_format(row, 5, rate, &percent)?;
+        }
+        summary.write_number(row, 6, class_runs.len() as f64)?;
+    }
+    summary.autofit();
+
+    let mut exported = 0;
+    let mut used_names = vec!["summary".to_string()];
+    for (class_id, class_runs) in &classes {
+        let rows = rows(class_runs);
+        let sheet = workbook.add_worksheet();
+        sheet.set_name(sheet_name(
+            class_id,
+            course_name(class_runs),
+            &mut used_names,
+        ))?;
+        write_header(sheet, &HEADERS, &header)?;
+        for (i, row) in rows.iter().enumerate() {
+            let index = i as u32 + 1;
+            for (col, cell) in row.cells().iter().enumerate() {
+                sheet.write_string(index, col as u16, *cell)?;
+            }
+            let format = if row.outcome == "success" {
+                &success
+            } else {
+                &failure
+            };
+            if row.outcome != "no_session" && row.outcome != "offline" {
+                sheet.write_string_with_format(index, 4, &row.outcome, format)?;
+            }
+        }
+        if !rows.is_empty() {
+            sheet.autofilter(0, 0, rows.len() as u32, HEADERS.len() as u16 - 1)?;
+        }
+        sheet.autofit_to_max_width(400);
+        exported += rows.len();
+    }
+
+    workbook.save(path)?;
+    Ok(exported)
+}
+
+/// Writes a bold header row and keeps it visible while scrolling.
+fn write_header(sheet: &mut Worksheet, columns: &[&str], format: &Format) -> Result<(), XlsxError> {
+    for (col, title) in columns.iter().enumerate() {
+        sheet.write_string_with_format(0, col as u16, *title, format)?;
+    }
+    sheet.set_freeze_panes(1, 0)?;
+    Ok(())
+}
+
+/// The task name of a class's most recent run.
+fn course_name<'a>(runs: &[&'a RunRecord]) -> &'a str {
+    runs.last()
+        .map(|r| r.task_name.as_str())
+        .unwrap_or_default()
+}
+
+/// A unique sheet name for a class, from the course name and class ID.
+///
+/// Characters Excel forbids are replaced and the name is shortened to 31 characters.
+fn sheet_name(class_id: &str, course: &str, used: &mut Vec<String>) -> String {
+    let base = if course.is_empty() {
+        class_id.to_string()
+    } else {
+        format!("{} {}", course, class_id)
+    };
+    let base: String = base
+        .chars()
+        .map(|c| if "[]:*?/\\".contains(c) { '_' } else { c })
+        .collect();
+    let base = match base.trim_matches('\'') {
+        "" => "Class".to_string(),
+        trimmed => trimmed.to_string(),
+    };
+    let mut name: String = base.chars().take(MAX_SHEET_NAME).collect();
+    let mut suffix = 2;
+    while used.contains(&name.to_lowercase()) {
+        let tail = format!(" ({})", suffix);
+        name = base
+            .chars()
+            .take(MAX_SHEET_NAME - tail.len())
+            .collect::<String>()
+            + tail.as_str();
+        suffix += 1;
+    }
+    used.push(name.to_lowercase());
+    name
+}
@@ -0,0 +1,333 @@
+use crate::i18n::{t, Msg};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+/// Semi-major axis of the Krasovsky 1940 ellipsoid used by GCJ-02.
+const KRASOVSKY_A: f64 = 6378245.0;
+
+/// First eccentricity squared of the Krasovsky 1940 ellipsoid.
+const KRASOVSKY_EE: f64 = 0.006_693_421_622_965_943;
+
+/// Scaled pi used by the BD-09 offset.
+const BD_X_PI: f64 = PI * 3000.0 / 180.0;
+
+/// Length of one degree of latitude in meters.
+const METERS_PER_DEGREE: f64 = 111_320.0;
+
+/// Geodetic coordinate systems in use by Chinese map services.
+///
+/// Defaults to GCJ-02, the system the check-in site and WeChat use, so locations saved
+/// before their system was recorded keep being sent unchanged.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CoordSystem {
+    /// GPS coordinates, used by Google Maps and OpenStreetMap.
+    Wgs84,
+    /// The offset system used by Amap, Tencent Maps and WeChat.
+    #[default]
+    Gcj02,
+    /// Baidu's additional offset on top of GCJ-02.
+    Bd09,
+}
+
+impl CoordSystem {
+    /// Converts a point from this system to another.
+    ///
+    /// # Arguments
+    ///
+    /// * `to` - The target coordinate system.
+    /// * `lat` - Latitude in this system.
+    /// * `lng` - Longitude in this system.
+    ///
+    /// # Returns
+    ///
+    /// * `(f64, f64)` - Latitude and longitude in the target system.
+    pub fn convert(self, to: CoordSystem, lat: f64, lng: f64) -> (f64, f64) {
+        if self == to {
+            return (lat, lng);
+        }
+        let (g_lat, g_lng) = match self {
+            CoordSystem::Wgs84 => wgs84_to_gcj02(lat, lng),
+            CoordSystem::Gcj02 => (lat, lng),
+            CoordSystem::Bd09 => bd09_to_gcj02(lat, lng),
+        };
+        match to {
+            CoordSystem::Wgs84 => gcj02_to_wgs84(g_lat, g_lng),
+            CoordSystem::Gcj02 => (g_lat, g_lng),
+            CoordSystem::Bd09 => gcj02_to_bd09(g_lat, g_lng),
+        }
+    }
+}
+
+/// Parses and sanity-checks a latitude/longitude pair.
+///
+/// Rejects values that are not numbers or out of range, the unset point 0,0, and
+/// pairs that look swapped (e.g. `116.4, 39.9` for Beijing).
+///
+/// # Arguments
+///
+/// * `lat` - The latitude as entered.
+/// * `lng` - The longitude as entered.
+///
+/// # Returns
+///
+/// * `Result<(f64, f64), String>` - The parsed latitude and longitude, or an actionable
+///   error message.
+pub fn validate_coordinates(lat: &str, lng: &str) -> Result<(f64, f64), String> {
+    let lat_val: f64 = lat
+        .trim()
+        .parse()
+        .map_err(|_| t(Msg::LatitudeNotNumber).to_string())?;
+    let lng_val: f64 = lng
+        .trim()
+        .parse()
+        .map_err(|_| t(Msg::LongitudeNotNumber).to_string())?;
+    if !lat_val.is_finite() || !lng_val.is_finite() {
+        return Err(t(Msg::CoordinatesOutOfRange).to_string());
+    }
+
+    let looks_swapped = (lat_val.abs() > 90.0 && lng_val.abs() <= 90.0)
+        || (out_of_china(lat_val, lng_val) && !out_of_china(lng_val, lat_val));
+    if looks_swapped {
+        return Err(t(Msg::CoordinatesSwapped).to_string());
+    }
+    if lat_val.abs() > 90.0 || lng_val.abs() > 180.0 {
+        return Err(t(Msg::CoordinatesOutOfRange).to_string());
+    }
+    if lat_val.abs() < 1e-6 && lng_val.abs() < 1e-6 {
+        return Err(t(Msg::CoordinatesUnset).to_string());
+    }
+    Ok((lat_val, lng_val))
+}
+
+/// Computes the great-circle distance between two points with the haversine formula.
+///
+/// # Arguments
+///
+/// * `lat1`, `lng1` - The first point.
+/// * `lat2`, `lng2` - The second point.
+///
+/// # Returns
+///
+/// * `f64` - The distance in meters.
+pub fn distance_m(lat1: f64, lng1: f64, lat2: f64, lng2: f64) -> f64 {
+    const EARTH_RADIUS_M: f64 = 6_371_000.0;
+    let d_lat = (lat2 - lat1).to_radians();
+    let d_lng = (lng2 - lng1).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2)
+        + lat1.to_radians().cos() * lat2.to_radians().cos() * (d_lng / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_M * a.sqrt().asin()
+}
+
+/// Moves a point by a distance in meters.
+///
+/// Uses a local flat-earth approximation, which is accurate for the short distances of
+/// GPS drift. Longitude degrees shrink with the cosine of the latitude.
+///
+/// # Arguments
+///
+/// * `lat` - Latitude of the starting point.
+/// * `lng` - Longitude of the starting point.
+/// * `north_m` - Distance to move north (negative for south).
+/// * `east_m` - Distance to move east (negative for west).
+///
+/// # Returns
+///
+/// * `(f64, f64)` - Latitude and longitude of the moved point.
+pub fn offset_by_meters(lat: f64, lng: f64, north_m: f64, east_m: f64) -> (f64, f64) {
+    let d_lat = north_m / METERS_PER_DEGREE;
+    let d_lng = east_m / (METERS_PER_DEGREE * lat.to_radians().cos().max(1e-6));
+    (lat + d_lat, lng + d_lng)
+}
+
+/// Returns whether a point lies outside mainland China, where GCJ-02 equals WGS-84.
+fn out_of_china(lat: f64, lng: f64) -> bool {
+    !(72.004..=137.8347).contains(&lng) || !(0.8293..=55.8271).contains(&lat)
+}
+
+/// The GCJ-02 latitude offset polynomial.
+fn transform_lat(x: f64, y: f64) -> f64 {
+    let mut ret = -100.0 + 2.0 * x + 3.0 * y + 0.2 * y * y + 0.1 * x * y + 0.2 * x.abs().sqrt();
+    ret += (20.0 * (6.0 * x * PI).sin() + 20.0 * (2.0 * x * PI).sin()) * 2.0 / 3.0;
+    ret += (20.0 * (y * PI).sin() + 40.0 * (y / 3.0 * PI).sin()) * 2.0 / 3.0;
+    ret += (160.0 * (y / 12.0 * PI).sin() + 320.0 * (y * PI / 30.0).sin()) * 2.0 / 3.0;
+    ret
+}
+
+/// The GCJ-02 longitude offset polynomial.
+fn transform_lng(x: f64, y: f64) -> f64 {
+    let mut ret = 300.0 + x + 2.0 * y + 0.1 * x * x + 0.1 * x * y + 0.1 * x.abs().sqrt();
+    ret += (20.0 * (6.0 * x * PI).sin() + 20.0 * (2.0 * x * PI).sin()) * 2.0 / 3.0;
+    ret += (20.0 * (x * PI).sin() + 40.0 * (x / 3.0 * PI).sin()) * 2.0 / 3.0;
+    ret += (150.0 * (x / 12.0 * PI).sin() + 300.0 * (x / 30.0 * PI).sin()) * 2.0 / 3.0;
+    ret
+}
+
+/// Converts WGS-84 coordinates (GPS, OpenStreetMap) to GCJ-02 (Amap, Tencent).
+///
+/// # Arguments
+///
+/// * `lat` - WGS-84 latitude.
+/// * `lng` - WGS-84 longitude.
+///
+/// # Returns
+///
+/// * `(f64, f64)` - GCJ-02 latitude and longitude.
+pub fn wgs84_to_gcj02(lat: f64, lng: f64) -> (f64, f64) {
+    if out_of_china(lat, lng) {
+        return (lat, lng);
+    }
+    let rad_lat = lat / 180.0 * PI;
+    let magic = 1.0 - KRASOVSKY_EE * rad_lat.sin() * rad_lat.sin();
+    let sqrt_magic = magic.sqrt();
+    let d_lat = transform_lat(lng - 105.0, lat - 35.0) * 180.0
+        / ((KRASOVSKY_A * (1.0 - KRASOVSKY_EE)) / (magic * sqrt_magic) * PI);
+    let d_lng = transform_lng(lng - 105.0, lat - 35.0) * 180.0
+        / (KRASOVSKY_A / sqrt_magic * rad_lat.cos() * PI);
+    (lat + d_lat, lng + d_lng)
+}
+
+/// Converts GCJ-02 coordinates to WGS-84.
+///
+/// GCJ-02 has no closed-form inverse, so the offset is removed iteratively until the
+/// round trip is accurate to well under a meter.
+///
+/// # Arguments
+///
+/// * `lat` - GCJ-02 latitude.
+/// * `lng` - GCJ-02 longitude.
+///
+/// # Returns
+///
+/// * `(f64, f64)` - WGS-84 latitude and longitude.
+pub fn gcj02_to_wgs84(lat: f64, lng: f64) -> (f64, f64) {
+    let (mut w_lat, mut w_lng) = (lat, lng);
+    for _ in 0..10 {
+        let (g_lat, g_lng) = wgs84_to_gcj02(w_lat, w_lng);
+        let (d_lat, d_lng) = (lat - g_lat, lng - g_lng);
+        w_lat += d_lat;
+        w_lng += d_lng;
+        if d_lat.abs() < 1e-9 && d_lng.abs() < 1e-9 {
+            break;
+        }
+    }
+    (w_lat, w_lng)
+}
+
+/// Converts BD-09 coordinates (Baidu) to GCJ-02.
+///
+/// # Arguments
+///
+/// * `lat` - BD-09 latitude.
+/// * `lng` - BD-09 longitude.
+///
+/// # Returns
+///
+/// * `(f64, f64)` - GCJ-02 latitude and longitude.
+pub fn bd09_to_gcj02(lat: f64, lng: f64) -> (f64, f64) {
+    let x = lng - 0.0065;
+    let y = lat - 0.006;
+    let z = (x * x + y * y).sqrt() - 0.00002 * (y * BD_X_PI).sin();
+    let theta = y.atan2(x) - 0.000003 * (x * BD_X_PI).cos();
+    (z * theta.sin(), z * theta.cos())
+}
+
+/// Converts GCJ-02 coordinates to BD-09 (Baidu).
+///
+/// # Arguments
+///
+/// * `lat` - GCJ-02 latitude.
+/// * `lng` - GCJ-02 longitude.
+///
+/// # Returns
+///
+/// * `(f64, f64)` - BD-09 latitude and longitude.
+pub fn gcj02_to_bd09(lat: f64, lng: f64) -> (f64, f64) {
+    let z = (lng * lng + lat * lat).sqrt() + 0.00002 * (lat * BD_X_PI).sin();
+    let theta = lat.atan2(lng) + 0.000003 * (lng * BD_X_PI).cos();
+    (z * theta.sin() + 0.006, z * theta.cos() + 0.0065)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A point in central Beijing, as GPS reports it.
+    const BEIJING: (f64, f64) = (39.915, 116.404);
+
+    #[test]
+    fn wgs84_converts_to_the_published_gcj02_point() {
+        let (lat, lng) = wgs84_to_gcj02(BEIJING.0, BEIJING.1);
+        assert!((lat - 39.916_404_281_501_64).abs() < 1e-6, "{}", lat);
+        assert!((lng - 116.410_244_499_169_38).abs() < 1e-6, "{}", lng);
+    }
+
+    #[test]
+    fn gcj02_to_wgs84_round_trips_within_a_meter() {
+        let (g_lat, g_lng) = wgs84_to_gcj02(BEIJING.0, BEIJING.1);
+        let (lat, lng) = gcj02_to_wgs84(g_lat, g_lng);
+        assert!(distance_m(lat, lng, BEIJING.0, BEIJING.1) < 1.0);
+    }
+
+    #[test]
+    fn bd09_round_trips_through_gcj02() {
+        let (b_lat, b_lng) = gcj02_to_bd09(BEIJING.0, BEIJING.1);
+        assert!(distance_m(b_lat, b_lng, BEIJING.0, BEIJING.1) > 100.0);
+        let (lat, lng) = bd09_to_gcj02(b_lat, b_lng);
+        assert!(distance_m(lat, lng, BEIJING.0, BEIJING.1) < 1.0);
+
+        let (b_lat, b_lng) = CoordSystem::Wgs84.convert(CoordSystem::Bd09, BEIJING.0, BEIJING.1);
+        let (lat, lng) = CoordSystem::Bd09.convert(CoordSystem::Wgs84, b_lat, b_lng);
+        assert!(distance_m(lat, lng, BEIJING.0, BEIJING.1) < 1.0);
+    }
+
+    #[test]
+    fn points_outside_china_are_not_offset() {
+        let london = (51.5074, -0.1278);
+        assert_eq!(wgs84_to_gcj02(london.0, london.1), london);
+        assert_eq!(gcj02_to_wgs84(london.0, london.1), london);
+        assert_eq!(
+            CoordSystem::Wgs84.convert(CoordSystem::Gcj02, london.0, london.1),
+            london
+        );
+    }
+
+    #[test]
+    fn valid_coordinates_are_parsed() {
+        assert_eq!(
+            validate_coordinates(" 39.908823", "116.397470 "),
+            Ok((39.908823, 116.39747))
+        );
+    }
+
+    #[test]
+    fn swapped_coordinates_are_rejected() {
+        let swapped = Err(t(Msg::CoordinatesSwapped).to_string());
+        assert_eq!(validate_coordinates("116.397470", "39.908823"), swapped);
+        assert_eq!(validate_coordinates("121.47", "31.23"), swapped);
+    }
+
+    #[test]
+    fn unset_point_is_rejected() {
+        assert_eq!(
+            validate_coordinates("0", "0"),
+            Err(t(Msg::CoordinatesUnset).to_string())
+        );
+    }
+
+    #[test]
+    fn out_of_range_and_non_numeric_values_are_rejected() {
+        let out_of_range = Err(t(Msg::CoordinatesOutOfRange).to_string());
+        assert_eq!(validate_coordinates("95", "120"), out_of_range);
+        assert_eq!(validate_coordinates("45", "200"), out_of_range);
+        assert_eq!(validate_coordinates("NaN", "116.4"), out_of_range);
+        assert_eq!(
+            validate_coordinates("north", "116.4"),
+            Err(t(Msg::LatitudeNotNumber).to_string())
+        );
+        assert_eq!(
+            validate_coordinates("39.9", ""),
+            Err(t(Msg::LongitudeNotNumber).to_string())
+        );
+    }
+}
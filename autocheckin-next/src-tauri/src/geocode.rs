@@ -0,0 +1,413 @@
+use crate::config::{GeocodingConfig, GeocodingProvider};
+use crate::geo::{gcj02_to_wgs84, CoordSystem};
+use crate::httplog;
+use crate::i18n::{t, tf, Msg};
+use crate::tls;
+use log::{debug, warn};
+use reqwest::blocking::Client;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+/// Maximum number of candidates returned by a lookup.
+const MAX_RESULTS: usize = 10;
+
+/// A place matching a geocoding query, in WGS-84 like the location picker.
+#[derive(Debug, Serialize, Clone)]
+pub struct GeocodeCandidate {
+    /// Name of the place.
+    pub name: String,
+    /// Full address, if the provider returned one.
+    pub address: Option<String>,
+    /// Latitude with six decimal places.
+    pub lat: String,
+    /// Longitude with six decimal places.
+    pub lng: String,
+}
+
+/// Decimal places coordinates are rounded to for cache keys (about 11 m).
+const CACHE_PRECISION: usize = 4;
+
+/// File the reverse-geocoding cache is persisted to, set once at startup.
+static CACHE_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// Cached reverse-geocoding results, loaded from disk on first use.
+static CACHE: Mutex<Option<HashMap<String, String>>> = Mutex::new(None);
+
+/// Accuracy assumed for a location derived from the public IP, in meters.
+const IP_ACCURACY_M: f64 = 5000.0;
+
+/// An approximate location derived from the machine's public IP address.
+#[derive(Debug, Serialize, Clone)]
+pub struct IpLocation {
+    /// Latitude with six decimal places, in WGS-84.
+    pub lat: String,
+    /// Longitude with six decimal places, in WGS-84.
+    pub lng: String,
+    /// City and region, if known.
+    pub place: Option<String>,
+    /// Rough accuracy in meters.
+    pub accuracy_m: f64,
+    /// Always `true`: IP locations are only good to the city level and need fine-tuning.
+    pub low_accuracy: bool,
+}
+
+/// Estimates the machine's location from its public IP address.
+///
+/// # Returns
+///
+/// * `Result<IpLocation, String>` - A city-level location, or an error message.
+pub fn locate_by_ip() -> Result<IpLocation, String> {
+    let client = tls::client_builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let body = fetch_json(
+        &client,
+        "http://ip-api.com/json/",
+        &[
+            ("lang", "zh-CN"),
+            ("fields", "status,message,lat,lon,city,regionName"),
+        ],
+    )?;
+    if body["status"].as_str() != Some("success") {
+        return Err(tf(
+            Msg::IpLocationFailed,
+            &[&body["message"].as_str().unwrap_or_default()],
+        ));
+    }
+    let lat = body["lat"]
+        .as_f64()
+        .ok_or_else(|| t(Msg::IpLocationIncomplete).to_string())?;
+    let lng = body["lon"]
+        .as_f64()
+        .ok_or_else(|| t(Msg::IpLocationIncomplete).to_string())?;
+    let place = [body["city"].as_str(), body["regionName"].as_str()]
+        .into_iter()
+        .flatten()
+        .filter(|p| !p.is_empty())
+        .collect::<Vec<_>>()
+        .join(", ");
+    Ok(IpLocation {
+        lat: format!("{:.6}", lat),
+        lng: format!("{:.6}", lng),
+        place: (!place.is_empty()).then_some(place),
+        accuracy_m: IP_ACCURACY_M,
+        low_accuracy: true,
+    })
+}
+
+/// Looks up candidate coordinates for an address or place name.
+///
+/// # Arguments
+///
+/// * `config` - The geocoding provider settings.
+/// * `query` - The address or place name (e.g. "XX大学第三教学楼").
+///
+/// # Returns
+///
+/// * `Result<Vec<GeocodeCandidate>, String>` - Matching places, best first, or an error message.
+pub fn geocode_address(
+    config: &GeocodingConfig,
+    query: &str,
+) -> Result<Vec<GeocodeCandidate>, String> {
+    if config.provider != GeocodingProvider::Nominatim && config.api_key.is_empty() {
+        return Err(t(Msg::GeocodingKeyMissing).to_string());
+    }
+    let client = tls::client_builder()
+        .user_agent(concat!("AutoCheckin-Next/", env!("CARGO_PKG_VERSION")))
+        .timeout(Duration::from_secs(15))
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let candidates = match config.provider {
+        GeocodingProvider::Nominatim => {
+            let body = fetch_json(
+                &client,
+                "https://nominatim.openstreetmap.org/search",
+                &[
+                    ("q", query),
+                    ("format", "jsonv2"),
+                    ("limit", &MAX_RESULTS.to_string()),
+                    ("accept-language", "zh-CN,en"),
+                ],
+            )?;
+            body.as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|place| {
+                    let address = place["display_name"].as_str().map(str::to_string);
+                    let name = place["name"]
+                        .as_str()
+                        .filter(|n| !n.is_empty())
+                        .map(str::to_string)
+                        .or_else(|| address.clone())?;
+                    Some(candidate(
+                        name,
+                        address,
+                        place["lat"].as_str()?.parse().ok()?,
+                        place["lon"].as_str()?.parse().ok()?,
+                        false,
+                    ))
+                })
+                .collect()
+        }
+        GeocodingProvider::Amap => {
+            let body = fetch_json(
+                &client,
+                "https://restapi.amap.com/v3/place/text",
+                &[
+                    ("keywords", query),
+                    ("offset", &MAX_RESULTS.to_string()),
+                    ("key", &config.api_key),
+                ],
+            )?;
+            if body["status"].as_str() != Some("1") {
+                return Err(format!(
+                    "Amap: {}",
+                    body["info"].as_str().unwrap_or("error")
+                ));
+            }
+            body["pois"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|poi| {
+                    let (lng, lat) = poi["location"].as_str()?.split_once(',')?;
+                    Some(candidate(
+                        poi["name"].as_str()?.to_string(),
+                        poi["address"].as_str().map(str::to_string),
+                        lat.parse().ok()?,
+                        lng.parse().ok()?,
+                        true,
+                    ))
+                })
+                .collect()
+        }
+        GeocodingProvider::Tencent => {
+            let body = fetch_json(
+                &client,
+                "https://apis.map.qq.com/ws/place/v1/suggestion",
+                &[
+                    ("keyword", query),
+                    ("page_size", &MAX_RESULTS.to_string()),
+                    ("key", &config.api_key),
+                ],
+            )?;
+            if body["status"].as_i64() != Some(0) {
+                return Err(format!(
+                    "Tencent: {}",
+                    body["message"].as_str().unwrap_or("error")
+                ));
+            }
+            body["data"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|place| {
+                    Some(candidate(
+                        place["title"].as_str()?.to_string(),
+                        place["address"].as_str().map(str::to_string),
+                        place["location"]["lat"].as_f64()?,
+                        place["location"]["lng"].as_f64()?,
+                        true,
+                    ))
+                })
+                .collect()
+        }
+    };
+    Ok(candidates)
+}
+
+/// Sends a GET request with query parameters and parses the JSON response.
+fn fetch_json(client: &Client, url: &str, params: &[(&str, &str)]) -> Result<Value, String> {
+    httplog::json(
+        httplog::send(client.get(url).query(params))
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| e.to_string())?,
+    )
+}
+
+/// Builds a candidate, converting GCJ-02 provider coordinates to WGS-84.
+fn candidate(
+    name: String,
+    address: Option<String>,
+    lat: f64,
+    lng: f64,
+    gcj02: bool,
+) -> GeocodeCandidate {
+    let (lat, lng) = if gcj02 {
+        gcj02_to_wgs84(lat, lng)
+    } else {
+        (lat, lng)
+    };
+    GeocodeCandidate {
+        name,
+        address: address.filter(|a| !a.is_empty()),
+        lat: format!("{:.6}", lat),
+        lng: format!("{:.6}", lng),
+    }
+}
+
+/// Sets the directory where the reverse-geocoding cache is persisted.
+///
+/// # Arguments
+///
+/// * `data_dir` - The app data directory.
+pub fn set_cache_dir(data_dir: PathBuf) {
+    let _ = CACHE_PATH.set(data_dir.join("geocode_cache.json"));
+}
+
+/// Looks up the address of a point, using the persistent cache when possible.
+///
+/// Results are cached per provider, keyed by coordinates rounded to about 11 m, so
+/// repeated signs at the same classroom do not call the provider again.
+///
+/// # Arguments
+///
+/// * `config` - The geocoding provider settings.
+/// * `lat` - Latitude of the point.
+/// * `lng` - Longitude of the point.
+/// * `system` - The coordinate system of `lat`/`lng`.
+///
+/// # Returns
+///
+/// * `Result<String, String>` - The formatted address, or an error message.
+pub fn reverse_geocode(
+    config: &GeocodingConfig,
+    lat: f64,
+    lng: f64,
+    system: CoordSystem,
+) -> Result<String, String> {
+    let key = format!(
+        "{:?}:{:.prec$},{:.prec$}",
+        config.provider,
+        lat,
+        lng,
+        prec = CACHE_PRECISION
+    );
+    {
+        let mut cache = CACHE.lock().unwrap();
+        if let Some(address) = cache.get_or_insert_with(read_cache).get(&key) {
+            debug!("Reverse geocode cache hit for {}", key);
+            return Ok(address.clone());
+        }
+    }
+
+    let address = fetch_address(config, lat, lng, system)?;
+    let mut cache = CACHE.lock().unwrap();
+    let cache = cache.get_or_insert_with(read_cache);
+    cache.insert(key, address.clone());
+    if let Some(path) = CACHE_PATH.get() {
+        let written = serde_json::to_string(cache)
+            .map_err(|e| e.to_string())
+            .and_then(|json| fs::write(path, json).map_err(|e| e.to_string()));
+        if let Err(e) = written {
+            warn!("Failed to save geocode cache: {}", e);
+        }
+    }
+    Ok(address)
+}
+
+/// Empties the reverse-geocoding cache in memory and on disk.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Ok if successful, or an error message if the file cannot be
+///   deleted.
+pub fn clear_cache() -> Result<(), String> {
+    let mut cache = CACHE.lock().unwrap();
+    *cache = Some(HashMap::new());
+    match CACHE_PATH.get() {
+        Some(path) if path.exists() => fs::remove_file(path).map_err(|e| e.to_string()),
+        _ => Ok(()),
+    }
+}
+
+/// Loads the persisted cache, or an empty one if it does not exist yet.
+fn read_cache() -> HashMap<String, String> {
+    CACHE_PATH
+        .get()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Queries the configured provider for the address of a point.
+fn fetch_address(
+    config: &GeocodingConfig,
+    lat: f64,
+    lng: f64,
+    system: CoordSystem,
+) -> Result<String, String> {
+    if config.provider != GeocodingProvider::Nominatim && config.api_key.is_empty() {
+        return Err(t(Msg::GeocodingKeyMissing).to_string());
+    }
+    let client = tls::client_builder()
+        .user_agent(concat!("AutoCheckin-Next/", env!("CARGO_PKG_VERSION")))
+        .timeout(Duration::from_secs(15))
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let address = match config.provider {
+        GeocodingProvider::Nominatim => {
+            let (lat, lng) = system.convert(CoordSystem::Wgs84, lat, lng);
+            let body = fetch_json(
+                &client,
+                "https://nominatim.openstreetmap.org/reverse",
+                &[
+                    ("lat", &lat.to_string()),
+                    ("lon", &lng.to_string()),
+                    ("format", "jsonv2"),
+                    ("accept-language", "zh-CN,en"),
+                ],
+            )?;
+            body["display_name"].as_str().map(str::to_string)
+        }
+        GeocodingProvider::Amap => {
+            let (lat, lng) = system.convert(CoordSystem::Gcj02, lat, lng);
+            let body = fetch_json(
+                &client,
+                "https://restapi.amap.com/v3/geocode/regeo",
+                &[
+                    ("location", &format!("{:.6},{:.6}", lng, lat)),
+                    ("key", &config.api_key),
+                ],
+            )?;
+            if body["status"].as_str() != Some("1") {
+                return Err(format!(
+                    "Amap: {}",
+                    body["info"].as_str().unwrap_or("error")
+                ));
+            }
+            body["regeocode"]["formatted_address"]
+                .as_str()
+                .map(str::to_string)
+        }
+        GeocodingProvider::Tencent => {
+            let (lat, lng) = system.convert(CoordSystem::Gcj02, lat, lng);
+            let body = fetch_json(
+                &client,
+                "https://apis.map.qq.com/ws/geocoder/v1/",
+                &[
+                    ("location", &format!("{:.6},{:.6}", lat, lng)),
+                    ("key", &config.api_key),
+                ],
+            )?;
+            if body["status"].as_i64() != Some(0) {
+                return Err(format!(
+                    "Tencent: {}",
+                    body["message"].as_str().unwrap_or("error")
+                ));
+            }
+            body["result"]["address"].as_str().map(str::to_string)
+        }
+    };
+    address
+        .filter(|a| !a.is_empty())
+        .ok_or_else(|| "No address found".to_string())
+}
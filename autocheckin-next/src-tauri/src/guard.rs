@@ -0,0 +1,163 @@
+use crate::i18n::{self, Msg};
+use log::{info, warn};
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+/// How long a confirmation token stays valid.
+const TOKEN_TTL: Duration = Duration::from_secs(60);
+
+/// The window rate limits are counted over.
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Calls per minute allowed for commands that expose cookies or write files.
+///
+/// Fixed rather than configurable, so a page that can call `update_config` cannot lift
+/// them. Login polling runs every two seconds and the task list reloads the config after
+/// every edit, so the ceilings leave room for normal use.
+const RATE_LIMITS: [(&str, usize); 6] = [
+    ("get_config", 60),
+    ("check_login_status", 60),
+    ("validate_cookie", 30),
+    ("export_history", 10),
+    ("export_last_har", 10),
+    ("report_issue", 5),
+];
+
+/// Commands that remove data and need a token from `request_confirmation`, with the
+/// question the native confirmation dialog asks.
+///
+/// `update_config` needs one only when the new config drops tasks or accounts.
+pub const DESTRUCTIVE_ACTIONS: [(&str, Msg); 4] = [
+    ("delete_task", Msg::ConfirmDeleteTask),
+    ("delete_account", Msg::ConfirmDeleteAccount),
+    ("purge_storage", Msg::ConfirmPurgeStorage),
+    ("update_config", Msg::ConfirmUpdateConfig),
+];
+
+/// Unused confirmation tokens with the action they were issued for.
+static TOKENS: LazyLock<Mutex<HashMap<String, (String, Instant)>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Recent call times per rate-limited command.
+static CALLS: LazyLock<Mutex<HashMap<&'static str, Vec<Instant>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Asks the user to confirm a destructive command in a native dialog, and issues a token
+/// for it if they do.
+///
+/// The dialog is shown by the backend, so a page cannot obtain a token without the user
+/// seeing the question and agreeing.
+///
+/// # Arguments
+///
+/// * `app` - The Tauri application handle.
+/// * `action` - The command to confirm, e.g. `delete_task`.
+///
+/// # Returns
+///
+/// * `Result<Option<String>, String>` - The token, valid for a minute, `None` if the user
+///   declined, or an error message if the action needs no confirmation.
+pub async fn request(app: &AppHandle, action: &str) -> Result<Option<String>, String> {
+    let Some(&(_, question)) = DESTRUCTIVE_ACTIONS.iter().find(|(name, _)| *name == action) else {
+        return Err(i18n::tf(Msg::UnknownAction, &[&action]));
+    };
+    let dialog = app
+        .dialog()
+        .message(i18n::t(question))
+        .title(i18n::t(Msg::ConfirmTitle))
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancel);
+    let confirmed = tauri::async_runtime::spawn_blocking(move || dialog.blocking_show())
+        .await
+        .map_err(|e| e.to_string())?;
+    if !confirmed {
+        info!("Confirmation of {} declined", action);
+        return Ok(None);
+    }
+    Ok(Some(issue(action)))
+}
+
+/// Issues a one-time token that allows one call of a destructive command.
+///
+/// # Arguments
+///
+/// * `action` - The command the token is for, e.g. `delete_task`.
+///
+/// # Returns
+///
+/// * `String` - The token, valid for a minute.
+fn issue(action: &str) -> String {
+    let token = uuid::Uuid::new_v4().to_string();
+    let mut tokens = TOKENS.lock().unwrap();
+    tokens.retain(|_, (_, issued)| issued.elapsed() < TOKEN_TTL);
+    tokens.insert(token.clone(), (action.to_string(), Instant::now()));
+    token
+}
+
+/// Consumes a confirmation token before a destructive command runs.
+///
+/// # Arguments
+///
+/// * `action` - The command about to run.
+/// * `token` - The token the caller presented.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Ok if the token was issued for this action and has not expired
+///   or been used, an error message otherwise.
+pub fn confirm(action: &str, token: &str) -> Result<(), String> {
+    let issued = TOKENS.lock().unwrap().remove(token);
+    match issued {
+        Some((issued_for, at)) if issued_for == action && at.elapsed() < TOKEN_TTL => Ok(()),
+        _ => {
+            warn!("Rejected {} without a valid confirmation token", action);
+            Err(i18n::t(Msg::ConfirmationRequired).to_string())
+        }
+    }
+}
+
+/// Counts a call of a sensitive command against its rate limit.
+///
+/// # Arguments
+///
+/// * `command` - The command being called; commands without a limit always pass.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Ok if the call is within the limit, an error message otherwise.
+pub fn rate_limit(command: &'static str) -> Result<(), String> {
+    let Some(&(_, limit)) = RATE_LIMITS.iter().find(|(name, _)| *name == command) else {
+        return Ok(());
+    };
+    let mut calls = CALLS.lock().unwrap();
+    let recent = calls.entry(command).or_default();
+    recent.retain(|at| at.elapsed() < RATE_WINDOW);
+    if recent.len() >= limit {
+        warn!(
+            "Rate limit of {} calls per minute hit by {}",
+            limit, command
+        );
+        return Err(i18n::tf(Msg::RateLimited, &[&command]));
+    }
+    recent.push(Instant::now());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_are_single_use_and_bound_to_their_action() {
+        let token = issue("delete_task");
+        assert!(confirm("delete_account", &token).is_err());
+
+        let token = issue("delete_task");
+        assert!(confirm("delete_task", &token).is_ok());
+        assert!(confirm("delete_task", &token).is_err());
+        assert!(confirm("update_config", "").is_err());
+    }
+}
@@ -0,0 +1,249 @@
+use chrono::{DateTime, Local};
+use reqwest::header::{HeaderMap, COOKIE, SET_COOKIE};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Maximum number of response body characters stored per entry.
+const BODY_LIMIT: usize = 20_000;
+
+/// Form fields whose values are replaced in recorded request bodies.
+const SECRET_FORM_FIELDS: &[&str] = &["pwd", "password"];
+
+/// Number of HAR files kept per task.
+const KEEP_FILES: usize = 10;
+
+/// One request/response pair to record.
+pub struct Exchange<'a> {
+    /// The HTTP method.
+    pub method: &'a str,
+    /// The request URL.
+    pub url: &'a str,
+    /// The request headers set by the executor (the cookie jar adds the Cookie header).
+    pub request_headers: &'a HeaderMap,
+    /// Form fields sent with the request, if any.
+    pub form: Option<&'a [(String, String)]>,
+    /// The response status code.
+    pub status: u16,
+    /// The response headers.
+    pub response_headers: &'a HeaderMap,
+    /// The response body.
+    pub body: &'a str,
+    /// When the request was sent.
+    pub started_at: DateTime<Local>,
+    /// Time until the response body was read.
+    pub elapsed: Duration,
+}
+
+/// Returns the directory HAR files are written to.
+///
+/// # Arguments
+///
+/// * `artifact_dir` - The debug artifact directory.
+///
+/// # Returns
+///
+/// * `PathBuf` - The `har` subdirectory.
+pub fn har_dir(artifact_dir: &Path) -> PathBuf {
+    artifact_dir.join("har")
+}
+
+/// Converts an exchange into a HAR 1.2 entry.
+///
+/// Cookie and Set-Cookie values and password form fields are redacted and the response
+/// body is truncated.
+///
+/// # Arguments
+///
+/// * `exchange` - The exchange to convert.
+///
+/// # Returns
+///
+/// * `Value` - The HAR entry.
+pub fn entry(exchange: &Exchange) -> Value {
+    let elapsed_ms = exchange.elapsed.as_secs_f64() * 1000.0;
+    let mut request_headers = headers(exchange.request_headers);
+    request_headers.push(json!({ "name": "cookie", "value": "<redacted>" }));
+    let query: Vec<Value> = reqwest::Url::parse(exchange.url)
+        .map(|url| {
+            url.query_pairs()
+                .map(|(name, value)| json!({ "name": name, "value": value }))
+                .collect()
+        })
+        .unwrap_or_default();
+    let mime_type = exchange
+        .response_headers
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("text/html");
+    let text: String = exchange.body.chars().take(BODY_LIMIT).collect();
+
+    let mut request = json!({
+        "method": exchange.method,
+        "url": exchange.url,
+        "httpVersion": "HTTP/1.1",
+        "cookies": [],
+        "headers": request_headers,
+        "queryString": query,
+        "headersSize": -1,
+        "bodySize": -1,
+    });
+    if let Some(form) = exchange.form {
+        let form: Vec<(&str, &str)> = form
+            .iter()
+            .map(|(name, value)| {
+                if SECRET_FORM_FIELDS.contains(&name.to_ascii_lowercase().as_str()) {
+                    (name.as_str(), "<redacted>")
+                } else {
+                    (name.as_str(), value.as_str())
+                }
+            })
+            .collect();
+        let text = reqwest::Url::parse_with_params("http://localhost/", &form)
+            .ok()
+            .and_then(|url| url.query().map(str::to_string))
+            .unwrap_or_default();
+        request["bodySize"] = json!(text.len());
+        request["postData"] = json!({
+            "mimeType": "application/x-www-form-urlencoded",
+            "params": form
+                .iter()
+                .map(|(name, value)| json!({ "name": name, "value": value }))
+                .collect::<Vec<_>>(),
+            "text": text,
+        });
+    }
+
+    json!({
+        "startedDateTime": exchange.started_at.to_rfc3339(),
+        "time": elapsed_ms,
+        "request": request,
+        "response": {
+            "status": exchange.status,
+            "statusText": reqwest::StatusCode::from_u16(exchange.status)
+                .ok()
+                .and_then(|s| s.canonical_reason())
+                .unwrap_or(""),
+            "httpVersion": "HTTP/1.1",
+            "cookies": [],
+            "headers": headers(exchange.response_headers),
+            "content": {
+                "size": exchange.body.len(),
+                "mimeType": mime_type,
+                "text": text,
+            },
+            "redirectURL": "",
+            "headersSize": -1,
+            "bodySize": exchange.body.len(),
+        },
+        "cache": {},
+        "timings": { "send": 0, "wait": elapsed_ms, "receive": 0 },
+    })
+}
+
+/// Writes the entries of one run to `<dir>/<task_id>/<time>.har`, keeping the newest files.
+///
+/// # Arguments
+///
+/// * `dir` - The HAR directory.
+/// * `task_id` - The task that was run.
+/// * `entries` - The recorded entries.
+///
+/// # Returns
+///
+/// * `Result<PathBuf, String>` - The path of the written file, or an error message.
+pub fn write_har(dir: &Path, task_id: &str, entries: Vec<Value>) -> Result<PathBuf, String> {
+    let task_dir = dir.join(task_id);
+    fs::create_dir_all(&task_dir).map_err(|e| e.to_string())?;
+    let har = json!({
+        "log": {
+            "version": "1.2",
+            "creator": {
+                "name": "AutoCheckin-Next",
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+            "entries": entries,
+        }
+    });
+    let path = task_dir.join(format!("{}.har", Local::now().format("%Y%m%d-%H%M%S%3f")));
+    let json = serde_json::to_string_pretty(&har).map_err(|e| e.to_string())?;
+    fs::write(&path, json).map_err(|e| e.to_string())?;
+
+    let mut files = har_files(&task_dir);
+    while files.len() > KEEP_FILES {
+        let _ = fs::remove_file(files.remove(0));
+    }
+    Ok(path)
+}
+
+/// Finds the newest HAR file of a task.
+///
+/// # Arguments
+///
+/// * `dir` - The HAR directory.
+/// * `task_id` - The task to look up.
+///
+/// # Returns
+///
+/// * `Option<PathBuf>` - The newest file, or `None` if the task has none.
+pub fn last_har(dir: &Path, task_id: &str) -> Option<PathBuf> {
+    har_files(&dir.join(task_id)).pop()
+}
+
+/// Lists the HAR files in a directory, oldest first.
+fn har_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.extension().is_some_and(|ext| ext == "har"))
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+/// Converts headers to HAR name/value pairs with cookie values redacted.
+fn headers(map: &HeaderMap) -> Vec<Value> {
+    map.iter()
+        .map(|(name, value)| {
+            let value = if name == COOKIE || name == SET_COOKIE {
+                "<redacted>"
+            } else {
+                value.to_str().unwrap_or("<binary>")
+            };
+            json!({ "name": name.as_str(), "value": value })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entry_redacts_the_password() {
+        let empty = HeaderMap::new();
+        let form = vec![
+            ("id".to_string(), "4012346".to_string()),
+            ("pwd".to_string(), "2580".to_string()),
+        ];
+        let exchange = Exchange {
+            method: "POST",
+            url: "http://example.com/student/punchs/course/1/4012346",
+            request_headers: &empty,
+            form: Some(&form),
+            status: 200,
+            response_headers: &empty,
+            body: "ok",
+            started_at: Local::now(),
+            elapsed: Duration::from_millis(5),
+        };
+        let har = entry(&exchange).to_string();
+        assert!(!har.contains("2580"), "{}", har);
+        assert!(har.contains("4012346"));
+        assert!(har.contains("%3Credacted%3E"));
+    }
+}
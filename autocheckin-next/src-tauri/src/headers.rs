@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+
+/// User Agent of the WeChat built-in browser (XWEB) on Android.
+pub const WECHAT_ANDROID_UA: &str = "Mozilla/5.0 (Linux; Android 12; PAL-AL00 Build/HUAWEIPAL-AL00; wv) AppleWebKit/537.36 (KHTML, like Gecko) Version/4.0 Chrome/116.0.0.0 Mobile Safari/537.36 XWEB/1160065 MMWEBSDK/20231202 MMWEBID/1136 MicroMessenger/8.0.47.2560(0x28002F35) WeChat/arm64 Weixin NetType/4G Language/zh_CN ABI/arm64";
+
+/// User Agent of the WeChat built-in browser (WKWebView) on iOS.
+pub const WECHAT_IOS_UA: &str = "Mozilla/5.0 (iPhone; CPU iPhone OS 17_1_2 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Mobile/15E148 MicroMessenger/8.0.47(0x18002f2c) NetType/WIFI Language/zh_CN";
+
+/// A consistent set of browser headers sent with every request to the check-in site.
+///
+/// Sites can flag requests whose headers do not match the claimed browser, e.g. a
+/// WeChat User Agent without the `X-Requested-With` header its Android webview sends.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum HeaderProfile {
+    /// The WeChat Android webview: Chromium client hints, `Sec-Fetch-*` and
+    /// `X-Requested-With: com.tencent.mm`.
+    #[default]
+    WechatAndroid,
+    /// The WeChat iOS webview: Safari-style headers without client hints.
+    WechatIos,
+    /// Only the Android User Agent, as sent by earlier versions.
+    Minimal,
+}
+
+impl HeaderProfile {
+    /// The User Agent the profile claims.
+    pub fn user_agent(self) -> &'static str {
+        match self {
+            HeaderProfile::WechatAndroid | HeaderProfile::Minimal => WECHAT_ANDROID_UA,
+            HeaderProfile::WechatIos => WECHAT_IOS_UA,
+        }
+    }
+
+    /// The headers the profile's browser sends for a page navigation, in addition to the
+    /// User Agent and Referer.
+    ///
+    /// # Returns
+    ///
+    /// * `&'static [(&'static str, &'static str)]` - Header names and values.
+    pub fn headers(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            HeaderProfile::WechatAndroid => &[
+                (
+                    "accept",
+                    "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,\
+                     image/webp,image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7",
+                ),
+                ("accept-language", "zh-CN,zh;q=0.9,en-US;q=0.8,en;q=0.7"),
+                ("upgrade-insecure-requests", "1"),
+                ("x-requested-with", "com.tencent.mm"),
+                (
+                    "sec-ch-ua",
+                    "\"Chromium\";v=\"116\", \"Not)A;Brand\";v=\"24\", \"Android WebView\";v=\"116\"",
+                ),
+                ("sec-ch-ua-mobile", "?1"),
+                ("sec-ch-ua-platform", "\"Android\""),
+                ("sec-fetch-site", "same-origin"),
+                ("sec-fetch-mode", "navigate"),
+                ("sec-fetch-user", "?1"),
+                ("sec-fetch-dest", "document"),
+            ],
+            HeaderProfile::WechatIos => &[
+                (
+                    "accept",
+                    "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8",
+                ),
+                ("accept-language", "zh-CN,zh-Hans;q=0.9"),
+                ("sec-fetch-site", "same-origin"),
+                ("sec-fetch-mode", "navigate"),
+                ("sec-fetch-dest", "document"),
+            ],
+            HeaderProfile::Minimal => &[],
+        }
+    }
+}
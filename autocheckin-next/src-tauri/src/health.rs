@@ -0,0 +1,234 @@
+use crate::config::TimeoutConfig;
+use crate::httplog;
+use crate::tls;
+use chrono::{DateTime, Local};
+use reqwest::Url;
+use serde::Serialize;
+use std::error::Error;
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Login site used for QR code login.
+const LOGIN_URL: &str = "https://login.b8n.cn/";
+
+/// WeCom API used for notifications.
+const WECOM_URL: &str = "https://qyapi.weixin.qq.com/";
+
+/// Timeout for opening the TCP connection to each host.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Result of the TLS handshake with a host.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TlsStatus {
+    /// The certificate was accepted.
+    Ok,
+    /// The handshake or certificate validation failed.
+    Failed,
+    /// The host is checked over plain HTTP.
+    NotUsed,
+    /// No handshake was attempted because the host could not be reached.
+    Unknown,
+}
+
+/// Reachability of one host.
+#[derive(Debug, Serialize, Clone)]
+pub struct HostHealth {
+    /// What the host is used for (`site`, `login` or `wecom`).
+    pub name: String,
+    /// The host name.
+    pub host: String,
+    /// Addresses the host resolved to.
+    pub addresses: Vec<String>,
+    /// Time taken by DNS resolution, if it succeeded.
+    pub dns_ms: Option<u64>,
+    /// Time taken to open a TCP connection, if it succeeded.
+    pub connect_ms: Option<u64>,
+    /// Result of the TLS handshake.
+    pub tls: TlsStatus,
+    /// Status code of a GET request to the host's root, if one was received.
+    pub http_status: Option<u16>,
+    /// Time until the response headers arrived, if a response was received.
+    pub latency_ms: Option<u64>,
+    /// The first error encountered, if any.
+    pub error: Option<String>,
+}
+
+impl HostHealth {
+    /// Whether the host answered an HTTP request.
+    fn reachable(&self) -> bool {
+        self.http_status.is_some()
+    }
+}
+
+/// Overall reading of a health check.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthVerdict {
+    /// Every host answered.
+    Healthy,
+    /// No host answered: the local network is down.
+    Offline,
+    /// The check-in site did not answer but other hosts did: the site is down.
+    SiteDown,
+    /// The check-in site answered but another host did not.
+    Degraded,
+}
+
+/// Connectivity report for a status panel.
+#[derive(Debug, Serialize, Clone)]
+pub struct HealthReport {
+    /// When the check ran.
+    pub checked_at: DateTime<Local>,
+    /// Overall reading.
+    pub verdict: HealthVerdict,
+    /// Per-host results: the check-in site, the login site and WeCom.
+    pub hosts: Vec<HostHealth>,
+}
+
+/// Checks DNS, TCP, TLS and HTTP reachability of the hosts the app depends on.
+///
+/// The hosts are checked in parallel.
+///
+/// # Arguments
+///
+/// * `base_url` - The check-in site base URL.
+/// * `timeouts` - The configured request timeouts.
+///
+/// # Returns
+///
+/// * `HealthReport` - The per-host results and an overall verdict.
+pub fn health_check(base_url: &str, timeouts: &TimeoutConfig) -> HealthReport {
+    let targets = [
+        ("site", base_url.to_string()),
+        ("login", LOGIN_URL.to_string()),
+        ("wecom", WECOM_URL.to_string()),
+    ];
+    let timeouts = *timeouts;
+    let handles: Vec<_> = targets
+        .into_iter()
+        .map(|(name, url)| thread::spawn(move || check_host(name, &url, &timeouts)))
+        .collect();
+    let hosts: Vec<HostHealth> = handles
+        .into_iter()
+        .map(|handle| handle.join().expect("health check thread panicked"))
+        .collect();
+
+    let site_up = hosts[0].reachable();
+    let others_up = hosts[1..].iter().filter(|h| h.reachable()).count();
+    let verdict = match (site_up, others_up) {
+        (true, n) if n == hosts.len() - 1 => HealthVerdict::Healthy,
+        (true, _) => HealthVerdict::Degraded,
+        (false, 0) => HealthVerdict::Offline,
+        (false, _) => HealthVerdict::SiteDown,
+    };
+
+    HealthReport {
+        checked_at: Local::now(),
+        verdict,
+        hosts,
+    }
+}
+
+/// Checks a single host, stopping at the first step that fails.
+fn check_host(name: &str, url: &str, timeouts: &TimeoutConfig) -> HostHealth {
+    let mut health = HostHealth {
+        name: name.to_string(),
+        host: String::new(),
+        addresses: vec![],
+        dns_ms: None,
+        connect_ms: None,
+        tls: TlsStatus::Unknown,
+        http_status: None,
+        latency_ms: None,
+        error: None,
+    };
+    let parsed = match Url::parse(url) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            health.error = Some(e.to_string());
+            return health;
+        }
+    };
+    let (Some(host), Some(port)) = (parsed.host_str(), parsed.port_or_known_default()) else {
+        health.error = Some(format!("Invalid URL: {}", url));
+        return health;
+    };
+    health.host = host.to_string();
+    let https = parsed.scheme() == "https";
+
+    let started = Instant::now();
+    let addrs: Vec<SocketAddr> = match (host, port).to_socket_addrs() {
+        Ok(addrs) => addrs.collect(),
+        Err(e) => {
+            health.error = Some(format!("DNS: {}", e));
+            return health;
+        }
+    };
+    health.dns_ms = Some(started.elapsed().as_millis() as u64);
+    health.addresses = addrs.iter().map(|a| a.ip().to_string()).collect();
+
+    let started = Instant::now();
+    if !addrs
+        .iter()
+        .any(|addr| TcpStream::connect_timeout(addr, CONNECT_TIMEOUT).is_ok())
+    {
+        health.error = Some("TCP: no address accepted a connection".to_string());
+        return health;
+    }
+    health.connect_ms = Some(started.elapsed().as_millis() as u64);
+
+    let client = match tls::client_builder()
+        .connect_timeout(Duration::from_secs(timeouts.connect_secs))
+        .timeout(Duration::from_secs(timeouts.request_secs))
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            health.error = Some(e.to_string());
+            return health;
+        }
+    };
+    let started = Instant::now();
+    match httplog::send(client.get(parsed)) {
+        Ok(resp) => {
+            health.latency_ms = Some(started.elapsed().as_millis() as u64);
+            health.http_status = Some(resp.status().as_u16());
+            health.tls = if https {
+                TlsStatus::Ok
+            } else {
+                TlsStatus::NotUsed
+            };
+        }
+        Err(e) => {
+            if !https {
+                health.tls = TlsStatus::NotUsed;
+            } else if is_tls_error(&e) {
+                health.tls = TlsStatus::Failed;
+            }
+            health.error = Some(error_chain(&e));
+        }
+    }
+    health
+}
+
+/// Returns whether a request error was caused by the TLS handshake or certificate.
+fn is_tls_error(error: &reqwest::Error) -> bool {
+    let text = error_chain(error).to_ascii_lowercase();
+    ["certificate", "tls", "ssl", "handshake"]
+        .iter()
+        .any(|needle| text.contains(needle))
+}
+
+/// Joins an error and its sources into one message.
+fn error_chain(error: &reqwest::Error) -> String {
+    let mut parts = vec![error.to_string()];
+    let mut source = error.source();
+    while let Some(cause) = source {
+        parts.push(cause.to_string());
+        source = cause.source();
+    }
+    parts.join(": ")
+}
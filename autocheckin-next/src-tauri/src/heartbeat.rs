@@ -0,0 +1,90 @@
+use crate::config::HeartbeatConfig;
+use crate::history::{RunOutcome, RunRecord};
+use crate::httplog;
+use crate::metrics::outcome_label;
+use crate::tls;
+use log::{debug, warn};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Timeout for a single ping.
+const PING_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// When the last cycle ping was sent.
+static LAST_CYCLE_PING: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Pings the cycle URL after a scheduler cycle, at most once per configured interval.
+///
+/// # Arguments
+///
+/// * `config` - The heartbeat settings.
+pub fn cycle(config: &HeartbeatConfig) {
+    if config.cycle_url.is_empty() {
+        return;
+    }
+    let interval = Duration::from_secs(config.cycle_interval_minutes.max(1) * 60);
+    {
+        let mut last = LAST_CYCLE_PING.lock().unwrap();
+        if last.is_some_and(|at| at.elapsed() < interval) {
+            return;
+        }
+        *last = Some(Instant::now());
+    }
+    ping(config.cycle_url.clone());
+}
+
+/// Pings the success or failure URL after a task run.
+///
+/// `{task}` and `{outcome}` in the URLs are replaced with the task name and the run
+/// outcome. Offline runs are not reported, since the ping could not be delivered anyway.
+///
+/// # Arguments
+///
+/// * `config` - The heartbeat settings.
+/// * `record` - The finished run.
+pub fn run_finished(config: &HeartbeatConfig, record: &RunRecord) {
+    if record.outcome == RunOutcome::Offline {
+        return;
+    }
+    let template = if !record.outcome.is_failure() {
+        config.run_success_url.clone()
+    } else if !config.run_failure_url.is_empty() {
+        config.run_failure_url.clone()
+    } else if !config.run_success_url.is_empty() {
+        // healthchecks.io signals a failure on the `/fail` path of the check.
+        format!("{}/fail", config.run_success_url.trim_end_matches('/'))
+    } else {
+        String::new()
+    };
+    if template.is_empty() {
+        return;
+    }
+    let url = template
+        .replace("{task}", &urlencode(&record.task_name))
+        .replace("{outcome}", outcome_label(record.outcome));
+    ping(url);
+}
+
+/// Sends a GET request to a monitoring URL in the background.
+fn ping(url: String) {
+    thread::spawn(move || {
+        let client = match tls::client_builder().timeout(PING_TIMEOUT).build() {
+            Ok(client) => client,
+            Err(e) => return warn!("Failed to send heartbeat: {}", e),
+        };
+        match httplog::send(client.get(&url)) {
+            Ok(resp) if resp.status().is_success() => debug!("Heartbeat sent"),
+            Ok(resp) => warn!("Heartbeat rejected with status {}", resp.status()),
+            Err(e) => warn!("Failed to send heartbeat: {}", e),
+        }
+    });
+}
+
+/// Percent-encodes a value for use in a URL path or query.
+fn urlencode(value: &str) -> String {
+    reqwest::Url::parse_with_params("http://localhost/", [("v", value)])
+        .ok()
+        .and_then(|url| url.query().map(|q| q.trim_start_matches("v=").to_string()))
+        .unwrap_or_default()
+}
@@ -0,0 +1,755 @@
+use chrono::{DateTime, Duration, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::AppHandle;
+use tauri::Manager;
+
+/// Maximum number of run records kept in the history file.
+const MAX_RECORDS: usize = 5000;
+
+/// Number of recent outcomes included in task statistics.
+const RECENT_OUTCOMES: usize = 30;
+
+/// Serializes read-modify-write access to the history file across worker threads.
+static HISTORY_LOCK: Mutex<()> = Mutex::new(());
+
+/// The overall outcome of a task run.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RunOutcome {
+    /// Every active session was signed successfully.
+    Success,
+    /// At least one sign attempt failed.
+    Failure,
+    /// The course page listed no active sessions.
+    NoSession,
+    /// The run could not complete (e.g. the session list could not be fetched).
+    Error,
+    /// The site asked for a login, so the session cookie is no longer valid.
+    CookieExpired,
+    /// The site showed a captcha, WAF or rate-limit page and the task is paused.
+    Blocked,
+    /// The network stayed down until the deferral deadline, so the run was skipped.
+    Offline,
+}
+
+impl RunOutcome {
+    /// Whether the outcome counts as a failed attempt.
+    pub fn is_failure(self) -> bool {
+        matches!(
+            self,
+            RunOutcome::Failure
+                | RunOutcome::Error
+                | RunOutcome::CookieExpired
+                | RunOutcome::Blocked
+        )
+    }
+}
+
+/// The result of a single sign attempt within a run.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SignRecord {
+    /// The check-in session ID.
+    pub sign_id: String,
+    /// Whether the sign was accepted.
+    pub success: bool,
+    /// The result message returned by the site.
+    pub message: String,
+    /// The submitted latitude.
+    pub lat: String,
+    /// The submitted longitude.
+    pub lng: String,
+    /// Milliseconds between discovering the open session and receiving the sign response.
+    pub latency_ms: u64,
+    /// Milliseconds spent on the sign request itself, excluding the pause before it.
+    #[serde(default)]
+    pub request_ms: Option<u64>,
+}
+
+/// Time spent in each phase of a run, in milliseconds. Phases that did not happen are `None`.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
+pub struct RunTimings {
+    /// Loading the course page and pausing during warm-up navigation.
+    pub warm_up_ms: Option<u64>,
+    /// Fetching the punch list.
+    pub fetch_ms: Option<u64>,
+    /// Parsing the punch list for open sessions.
+    pub parse_ms: Option<u64>,
+    /// Delivering WeCom notifications.
+    pub notify_ms: Option<u64>,
+}
+
+/// Average run timings of a task.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TimingStats {
+    /// Average time to fetch the punch list.
+    pub avg_fetch_ms: Option<f64>,
+    /// Average time to parse the punch list.
+    pub avg_parse_ms: Option<f64>,
+    /// Average time of a sign request.
+    pub avg_sign_request_ms: Option<f64>,
+    /// 95th percentile of the sign request time.
+    pub p95_sign_request_ms: Option<u64>,
+    /// Average time spent delivering notifications per run that sent any.
+    pub avg_notify_ms: Option<f64>,
+    /// Average duration of a whole run.
+    pub avg_run_ms: Option<f64>,
+}
+
+/// A single execution of a task.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RunRecord {
+    /// Unique identifier for the run.
+    pub id: String,
+    /// ID of the task that was executed.
+    pub task_id: String,
+    /// Name of the task at the time of the run.
+    pub task_name: String,
+    /// Class ID of the task at the time of the run.
+    pub class_id: String,
+    /// When the run started.
+    pub started_at: DateTime<Local>,
+    /// When the run finished.
+    pub finished_at: DateTime<Local>,
+    /// The overall outcome.
+    pub outcome: RunOutcome,
+    /// Summary message (error text for failed runs).
+    pub message: String,
+    /// Individual sign attempts.
+    pub signs: Vec<SignRecord>,
+    /// Scheme the site was reached over (`https` or `http`), if it was reached.
+    #[serde(default)]
+    pub scheme: Option<String>,
+    /// Seconds the run was deferred while waiting for the network, if it was.
+    #[serde(default)]
+    pub deferred_secs: Option<u64>,
+    /// Time spent in each phase of the run.
+    #[serde(default)]
+    pub timings: RunTimings,
+    /// Whether receipts of the successful signs were saved, see `get_receipt`.
+    #[serde(default)]
+    pub receipt: bool,
+}
+
+/// Attendance of one check-in session as shown on the site.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum AttendanceStatus {
+    /// Signed in time.
+    Present,
+    /// Signed after the session's deadline.
+    Late,
+    /// Not signed.
+    Absent,
+}
+
+/// A past check-in session imported from the site's punch history.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SiteAttendance {
+    /// Class ID of the course.
+    pub class_id: String,
+    /// The check-in session ID, if the card shows one.
+    pub sign_id: Option<String>,
+    /// Day of the session.
+    pub date: NaiveDate,
+    /// Attendance the site recorded.
+    pub status: AttendanceStatus,
+    /// Who signed, if the site shows it (e.g. a teacher signing on the student's behalf).
+    pub signed_by: Option<String>,
+    /// When the record was imported.
+    pub synced_at: DateTime<Local>,
+}
+
+impl SiteAttendance {
+    /// Identifies the session, so syncing the same page twice does not add duplicates.
+    fn key(&self) -> (String, String) {
+        (
+            self.class_id.clone(),
+            self.sign_id
+                .clone()
+                .unwrap_or_else(|| self.date.to_string()),
+        )
+    }
+}
+
+/// Where the attendance of a day was taken from.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AttendanceSource {
+    /// Records synced from the site's punch history.
+    Site,
+    /// The app's own sign attempts.
+    Local,
+}
+
+/// Attendance of a course on one day.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AttendanceDay {
+    /// The day.
+    pub date: NaiveDate,
+    /// Sessions signed in time.
+    pub present: usize,
+    /// Sessions signed late.
+    pub late: usize,
+    /// Sessions not signed.
+    pub absent: usize,
+    /// Where the counts come from.
+    pub source: AttendanceSource,
+}
+
+/// Attendance of a course per day, with totals.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AttendanceReport {
+    /// Class ID of the course.
+    pub class_id: String,
+    /// Days with at least one session, oldest first.
+    pub days: Vec<AttendanceDay>,
+    /// Sessions signed in time.
+    pub present: usize,
+    /// Sessions signed late.
+    pub late: usize,
+    /// Sessions not signed.
+    pub absent: usize,
+    /// Present and late sessions divided by all sessions, or `None` if there were none.
+    pub attendance_rate: Option<f64>,
+}
+
+/// Aggregated statistics for a single task.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TaskStats {
+    /// ID of the task.
+    pub task_id: String,
+    /// Number of runs in the range.
+    pub total_runs: usize,
+    /// Number of successful runs.
+    pub successes: usize,
+    /// Number of failed or errored runs.
+    pub failures: usize,
+    /// Number of runs that found no active session.
+    pub no_session: usize,
+    /// Successes divided by attempted runs, or `None` if nothing was attempted.
+    pub success_rate: Option<f64>,
+    /// Average latency of successful signs in milliseconds.
+    pub avg_latency_ms: Option<f64>,
+    /// Failure messages and how often each occurred.
+    pub failure_breakdown: HashMap<String, usize>,
+    /// The most recent outcomes, newest first.
+    pub recent: Vec<RunOutcome>,
+    /// Consecutive successful days up to the most recent run.
+    pub current_streak: u32,
+    /// Longest run of consecutive successful days.
+    pub longest_streak: u32,
+    /// Average timings of the runs in the range.
+    pub timings: TimingStats,
+}
+
+/// Retrieves the path to the history file.
+///
+/// # Arguments
+///
+/// * `app_handle` - Handle to the Tauri application.
+///
+/// # Returns
+///
+/// * `PathBuf` - The path to the `history.json` file in the app's data directory.
+pub fn get_history_path(app_handle: &AppHandle) -> PathBuf {
+    app_handle
+        .path()
+        .app_data_dir()
+        .expect("failed to get app data dir")
+        .join("history.json")
+}
+
+/// Retrieves the directory where debug-mode HTML artifacts are stored.
+///
+/// # Arguments
+///
+/// * `app_handle` - Handle to the Tauri application.
+///
+/// # Returns
+///
+/// * `PathBuf` - The `artifacts` directory in the app's data directory.
+pub fn get_artifacts_dir(app_handle: &AppHandle) -> PathBuf {
+    app_handle
+        .path()
+        .app_data_dir()
+        .expect("failed to get app data dir")
+        .join("artifacts")
+}
+
+/// Loads all run records from the history file.
+///
+/// # Arguments
+///
+/// * `app_handle` - Handle to the Tauri application.
+///
+/// # Returns
+///
+/// * `Vec<RunRecord>` - The stored records, oldest first. Empty if none exist.
+pub fn load_history(app_handle: &AppHandle) -> Vec<RunRecord> {
+    let _guard = HISTORY_LOCK.lock().unwrap();
+    read_history(&get_history_path(app_handle))
+}
+
+/// Appends a run record to the history file, trimming the oldest records past the limit.
+///
+/// # Arguments
+///
+/// * `app_handle` - Handle to the Tauri application.
+/// * `record` - The record to append.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Ok if successful, or an error message string on failure.
+pub fn append_history(app_handle: &AppHandle, record: RunRecord) -> Result<(), String> {
+    let _guard = HISTORY_LOCK.lock().unwrap();
+    let path = get_history_path(app_handle);
+    let mut records = read_history(&path);
+    records.push(record);
+    if records.len() > MAX_RECORDS {
+        let excess = records.len() - MAX_RECORDS;
+        records.drain(..excess);
+    }
+    write_history(&path, &records)
+}
+
+/// Deletes the run history and the attendance imported from the site.
+///
+/// # Arguments
+///
+/// * `app_handle` - Handle to the Tauri application.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Ok if successful, or an error message string on failure.
+pub fn clear_history(app_handle: &AppHandle) -> Result<(), String> {
+    let _guard = HISTORY_LOCK.lock().unwrap();
+    for path in [
+        get_history_path(app_handle),
+        get_attendance_path(app_handle),
+    ] {
+        if path.exists() {
+            fs::remove_file(&path).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+/// Retrieves the path to the file of attendance imported from the site.
+///
+/// # Arguments
+///
+/// * `app_handle` - Handle to the Tauri application.
+///
+/// # Returns
+///
+/// * `PathBuf` - The `attendance.json` file in the app's data directory.
+pub fn get_attendance_path(app_handle: &AppHandle) -> PathBuf {
+    app_handle
+        .path()
+        .app_data_dir()
+        .expect("failed to get app data dir")
+        .join("attendance.json")
+}
+
+/// Loads the attendance records imported from the site.
+///
+/// # Arguments
+///
+/// * `app_handle` - Handle to the Tauri application.
+///
+/// # Returns
+///
+/// * `Vec<SiteAttendance>` - The stored records. Empty if none exist.
+pub fn load_attendance(app_handle: &AppHandle) -> Vec<SiteAttendance> {
+    let _guard = HISTORY_LOCK.lock().unwrap();
+    fs::read_to_string(get_attendance_path(app_handle))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Stores attendance records scraped from the site.
+///
+/// A record for a session that is already stored replaces it, since the site may
+/// change an absence to present or late afterwards.
+///
+/// # Arguments
+///
+/// * `app_handle` - Handle to the Tauri application.
+/// * `records` - The scraped records.
+///
+/// # Returns
+///
+/// * `Result<usize, String>` - The number of sessions not stored before, or an error
+///   message string on failure.
+pub fn merge_attendance(
+    app_handle: &AppHandle,
+    records: Vec<SiteAttendance>,
+) -> Result<usize, String> {
+    let stored = load_attendance(app_handle);
+    let _guard = HISTORY_LOCK.lock().unwrap();
+    let mut merged: BTreeMap<(String, String), SiteAttendance> =
+        stored.into_iter().map(|r| (r.key(), r)).collect();
+    let mut added = 0;
+    for record in records {
+        if merged.insert(record.key(), record).is_none() {
+            added += 1;
+        }
+    }
+    let mut records: Vec<SiteAttendance> = merged.into_values().collect();
+    records.sort_by(|a, b| {
+        a.date
+            .cmp(&b.date)
+            .then_with(|| a.class_id.cmp(&b.class_id))
+    });
+    let path = get_attendance_path(app_handle);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let content = serde_json::to_string(&records).map_err(|e| e.to_string())?;
+    fs::write(path, content).map_err(|e| e.to_string())?;
+    Ok(added)
+}
+
+/// Reads and parses the history file, returning an empty list if it is missing or invalid.
+fn read_history(path: &Path) -> Vec<RunRecord> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Writes the records to the history file, creating the parent directory if needed.
+fn write_history(path: &Path, records: &[RunRecord]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let content = serde_json::to_string(records).map_err(|e| e.to_string())?;
+    fs::write(path, content).map_err(|e| e.to_string())
+}
+
+/// Computes statistics for a task from its run history.
+///
+/// # Arguments
+///
+/// * `records` - All run records.
+/// * `task_id` - The task to compute statistics for.
+/// * `range_days` - Only consider runs from the last N days, or all runs if `None`.
+///
+/// # Returns
+///
+/// * `TaskStats` - The aggregated statistics.
+pub fn compute_stats(records: &[RunRecord], task_id: &str, range_days: Option<u32>) -> TaskStats {
+    let since = range_days.map(|d| Local::now() - Duration::days(d as i64));
+    let runs: Vec<&RunRecord> = records
+        .iter()
+        .filter(|r| r.task_id == task_id)
+        .filter(|r| since.is_none_or(|s| r.started_at >= s))
+        .collect();
+
+    let count = |outcome: RunOutcome| runs.iter().filter(|r| r.outcome == outcome).count();
+    let successes = count(RunOutcome::Success);
+    let failures = runs.iter().filter(|r| r.outcome.is_failure()).count();
+    let no_session = count(RunOutcome::NoSession);
+
+    let attempted = successes + failures;
+    let success_rate = (attempted > 0).then(|| successes as f64 / attempted as f64);
+
+    let latencies: Vec<u64> = runs
+        .iter()
+        .flat_map(|r| r.signs.iter())
+        .filter(|s| s.success)
+        .map(|s| s.latency_ms)
+        .collect();
+    let avg_latency_ms = (!latencies.is_empty())
+        .then(|| latencies.iter().sum::<u64>() as f64 / latencies.len() as f64);
+
+    let mut failure_breakdown = HashMap::new();
+    for run in &runs {
+        match run.outcome {
+            RunOutcome::Error | RunOutcome::CookieExpired | RunOutcome::Blocked => {
+                *failure_breakdown.entry(run.message.clone()).or_insert(0) += 1
+            }
+            RunOutcome::Failure => {
+                for sign in run.signs.iter().filter(|s| !s.success) {
+                    *failure_breakdown.entry(sign.message.clone()).or_insert(0) += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let (current_streak, longest_streak) = compute_streak(records, task_id);
+    let timings = compute_timing_stats(&runs);
+
+    TaskStats {
+        task_id: task_id.to_string(),
+        total_runs: runs.len(),
+        successes,
+        failures,
+        no_session,
+        success_rate,
+        avg_latency_ms,
+        failure_breakdown,
+        recent: runs
+            .iter()
+            .rev()
+            .take(RECENT_OUTCOMES)
+            .map(|r| r.outcome)
+            .collect(),
+        current_streak,
+        longest_streak,
+        timings,
+    }
+}
+
+/// Averages the phase timings of a set of runs.
+fn compute_timing_stats(runs: &[&RunRecord]) -> TimingStats {
+    let average = |values: Vec<u64>| {
+        (!values.is_empty()).then(|| values.iter().sum::<u64>() as f64 / values.len() as f64)
+    };
+    let phase = |get: fn(&RunTimings) -> Option<u64>| {
+        average(runs.iter().filter_map(|r| get(&r.timings)).collect())
+    };
+
+    let mut sign_requests: Vec<u64> = runs
+        .iter()
+        .flat_map(|r| r.signs.iter())
+        .filter_map(|s| s.request_ms)
+        .collect();
+    sign_requests.sort_unstable();
+    let p95_sign_request_ms = (!sign_requests.is_empty())
+        .then(|| sign_requests[(sign_requests.len() * 95).div_ceil(100) - 1]);
+
+    TimingStats {
+        avg_fetch_ms: phase(|t| t.fetch_ms),
+        avg_parse_ms: phase(|t| t.parse_ms),
+        avg_sign_request_ms: average(sign_requests),
+        p95_sign_request_ms,
+        avg_notify_ms: phase(|t| t.notify_ms),
+        avg_run_ms: average(
+            runs.iter()
+                .filter(|r| r.outcome != RunOutcome::Offline)
+                .map(|r| (r.finished_at - r.started_at).num_milliseconds().max(0) as u64)
+                .collect(),
+        ),
+    }
+}
+
+/// Tallies a course's attendance per day from synced site records and local runs.
+///
+/// Days with synced records use them only, since the site knows about sessions the app
+/// never saw and about late signs. Other days count each session the app attempted:
+/// present if any sign of it succeeded, absent otherwise. Local runs cannot tell late
+/// signs apart.
+///
+/// # Arguments
+///
+/// * `records` - All run records.
+/// * `site` - All synced attendance records.
+/// * `class_id` - The course to report on.
+/// * `range_days` - Only consider the last N days, or everything if `None`.
+///
+/// # Returns
+///
+/// * `AttendanceReport` - The per-day tallies and totals.
+pub fn compute_attendance(
+    records: &[RunRecord],
+    site: &[SiteAttendance],
+    class_id: &str,
+    range_days: Option<u32>,
+) -> AttendanceReport {
+    let since = range_days.map(|d| (Local::now() - Duration::days(d as i64)).date_naive());
+    let in_range = |date: NaiveDate| since.is_none_or(|s| date >= s);
+    let mut days: BTreeMap<NaiveDate, AttendanceDay> = BTreeMap::new();
+    let empty_day = |date, source| AttendanceDay {
+        date,
+        present: 0,
+        late: 0,
+        absent: 0,
+        source,
+    };
+
+    for record in site
+        .iter()
+        .filter(|r| r.class_id == class_id && in_range(r.date))
+    {
+        let day = days
+            .entry(record.date)
+            .or_insert_with(|| empty_day(record.date, AttendanceSource::Site));
+        match record.status {
+            AttendanceStatus::Present => day.present += 1,
+            AttendanceStatus::Late => day.late += 1,
+            AttendanceStatus::Absent => day.absent += 1,
+        }
+    }
+
+    let mut local: BTreeMap<NaiveDate, HashMap<&str, bool>> = BTreeMap::new();
+    for run in records.iter().filter(|r| r.class_id == class_id) {
+        let date = run.started_at.date_naive();
+        if !in_range(date) || days.contains_key(&date) {
+            continue;
+        }
+        let sessions = local.entry(date).or_default();
+        for sign in &run.signs {
+            *sessions.entry(sign.sign_id.as_str()).or_insert(false) |= sign.success;
+        }
+    }
+    for (date, sessions) in local.into_iter().filter(|(_, s)| !s.is_empty()) {
+        let present = sessions.values().filter(|signed| **signed).count();
+        days.insert(
+            date,
+            AttendanceDay {
+                present,
+                absent: sessions.len() - present,
+                ..empty_day(date, AttendanceSource::Local)
+            },
+        );
+    }
+
+    let days: Vec<AttendanceDay> = days.into_values().collect();
+    let present = days.iter().map(|d| d.present).sum();
+    let late = days.iter().map(|d| d.late).sum();
+    let absent = days.iter().map(|d| d.absent).sum();
+    let total = present + late + absent;
+    AttendanceReport {
+        class_id: class_id.to_string(),
+        days,
+        present,
+        late,
+        absent,
+        attendance_rate: (total > 0).then(|| (present + late) as f64 / total as f64),
+    }
+}
+
+/// Computes the current and longest streak of successful days for a task.
+///
+/// A day counts as successful if any run succeeded and as failed if runs failed without
+/// any success. Days that only found no active session or were offline neither extend nor
+/// break a streak.
+///
+/// # Arguments
+///
+/// * `records` - All run records.
+/// * `task_id` - The task to compute the streak for.
+///
+/// # Returns
+///
+/// * `(u32, u32)` - The current streak and the longest streak, in days.
+pub fn compute_streak(records: &[RunRecord], task_id: &str) -> (u32, u32) {
+    let mut days: BTreeMap<NaiveDate, bool> = BTreeMap::new();
+    for run in records.iter().filter(|r| r.task_id == task_id) {
+        let day = run.started_at.date_naive();
+        match run.outcome {
+            RunOutcome::Success => {
+                days.insert(day, true);
+            }
+            RunOutcome::Failure
+            | RunOutcome::Error
+            | RunOutcome::CookieExpired
+            | RunOutcome::Blocked => {
+                days.entry(day).or_insert(false);
+            }
+            RunOutcome::NoSession | RunOutcome::Offline => {}
+        }
+    }
+
+    let mut current = 0;
+    let mut longest = 0;
+    for success in days.values() {
+        current = if *success { current + 1 } else { 0 };
+        longest = longest.max(current);
+    }
+    (current, longest)
+}
+
+/// Counts the failed runs at the end of a task's history.
+///
+/// Runs that found no active session are skipped rather than ending the count, and so
+/// are runs paused by an anti-bot page or skipped while offline, which are not caused by
+/// the task's settings. Runs started before `since` are not counted, so a task that was
+/// disabled for failing starts over once it is switched back on.
+///
+/// # Arguments
+///
+/// * `records` - All run records.
+/// * `task_id` - The task to inspect.
+/// * `since` - When the task was last switched on, if known.
+///
+/// # Returns
+///
+/// * `u32` - The number of consecutive failed or errored runs.
+pub fn consecutive_failures(
+    records: &[RunRecord],
+    task_id: &str,
+    since: Option<DateTime<Local>>,
+) -> u32 {
+    let mut count = 0;
+    for run in records
+        .iter()
+        .rev()
+        .filter(|r| r.task_id == task_id)
+        .take_while(|r| since.is_none_or(|since| r.started_at >= since))
+    {
+        match run.outcome {
+            RunOutcome::Failure | RunOutcome::Error | RunOutcome::CookieExpired => count += 1,
+            RunOutcome::Success => break,
+            RunOutcome::NoSession | RunOutcome::Blocked | RunOutcome::Offline => {}
+        }
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn run(hour: u32, outcome: RunOutcome) -> RunRecord {
+        let at = Local.with_ymd_and_hms(2026, 10, 12, hour, 0, 0).unwrap();
+        RunRecord {
+            id: format!("run-{}", hour),
+            task_id: "t1".to_string(),
+            task_name: "Math".to_string(),
+            class_id: "1".to_string(),
+            started_at: at,
+            finished_at: at,
+            outcome,
+            message: String::new(),
+            signs: Vec::new(),
+            scheme: None,
+            deferred_secs: None,
+            timings: RunTimings::default(),
+            receipt: false,
+        }
+    }
+
+    #[test]
+    fn failure_streak_skips_neutral_runs_and_ends_at_success() {
+        let records = vec![
+            run(8, RunOutcome::Failure),
+            run(9, RunOutcome::Success),
+            run(10, RunOutcome::Error),
+            run(11, RunOutcome::NoSession),
+            run(12, RunOutcome::CookieExpired),
+        ];
+        assert_eq!(consecutive_failures(&records, "t1", None), 2);
+        assert_eq!(consecutive_failures(&records, "t2", None), 0);
+    }
+
+    #[test]
+    fn failure_streak_starts_over_when_re_enabled() {
+        let records = vec![
+            run(8, RunOutcome::Failure),
+            run(9, RunOutcome::Failure),
+            run(10, RunOutcome::Failure),
+            run(12, RunOutcome::Failure),
+        ];
+        let enabled_at = Local.with_ymd_and_hms(2026, 10, 12, 11, 0, 0).unwrap();
+        assert_eq!(consecutive_failures(&records, "t1", Some(enabled_at)), 1);
+    }
+}
@@ -0,0 +1,156 @@
+use crate::config::{ScriptConfig, Task};
+use crate::history::RunRecord;
+use log::{debug, info, warn};
+use rhai::{Dynamic, Engine, Scope, AST};
+use std::fs;
+
+/// Name of the script function called before a run.
+const PRE_RUN: &str = "pre_run";
+
+/// Name of the script function called after a run.
+const POST_RUN: &str = "post_run";
+
+/// Operation budget of a single hook call, stopping scripts stuck in a loop.
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// What the pre-run hook decided.
+pub enum PreRun {
+    /// Run the task, possibly with parameters changed by the script.
+    Run(Box<Task>),
+    /// Skip this run.
+    Veto,
+}
+
+/// Calls the script's `pre_run(task)` function before a run.
+///
+/// The task is passed as an object map with the same fields as in the config file. The
+/// script may return `false` to skip the run, or a changed copy of the map to run with
+/// different parameters, e.g. a computed `location`. Changes only apply to this run and
+/// are not saved. Any other return value, a missing function or a script error runs the
+/// task unchanged.
+///
+/// # Arguments
+///
+/// * `config` - The scripting settings.
+/// * `task` - The task about to run.
+///
+/// # Returns
+///
+/// * `PreRun` - Whether and with which parameters to run the task.
+pub fn pre_run(config: &ScriptConfig, task: &Task) -> PreRun {
+    let unchanged = || PreRun::Run(Box::new(task.clone()));
+    let Some((engine, ast)) = load(config) else {
+        return unchanged();
+    };
+    if !defines(&ast, PRE_RUN) {
+        return unchanged();
+    }
+    let result = rhai::serde::to_dynamic(task)
+        .and_then(|arg| engine.call_fn::<Dynamic>(&mut Scope::new(), &ast, PRE_RUN, (arg,)));
+    let value = match result {
+        Ok(value) => value,
+        Err(e) => {
+            warn!("pre_run hook failed for {}: {}", task.name, e);
+            return unchanged();
+        }
+    };
+    if value.as_bool() == Ok(false) {
+        info!("Run of {} vetoed by the pre_run hook", task.name);
+        return PreRun::Veto;
+    }
+    if !value.is_map() {
+        return unchanged();
+    }
+    match rhai::serde::from_dynamic::<Task>(&value) {
+        Ok(changed) => {
+            debug!("pre_run hook changed the parameters of {}", task.name);
+            PreRun::Run(Box::new(changed))
+        }
+        Err(e) => {
+            warn!(
+                "pre_run hook returned an invalid task for {}: {}",
+                task.name, e
+            );
+            unchanged()
+        }
+    }
+}
+
+/// Calls the script's `post_run(task, record)` function after a run.
+///
+/// Both arguments are object maps; the return value is ignored. Use this to log results
+/// or call out to other systems from the script.
+///
+/// # Arguments
+///
+/// * `config` - The scripting settings.
+/// * `task` - The task that ran, as passed to the run.
+/// * `record` - The finished run.
+pub fn post_run(config: &ScriptConfig, task: &Task, record: &RunRecord) {
+    let Some((engine, ast)) = load(config) else {
+        return;
+    };
+    if !defines(&ast, POST_RUN) {
+        return;
+    }
+    let result = rhai::serde::to_dynamic(task)
+        .and_then(|task| Ok((task, rhai::serde::to_dynamic(record)?)))
+        .and_then(|args| engine.call_fn::<Dynamic>(&mut Scope::new(), &ast, POST_RUN, args));
+    if let Err(e) = result {
+        warn!("post_run hook failed for {}: {}", task.name, e);
+    }
+}
+
+/// Checks that the configured script compiles.
+///
+/// # Arguments
+///
+/// * `config` - The scripting settings.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Ok if no script is set or it compiles, error message otherwise.
+pub fn validate(config: &ScriptConfig) -> Result<(), String> {
+    if config.path.trim().is_empty() {
+        return Ok(());
+    }
+    compile(&engine(), &config.path).map(|_| ())
+}
+
+/// Reads and compiles the script. It is read again for each call, so edits apply to the
+/// next run without restarting.
+fn load(config: &ScriptConfig) -> Option<(Engine, AST)> {
+    if config.path.trim().is_empty() {
+        return None;
+    }
+    let engine = engine();
+    match compile(&engine, &config.path) {
+        Ok(ast) => Some((engine, ast)),
+        Err(e) => {
+            warn!("Hook script not loaded: {}", e);
+            None
+        }
+    }
+}
+
+/// Compiles the script at `path`.
+fn compile(engine: &Engine, path: &str) -> Result<AST, String> {
+    let source = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    engine
+        .compile(source)
+        .map_err(|e| format!("{}: {}", path, e))
+}
+
+/// Creates an engine whose `print` and `debug` go to the app log.
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.on_print(|text| info!("[hook] {}", text));
+    engine.on_debug(|text, _, _| debug!("[hook] {}", text));
+    engine
+}
+
+/// Whether the script defines a function with the given name.
+fn defines(ast: &AST, name: &str) -> bool {
+    ast.iter_functions().any(|f| f.name == name)
+}
@@ -0,0 +1,210 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// How long a client may take to send its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest request or header line accepted, in bytes.
+const MAX_LINE: u64 = 8 * 1024;
+
+/// Most header lines read from one request.
+const MAX_HEADERS: usize = 64;
+
+/// An HTTP/1.1 request read by one of the local listeners.
+#[derive(Debug, Default)]
+pub struct Request {
+    /// The method, e.g. `GET`.
+    pub method: String,
+    /// The request target: path and query string.
+    pub target: String,
+    /// Header names and values, in the order received.
+    pub headers: Vec<(String, String)>,
+    /// The body, cut off at the size the listener accepts.
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// The value of a header, matched case-insensitively.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The header name.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// The path and the query string, without the `?`.
+    pub fn path_and_query(&self) -> (&str, &str) {
+        self.target
+            .split_once('?')
+            .unwrap_or((self.target.as_str(), ""))
+    }
+}
+
+/// Reads a request: the request line, up to `MAX_HEADERS` headers and the body.
+///
+/// Lines longer than `MAX_LINE` are rejected, and a client that stops sending times out.
+///
+/// # Arguments
+///
+/// * `stream` - The accepted connection.
+/// * `max_body` - Largest body read; longer bodies are cut off, and 0 skips the body.
+///
+/// # Returns
+///
+/// * `std::io::Result<Request>` - The request, or an error if the client sent too long a
+///   line, timed out or disconnected.
+pub fn read_request(stream: &TcpStream, max_body: usize) -> std::io::Result<Request> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream);
+    let request_line = read_line(&mut reader)?;
+    let mut parts = request_line.split_whitespace();
+    let mut request = Request {
+        method: parts.next().unwrap_or_default().to_string(),
+        target: parts.next().unwrap_or_default().to_string(),
+        ..Request::default()
+    };
+    for _ in 0..MAX_HEADERS {
+        let line = read_line(&mut reader)?;
+        if line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            request
+                .headers
+                .push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    let length: usize = request
+        .header("content-length")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    request.body = vec![0; length.min(max_body)];
+    reader.read_exact(&mut request.body)?;
+    Ok(request)
+}
+
+/// Reads one line of at most `MAX_LINE` bytes; an empty string means end of stream.
+fn read_line(reader: &mut impl BufRead) -> std::io::Result<String> {
+    let mut line = String::new();
+    reader.by_ref().take(MAX_LINE).read_line(&mut line)?;
+    if line.len() as u64 == MAX_LINE && !line.ends_with('\n') {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "request line too long",
+        ));
+    }
+    Ok(line)
+}
+
+/// Writes a complete response and closes the connection.
+///
+/// # Arguments
+///
+/// * `stream` - The connection.
+/// * `status` - The status, e.g. `200 OK`.
+/// * `content_type` - The `Content-Type` of the body.
+/// * `body` - The body.
+///
+/// # Returns
+///
+/// * `std::io::Result<()>` - An error if the client went away.
+pub fn write_response(
+    mut stream: &TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &str,
+) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )
+}
+
+/// Accepts connections in a background thread and handles each in a thread of its own,
+/// at most `max_connections` at once. Connections beyond that get `503 Service
+/// Unavailable`.
+///
+/// # Arguments
+///
+/// * `listener` - The bound listener.
+/// * `max_connections` - Most connections handled at the same time.
+/// * `handle` - Handles one connection.
+pub fn spawn<F>(listener: TcpListener, max_connections: usize, handle: F)
+where
+    F: Fn(TcpStream) + Send + Sync + 'static,
+{
+    let handle = Arc::new(handle);
+    let open = Arc::new(AtomicUsize::new(0));
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            if open.fetch_add(1, Ordering::SeqCst) >= max_connections {
+                open.fetch_sub(1, Ordering::SeqCst);
+                let _ = write_response(
+                    &stream,
+                    "503 Service Unavailable",
+                    "text/plain",
+                    "Too many connections\n",
+                );
+                continue;
+            }
+            let handle = handle.clone();
+            let open = open.clone();
+            thread::spawn(move || {
+                handle(stream);
+                open.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    /// Sends `raw` to a fresh listener and reads it back as a request.
+    fn roundtrip(raw: Vec<u8>) -> std::io::Result<Request> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        thread::spawn(move || {
+            let _ = client.write_all(&raw);
+            thread::sleep(Duration::from_millis(200));
+        });
+        read_request(&server, 64)
+    }
+
+    #[test]
+    fn request_is_parsed_with_headers_and_body() {
+        let request = roundtrip(
+            b"POST /api/run?x=1 HTTP/1.1\r\nAuthorization: Bearer t\r\nContent-Length: 5\r\n\r\nhello"
+                .to_vec(),
+        )
+        .unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path_and_query(), ("/api/run", "x=1"));
+        assert_eq!(request.header("authorization"), Some("Bearer t"));
+        assert_eq!(request.body, b"hello");
+    }
+
+    #[test]
+    fn overlong_line_is_rejected() {
+        let mut raw = b"GET /".to_vec();
+        raw.extend(std::iter::repeat_n(b'a', MAX_LINE as usize));
+        raw.extend(b" HTTP/1.1\r\n\r\n");
+        let error = roundtrip(raw).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,205 @@
+use crate::spans::{self, SpanKind};
+use log::debug;
+use regex::Regex;
+use reqwest::blocking::{RequestBuilder, Response};
+use reqwest::header::{HeaderMap, AUTHORIZATION, COOKIE};
+use reqwest::Url;
+use serde::de::DeserializeOwned;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::LazyLock;
+use std::time::Instant;
+
+/// Maximum number of body characters written to the log per response.
+const BODY_LIMIT: usize = 2000;
+
+/// Query parameters whose values are replaced in logged URLs.
+const SECRET_PARAMS: &[&str] = &[
+    "corpsecret",
+    "secret",
+    "access_token",
+    "key",
+    "sig",
+    "sign",
+    "sess",
+    "token",
+    "pwd",
+    "password",
+];
+
+/// Whether outbound requests are logged. Follows the debug flag of the global config.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Matches secret values in JSON bodies, e.g. the token returned by WeCom or a
+/// check-in password echoed back by the site.
+static SECRET_FIELD: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"("(?:access_token|corpsecret|secret|token|pwd|password)"\s*:\s*")[^"]*(")"#)
+        .unwrap()
+});
+
+/// Matches a Telegram bot token in a request path, e.g. `/bot123:ABC/getUpdates`.
+static BOT_TOKEN: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"/bot\d+:[\w-]+/").unwrap());
+
+/// Turns request logging on or off.
+///
+/// # Arguments
+///
+/// * `enabled` - Whether debug mode is enabled.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether request logging is on.
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Sends a request, logging its method, URL, status and duration when logging is on.
+///
+/// Cookie and Authorization headers and secret query parameters are redacted. While
+/// tracing is on, the request is also recorded as a client span.
+///
+/// # Arguments
+///
+/// * `request` - The request to send.
+///
+/// # Returns
+///
+/// * `reqwest::Result<Response>` - The response, or the send error.
+pub fn send(request: RequestBuilder) -> reqwest::Result<Response> {
+    let logging = enabled();
+    if !logging && !spans::enabled() {
+        return request.send();
+    }
+    let built = request.try_clone().and_then(|copy| copy.build().ok());
+    let (method, url) = built
+        .as_ref()
+        .map(|b| (b.method().to_string(), redact_url(b.url())))
+        .unwrap_or_else(|| ("?".to_string(), "<streamed request>".to_string()));
+
+    let mut span = spans::start(&format!("HTTP {}", method), SpanKind::Client);
+    span.set("http.request.method", method.as_str());
+    span.set("url.full", url.as_str());
+    let started = Instant::now();
+    let result = request.send();
+    let elapsed = started.elapsed().as_millis();
+    match &result {
+        Ok(resp) => {
+            span.set("http.response.status_code", resp.status().as_u16());
+            if resp.status().is_server_error() {
+                span.fail(&resp.status().to_string());
+            }
+        }
+        Err(e) => span.fail(&e.to_string()),
+    }
+
+    if logging {
+        let headers = built
+            .as_ref()
+            .map(|b| describe_headers(b.headers()))
+            .unwrap_or_default();
+        match &result {
+            Ok(resp) => debug!(
+                "HTTP {} {}{} -> {} in {} ms",
+                method,
+                url,
+                headers,
+                resp.status(),
+                elapsed
+            ),
+            Err(e) => debug!(
+                "HTTP {} {}{} -> error in {} ms: {}",
+                method, url, headers, elapsed, e
+            ),
+        }
+    }
+    result
+}
+
+/// Reads a JSON response body, logging it truncated and redacted when logging is on.
+///
+/// # Arguments
+///
+/// * `resp` - The response to read.
+///
+/// # Returns
+///
+/// * `Result<T, String>` - The parsed body, or an error message.
+pub fn json<T: DeserializeOwned>(resp: Response) -> Result<T, String> {
+    if !enabled() {
+        return resp.json().map_err(|e| e.to_string());
+    }
+    let url = redact_url(resp.url());
+    let text = resp.text().map_err(|e| e.to_string())?;
+    log_body(&url, &text);
+    serde_json::from_str(&text).map_err(|e| e.to_string())
+}
+
+/// Logs a response body, truncated and redacted, when logging is on.
+///
+/// # Arguments
+///
+/// * `url` - The URL the body was fetched from, already redacted.
+/// * `body` - The response body.
+pub fn log_body(url: &str, body: &str) {
+    if !enabled() {
+        return;
+    }
+    let truncated: String = body.chars().take(BODY_LIMIT).collect();
+    debug!(
+        "HTTP body of {} ({} bytes): {}",
+        url,
+        body.len(),
+        SECRET_FIELD.replace_all(&truncated, "$1<redacted>$2")
+    );
+}
+
+/// Formats a URL with the values of secret query parameters and bot tokens replaced.
+///
+/// # Arguments
+///
+/// * `url` - The URL to redact.
+///
+/// # Returns
+///
+/// * `String` - The redacted URL.
+pub fn redact_url(url: &Url) -> String {
+    if url.query().is_none() {
+        return BOT_TOKEN
+            .replace(url.as_str(), "/bot<redacted>/")
+            .into_owned();
+    }
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(name, value)| {
+            let value = if SECRET_PARAMS.contains(&name.to_ascii_lowercase().as_str()) {
+                "<redacted>".to_string()
+            } else {
+                value.into_owned()
+            };
+            (name.into_owned(), value)
+        })
+        .collect();
+    let mut redacted = url.clone();
+    redacted.query_pairs_mut().clear().extend_pairs(pairs);
+    BOT_TOKEN
+        .replace(redacted.as_str(), "/bot<redacted>/")
+        .into_owned()
+}
+
+/// Formats request headers for the log, with credentials redacted.
+fn describe_headers(headers: &HeaderMap) -> String {
+    if headers.is_empty() {
+        return String::new();
+    }
+    let parts: Vec<String> = headers
+        .iter()
+        .map(|(name, value)| {
+            if name == COOKIE || name == AUTHORIZATION {
+                format!("{}: <redacted>", name)
+            } else {
+                format!("{}: {}", name, value.to_str().unwrap_or("<binary>"))
+            }
+        })
+        .collect();
+    format!(" [{}]", parts.join("; "))
+}
@@ -0,0 +1,631 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::sync::Mutex;
+
+/// The locale used for backend messages, set from the configuration.
+static LOCALE: Mutex<Locale> = Mutex::new(Locale::ZhCn);
+
+/// Languages supported by the backend message catalog.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    /// Simplified Chinese.
+    #[default]
+    #[serde(rename = "zh-CN", alias = "zh")]
+    ZhCn,
+    /// English.
+    #[serde(rename = "en")]
+    En,
+}
+
+/// User-facing messages produced by the backend.
+///
+/// Messages with arguments use `{}` placeholders, filled in order by `tf`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Msg {
+    /// A command referenced a task ID that does not exist.
+    TaskNotFound,
+    /// An account ID does not match any account.
+    AccountNotFound,
+    /// An account to delete is still referenced by tasks.
+    /// Arguments: number of tasks.
+    AccountInUse,
+    /// A password-protected session was found for a task without a password.
+    PasswordMissing,
+    /// A disabled task was asked to run.
+    TaskDisabled,
+    /// A destructive command was called without a valid confirmation token.
+    ConfirmationRequired,
+    /// A confirmation token was requested for an action that needs none.
+    /// Arguments: action.
+    UnknownAction,
+    /// A login poll arrived without a QR code fetched first.
+    NoLoginInProgress,
+    /// The QR login page carried no login parameters.
+    QrParamsMissing,
+    /// A login completed but the check-in site set no session cookie.
+    LoginNoCookie,
+    /// Title of the native dialog confirming a destructive action.
+    ConfirmTitle,
+    /// Native confirmation dialog text for deleting a task.
+    ConfirmDeleteTask,
+    /// Native confirmation dialog text for deleting an account.
+    ConfirmDeleteAccount,
+    /// Native confirmation dialog text for purging stored data.
+    ConfirmPurgeStorage,
+    /// Native confirmation dialog text for saving a config that removes tasks or accounts.
+    ConfirmUpdateConfig,
+    /// A sensitive command was called too often.
+    /// Arguments: command.
+    RateLimited,
+    /// Simulated time was advanced while accelerated time is off.
+    DevTimeDisabled,
+    /// An update was requested but none is available.
+    NoUpdateAvailable,
+    /// The latest release has a tag that is not a version.
+    /// Arguments: tag, parse error.
+    InvalidReleaseTag,
+    /// Updates were requested but no updater public key is configured.
+    UpdaterNotConfigured,
+    /// An imported calendar had no usable events.
+    NoCalendarEvents,
+    /// An unknown log level was requested. Arguments: level.
+    UnknownLogLevel,
+    /// A map link contained no recognizable coordinates.
+    NoCoordinatesInLink,
+    /// The geocoding provider needs an API key that is not configured.
+    GeocodingKeyMissing,
+    /// The IP location service refused the lookup. Arguments: reason.
+    IpLocationFailed,
+    /// The IP location service answered without coordinates.
+    IpLocationIncomplete,
+    /// The latitude is not a number.
+    LatitudeNotNumber,
+    /// The longitude is not a number.
+    LongitudeNotNumber,
+    /// The latitude or longitude is outside the valid range.
+    CoordinatesOutOfRange,
+    /// The latitude and longitude appear to be swapped.
+    CoordinatesSwapped,
+    /// The location is 0,0, meaning it was never set.
+    CoordinatesUnset,
+    /// A location share code could not be read.
+    InvalidShareCode,
+    /// A cron expression could not be parsed. Arguments: expression, invalid field.
+    InvalidSchedule,
+    /// No HAR file exists for the task; debug mode must be on during a run.
+    NoHarRecorded,
+    /// A run was requested while the app is quitting.
+    ShuttingDown,
+    /// A task was added, removed or toggled while tasks come from a managed task list.
+    TasksManaged,
+    /// Installing a background service was requested on an unsupported platform.
+    #[cfg_attr(
+        any(target_os = "windows", target_os = "linux", target_os = "macos"),
+        allow(dead_code)
+    )]
+    ServiceUnsupported,
+    /// The user's configuration directory could not be found.
+    #[cfg_attr(not(any(target_os = "linux", target_os = "macos")), allow(dead_code))]
+    HomeNotSet,
+    /// The managed task list was refreshed without a URL.
+    ManagedUrlMissing,
+    /// The managed task list was refreshed without a public key to verify it with.
+    ManagedKeyMissing,
+    /// The site profile was refreshed without a URL or public key.
+    ProfileSourceMissing,
+    /// A site profile has an invalid `min_app_version`. Arguments: parse error.
+    ProfileInvalidMinVersion,
+    /// A site profile requires a newer app. Arguments: minimum version.
+    ProfileNeedsNewerApp,
+    /// A configured minisign public key could not be read. Arguments: error.
+    InvalidPublicKey,
+    /// A downloaded minisign signature could not be read. Arguments: error.
+    InvalidSignature,
+    /// A download does not match its minisign signature. Arguments: error.
+    SignatureMismatch,
+    /// A task in the managed task list has no ID. Arguments: task name.
+    ManagedTaskNoId,
+    /// Two tasks in the managed task list share an ID. Arguments: task ID.
+    ManagedDuplicateId,
+    /// The REST API was started without a token.
+    ApiTokenEmpty,
+    /// The WeCom callback receiver was started without a token.
+    WeComTokenEmpty,
+    /// The WeCom EncodingAESKey is not a valid 43-character key.
+    WeComAesKeyInvalid,
+    /// A deep link is not an add-task link. Arguments: link.
+    UnsupportedLink,
+    /// An add-task link has no class ID.
+    LinkNoClassId,
+    /// An add-task link has a time that is not HH:MM. Arguments: time.
+    LinkInvalidTime,
+    /// A link has an unknown coordinate system. Arguments: value.
+    LinkInvalidCoordSystem,
+    /// A date range ends before it starts.
+    RangeReversed,
+    /// A date range is longer than allowed. Arguments: maximum days.
+    RangeTooLong,
+    /// A run ID is not a valid ID. Arguments: run ID.
+    InvalidRunId,
+    /// An export path has an unknown extension. Arguments: extension.
+    UnsupportedExportFormat,
+    /// Description of an exported calendar event. Arguments: class ID.
+    CalendarDescription,
+    /// Tray header and tooltip for the next run. Arguments: task name, time.
+    TrayNextRun,
+    /// Tray header and tooltip when nothing is scheduled.
+    TrayNoUpcomingRun,
+    /// Tray item that shows the main window.
+    TrayShow,
+    /// Tray submenu for running a single task.
+    TrayRunNow,
+    /// Tray item that runs every enabled task.
+    TrayRunAll,
+    /// Tray item that installs an update. Arguments: version.
+    TrayUpdateAvailable,
+    /// Tray item that exits the app.
+    TrayQuit,
+    /// Notification title for a sign result. Arguments: task name.
+    SignResultTitle,
+    /// Notification title for a failed sign. Arguments: task name.
+    SignFailedTitle,
+    /// Notification body for a sign. Arguments: task name, result, latitude, longitude.
+    SignResultBody,
+    /// Notification title for a broken streak. Arguments: task name.
+    StreakBrokenTitle,
+    /// Notification body for a broken streak. Arguments: days, task name.
+    StreakBrokenBody,
+    /// Notification title for an auto-disabled task. Arguments: task name.
+    TaskDisabledTitle,
+    /// Notification body for an auto-disabled task. Arguments: task name, failures.
+    TaskDisabledBody,
+    /// Notification title for a success rate below the alert threshold. Arguments: task name.
+    SuccessRateLowTitle,
+    /// Notification body for a success rate below the alert threshold.
+    /// Arguments: task name, days, rate, successes, attempts, threshold.
+    SuccessRateLowBody,
+    /// Notification title for scheduled runs that keep finding no session.
+    /// Arguments: task name.
+    SessionsMissingTitle,
+    /// Notification body for scheduled runs that keep finding no session.
+    /// Arguments: task name, number of runs.
+    SessionsMissingBody,
+    /// Hint appended to alerts about a class's check-in pattern. Arguments: usual time.
+    UsualSessionTime,
+    /// Notification title for a session found at an unusual time. Arguments: task name.
+    UnusualSessionTitle,
+    /// Notification body for a session found at an unusual time.
+    /// Arguments: task name, time found.
+    UnusualSessionBody,
+    /// Notification title for an expired cookie. Arguments: task name.
+    CookieExpiredTitle,
+    /// Notification body for an expired cookie. Arguments: task name.
+    CookieExpiredBody,
+    /// Notification title for a location outside the required radius. Arguments: task name.
+    LocationMismatchTitle,
+    /// Notification body for a location outside the required radius.
+    /// Arguments: task name, distance, allowed radius.
+    LocationMismatchBody,
+    /// Notification title for a run that crashed. Arguments: task name.
+    RunCrashedTitle,
+    /// Notification body for a run that crashed. Arguments: task name, panic message.
+    RunCrashedBody,
+    /// Notification title for an anti-bot page. Arguments: task name.
+    SiteBlockedTitle,
+    /// Notification body for an anti-bot page.
+    /// Arguments: task name, page kind and artifact path, pause in minutes.
+    SiteBlockedBody,
+    /// Notification title for a punch listing that no longer matches the site profile.
+    /// Arguments: task name.
+    LayoutChangedTitle,
+    /// Notification body for a punch listing that no longer matches the site profile.
+    /// Arguments: task name, changes.
+    LayoutChangedBody,
+    /// Local notification title reminding the user of a run the OS may not wake the app for.
+    /// Arguments: task name.
+    #[cfg_attr(not(mobile), allow(dead_code))]
+    RunReminderTitle,
+    /// Local notification body reminding the user of a run the OS may not wake the app for.
+    #[cfg_attr(not(mobile), allow(dead_code))]
+    RunReminderBody,
+    /// Name of a captcha page.
+    BlockCaptcha,
+    /// Name of a firewall page.
+    BlockWaf,
+    /// Name of a rate-limit page.
+    BlockRateLimit,
+    /// Header line of WeCom messages.
+    WeComHeader,
+    /// Footer line of WeCom messages. Arguments: time.
+    WeComTime,
+    /// Chat command help: the `status` command.
+    RemoteHelpStatus,
+    /// Chat command help: the `run` command.
+    RemoteHelpRun,
+    /// Chat command help: the `pause` command.
+    RemoteHelpPause,
+    /// Chat command help: the `resume` command.
+    RemoteHelpResume,
+    /// Chat command help: the `lastlog` command.
+    RemoteHelpLastLog,
+    /// Chat reply to `run` without a task.
+    RemoteRunUsage,
+    /// Chat reply to a started run. Arguments: task name.
+    RemoteStarted,
+    /// Chat reply to `status` without tasks.
+    RemoteNoTasks,
+    /// Last result of a task in the chat status. Arguments: outcome, time.
+    RemoteLastRun,
+    /// Last result of a task that never ran, in the chat status.
+    RemoteNeverRun,
+    /// Marker of an enabled task in the chat status.
+    RemoteTaskOn,
+    /// Marker of a disabled task in the chat status.
+    RemoteTaskPaused,
+    /// Last line of the chat status. Arguments: task name, time.
+    RemoteNextRun,
+    /// Chat reply to resumed tasks. Arguments: task names.
+    RemoteResumed,
+    /// Chat reply to paused tasks. Arguments: task names.
+    RemotePaused,
+    /// Chat reply to `resume` when every task is enabled.
+    RemoteNothingToResume,
+    /// Chat reply to `pause` when every task is disabled.
+    RemoteNothingToPause,
+    /// Chat reply to `lastlog` with an empty log.
+    RemoteNoLogs,
+    /// A chat command named no known task. Arguments: query.
+    RemoteNoTaskMatch,
+    /// A chat command named several tasks. Arguments: query, task names.
+    RemoteAmbiguousTask,
+}
+
+/// Sets the locale used for subsequent messages.
+///
+/// # Arguments
+///
+/// * `locale` - The configured locale.
+pub fn set_locale(locale: Locale) {
+    *LOCALE.lock().unwrap() = locale;
+}
+
+/// Looks up a message in the current locale.
+///
+/// # Arguments
+///
+/// * `msg` - The message to translate.
+///
+/// # Returns
+///
+/// * `&'static str` - The translated text, with `{}` placeholders left in place.
+pub fn t(msg: Msg) -> &'static str {
+    let locale = *LOCALE.lock().unwrap();
+    match locale {
+        Locale::ZhCn => zh_cn(msg),
+        Locale::En => en(msg),
+    }
+}
+
+/// Looks up a message in the current locale and fills its placeholders.
+///
+/// # Arguments
+///
+/// * `msg` - The message to translate.
+/// * `args` - Values substituted for the `{}` placeholders, in order.
+///
+/// # Returns
+///
+/// * `String` - The translated text.
+pub fn tf(msg: Msg, args: &[&dyn Display]) -> String {
+    let mut parts = t(msg).split("{}");
+    let mut out = parts.next().unwrap_or_default().to_string();
+    for (i, part) in parts.enumerate() {
+        if let Some(arg) = args.get(i) {
+            out.push_str(&arg.to_string());
+        }
+        out.push_str(part);
+    }
+    out
+}
+
+/// The Simplified Chinese catalog.
+fn zh_cn(msg: Msg) -> &'static str {
+    match msg {
+        Msg::TaskNotFound => "未找到任务",
+        Msg::AccountNotFound => "未找到账号",
+        Msg::AccountInUse => "仍有 {} 个任务使用该账号",
+        Msg::PasswordMissing => "该签到需要密码，请在任务中设置签到密码",
+        Msg::TaskDisabled => "任务已停用",
+        Msg::ConfirmationRequired => "操作未经确认或确认已过期，请重试",
+        Msg::UnknownAction => "操作 {} 无需确认",
+        Msg::NoLoginInProgress => "没有进行中的登录，请重新获取二维码",
+        Msg::QrParamsMissing => "无法从登录页面获取二维码参数",
+        Msg::LoginNoCookie => "登录已完成，但签到网站没有返回登录 Cookie",
+        Msg::ConfirmTitle => "确认操作",
+        Msg::ConfirmDeleteTask => "确定要删除该任务吗？",
+        Msg::ConfirmDeleteAccount => "确定要删除该账号吗？",
+        Msg::ConfirmPurgeStorage => "确定要清除所选的本地数据吗？此操作无法撤销。",
+        Msg::ConfirmUpdateConfig => "保存后将删除部分任务或账号，确定继续吗？",
+        Msg::RateLimited => "{} 调用过于频繁，请稍后再试",
+        Msg::DevTimeDisabled => "未启用加速时间模式",
+        Msg::NoUpdateAvailable => "没有可用的更新",
+        Msg::InvalidReleaseTag => "版本标签 {} 无效：{}",
+        Msg::UpdaterNotConfigured => "此版本未配置更新签名公钥，无法检查或安装更新",
+        Msg::NoCalendarEvents => "日历中没有找到带时间的事件",
+        Msg::UnknownLogLevel => "未知的日志级别：{}",
+        Msg::NoCoordinatesInLink => "链接中没有找到坐标",
+        Msg::GeocodingKeyMissing => "当前地理编码服务需要 API Key，请在设置中填写",
+        Msg::IpLocationFailed => "IP 定位失败：{}",
+        Msg::IpLocationIncomplete => "IP 定位没有返回坐标",
+        Msg::LatitudeNotNumber => "纬度不是有效的数字",
+        Msg::LongitudeNotNumber => "经度不是有效的数字",
+        Msg::CoordinatesOutOfRange => {
+            "坐标超出范围：纬度应在 -90 到 90 之间，经度应在 -180 到 180 之间"
+        }
+        Msg::CoordinatesSwapped => "经纬度似乎填反了：纬度（如 39.9）在前，经度（如 116.4）在后",
+        Msg::CoordinatesUnset => "位置为 0,0，请先在地图上选择签到位置",
+        Msg::InvalidShareCode => "无效的位置分享码",
+        Msg::InvalidSchedule => "无效的定时表达式 \"{}\"：无法识别 \"{}\"",
+        Msg::NoHarRecorded => "该任务没有请求记录，请开启调试模式后运行一次",
+        Msg::ShuttingDown => "应用正在退出，无法启动新的运行",
+        Msg::TasksManaged => "任务由远程任务列表统一管理，只能修改 Cookie 和启用状态",
+        Msg::ServiceUnsupported => "当前系统不支持安装后台服务",
+        Msg::HomeNotSet => "未设置 HOME 环境变量",
+        Msg::ManagedUrlMissing => "未设置远程任务列表地址",
+        Msg::ManagedKeyMissing => "未设置远程任务列表的公钥",
+        Msg::ProfileSourceMissing => "未设置站点配置的地址或公钥",
+        Msg::ProfileInvalidMinVersion => "站点配置的 min_app_version 无效：{}",
+        Msg::ProfileNeedsNewerApp => "站点配置需要 {} 或更高版本的应用",
+        Msg::InvalidPublicKey => "公钥无效：{}",
+        Msg::InvalidSignature => "签名无效：{}",
+        Msg::SignatureMismatch => "签名校验失败：{}",
+        Msg::ManagedTaskNoId => "任务 \"{}\" 没有 ID",
+        Msg::ManagedDuplicateId => "任务 ID 重复：{}",
+        Msg::ApiTokenEmpty => "API 令牌为空",
+        Msg::WeComTokenEmpty => "回调 Token 为空",
+        Msg::WeComAesKeyInvalid => "EncodingAESKey 必须是企业微信后台显示的 43 位字符",
+        Msg::UnsupportedLink => "不支持的链接：{}",
+        Msg::LinkNoClassId => "链接中没有 class_id",
+        Msg::LinkInvalidTime => "链接中的时间无效：{}",
+        Msg::LinkInvalidCoordSystem => "链接中的坐标系无效：{}",
+        Msg::RangeReversed => "结束日期早于开始日期",
+        Msg::RangeTooLong => "日期范围超过 {} 天",
+        Msg::InvalidRunId => "无效的运行 ID：{}",
+        Msg::UnsupportedExportFormat => "不支持的导出格式：.{}",
+        Msg::CalendarDescription => "班级 {} 自动签到",
+        Msg::TrayNextRun => "下次：{} {}",
+        Msg::TrayNoUpcomingRun => "暂无计划任务",
+        Msg::TrayShow => "显示主窗口",
+        Msg::TrayRunNow => "立即运行",
+        Msg::TrayRunAll => "立即运行所有已启用任务",
+        Msg::TrayUpdateAvailable => "发现新版本：v{}（点击安装）",
+        Msg::TrayQuit => "退出",
+        Msg::SignResultTitle => "{} 签到结果",
+        Msg::SignFailedTitle => "{} 签到失败",
+        Msg::SignResultBody => "任务 [{}] 结果：{}（位置：{},{}）",
+        Msg::StreakBrokenTitle => "{} 连续签到中断",
+        Msg::StreakBrokenBody => "{} 天的连续签到记录（{}）已中断。",
+        Msg::TaskDisabledTitle => "{} 已停用",
+        Msg::TaskDisabledBody => "{} 已连续失败 {} 次并被停用。请检查 Cookie 和位置后重新启用。",
+        Msg::SuccessRateLowTitle => "{} 签到成功率下降",
+        Msg::SuccessRateLowBody => {
+            "{} 最近 {} 天的签到成功率为 {}%（{}/{}），低于设定的 {}%。请检查位置和 Cookie。"
+        }
+        Msg::SessionsMissingTitle => "{} 多次未发现签到",
+        Msg::SessionsMissingBody => {
+            "{} 最近 {} 次按计划运行都没有发现进行中的签到，课程时间可能已调整，请检查任务时间。"
+        }
+        Msg::UsualSessionTime => "该课程的签到通常在 {} 左右开始。",
+        Msg::UnusualSessionTitle => "{} 出现非常规签到",
+        Msg::UnusualSessionBody => {
+            "{} 在 {} 发现了签到，这个时间没有任何任务覆盖。如果这是新的上课时间，请添加或调整任务。"
+        }
+        Msg::CookieExpiredTitle => "{} Cookie 已失效",
+        Msg::CookieExpiredBody => "{} 的登录状态已失效，请重新扫码登录。",
+        Msg::LocationMismatchTitle => "{} 位置超出范围",
+        Msg::LocationMismatchBody => {
+            "{} 配置的位置距离签到要求的位置 {} 米，超出允许的 {} 米。请更新任务位置。"
+        }
+        Msg::RunCrashedTitle => "{} 运行出错",
+        Msg::RunCrashedBody => {
+            "{} 运行时发生内部错误：{}。已记录崩溃报告，下次计划时间会照常运行。"
+        }
+        Msg::SiteBlockedTitle => "{} 需要手动处理",
+        Msg::SiteBlockedBody => {
+            "{} 的签到网站返回了拦截页面：{}。任务将暂停 {} 分钟，请在微信中打开课程页面完成验证。"
+        }
+        Msg::LayoutChangedTitle => "{} 签到页面结构已变化",
+        Msg::LayoutChangedBody => {
+            "{} 的签到页面与当前解析规则不再匹配：{}。签到可能无法识别，请更新应用或站点配置。"
+        }
+        Msg::RunReminderTitle => "{} 即将签到",
+        Msg::RunReminderBody => "如果签到没有自动完成，请打开应用",
+        Msg::BlockCaptcha => "验证码",
+        Msg::BlockWaf => "防火墙拦截",
+        Msg::BlockRateLimit => "访问频率限制",
+        Msg::WeComHeader => "【签到助手】",
+        Msg::WeComTime => "时间：{}",
+        Msg::RemoteHelpStatus => "任务、上次结果和下次运行",
+        Msg::RemoteHelpRun => "立即运行任务",
+        Msg::RemoteHelpPause => "停用一个任务或全部任务",
+        Msg::RemoteHelpResume => "启用一个任务或全部任务",
+        Msg::RemoteHelpLastLog => "最近的日志",
+        Msg::RemoteRunUsage => "用法：run <任务>",
+        Msg::RemoteStarted => "已开始运行 {}",
+        Msg::RemoteNoTasks => "没有配置任务",
+        Msg::RemoteLastRun => "{}（{}）",
+        Msg::RemoteNeverRun => "从未运行",
+        Msg::RemoteTaskOn => "[启用]",
+        Msg::RemoteTaskPaused => "[已暂停]",
+        Msg::RemoteNextRun => "下次：{}，{}",
+        Msg::RemoteResumed => "已恢复 {}",
+        Msg::RemotePaused => "已暂停 {}",
+        Msg::RemoteNothingToResume => "无需更改：所有任务都已启用",
+        Msg::RemoteNothingToPause => "无需更改：所有任务都已暂停",
+        Msg::RemoteNoLogs => "还没有日志",
+        Msg::RemoteNoTaskMatch => "没有与“{}”匹配的任务",
+        Msg::RemoteAmbiguousTask => "“{}”匹配多个任务：{}",
+    }
+}
+
+/// The English catalog.
+fn en(msg: Msg) -> &'static str {
+    match msg {
+        Msg::TaskNotFound => "Task not found",
+        Msg::AccountNotFound => "Account not found",
+        Msg::AccountInUse => "The account is still used by {} task(s)",
+        Msg::PasswordMissing => "This check-in needs a password; set one on the task",
+        Msg::TaskDisabled => "Task is disabled",
+        Msg::ConfirmationRequired => {
+            "The action was not confirmed or the confirmation expired; try again"
+        }
+        Msg::UnknownAction => "Action {} needs no confirmation",
+        Msg::NoLoginInProgress => "No login in progress; fetch a new QR code",
+        Msg::QrParamsMissing => "Could not extract QR params from the login page",
+        Msg::LoginNoCookie => "Login completed without a session cookie",
+        Msg::ConfirmTitle => "Confirm",
+        Msg::ConfirmDeleteTask => "Delete this task?",
+        Msg::ConfirmDeleteAccount => "Delete this account?",
+        Msg::ConfirmPurgeStorage => "Delete the selected local data? This cannot be undone.",
+        Msg::ConfirmUpdateConfig => "Saving removes some tasks or accounts. Continue?",
+        Msg::RateLimited => "{} was called too often; try again later",
+        Msg::DevTimeDisabled => "Accelerated time is not enabled",
+        Msg::NoUpdateAvailable => "No update available",
+        Msg::InvalidReleaseTag => "Invalid release tag {}: {}",
+        Msg::UpdaterNotConfigured => {
+            "This build has no update signing key, so updates cannot be checked or installed"
+        }
+        Msg::NoCalendarEvents => "No timed events found in calendar",
+        Msg::UnknownLogLevel => "Unknown log level: {}",
+        Msg::NoCoordinatesInLink => "No coordinates found in map link",
+        Msg::GeocodingKeyMissing => "The geocoding provider requires an API key in settings",
+        Msg::IpLocationFailed => "IP location failed: {}",
+        Msg::IpLocationIncomplete => "IP location returned no coordinates",
+        Msg::LatitudeNotNumber => "Latitude is not a valid number",
+        Msg::LongitudeNotNumber => "Longitude is not a valid number",
+        Msg::CoordinatesOutOfRange => {
+            "Coordinates out of range: latitude must be -90 to 90, longitude -180 to 180"
+        }
+        Msg::CoordinatesSwapped => {
+            "Latitude and longitude look swapped: latitude (e.g. 39.9) comes first, \
+             longitude (e.g. 116.4) second"
+        }
+        Msg::CoordinatesUnset => "Location is 0,0; pick the check-in location on the map first",
+        Msg::InvalidShareCode => "Invalid location share code",
+        Msg::InvalidSchedule => "Invalid schedule \"{}\": cannot read \"{}\"",
+        Msg::NoHarRecorded => "No request log for this task; run it once with debug mode on",
+        Msg::ShuttingDown => "The app is quitting; no new runs are started",
+        Msg::TasksManaged => {
+            "Tasks come from a managed task list; only the cookie and whether a task is enabled \
+             can be changed"
+        }
+        Msg::ServiceUnsupported => "Background services are not supported on this system",
+        Msg::HomeNotSet => "HOME is not set",
+        Msg::ManagedUrlMissing => "Managed task list URL is not set",
+        Msg::ManagedKeyMissing => "Public key of the managed task list is not set",
+        Msg::ProfileSourceMissing => "Site profile URL or public key is not set",
+        Msg::ProfileInvalidMinVersion => "Invalid min_app_version in site profile: {}",
+        Msg::ProfileNeedsNewerApp => "Site profile requires app version {} or newer",
+        Msg::InvalidPublicKey => "Invalid public key: {}",
+        Msg::InvalidSignature => "Invalid signature: {}",
+        Msg::SignatureMismatch => "Signature check failed: {}",
+        Msg::ManagedTaskNoId => "Task \"{}\" has no ID",
+        Msg::ManagedDuplicateId => "Duplicate task ID: {}",
+        Msg::ApiTokenEmpty => "The API token is empty",
+        Msg::WeComTokenEmpty => "The callback token is empty",
+        Msg::WeComAesKeyInvalid => {
+            "The EncodingAESKey must be the 43 characters shown in the WeCom console"
+        }
+        Msg::UnsupportedLink => "Unsupported link: {}",
+        Msg::LinkNoClassId => "The link has no class_id",
+        Msg::LinkInvalidTime => "Invalid time in link: {}",
+        Msg::LinkInvalidCoordSystem => "Invalid coordinate system in link: {}",
+        Msg::RangeReversed => "The end of the range is before its start",
+        Msg::RangeTooLong => "The range is longer than {} days",
+        Msg::InvalidRunId => "Invalid run ID: {}",
+        Msg::UnsupportedExportFormat => "Unsupported export format: .{}",
+        Msg::CalendarDescription => "Auto check-in for class {}",
+        Msg::TrayNextRun => "Next: {} at {}",
+        Msg::TrayNoUpcomingRun => "No upcoming runs",
+        Msg::TrayShow => "Show",
+        Msg::TrayRunNow => "Run now",
+        Msg::TrayRunAll => "Run all enabled tasks now",
+        Msg::TrayUpdateAvailable => "Update available: v{} (install)",
+        Msg::TrayQuit => "Quit",
+        Msg::SignResultTitle => "{} Check-in Result",
+        Msg::SignFailedTitle => "{} Check-in Failed",
+        Msg::SignResultBody => "Task [{}] Result: {} (Loc: {},{})",
+        Msg::StreakBrokenTitle => "{} Streak Broken",
+        Msg::StreakBrokenBody => "The {}-day check-in streak for {} has been broken.",
+        Msg::TaskDisabledTitle => "{} Disabled",
+        Msg::TaskDisabledBody => {
+            "{} failed {} times in a row and has been disabled. \
+             Check the cookie and location, then re-enable it."
+        }
+        Msg::SuccessRateLowTitle => "{} Success Rate Dropped",
+        Msg::SuccessRateLowBody => {
+            "{}: the success rate over the last {} days is {}% ({}/{}), \
+             below the alert threshold of {}%. Check the location and cookie."
+        }
+        Msg::SessionsMissingTitle => "{} Keeps Finding No Session",
+        Msg::SessionsMissingBody => {
+            "{}: the last {} scheduled runs found no open check-in. \
+             The class may have moved; check the task's time."
+        }
+        Msg::UsualSessionTime => "Its check-ins usually open around {}.",
+        Msg::UnusualSessionTitle => "{} Check-in at an Unusual Time",
+        Msg::UnusualSessionBody => {
+            "{} found a check-in at {}, a time no task covers. \
+             If the class has a new slot, add or adjust a task."
+        }
+        Msg::CookieExpiredTitle => "{} Cookie Expired",
+        Msg::CookieExpiredBody => "The login session for {} has expired. Please log in again.",
+        Msg::LocationMismatchTitle => "{} Location Out of Range",
+        Msg::LocationMismatchBody => {
+            "The configured location of {} is {} m from the required location, \
+             more than the allowed {} m. Please update the task location."
+        }
+        Msg::RunCrashedTitle => "{} Run Crashed",
+        Msg::RunCrashedBody => {
+            "The run of {} hit an internal error: {}. A crash report was saved; the task \
+             will run again at its next scheduled time."
+        }
+        Msg::SiteBlockedTitle => "{} Needs Manual Action",
+        Msg::SiteBlockedBody => {
+            "The check-in site showed a blocking page for {}: {}. The task is paused for {} \
+             minutes; open the course page in WeChat and complete the verification."
+        }
+        Msg::LayoutChangedTitle => "{} Check-in Page Changed",
+        Msg::LayoutChangedBody => {
+            "The check-in page of {} no longer matches the parsing rules: {}. \
+             Sessions may go unnoticed; update the app or the site profile."
+        }
+        Msg::RunReminderTitle => "{} is due",
+        Msg::RunReminderBody => "Open the app if the check-in does not run on its own",
+        Msg::BlockCaptcha => "captcha",
+        Msg::BlockWaf => "firewall block",
+        Msg::BlockRateLimit => "rate limit",
+        Msg::WeComHeader => "【Checkin Magic】",
+        Msg::WeComTime => "Time: {}",
+        Msg::RemoteHelpStatus => "Tasks, last results and the next run",
+        Msg::RemoteHelpRun => "Run a task now",
+        Msg::RemoteHelpPause => "Disable a task, or all tasks",
+        Msg::RemoteHelpResume => "Enable a task, or all tasks",
+        Msg::RemoteHelpLastLog => "Recent log lines",
+        Msg::RemoteRunUsage => "Usage: run <task>",
+        Msg::RemoteStarted => "Started {}",
+        Msg::RemoteNoTasks => "No tasks configured",
+        Msg::RemoteLastRun => "{} at {}",
+        Msg::RemoteNeverRun => "never run",
+        Msg::RemoteTaskOn => "[on]",
+        Msg::RemoteTaskPaused => "[paused]",
+        Msg::RemoteNextRun => "Next: {} at {}",
+        Msg::RemoteResumed => "Resumed {}",
+        Msg::RemotePaused => "Paused {}",
+        Msg::RemoteNothingToResume => "Nothing to change: all tasks are already resumed",
+        Msg::RemoteNothingToPause => "Nothing to change: all tasks are already paused",
+        Msg::RemoteNoLogs => "No log lines yet",
+        Msg::RemoteNoTaskMatch => "No task matches \"{}\"",
+        Msg::RemoteAmbiguousTask => "\"{}\" matches several tasks: {}",
+    }
+}
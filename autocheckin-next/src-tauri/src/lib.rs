@@ -1,71 +1,171 @@
+mod analytics;
+mod anchor;
+mod anomaly;
+mod api;
 mod auth;
+mod calendar;
+mod cli;
 mod config;
+mod cookie;
+mod crash;
+mod cron;
+mod deeplink;
+mod devtime;
+mod drift;
+mod events;
+mod export;
+mod geo;
+mod geocode;
+mod guard;
+mod har;
+mod headers;
+mod health;
+mod heartbeat;
+mod history;
+mod hooks;
+mod httpd;
+mod httplog;
+mod i18n;
+mod logs;
+mod managed;
+mod maplink;
+mod metrics;
+mod mobile;
+#[cfg(feature = "mock-site")]
+pub mod mock;
+mod mqtt;
+mod preset;
+mod profile;
+mod receipt;
+mod release;
+mod remote;
+mod report;
+mod retry;
 mod scheduler;
+mod service;
+mod spans;
+mod storage;
 mod task;
+mod telegram;
+mod throttle;
+mod timeline;
+mod tls;
+mod tray;
+#[cfg(desktop)]
+mod updater;
+mod webhooks;
+mod wecom;
 
-use crate::auth::AuthHandler;
-use crate::config::{load_config, save_config, AppConfig, ConfigState, Task};
-use crate::scheduler::start_scheduler;
+use crate::analytics::FailureAnalytics;
+use crate::anomaly::Anomaly;
+use crate::auth::{AuthHandler, LoginSession, LoginState};
+use crate::calendar::{parse_ics, render_ics};
+use crate::config::{load_config, save_config, Account, AppConfig, ConfigState, Task, TaskPage};
+use crate::crash::CrashReport;
+use crate::deeplink::TaskDraft;
+use crate::geocode::{GeocodeCandidate, IpLocation};
+use crate::health::HealthReport;
+use crate::history::{
+    compute_attendance, compute_stats, load_history, AttendanceReport, TaskStats,
+};
+use crate::logs::LogLine;
+use crate::maplink::MapLocation;
+use crate::preset::LocationPreset;
+use crate::receipt::Receipt;
+use crate::release::ReleaseInfo;
+use crate::report::IssueReport;
+use crate::scheduler::{
+    run_task_now as run_task, shared_executor, start_scheduler, ExecutorState, SystemClock,
+};
+use crate::storage::{StorageCategory, StorageReport};
+use crate::task::{SessionStatus, TaskExecutor};
+use crate::timeline::TimelineDay;
+use crate::tray::{create_tray, refresh_tray_menu};
+use chrono::NaiveDate;
 use std::sync::Mutex;
-use tauri::menu::{Menu, MenuItem};
-use tauri::tray::TrayIconBuilder;
 use tauri::{AppHandle, Manager, State};
 
 // Commands
 
 /// Tauri command to fetch a login QR code.
 ///
-/// Uses `AuthHandler` to retrieve a QR code image (Base64 encoded) and a check URL.
+/// Uses `AuthHandler` to retrieve a QR code image (Base64 encoded) and a check URL. The
+/// handler is kept in `LoginState`, so `check_login_status` polls with the same cookies.
+///
+/// # Arguments
+///
+/// * `state` - The application configuration state, for the HTTP timeouts.
+/// * `login` - The login in progress.
 ///
 /// # Returns
 ///
 /// * `Result<(String, String), String>` - Base64 image and check URL, or an error message.
 #[tauri::command]
-fn get_login_qr() -> Result<(String, String), String> {
-    let auth = AuthHandler::new();
-    auth.get_qr_code()
+fn get_login_qr(
+    state: State<ConfigState>,
+    login: State<LoginState>,
+) -> Result<(String, String), String> {
+    let timeouts = state.0.lock().unwrap().global.timeouts;
+    login.start(AuthHandler::new(&timeouts))
 }
 
 /// Tauri command to check the status of a login attempt.
 ///
-/// Polls the provided URL to see if the user has scanned the QR code and logged in.
+/// Polls the provided URL to see if the user has scanned the QR code and logged in, using
+/// the handler `get_login_qr` started.
 ///
 /// # Arguments
 ///
+/// * `login` - The login in progress.
 /// * `url` - The check URL returned by `get_login_qr`.
 ///
 /// # Returns
 ///
-/// * `Result<Option<(String, String)>, String>` - Session info if successful, None if pending, or an error.
+/// * `Result<Option<LoginSession>, String>` - The session cookie and the student's class
+///   IDs if successful, None if pending, or an error.
 #[tauri::command]
-fn check_login_status(url: String) -> Result<Option<(String, String)>, String> {
-    let auth = AuthHandler::new();
-    auth.check_login(&url)
+fn check_login_status(
+    login: State<LoginState>,
+    url: String,
+) -> Result<Option<LoginSession>, String> {
+    guard::rate_limit("check_login_status")?;
+    login.check(&url)
 }
 
 /// Tauri command to retrieve the current application configuration.
 ///
+/// Cookies stay in the backend: tasks and accounts come with `has_cookie` set instead.
+/// Calls are rate limited.
+///
 /// # Arguments
 ///
 /// * `app_handle` - The Tauri application handle.
 ///
 /// # Returns
 ///
-/// * `AppConfig` - The current configuration.
+/// * `Result<AppConfig, String>` - The configuration without cookies, or an error message
+///   if called too often.
 #[tauri::command]
-fn get_config(app_handle: AppHandle) -> AppConfig {
-    load_config(&app_handle)
+fn get_config(app_handle: AppHandle) -> Result<AppConfig, String> {
+    guard::rate_limit("get_config")?;
+    Ok(load_config(&app_handle).redacted())
 }
 
 /// Tauri command to update the application configuration.
 ///
-/// Updates the in-memory state and persists the configuration to disk.
+/// Updates the in-memory state and persists the configuration to disk. A CA bundle that
+/// cannot be loaded is rejected before anything is saved. While tasks come from a managed
+/// task list, the tasks in `new_config` are ignored. A config that drops existing tasks
+/// or accounts needs a confirmation token, like deleting them one by one. Empty cookies,
+/// as in the redacted config from `get_config`, keep the stored ones.
 ///
 /// # Arguments
 ///
 /// * `app_handle` - The Tauri application handle.
 /// * `state` - The managed configuration state.
 /// * `new_config` - The new configuration object.
+/// * `confirm_token` - A token from `request_confirmation` for `update_config`, needed
+///   only when tasks or accounts are removed.
 ///
 /// # Returns
 ///
@@ -74,17 +174,90 @@ fn get_config(app_handle: AppHandle) -> AppConfig {
 fn update_config(
     app_handle: AppHandle,
     state: State<ConfigState>,
-    new_config: AppConfig,
+    mut new_config: AppConfig,
+    confirm_token: Option<String>,
 ) -> Result<(), String> {
+    tls::configure(&new_config.global.tls)?;
+    if managed::is_active(&new_config.global.managed_tasks) {
+        new_config.tasks = state.0.lock().unwrap().tasks.clone();
+    }
+    new_config.restore_cookies(&state.0.lock().unwrap());
+    if removes_entries(&state.0.lock().unwrap(), &new_config) {
+        guard::confirm(
+            "update_config",
+            confirm_token.as_deref().unwrap_or_default(),
+        )?;
+    }
+    new_config.link_accounts();
     save_config(&app_handle, &new_config)?;
+    logs::apply_level(&new_config.global.logging.level, new_config.global.debug);
+    httplog::set_enabled(new_config.global.debug);
+    spans::configure(&new_config.global.tracing);
+    i18n::set_locale(new_config.global.locale);
+    refresh_tray_menu(&app_handle, &new_config.tasks);
+    events::publish("config_changed", serde_json::json!({}));
     *state.0.lock().unwrap() = new_config;
     Ok(())
 }
 
+/// Returns whether saving `new` would drop a task or an account that `current` has.
+fn removes_entries(current: &AppConfig, new: &AppConfig) -> bool {
+    current
+        .tasks
+        .iter()
+        .any(|t| !new.tasks.iter().any(|n| n.id == t.id))
+        || current
+            .accounts
+            .iter()
+            .any(|a| !new.accounts.iter().any(|n| n.id == a.id))
+}
+
+/// Validates a task's schedule, location and anchor locations before it is saved.
+///
+/// An empty location is allowed for disabled drafts (e.g. imported from a calendar) and
+/// when the IP location fallback is enabled.
+fn validate_task(task: &Task, ip_fallback: bool) -> Result<(), String> {
+    if let Some(Err(e)) = task.cron() {
+        return Err(e);
+    }
+    for anchor in &task.anchors {
+        geo::validate_coordinates(&anchor.location.lat, &anchor.location.lng)
+            .map_err(|e| format!("{}: {}", anchor.name, e))?;
+    }
+    let location = &task.location;
+    if location.is_unset() && (!task.enable || ip_fallback) {
+        return Ok(());
+    }
+    geo::validate_coordinates(&location.lat, &location.lng).map(|_| ())
+}
+
+/// Checks that the account a task references exists.
+fn validate_account(config: &AppConfig, task: &Task) -> Result<(), String> {
+    match &task.account_id {
+        Some(id) if !config.accounts.iter().any(|a| &a.id == id) => {
+            Err(i18n::t(i18n::Msg::AccountNotFound).to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Saves a cookie pasted into a task to the task's account, so every task of the account
+/// uses it. An empty cookie leaves the account unchanged.
+fn update_account_cookie(config: &mut AppConfig, task: &Task) {
+    let account = task
+        .account_id
+        .as_ref()
+        .and_then(|id| config.accounts.iter_mut().find(|a| &a.id == id));
+    if let Some(account) = account.filter(|_| !task.cookie.is_empty()) {
+        account.set_cookie(task.cookie.clone());
+    }
+}
+
 /// Tauri command to add a new task.
 ///
-/// Assigns a new UUID to the task if one is not present, adds it to the configuration,
-/// and saves the configuration to disk.
+/// Validates the task's location and account, normalizes its cookie, assigns a new UUID
+/// to the task if one is not present, adds it to the configuration, and saves the
+/// configuration to disk. A cookie pasted into a task without an account creates one.
 ///
 /// # Arguments
 ///
@@ -94,7 +267,8 @@ fn update_config(
 ///
 /// # Returns
 ///
-/// * `Result<(), String>` - Ok on success, error message on failure.
+/// * `Result<(), String>` - Ok on success, error message if the location is invalid or
+///   saving fails.
 #[tauri::command]
 fn add_task(
     app_handle: AppHandle,
@@ -102,17 +276,28 @@ fn add_task(
     mut task: Task,
 ) -> Result<(), String> {
     let mut config = state.0.lock().unwrap();
+    managed::ensure_editable(&config)?;
+    validate_task(&task, config.global.ip_location_fallback)?;
+    validate_account(&config, &task)?;
+    task.cookie = cookie::normalize_cookie(&task.cookie);
     if task.id.is_empty() {
         task.id = uuid::Uuid::new_v4().to_string();
     }
+    update_account_cookie(&mut config, &task);
     config.tasks.push(task);
+    config.link_accounts();
     save_config(&app_handle, &config)?;
+    refresh_tray_menu(&app_handle, &config.tasks);
+    events::publish("config_changed", serde_json::json!({}));
     Ok(())
 }
 
 /// Tauri command to update an existing task.
 ///
-/// Finds the task by ID and updates it. Saves the configuration to disk.
+/// Validates the task's location and account, normalizes its cookie, finds the task by
+/// ID and updates it. Saves the configuration to disk. A changed cookie is saved to the
+/// task's account, for all its tasks; an empty cookie keeps the stored one. While tasks
+/// come from a managed task list, only the cookie, account and enable flag are updated.
 ///
 /// # Arguments
 ///
@@ -124,14 +309,40 @@ fn add_task(
 ///
 /// * `Result<(), String>` - Ok on success, error message if task not found or save fails.
 #[tauri::command]
-fn update_task(app_handle: AppHandle, state: State<ConfigState>, task: Task) -> Result<(), String> {
+fn update_task(
+    app_handle: AppHandle,
+    state: State<ConfigState>,
+    mut task: Task,
+) -> Result<(), String> {
     let mut config = state.0.lock().unwrap();
+    validate_task(&task, config.global.ip_location_fallback)?;
+    validate_account(&config, &task)?;
+    task.cookie = cookie::normalize_cookie(&task.cookie);
+    let managed = managed::is_active(&config.global.managed_tasks);
     if let Some(idx) = config.tasks.iter().position(|t| t.id == task.id) {
-        config.tasks[idx] = task;
+        if task.cookie.is_empty() {
+            task.cookie = config.tasks[idx].cookie.clone();
+        }
+        update_account_cookie(&mut config, &task);
+        let enable = task.enable;
+        task.enable = config.tasks[idx].enable;
+        task.enabled_at = config.tasks[idx].enabled_at;
+        task.set_enable(enable);
+        if managed {
+            config.tasks[idx].cookie = task.cookie;
+            config.tasks[idx].account_id = task.account_id;
+            config.tasks[idx].enable = task.enable;
+            config.tasks[idx].enabled_at = task.enabled_at;
+        } else {
+            config.tasks[idx] = task;
+        }
+        config.link_accounts();
         save_config(&app_handle, &config)?;
+        refresh_tray_menu(&app_handle, &config.tasks);
+        events::publish("config_changed", serde_json::json!({}));
         Ok(())
     } else {
-        Err("Task not found".to_string())
+        Err(i18n::t(i18n::Msg::TaskNotFound).to_string())
     }
 }
 
@@ -144,74 +355,1076 @@ fn update_task(app_handle: AppHandle, state: State<ConfigState>, task: Task) ->
 /// * `app_handle` - The Tauri application handle.
 /// * `state` - The managed configuration state.
 /// * `task_id` - The ID of the task to delete.
+/// * `confirm_token` - A token from `request_confirmation` for `delete_task`.
 ///
 /// # Returns
 ///
-/// * `Result<(), String>` - Ok on success, error message if task not found or save fails.
+/// * `Result<(), String>` - Ok on success, error message if the token is invalid, the task
+///   is not found or saving fails.
 #[tauri::command]
 fn delete_task(
     app_handle: AppHandle,
     state: State<ConfigState>,
     task_id: String,
+    confirm_token: String,
 ) -> Result<(), String> {
+    guard::confirm("delete_task", &confirm_token)?;
     let mut config = state.0.lock().unwrap();
+    managed::ensure_editable(&config)?;
     if let Some(idx) = config.tasks.iter().position(|t| t.id == task_id) {
         config.tasks.remove(idx);
         save_config(&app_handle, &config)?;
+        refresh_tray_menu(&app_handle, &config.tasks);
+        events::publish("config_changed", serde_json::json!({}));
         Ok(())
     } else {
-        Err("Task not found".to_string())
+        Err(i18n::t(i18n::Msg::TaskNotFound).to_string())
+    }
+}
+
+/// Tauri command to add a site account.
+///
+/// Normalizes the account's cookie, assigns a new UUID if one is not present and fills
+/// in the site's host as the base domain if none is given.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `state` - The managed configuration state.
+/// * `account` - The account to add.
+///
+/// # Returns
+///
+/// * `Result<Account, String>` - The saved account without its cookie, or an error message
+///   if saving fails.
+#[tauri::command]
+fn add_account(
+    app_handle: AppHandle,
+    state: State<ConfigState>,
+    mut account: Account,
+) -> Result<Account, String> {
+    let mut config = state.0.lock().unwrap();
+    account.cookie = cookie::normalize_cookie(&account.cookie);
+    if account.id.is_empty() {
+        account.id = uuid::Uuid::new_v4().to_string();
+    }
+    if account.base_domain.trim().is_empty() {
+        account.base_domain = config.global.endpoints.host();
+    }
+    config.accounts.push(account.clone());
+    save_config(&app_handle, &config)?;
+    events::publish("config_changed", serde_json::json!({}));
+    account.redact();
+    Ok(account)
+}
+
+/// Tauri command to update a site account.
+///
+/// The new cookie takes effect for every task referencing the account. A new cookie clears
+/// an expiry that has passed; an empty one keeps the stored cookie.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `state` - The managed configuration state.
+/// * `account` - The updated account (must have a matching ID).
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Ok on success, error message if the account is not found or
+///   saving fails.
+#[tauri::command]
+fn update_account(
+    app_handle: AppHandle,
+    state: State<ConfigState>,
+    mut account: Account,
+) -> Result<(), String> {
+    let mut config = state.0.lock().unwrap();
+    account.cookie = cookie::normalize_cookie(&account.cookie);
+    let Some(idx) = config.accounts.iter().position(|a| a.id == account.id) else {
+        return Err(i18n::t(i18n::Msg::AccountNotFound).to_string());
+    };
+    let cookie = std::mem::take(&mut account.cookie);
+    account.cookie = config.accounts[idx].cookie.clone();
+    if !cookie.is_empty() {
+        account.set_cookie(cookie);
     }
+    config.accounts[idx] = account;
+    config.link_accounts();
+    save_config(&app_handle, &config)?;
+    events::publish("config_changed", serde_json::json!({}));
+    Ok(())
 }
 
+/// Tauri command to delete a site account that no task references.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `state` - The managed configuration state.
+/// * `account_id` - The ID of the account to delete.
+/// * `confirm_token` - A token from `request_confirmation` for `delete_account`.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Ok on success, error message if the token is invalid, the
+///   account is not found or still in use, or saving fails.
+#[tauri::command]
+fn delete_account(
+    app_handle: AppHandle,
+    state: State<ConfigState>,
+    account_id: String,
+    confirm_token: String,
+) -> Result<(), String> {
+    guard::confirm("delete_account", &confirm_token)?;
+    let mut config = state.0.lock().unwrap();
+    let Some(idx) = config.accounts.iter().position(|a| a.id == account_id) else {
+        return Err(i18n::t(i18n::Msg::AccountNotFound).to_string());
+    };
+    let users = config
+        .tasks
+        .iter()
+        .filter(|t| t.account_id.as_deref() == Some(account_id.as_str()))
+        .count();
+    if users > 0 {
+        return Err(i18n::tf(i18n::Msg::AccountInUse, &[&users]));
+    }
+    config.accounts.remove(idx);
+    save_config(&app_handle, &config)?;
+    events::publish("config_changed", serde_json::json!({}));
+    Ok(())
+}
+
+/// Tauri command to run a task immediately, outside its schedule.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `task_id` - The ID of the task to run.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Ok once the run is started, error message if the task is
+///   missing or disabled.
+#[tauri::command]
+fn run_task_now(app_handle: AppHandle, task_id: String) -> Result<(), String> {
+    run_task(&app_handle, &task_id)
+}
+
+/// Tauri command to search tasks with filtering and pagination.
+///
+/// `query` matches case-insensitively against the task name, the class ID and the name
+/// and ID of the task's account, `tag` must be one of the task's tags, and `enabled`
+/// filters on the enable flag. Omitted filters match everything.
+///
+/// # Arguments
+///
+/// * `state` - The managed configuration state.
+/// * `query` - Optional text to search for.
+/// * `tag` - Optional tag the task must carry.
+/// * `enabled` - Optional enable state to match.
+/// * `page` - 1-based page number (defaults to 1).
+/// * `page_size` - Tasks per page (defaults to 20).
+///
+/// # Returns
+///
+/// * `TaskPage` - The matching tasks on the requested page, without their cookies, and the
+///   total match count.
+#[tauri::command]
+fn search_tasks(
+    state: State<ConfigState>,
+    query: Option<String>,
+    tag: Option<String>,
+    enabled: Option<bool>,
+    page: Option<usize>,
+    page_size: Option<usize>,
+) -> TaskPage {
+    let config = state.0.lock().unwrap();
+    let query = query
+        .map(|q| q.trim().to_lowercase())
+        .filter(|q| !q.is_empty());
+    let page = page.unwrap_or(1).max(1);
+    let page_size = page_size.filter(|s| *s > 0).unwrap_or(20);
+
+    let matches: Vec<&Task> = config
+        .tasks
+        .iter()
+        .filter(|t| enabled.is_none_or(|e| t.enable == e))
+        .filter(|t| tag.as_ref().is_none_or(|tag| t.tags.contains(tag)))
+        .filter(|t| {
+            query.as_ref().is_none_or(|q| {
+                let account = t
+                    .account_id
+                    .as_ref()
+                    .and_then(|id| config.accounts.iter().find(|a| &a.id == id));
+                t.name.to_lowercase().contains(q)
+                    || t.class_id.to_lowercase().contains(q)
+                    || account.is_some_and(|a| {
+                        a.name.to_lowercase().contains(q) || a.id.to_lowercase().contains(q)
+                    })
+            })
+        })
+        .collect();
+
+    TaskPage {
+        total: matches.len(),
+        items: matches
+            .into_iter()
+            .skip((page - 1) * page_size)
+            .take(page_size)
+            .map(|t| {
+                let mut task = t.clone();
+                task.redact();
+                task
+            })
+            .collect(),
+        page,
+        page_size,
+    }
+}
+
+/// Tauri command to compute run statistics for a task.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `task_id` - The ID of the task.
+/// * `range` - Number of days to look back; all history if omitted.
+///
+/// # Returns
+///
+/// * `TaskStats` - Success rate, latency, failure breakdown and recent outcomes.
+#[tauri::command]
+fn get_task_stats(app_handle: AppHandle, task_id: String, range: Option<u32>) -> TaskStats {
+    compute_stats(&load_history(&app_handle), &task_id, range)
+}
+
+/// Tauri command to list scheduled, actual, unscheduled and missed runs per day, for a
+/// calendar view.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `state` - The managed configuration state.
+/// * `from` - The first day.
+/// * `to` - The last day, inclusive.
+///
+/// # Returns
+///
+/// * `Result<Vec<TimelineDay>, String>` - One entry per day of the range, or an error
+///   message if the range is invalid.
+#[tauri::command]
+fn get_timeline(
+    app_handle: AppHandle,
+    state: State<ConfigState>,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<TimelineDay>, String> {
+    let tasks = state.0.lock().unwrap().tasks.clone();
+    timeline::build_timeline(
+        &tasks,
+        &load_history(&app_handle),
+        from,
+        to,
+        chrono::Local::now().naive_local(),
+    )
+}
+
+/// Tauri command to list deviations from the classes' usual check-in patterns.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `state` - The configuration state.
+///
+/// # Returns
+///
+/// * `Vec<Anomaly>` - Tasks whose scheduled runs keep finding no session, and recent
+///   sessions found at times no task covers.
+#[tauri::command]
+fn get_anomalies(app_handle: AppHandle, state: State<ConfigState>) -> Vec<Anomaly> {
+    let config = state.0.lock().unwrap().clone();
+    anomaly::detect(
+        &config.tasks,
+        &load_history(&app_handle),
+        &config.global.anomalies,
+    )
+}
+
+/// Tauri command to break down failed runs by cause, weekday and task.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `range` - Number of days to look back; all history if omitted.
+///
+/// # Returns
+///
+/// * `FailureAnalytics` - Failure counts per category, weekday and task.
+#[tauri::command]
+fn get_failure_analytics(app_handle: AppHandle, range: Option<u32>) -> FailureAnalytics {
+    analytics::failure_analytics(&load_history(&app_handle), range)
+}
+
+/// Tauri command to tally a course's attendance per day.
+///
+/// Combines the sessions synced from the site with the app's own sign attempts.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `class_id` - The class ID of the course.
+/// * `range` - Number of days to look back; everything if omitted.
+///
+/// # Returns
+///
+/// * `AttendanceReport` - Present, late and absent counts per day and in total.
+#[tauri::command]
+fn get_attendance_report(
+    app_handle: AppHandle,
+    class_id: String,
+    range: Option<u32>,
+) -> AttendanceReport {
+    compute_attendance(
+        &load_history(&app_handle),
+        &history::load_attendance(&app_handle),
+        &class_id,
+        range,
+    )
+}
+
+/// Tauri command to import a course's past check-in sessions from the site.
+///
+/// Reads the punch history with the task's cookie and stores the date, attendance and
+/// signer of each session, so reports also cover days the app was not running.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `state` - The managed configuration state.
+/// * `task_id` - The task whose course and cookie are used.
+///
+/// # Returns
+///
+/// * `Result<usize, String>` - The number of sessions not imported before, or an error
+///   message if the task is missing or the history cannot be read.
+#[tauri::command]
+fn sync_attendance(
+    app_handle: AppHandle,
+    state: State<ConfigState>,
+    task_id: String,
+) -> Result<usize, String> {
+    let (task, global) = {
+        let config = state.0.lock().unwrap();
+        let task = config
+            .tasks
+            .iter()
+            .find(|t| t.id == task_id)
+            .cloned()
+            .ok_or(i18n::t(i18n::Msg::TaskNotFound))?;
+        (task, config.global.clone())
+    };
+    let records = shared_executor(&app_handle, &global).fetch_attendance(&task)?;
+    history::merge_attendance(&app_handle, records)
+}
+
+/// Tauri command to check whether the site still accepts a session cookie.
+///
+/// Checks the cookie of a task, or a raw cookie for a class when no task is given, with a
+/// single request for the course page.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `state` - The managed configuration state.
+/// * `task_id` - The task whose course and cookie are checked.
+/// * `cookie` - A cookie to check instead of a task's.
+/// * `class_id` - The class whose course page is requested with `cookie`.
+///
+/// # Returns
+///
+/// * `Result<SessionStatus, String>` - Whether the session is valid and the student's name,
+///   or an error message if the task is missing or the site could not be reached.
+#[tauri::command]
+fn validate_cookie(
+    app_handle: AppHandle,
+    state: State<ConfigState>,
+    task_id: Option<String>,
+    cookie: Option<String>,
+    class_id: Option<String>,
+) -> Result<SessionStatus, String> {
+    guard::rate_limit("validate_cookie")?;
+    let (task, global) = {
+        let config = state.0.lock().unwrap();
+        let task = match task_id {
+            Some(task_id) => config
+                .tasks
+                .iter()
+                .find(|t| t.id == task_id)
+                .cloned()
+                .ok_or(i18n::t(i18n::Msg::TaskNotFound))?,
+            None => Task {
+                cookie: cookie::normalize_cookie(&cookie.unwrap_or_default()),
+                class_id: class_id.unwrap_or_default(),
+                ..Task::default()
+            },
+        };
+        (task, config.global.clone())
+    };
+    if task.id.is_empty() {
+        // A fresh executor, so the raw cookie does not share a cached session.
+        TaskExecutor::new(&global, history::get_artifacts_dir(&app_handle)).validate_session(&task)
+    } else {
+        shared_executor(&app_handle, &global).validate_session(&task)
+    }
+}
+
+/// Tauri command to export the schedules of enabled tasks as an iCalendar file.
+///
+/// # Arguments
+///
+/// * `state` - The managed configuration state.
+/// * `path` - The destination file path.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Ok on success, error message if the file cannot be written.
+#[tauri::command]
+fn export_ics(state: State<ConfigState>, path: String) -> Result<(), String> {
+    let content = render_ics(&state.0.lock().unwrap().tasks);
+    std::fs::write(path, content).map_err(|e| e.to_string())
+}
+
+/// Tauri command to export the run history as a CSV or Excel file.
+///
+/// The format follows the extension of `path`. Excel files get an attendance summary and
+/// one sheet per class, for handing in as attendance evidence.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `path` - The destination file path, ending in `.csv` or `.xlsx`.
+/// * `range` - Number of days to look back; all history if omitted.
+///
+/// # Returns
+///
+/// * `Result<usize, String>` - The number of exported rows, or an error message.
+#[tauri::command]
+fn export_history(
+    app_handle: AppHandle,
+    path: String,
+    range: Option<u32>,
+) -> Result<usize, String> {
+    guard::rate_limit("export_history")?;
+    export::export_history(
+        &load_history(&app_handle),
+        &history::load_attendance(&app_handle),
+        &path,
+        range,
+    )
+}
+
+/// Tauri command to load the receipts of a run, as evidence of its successful signs.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `run_id` - The ID of the run in the history.
+///
+/// # Returns
+///
+/// * `Result<Vec<Receipt>, String>` - One receipt per successful sign, or an error message
+///   if the run has none.
+#[tauri::command]
+fn get_receipt(app_handle: AppHandle, run_id: String) -> Result<Vec<Receipt>, String> {
+    receipt::load(&app_handle, &run_id)
+}
+
+/// Tauri command to export the HAR file of a task's most recent debug-mode run.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `task_id` - The task whose requests are exported.
+/// * `path` - The destination file path.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Ok on success, error message if no HAR exists or the copy fails.
+#[tauri::command]
+fn export_last_har(app_handle: AppHandle, task_id: String, path: String) -> Result<(), String> {
+    guard::rate_limit("export_last_har")?;
+    let dir = har::har_dir(&history::get_artifacts_dir(&app_handle));
+    let source = har::last_har(&dir, &task_id).ok_or(i18n::t(i18n::Msg::NoHarRecorded))?;
+    std::fs::copy(source, path)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Tauri command to propose tasks from an iCalendar timetable.
+///
+/// The proposals are not saved; the frontend fills in class IDs and cookies and adds
+/// them with `add_task`.
+///
+/// # Arguments
+///
+/// * `path` - The path of the `.ics` file to import.
+///
+/// # Returns
+///
+/// * `Result<Vec<Task>, String>` - Proposed (disabled) tasks, or an error message.
+#[tauri::command]
+fn import_ics(path: String) -> Result<Vec<Task>, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    parse_ics(&content)
+}
+
+/// Tauri command to read a location from a map share link.
+///
+/// Supports Amap, Baidu Maps and Tencent Maps links, including short links.
+///
+/// # Arguments
+///
+/// * `url` - The share link, or share text containing it.
+///
+/// # Returns
+///
+/// * `Result<MapLocation, String>` - The WGS-84 coordinates and place name, or an error message.
+#[tauri::command]
+fn parse_map_link(url: String) -> Result<MapLocation, String> {
+    maplink::parse_map_link(&url)
+}
+
+/// Tauri command to look up coordinates for an address or place name.
+///
+/// Uses the geocoding provider from the global settings.
+///
+/// # Arguments
+///
+/// * `state` - The application configuration state.
+/// * `query` - The address or place name.
+///
+/// # Returns
+///
+/// * `Result<Vec<GeocodeCandidate>, String>` - Candidate places in WGS-84, or an error message.
+#[tauri::command]
+fn geocode_address(
+    state: State<ConfigState>,
+    query: String,
+) -> Result<Vec<GeocodeCandidate>, String> {
+    let config = state.0.lock().unwrap().global.geocoding.clone();
+    geocode::geocode_address(&config, &query)
+}
+
+/// Tauri command to look up the address of a point.
+///
+/// Results are cached on disk, keyed by rounded coordinates.
+///
+/// # Arguments
+///
+/// * `state` - The application configuration state.
+/// * `lat` - Latitude of the point, in WGS-84.
+/// * `lng` - Longitude of the point, in WGS-84.
+///
+/// # Returns
+///
+/// * `Result<String, String>` - The address, or an error message.
+#[tauri::command]
+fn reverse_geocode(state: State<ConfigState>, lat: f64, lng: f64) -> Result<String, String> {
+    let config = state.0.lock().unwrap().global.geocoding.clone();
+    geocode::reverse_geocode(&config, lat, lng, geo::CoordSystem::Wgs84)
+}
+
+/// Tauri command to estimate the current location from the public IP address.
+///
+/// The result is only accurate to the city level and is marked as low accuracy.
+///
+/// # Returns
+///
+/// * `Result<IpLocation, String>` - The approximate location, or an error message.
+#[tauri::command]
+fn locate_by_ip() -> Result<IpLocation, String> {
+    geocode::locate_by_ip()
+}
+
+/// Tauri command to check whether the site, login server and WeCom API are reachable.
+///
+/// Reports DNS resolution, TCP connect time, TLS status and HTTP latency for each host,
+/// and whether a failure points at the local network or at the site.
+///
+/// # Arguments
+///
+/// * `state` - The application configuration state.
+///
+/// # Returns
+///
+/// * `HealthReport` - The per-host results and an overall verdict.
+#[tauri::command]
+fn health_check(state: State<ConfigState>) -> HealthReport {
+    let global = state.0.lock().unwrap().global.clone();
+    health::health_check(&global.endpoints.base_url, &global.timeouts)
+}
+
+/// Tauri command to create a share code for a task's location.
+///
+/// The code contains the task name, location and drift radius, but no cookie or other secrets.
+///
+/// # Arguments
+///
+/// * `state` - The application configuration state.
+/// * `task_id` - The task whose location is shared.
+///
+/// # Returns
+///
+/// * `Result<String, String>` - The share code, or an error message.
+#[tauri::command]
+fn export_location_preset(state: State<ConfigState>, task_id: String) -> Result<String, String> {
+    let config = state.0.lock().unwrap();
+    let task = config
+        .tasks
+        .iter()
+        .find(|t| t.id == task_id)
+        .ok_or(i18n::t(i18n::Msg::TaskNotFound))?;
+    preset::encode_share_code(&LocationPreset::from_task(task))
+}
+
+/// Tauri command to read a location preset from a share code.
+///
+/// # Arguments
+///
+/// * `code` - The share code, or a message containing it.
+///
+/// # Returns
+///
+/// * `Result<LocationPreset, String>` - The preset for the UI to apply, or an error message.
+#[tauri::command]
+fn import_location_preset(code: String) -> Result<LocationPreset, String> {
+    preset::decode_share_code(&code)
+}
+
+/// Tauri command to compare the running version with the latest GitHub release.
+///
+/// Works on every platform, independently of the updater plugin.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+///
+/// # Returns
+///
+/// * `Result<ReleaseInfo, String>` - The latest version, release notes and URL, or an error message.
+#[tauri::command]
+fn check_latest_version(app_handle: AppHandle) -> Result<ReleaseInfo, String> {
+    release::check_latest_version(&app_handle)
+}
+
+/// Tauri command to generate a sanitized bug report.
+///
+/// The report is copied to the clipboard and the GitHub new-issue page is opened
+/// pre-filled with it. Cookies, WeCom credentials, custom headers and locations are redacted.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `task_id` - The task the report is about; its latest failed response is included.
+///
+/// # Returns
+///
+/// * `Result<IssueReport, String>` - The generated report, or an error message.
+#[tauri::command]
+fn report_issue(app_handle: AppHandle, task_id: Option<String>) -> Result<IssueReport, String> {
+    guard::rate_limit("report_issue")?;
+    let report = report::generate_issue_report(&app_handle, task_id.as_deref())?;
+    report::submit_issue_report(&app_handle, &report)?;
+    Ok(report)
+}
+
+/// Tauri command to register the app to start in the background at boot or login.
+///
+/// # Arguments
+///
+/// * `headless` - Start without window or tray (default `true`); only honored on macOS.
+///
+/// # Returns
+///
+/// * `Result<String, String>` - A description of what was installed, or an error message.
+#[tauri::command]
+fn install_service(headless: Option<bool>) -> Result<String, String> {
+    service::install_service(headless.unwrap_or(true))
+}
+
+/// Tauri command to remove the boot registration made by `install_service`.
+///
+/// # Returns
+///
+/// * `Result<String, String>` - A description of what was removed, or an error message.
+#[tauri::command]
+fn uninstall_service() -> Result<String, String> {
+    service::uninstall_service()
+}
+
+/// Tauri command to check the update endpoint for a newer version.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+///
+/// # Returns
+///
+/// * `Result<Option<updater::UpdateInfo>, String>` - Details of the update, `None` if up
+///   to date, or an error message.
+#[cfg(desktop)]
+#[tauri::command]
+async fn check_for_updates(app_handle: AppHandle) -> Result<Option<updater::UpdateInfo>, String> {
+    updater::check_for_updates(&app_handle).await
+}
+
+/// Tauri command to download and install the available update, restarting the app.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - An error message if there is no update or installation fails.
+#[cfg(desktop)]
+#[tauri::command]
+async fn install_update(app_handle: AppHandle) -> Result<(), String> {
+    updater::install_update(&app_handle).await
+}
+
+/// Tauri command to fetch recent log lines for the in-app log viewer.
+///
+/// New lines are also pushed live to the frontend as `log_line` events.
+///
+/// # Arguments
+///
+/// * `level` - Minimum severity to include (`error`, `warn`, `info`, `debug`, `trace`).
+/// * `limit` - Maximum number of lines to return (defaults to 200).
+///
+/// # Returns
+///
+/// * `Result<Vec<LogLine>, String>` - Matching lines, oldest first, or an error for an
+///   unknown level.
+#[tauri::command]
+fn get_recent_logs(level: Option<String>, limit: Option<usize>) -> Result<Vec<LogLine>, String> {
+    logs::recent_logs(level.as_deref(), limit.unwrap_or(200))
+}
+
+/// Tauri command to list crash reports left by previous sessions.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+///
+/// # Returns
+///
+/// * `Vec<CrashReport>` - Reports that have not been dismissed yet.
+#[tauri::command]
+fn get_crash_reports(app_handle: AppHandle) -> Vec<CrashReport> {
+    crash::pending_crash_reports(&app_handle)
+}
+
+/// Tauri command to dismiss all pending crash reports.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Ok on success, error message if a report cannot be moved.
+#[tauri::command]
+fn dismiss_crash_reports(app_handle: AppHandle) -> Result<(), String> {
+    crash::dismiss_crash_reports(&app_handle)
+}
+
+/// Tauri command to report how much disk space each kind of stored data uses.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+///
+/// # Returns
+///
+/// * `StorageReport` - Sizes of logs, history, debug artifacts, receipts, crash reports
+///   and caches.
+#[tauri::command]
+fn get_storage_report(app_handle: AppHandle) -> StorageReport {
+    storage::storage_report(&app_handle)
+}
+
+/// Tauri command to delete the stored data of one category.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `category` - The category to purge.
+/// * `confirm_token` - A token from `request_confirmation` for `purge_storage`.
+///
+/// # Returns
+///
+/// * `Result<u64, String>` - The number of bytes freed, or an error message if the token is
+///   invalid or deleting fails.
+#[tauri::command]
+fn purge_storage(
+    app_handle: AppHandle,
+    category: StorageCategory,
+    confirm_token: String,
+) -> Result<u64, String> {
+    guard::confirm("purge_storage", &confirm_token)?;
+    storage::purge(&app_handle, category)
+}
+
+/// Tauri command to confirm a destructive action before calling it.
+///
+/// Shows a native confirmation dialog and returns a token the frontend passes to the
+/// destructive command, so a page cannot delete data without the user agreeing.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `action` - The command to confirm: `delete_task`, `delete_account`, `purge_storage`
+///   or `update_config`.
+///
+/// # Returns
+///
+/// * `Result<Option<String>, String>` - A one-time token valid for a minute, `None` if the
+///   user declined, or an error message if the action needs no confirmation.
+#[tauri::command]
+async fn request_confirmation(
+    app_handle: AppHandle,
+    action: String,
+) -> Result<Option<String>, String> {
+    guard::request(&app_handle, &action).await
+}
+
+/// Tauri command to download and apply the latest signed site profile now.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `state` - The managed configuration state, for the profile source.
+///
+/// # Returns
+///
+/// * `Result<u32, String>` - The profile version in use, or an error message.
+#[tauri::command]
+fn refresh_site_profile(app_handle: AppHandle, state: State<ConfigState>) -> Result<u32, String> {
+    let config = state.0.lock().unwrap().global.site_profile.clone();
+    profile::refresh(&app_handle, &config)
+}
+
+/// Tauri command to download the managed task list now.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `state` - The managed configuration state, for the task list source.
+///
+/// # Returns
+///
+/// * `Result<usize, String>` - The number of tasks in the list, or an error message.
+#[tauri::command]
+fn refresh_managed_tasks(
+    app_handle: AppHandle,
+    state: State<ConfigState>,
+) -> Result<usize, String> {
+    let config = state.0.lock().unwrap().global.managed_tasks.clone();
+    managed::refresh(&app_handle, &config)
+}
+
+/// Tauri command to collect the tasks received from `autocheckin://add-task` links.
+///
+/// The frontend calls this on start and on the `task-drafts` event, and opens the task
+/// form pre-filled with them.
+///
+/// # Returns
+///
+/// * `Vec<TaskDraft>` - The drafts not collected yet, oldest first.
+#[tauri::command]
+fn take_task_drafts() -> Vec<TaskDraft> {
+    deeplink::take_drafts()
+}
+
+/// Tauri command to move the simulated clock forward in accelerated-time mode.
+///
+/// Only available when a debug build is started with `--dev-time=<scale>` or the
+/// `AUTOCHECKIN_DEV_TIME` environment variable.
+///
+/// # Arguments
+///
+/// * `minutes` - How far to move the clock.
+///
+/// # Returns
+///
+/// * `Result<NaiveDateTime, String>` - The new simulated time, or an error message if
+///   accelerated time is off.
+#[tauri::command]
+fn debug_advance_time(minutes: u32) -> Result<chrono::NaiveDateTime, String> {
+    devtime::advance(minutes)
+}
+
+/// Command-line flag that runs only the scheduler, without a window or tray icon.
+const HEADLESS_FLAG: &str = "--headless";
+
 /// The main entry point for the Tauri application.
 ///
 /// Configures plugins, initializes state, sets up the system tray, starts the scheduler,
-/// and registers command handlers.
+/// and registers command handlers. With `--headless`, the window and tray are skipped and
+/// the app keeps running the scheduler with logging and notifications until it is stopped.
+/// A command-line command (see `cli`) runs once and exits instead.
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tauri::Builder::default()
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let headless = args.iter().any(|arg| arg == HEADLESS_FLAG);
+    let command = cli::parse(&args);
+    #[allow(unused_mut)]
+    let mut builder = tauri::Builder::default();
+    // On Windows and Linux, opening a link starts a second process; hand its arguments
+    // to the running one instead. Command-line commands run next to the app.
+    #[cfg(desktop)]
+    if command.is_none() {
+        builder = builder.plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.show();
+                let _ = window.set_focus();
+            }
+        }));
+    }
+    builder
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
-        .plugin(tauri_plugin_log::Builder::default().build())
-        .setup(|app| {
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .setup(move |app| {
             // Initialize config state
             let config = load_config(app.handle());
-            app.manage(ConfigState(Mutex::new(config)));
+
+            // Logging
+            app.handle()
+                .plugin(logs::build_plugin(app.handle(), &config.global.logging))?;
+            logs::apply_level(&config.global.logging.level, config.global.debug);
+            httplog::set_enabled(config.global.debug);
+            spans::configure(&config.global.tracing);
+            if let Err(e) = tls::configure(&config.global.tls) {
+                log::warn!("Failed to load CA bundle: {}", e);
+            }
+            i18n::set_locale(config.global.locale);
+            logs::attach(app.handle().clone());
+            crash::install_panic_hook(app.handle());
+            if let Ok(data_dir) = app.path().app_data_dir() {
+                geocode::set_cache_dir(data_dir);
+            }
+            profile::load_cached(app.handle());
+            crash::warn_pending_reports(app.handle());
+
+            // Command-line mode: run the command and exit without starting the app.
+            if let Some(command) = command {
+                app.manage(ConfigState(Mutex::new(config)));
+                app.manage(ExecutorState::default());
+                let code = cli::execute(app.handle(), command);
+                log::logger().flush();
+                std::process::exit(code);
+            }
+
+            if config.global.metrics.enable {
+                if let Err(e) = metrics::serve(&config.global.metrics) {
+                    log::warn!("Failed to start metrics endpoint: {}", e);
+                }
+            }
+            if config.global.api.enable {
+                if let Err(e) = api::serve(app.handle().clone(), &config.global.api) {
+                    log::warn!("Failed to start REST API: {}", e);
+                }
+            }
+            if config.global.mqtt.enable {
+                mqtt::start(app.handle().clone(), config.global.mqtt.clone());
+            }
+            if config.global.telegram.enable {
+                telegram::start(app.handle().clone(), config.global.telegram.clone());
+            }
+            if config.global.wecom.callback.enable {
+                if let Err(e) = wecom::serve(app.handle().clone(), &config.global.wecom) {
+                    log::warn!("Failed to start WeCom callback receiver: {}", e);
+                }
+            }
+
+            #[cfg(mobile)]
+            app.handle().plugin(mobile::init())?;
 
             // System Tray
-            let quit_i = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
-            let show_i = MenuItem::with_id(app, "show", "Show", true, None::<&str>)?;
-            let menu = Menu::with_items(app, &[&show_i, &quit_i])?;
-
-            let _tray = TrayIconBuilder::new()
-                .menu(&menu)
-                .show_menu_on_left_click(false)
-                .on_menu_event(|app, event| match event.id.as_ref() {
-                    "quit" => {
-                        app.exit(0);
-                    }
-                    "show" => {
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.show();
-                            let _ = window.set_focus();
-                        }
-                    }
-                    _ => {}
-                })
-                .build(app)?;
+            if !headless {
+                create_tray(app, &config.tasks)?;
+            }
+            app.manage(ConfigState(Mutex::new(config)));
+            app.manage(ExecutorState::default());
+            app.manage(LoginState::default());
+            // Dev-only and system commands are not granted to the window in release builds.
+            #[cfg(debug_assertions)]
+            app.add_capability(include_str!("../capabilities/debug/debug.json"))?;
 
             if let Some(window) = app.get_webview_window("main") {
-                let _ = window.show();
-                let _ = window.set_focus();
+                if headless {
+                    log::info!("Running headless: scheduler only, no window or tray");
+                    let _ = window.destroy();
+                } else {
+                    let _ = window.show();
+                    let _ = window.set_focus();
+                }
             }
 
+            // Links such as autocheckin://add-task?class_id=... open the task form.
+            {
+                use tauri_plugin_deep_link::DeepLinkExt;
+                #[cfg(any(target_os = "windows", target_os = "linux"))]
+                if let Err(e) = app.deep_link().register_all() {
+                    log::warn!(
+                        "Failed to register the {} link scheme: {}",
+                        deeplink::SCHEME,
+                        e
+                    );
+                }
+                let app_handle = app.handle().clone();
+                app.deep_link()
+                    .on_open_url(move |event| deeplink::handle_urls(&app_handle, event.urls()));
+                if let Ok(Some(urls)) = app.deep_link().get_current() {
+                    deeplink::handle_urls(app.handle(), urls);
+                }
+            }
+
+            #[cfg(desktop)]
+            {
+                app.handle()
+                    .plugin(tauri_plugin_updater::Builder::new().build())?;
+                if updater::is_configured(app.handle()) {
+                    let app_handle = app.handle().clone();
+                    tauri::async_runtime::spawn(async move {
+                        if let Err(e) = updater::check_for_updates(&app_handle).await {
+                            log::warn!("Update check failed: {}", e);
+                        }
+                    });
+                } else {
+                    log::info!("Update check skipped: no updater public key is configured");
+                }
+            }
+
+            profile::start(app.handle().clone());
+            managed::start(app.handle().clone());
+
             // Start scheduler
             let app_handle = app.handle().clone();
-            tauri::async_runtime::spawn(async move {
-                start_scheduler(app_handle).await;
-            });
+            match devtime::requested_scale(&args) {
+                Some(scale) => {
+                    let clock = devtime::init(scale);
+                    tauri::async_runtime::spawn(start_scheduler(app_handle, clock));
+                }
+                None => {
+                    tauri::async_runtime::spawn(start_scheduler(app_handle, SystemClock));
+                }
+            }
 
             Ok(())
         })
@@ -222,8 +1435,70 @@ pub fn run() {
             update_config,
             add_task,
             update_task,
-            delete_task
+            delete_task,
+            add_account,
+            update_account,
+            delete_account,
+            run_task_now,
+            search_tasks,
+            get_task_stats,
+            sync_attendance,
+            validate_cookie,
+            get_attendance_report,
+            get_failure_analytics,
+            get_timeline,
+            get_anomalies,
+            export_ics,
+            export_history,
+            get_receipt,
+            import_ics,
+            export_last_har,
+            get_recent_logs,
+            get_crash_reports,
+            dismiss_crash_reports,
+            get_storage_report,
+            purge_storage,
+            request_confirmation,
+            parse_map_link,
+            geocode_address,
+            reverse_geocode,
+            locate_by_ip,
+            health_check,
+            export_location_preset,
+            import_location_preset,
+            check_latest_version,
+            report_issue,
+            install_service,
+            uninstall_service,
+            refresh_site_profile,
+            refresh_managed_tasks,
+            take_task_drafts,
+            debug_advance_time,
+            #[cfg(desktop)]
+            check_for_updates,
+            #[cfg(desktop)]
+            install_update
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(move |app_handle, event| {
+            // Let in-flight runs finish before the process exits, whether quitting from
+            // the tray or by closing the last window.
+            if let tauri::RunEvent::ExitRequested { code, api, .. } = event {
+                // Headless mode has no windows; keep running when the main one is removed.
+                if headless && code.is_none() {
+                    api.prevent_exit();
+                    return;
+                }
+                if scheduler::begin_shutdown() {
+                    api.prevent_exit();
+                    let app_handle = app_handle.clone();
+                    std::thread::spawn(move || {
+                        scheduler::wait_for_runs();
+                        log::logger().flush();
+                        app_handle.exit(code.unwrap_or(0));
+                    });
+                }
+            }
+        });
 }
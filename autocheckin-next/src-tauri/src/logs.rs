@@ -0,0 +1,469 @@
+use crate::config::{LogFormat, LoggingConfig};
+use crate::i18n::{tf, Msg};
+use chrono::{DateTime, Local, NaiveDate};
+use log::{warn, Level, LevelFilter};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+use tauri::plugin::TauriPlugin;
+use tauri::{AppHandle, Emitter, Manager, Wry};
+use tauri_plugin_log::{fern, Target, TargetKind};
+
+/// Number of log lines kept in memory for the log viewer.
+const BUFFER_SIZE: usize = 2000;
+
+/// Recent log lines, oldest first.
+static BUFFER: Mutex<VecDeque<LogLine>> = Mutex::new(VecDeque::new());
+
+/// Problems setting up the log files, logged once the logger is running.
+static SETUP_ERRORS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// App handle used to emit `log_line` events, set once the app is running.
+static APP_HANDLE: OnceLock<AppHandle> = OnceLock::new();
+
+thread_local! {
+    /// Structured fields attached to every log line emitted on this thread.
+    static CONTEXT: RefCell<Map<String, Value>> = RefCell::new(Map::new());
+}
+
+/// A single log line.
+#[derive(Debug, Serialize, Clone)]
+pub struct LogLine {
+    /// Local time the line was logged, in RFC 3339 format.
+    pub timestamp: String,
+    /// Severity of the line (`ERROR`, `WARN`, `INFO`, `DEBUG`, `TRACE`).
+    pub level: String,
+    /// The module that emitted the line.
+    pub target: String,
+    /// The log message.
+    pub message: String,
+}
+
+/// A log file that is rotated while the app runs.
+///
+/// Before a new line is written, the file is rotated when it has grown past the size
+/// limit or the day has changed since it was opened, so lines are never split across files.
+struct RotatingFile {
+    /// The log directory.
+    dir: PathBuf,
+    /// The file name without extension.
+    name: String,
+    /// The file extension (`log` or `jsonl`).
+    ext: &'static str,
+    /// Size in bytes after which the file is rotated.
+    max_size: u64,
+    /// Number of files (current plus rotated) to keep.
+    keep_files: usize,
+    /// The open file, or `None` if reopening it after a rotation failed.
+    file: Option<BufWriter<File>>,
+    /// Current size of the file in bytes.
+    size: u64,
+    /// The day the file was opened.
+    opened_on: NaiveDate,
+    /// Whether the last write ended a line.
+    line_start: bool,
+}
+
+impl RotatingFile {
+    /// Rotates the existing file if needed and opens it for appending.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The log directory.
+    /// * `name` - The file name without extension.
+    /// * `ext` - The file extension.
+    /// * `config` - The logging settings.
+    ///
+    /// # Returns
+    ///
+    /// * `io::Result<Self>` - The open file, or the error opening it.
+    fn open(dir: &Path, name: &str, ext: &'static str, config: &LoggingConfig) -> io::Result<Self> {
+        let max_size = config.max_file_size_kb * 1024;
+        rotate(dir, name, ext, max_size, config.keep_files);
+        let mut file = Self {
+            dir: dir.to_path_buf(),
+            name: name.to_string(),
+            ext,
+            max_size,
+            keep_files: config.keep_files,
+            file: None,
+            size: 0,
+            opened_on: Local::now().date_naive(),
+            line_start: true,
+        };
+        file.reopen()?;
+        Ok(file)
+    }
+
+    /// Opens the current file for appending.
+    fn reopen(&mut self) -> io::Result<()> {
+        let path = self.dir.join(format!("{}.{}", self.name, self.ext));
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        self.size = file.metadata()?.len();
+        self.opened_on = Local::now().date_naive();
+        self.file = Some(BufWriter::new(file));
+        Ok(())
+    }
+
+    /// Closes the file, renames it to a dated name and starts a new one.
+    fn rotate_now(&mut self) -> io::Result<()> {
+        if let Some(mut file) = self.file.take() {
+            file.flush()?;
+        }
+        rotate(&self.dir, &self.name, self.ext, 0, self.keep_files);
+        self.reopen()
+    }
+}
+
+impl Write for RotatingFile {
+    /// Writes to the file, rotating it first when a new line starts and it is due.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let due = self.size > self.max_size || Local::now().date_naive() != self.opened_on;
+        if self.line_start && self.size > 0 && due {
+            self.rotate_now()?;
+        }
+        let file = self
+            .file
+            .as_mut()
+            .ok_or_else(|| io::Error::other("log file is not open"))?;
+        let written = file.write(buf)?;
+        self.size += written as u64;
+        self.line_start = buf[..written].ends_with(b"\n");
+        Ok(written)
+    }
+
+    /// Flushes buffered lines to the file.
+    fn flush(&mut self) -> io::Result<()> {
+        match self.file.as_mut() {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Restores the previous log context when dropped.
+pub struct ContextGuard {
+    /// The field values replaced by this guard, restored on drop.
+    previous: Vec<(String, Option<Value>)>,
+}
+
+impl Drop for ContextGuard {
+    /// Removes or restores the fields set by `with_context`.
+    fn drop(&mut self) {
+        CONTEXT.with(|ctx| {
+            let mut ctx = ctx.borrow_mut();
+            for (key, value) in self.previous.drain(..).rev() {
+                match value {
+                    Some(v) => ctx.insert(key, v),
+                    None => ctx.remove(&key),
+                };
+            }
+        });
+    }
+}
+
+/// Attaches structured fields to log lines on the current thread until the guard drops.
+///
+/// The fields appear in JSON log output (e.g. `task_id`, `sign_id`, `duration_ms`).
+///
+/// # Arguments
+///
+/// * `fields` - Field names and values.
+///
+/// # Returns
+///
+/// * `ContextGuard` - Removes the fields again when dropped.
+pub fn with_context<const N: usize>(fields: [(&str, Value); N]) -> ContextGuard {
+    CONTEXT.with(|ctx| {
+        let mut ctx = ctx.borrow_mut();
+        let previous = fields
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), ctx.insert(key.to_string(), value)))
+            .collect();
+        ContextGuard { previous }
+    })
+}
+
+/// Builds the log plugin with the configured output format and rotation.
+///
+/// Text lines go to stdout and `<name>.log`, JSON lines to `<name>.jsonl`, depending
+/// on the configured format. The plugin itself records every level; `apply_level` sets
+/// the effective level so it can change at runtime. Each file is rotated on startup and
+/// while running, whenever it exceeds the size limit or the day changes.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `config` - The logging settings.
+///
+/// # Returns
+///
+/// * `TauriPlugin<Wry>` - The configured log plugin.
+pub fn build_plugin(app_handle: &AppHandle, config: &LoggingConfig) -> TauriPlugin<Wry> {
+    let name = app_handle.package_info().name.clone();
+    let mut builder = tauri_plugin_log::Builder::default()
+        .level(LevelFilter::Trace)
+        .format(|out, message, _| out.finish(format_args!("{}", message)))
+        .clear_targets()
+        .target(Target::new(TargetKind::Dispatch(viewer_dispatch())));
+
+    let log_dir = app_handle.path().app_log_dir().ok().filter(|dir| {
+        fs::create_dir_all(dir)
+            .map_err(|e| setup_error(format!("Failed to create log dir: {}", e)))
+            .is_ok()
+    });
+    let open_log = |ext: &'static str| {
+        let dir = log_dir.as_ref()?;
+        RotatingFile::open(dir, &name, ext, config)
+            .map(|file| fern::Output::writer(Box::new(file), "\n"))
+            .map_err(|e| setup_error(format!("Failed to open log file: {}", e)))
+            .ok()
+    };
+
+    if config.format != LogFormat::Json {
+        let mut text = fern::Dispatch::new()
+            .format(|out, message, record| {
+                out.finish(format_args!(
+                    "{}[{}][{}] {}",
+                    Local::now().format("[%Y-%m-%d][%H:%M:%S]"),
+                    record.target(),
+                    record.level(),
+                    message
+                ))
+            })
+            .chain(std::io::stdout());
+        if let Some(file) = open_log("log") {
+            text = text.chain(file);
+        }
+        builder = builder.target(Target::new(TargetKind::Dispatch(text)));
+    }
+
+    if config.format != LogFormat::Text {
+        let mut json = fern::Dispatch::new().format(|out, message, record| {
+            let mut entry = CONTEXT.with(|ctx| ctx.borrow().clone());
+            entry.insert("ts".into(), Local::now().to_rfc3339().into());
+            entry.insert("level".into(), record.level().as_str().into());
+            entry.insert("target".into(), record.target().into());
+            entry.insert("message".into(), message.to_string().into());
+            out.finish(format_args!("{}", Value::Object(entry)))
+        });
+        if config.format == LogFormat::Json {
+            json = json.chain(std::io::stdout());
+        }
+        if let Some(file) = open_log("jsonl") {
+            json = json.chain(file);
+        }
+        builder = builder.target(Target::new(TargetKind::Dispatch(json)));
+    }
+
+    builder.build()
+}
+
+/// Sets the effective log level.
+///
+/// Debug mode raises the level to at least `debug`.
+///
+/// # Arguments
+///
+/// * `level` - The level name (`error`, `warn`, `info`, `debug`, `trace`, `off`).
+/// * `debug` - Whether debug mode is enabled.
+pub fn apply_level(level: &str, debug: bool) {
+    let filter = LevelFilter::from_str(level).unwrap_or_else(|_| {
+        warn!("Unknown log level: {}", level);
+        LevelFilter::Info
+    });
+    log::set_max_level(if debug {
+        filter.max(LevelFilter::Debug)
+    } else {
+        filter
+    });
+}
+
+/// Records a problem setting up the log files, to be logged by `attach`.
+fn setup_error(message: String) {
+    SETUP_ERRORS.lock().unwrap().push(message);
+}
+
+/// Rotates a log file and prunes old rotated copies.
+///
+/// The file is renamed to `<name>_<date>.<ext>` when it exceeds `max_size` bytes or was
+/// last written on an earlier day. Only the newest `keep_files - 1` rotated copies are kept.
+fn rotate(dir: &Path, name: &str, ext: &str, max_size: u64, keep_files: usize) {
+    let current = dir.join(format!("{}.{}", name, ext));
+    if let Ok(meta) = fs::metadata(&current) {
+        let modified: DateTime<Local> = meta
+            .modified()
+            .map(Into::into)
+            .unwrap_or_else(|_| Local::now());
+        if meta.len() > max_size || modified.date_naive() < Local::now().date_naive() {
+            let dated = dir.join(format!(
+                "{}_{}.{}",
+                name,
+                modified.format("%Y-%m-%d_%H-%M-%S"),
+                ext
+            ));
+            let _ = fs::rename(&current, dated);
+        }
+    }
+
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let prefix = format!("{}_", name);
+    let suffix = format!(".{}", ext);
+    let mut rotated: Vec<_> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(&prefix) && n.ends_with(&suffix))
+        })
+        .collect();
+    rotated.sort();
+    let keep = keep_files.saturating_sub(1);
+    if rotated.len() > keep {
+        for path in &rotated[..rotated.len() - keep] {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Creates a log dispatch that captures lines for the in-app log viewer.
+///
+/// Each line is stored in a bounded in-memory buffer and emitted to the frontend as a
+/// `log_line` event once `attach` has been called.
+///
+/// # Returns
+///
+/// * `fern::Dispatch` - The dispatch registered as a log target.
+fn viewer_dispatch() -> fern::Dispatch {
+    fern::Dispatch::new().chain(fern::Output::call(|record| {
+        let line = LogLine {
+            timestamp: Local::now().to_rfc3339(),
+            level: record.level().to_string(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+        };
+
+        {
+            let mut buffer = BUFFER.lock().unwrap();
+            if buffer.len() == BUFFER_SIZE {
+                buffer.pop_front();
+            }
+            buffer.push_back(line.clone());
+        }
+
+        if let Some(app_handle) = APP_HANDLE.get() {
+            // Emit asynchronously so logging done while emitting cannot re-enter this callback.
+            let app_handle = app_handle.clone();
+            tauri::async_runtime::spawn(async move {
+                let _ = app_handle.emit("log_line", line);
+            });
+        }
+    }))
+}
+
+/// Starts forwarding captured log lines to the frontend, and logs any problems setting
+/// up the log files.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+pub fn attach(app_handle: AppHandle) {
+    let _ = APP_HANDLE.set(app_handle);
+    for message in SETUP_ERRORS.lock().unwrap().drain(..) {
+        warn!("{}", message);
+    }
+}
+
+/// Returns the most recent captured log lines.
+///
+/// # Arguments
+///
+/// * `level` - Minimum severity to include (e.g. `"warn"` returns warnings and errors).
+/// * `limit` - Maximum number of lines to return.
+///
+/// # Returns
+///
+/// * `Result<Vec<LogLine>, String>` - Matching lines, oldest first, or an error for an
+///   unknown level.
+pub fn recent_logs(level: Option<&str>, limit: usize) -> Result<Vec<LogLine>, String> {
+    let min_level = level
+        .map(Level::from_str)
+        .transpose()
+        .map_err(|_| tf(Msg::UnknownLogLevel, &[&level.unwrap_or_default()]))?;
+
+    let buffer = BUFFER.lock().unwrap();
+    let mut lines: Vec<LogLine> = buffer
+        .iter()
+        .rev()
+        .filter(|line| {
+            min_level.is_none_or(|min| Level::from_str(&line.level).is_ok_and(|l| l <= min))
+        })
+        .take(limit)
+        .cloned()
+        .collect();
+    lines.reverse();
+    Ok(lines)
+}
+
+/// Returns the most recent log lines without blocking.
+///
+/// Used from the panic hook, where the buffer lock may be held by the panicking thread.
+///
+/// # Arguments
+///
+/// * `limit` - Maximum number of lines to return.
+///
+/// # Returns
+///
+/// * `Vec<LogLine>` - The latest lines, oldest first, or nothing if the buffer is busy.
+pub fn snapshot_logs(limit: usize) -> Vec<LogLine> {
+    let Ok(buffer) = BUFFER.try_lock() else {
+        return vec![];
+    };
+    let skip = buffer.len().saturating_sub(limit);
+    buffer.iter().skip(skip).cloned().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_rotates_at_line_boundaries_once_full() {
+        let dir = std::env::temp_dir().join(format!("autocheckin-logs-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let config = LoggingConfig {
+            max_file_size_kb: 1,
+            keep_files: 3,
+            ..LoggingConfig::default()
+        };
+        let mut file = RotatingFile::open(&dir, "app", "log", &config).unwrap();
+        let line = "x".repeat(700);
+        for _ in 0..3 {
+            file.write_all(line.as_bytes()).unwrap();
+            file.write_all(b"\n").unwrap();
+            file.flush().unwrap();
+        }
+
+        let current = fs::read_to_string(dir.join("app.log")).unwrap();
+        assert_eq!(current, format!("{}\n", line));
+        let rotated: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name().to_string_lossy().into_owned())
+            .filter(|n| n.starts_with("app_") && n.ends_with(".log"))
+            .collect();
+        assert_eq!(rotated.len(), 1);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
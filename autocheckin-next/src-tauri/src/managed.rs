@@ -0,0 +1,195 @@
+use crate::config::{save_config, AppConfig, ConfigState, ManagedTasksConfig, Task};
+use crate::events;
+use crate::geo::validate_coordinates;
+use crate::i18n::{t, tf, Msg};
+use crate::profile;
+use crate::tray::refresh_tray_menu;
+use log::{info, warn};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// The task list published by an admin.
+#[derive(Debug, Deserialize)]
+struct TaskList {
+    /// The class definitions; cookies are ignored and kept locally instead.
+    tasks: Vec<Task>,
+}
+
+/// Whether the tasks come from a managed task list and cannot be edited locally.
+///
+/// # Arguments
+///
+/// * `config` - The managed task list settings.
+///
+/// # Returns
+///
+/// * `bool` - `true` if a task list URL is set.
+pub fn is_active(config: &ManagedTasksConfig) -> bool {
+    !config.url.trim().is_empty()
+}
+
+/// Rejects a change to the task list while it is managed remotely.
+///
+/// # Arguments
+///
+/// * `config` - The current configuration.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Ok if tasks are local, or an error message if they are managed.
+pub fn ensure_editable(config: &AppConfig) -> Result<(), String> {
+    if is_active(&config.global.managed_tasks) {
+        return Err(t(Msg::TasksManaged).to_string());
+    }
+    Ok(())
+}
+
+/// Downloads the task list, verifies its signature and replaces the local tasks.
+///
+/// The signature is fetched from the list URL with `.minisig` appended and must be made
+/// with the key matching `public_key`, like the site profile's. Each task keeps the
+/// account, cookie and enable flag stored locally for the same task ID; tasks new to this
+/// device start without an account.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `config` - The managed task list settings.
+///
+/// # Returns
+///
+/// * `Result<usize, String>` - The number of tasks, or an error message if no URL or key
+///   is set, the download fails or the list is rejected.
+pub fn refresh(app_handle: &AppHandle, config: &ManagedTasksConfig) -> Result<usize, String> {
+    if !is_active(config) {
+        return Err(t(Msg::ManagedUrlMissing).to_string());
+    }
+    if config.public_key.trim().is_empty() {
+        return Err(t(Msg::ManagedKeyMissing).to_string());
+    }
+    let body = profile::fetch_signed(app_handle, config.url.trim(), &config.public_key)?;
+    let list: TaskList = serde_json::from_slice(&body).map_err(|e| e.to_string())?;
+    validate(&list.tasks)?;
+
+    let state = app_handle.state::<ConfigState>();
+    let mut app_config = state.0.lock().unwrap();
+    let tasks = merge(list.tasks, &app_config.tasks);
+    let count = tasks.len();
+    let unchanged =
+        serde_json::to_value(&tasks).ok() == serde_json::to_value(&app_config.tasks).ok();
+    if unchanged {
+        return Ok(count);
+    }
+    app_config.tasks = tasks;
+    save_config(app_handle, &app_config)?;
+    refresh_tray_menu(app_handle, &app_config.tasks);
+    events::publish("config_changed", serde_json::json!({}));
+    let _ = app_handle.emit("config-changed", ());
+    info!("Managed task list updated: {} tasks", count);
+    Ok(count)
+}
+
+/// Applies the locally kept fields of the tasks in `local` to the same task IDs in
+/// `managed`: the account, cookie and enable flag, so pausing a task survives refreshes.
+fn merge(managed: Vec<Task>, local: &[Task]) -> Vec<Task> {
+    managed
+        .into_iter()
+        .map(|mut task| {
+            if let Some(local) = local.iter().find(|local| local.id == task.id) {
+                task.cookie = local.cookie.clone();
+                task.account_id = local.account_id.clone();
+                task.enable = local.enable;
+                task.enabled_at = local.enabled_at;
+            } else {
+                task.cookie.clear();
+                task.account_id = None;
+            }
+            task
+        })
+        .collect()
+}
+
+/// Checks that every task has a unique ID and a valid location.
+fn validate(tasks: &[Task]) -> Result<(), String> {
+    let mut ids = HashSet::new();
+    for task in tasks {
+        if task.id.trim().is_empty() {
+            return Err(tf(Msg::ManagedTaskNoId, &[&task.name]));
+        }
+        if !ids.insert(task.id.as_str()) {
+            return Err(tf(Msg::ManagedDuplicateId, &[&task.id]));
+        }
+        if !task.location.is_unset() {
+            validate_coordinates(&task.location.lat, &task.location.lng)
+                .map_err(|e| format!("{}: {}", task.name, e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Refreshes the task list in the background, at startup and then at the configured
+/// interval. Does nothing while no URL is configured.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+pub fn start(app_handle: AppHandle) {
+    thread::spawn(move || loop {
+        let config = app_handle
+            .state::<ConfigState>()
+            .0
+            .lock()
+            .unwrap()
+            .global
+            .managed_tasks
+            .clone();
+        if is_active(&config) {
+            if let Err(e) = refresh(&app_handle, &config) {
+                warn!("Managed task list refresh failed: {}", e);
+            }
+        }
+        thread::sleep(Duration::from_secs(config.refresh_hours.max(1) * 3600));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_keeps_the_local_session_and_enable_flag() {
+        let managed = vec![
+            Task {
+                id: "math".to_string(),
+                name: "Math (renamed)".to_string(),
+                cookie: "from-list".to_string(),
+                enable: true,
+                ..Task::default()
+            },
+            Task {
+                id: "new".to_string(),
+                cookie: "from-list".to_string(),
+                enable: true,
+                ..Task::default()
+            },
+        ];
+        let local = vec![Task {
+            id: "math".to_string(),
+            name: "Math".to_string(),
+            cookie: "local".to_string(),
+            account_id: Some("a".to_string()),
+            enable: false,
+            ..Task::default()
+        }];
+
+        let merged = merge(managed, &local);
+        assert_eq!(merged[0].name, "Math (renamed)");
+        assert_eq!(merged[0].cookie, "local");
+        assert_eq!(merged[0].account_id.as_deref(), Some("a"));
+        assert!(!merged[0].enable);
+        assert!(merged[1].cookie.is_empty() && merged[1].enable);
+    }
+}
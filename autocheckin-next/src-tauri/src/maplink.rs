@@ -0,0 +1,128 @@
+use crate::geo::CoordSystem;
+use crate::httplog;
+use crate::i18n::{t, Msg};
+use crate::tls;
+use regex::Regex;
+use reqwest::Url;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+/// Finds the URL in share text.
+static SHARE_URL: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"https?://\S+").unwrap());
+
+/// A location extracted from a map share link, in WGS-84 like the location picker.
+#[derive(Debug, Serialize, Clone)]
+pub struct MapLocation {
+    /// Latitude with six decimal places.
+    pub lat: String,
+    /// Longitude with six decimal places.
+    pub lng: String,
+    /// The place name from the link, if any.
+    pub name: Option<String>,
+}
+
+/// Extracts coordinates from an Amap, Baidu Maps or Tencent Maps share link.
+///
+/// The link may be embedded in share text (e.g. "我在这里 https://surl.amap.com/..."). Short
+/// links without coordinates are resolved by following their redirects. Provider
+/// coordinates (GCJ-02 or BD-09) are converted to WGS-84.
+///
+/// # Arguments
+///
+/// * `link` - The share link or share text.
+///
+/// # Returns
+///
+/// * `Result<MapLocation, String>` - The location, or an error message if no coordinates
+///   were found.
+pub fn parse_map_link(link: &str) -> Result<MapLocation, String> {
+    let raw = SHARE_URL
+        .find(link)
+        .map(|m| m.as_str())
+        .unwrap_or(link.trim());
+    let url = Url::parse(raw).map_err(|e| e.to_string())?;
+    if let Some(location) = parse_url(&url) {
+        return Ok(location);
+    }
+
+    let client = tls::client_builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let resolved = httplog::send(client.get(url)).map_err(|e| e.to_string())?;
+    parse_url(resolved.url()).ok_or_else(|| t(Msg::NoCoordinatesInLink).to_string())
+}
+
+/// Reads the coordinates and place name from a provider URL.
+fn parse_url(url: &Url) -> Option<MapLocation> {
+    let host = url.host_str()?.to_ascii_lowercase();
+    let query: HashMap<String, String> = url.query_pairs().into_owned().collect();
+    let get = |key: &str| query.get(key).map(String::as_str);
+
+    let (lat, lng, datum, name) = if host.contains("amap.com") || host.contains("gaode.com") {
+        let datum = match get("coordinate") {
+            Some("wgs84") => CoordSystem::Wgs84,
+            _ => CoordSystem::Gcj02,
+        };
+        let (lat, lng) = if let Some(v) = get("position").or(get("lnglat")) {
+            lng_lat(v)?
+        } else if let Some(v) = get("p") {
+            // p=<poi id>,<lat>,<lng>,<name>,...
+            let mut parts = v.split(',').skip(1);
+            (parts.next()?.parse().ok()?, parts.next()?.parse().ok()?)
+        } else if let Some(v) = get("q") {
+            lat_lng(v)?
+        } else {
+            (
+                get("lat")?.parse().ok()?,
+                get("lng").or(get("lon"))?.parse().ok()?,
+            )
+        };
+        (lat, lng, datum, get("name"))
+    } else if host.contains("baidu.com") {
+        let datum = match get("coord_type") {
+            Some("wgs84") => CoordSystem::Wgs84,
+            Some("gcj02") => CoordSystem::Gcj02,
+            _ => CoordSystem::Bd09,
+        };
+        let (lat, lng) = lat_lng(get("location").or(get("latlng"))?)?;
+        (lat, lng, datum, get("title"))
+    } else if host.contains("qq.com") {
+        if let Some(marker) = get("marker") {
+            // marker=coord:<lat>,<lng>;title:<name>;addr:<address>
+            let fields: HashMap<&str, &str> = marker
+                .split(';')
+                .filter_map(|f| f.split_once(':'))
+                .collect();
+            let (lat, lng) = lat_lng(fields.get("coord")?)?;
+            (lat, lng, CoordSystem::Gcj02, fields.get("title").copied())
+        } else {
+            let (lat, lng) = lat_lng(get("coord").or(get("center"))?)?;
+            (lat, lng, CoordSystem::Gcj02, get("title"))
+        }
+    } else {
+        return None;
+    };
+
+    let (lat, lng) = datum.convert(CoordSystem::Wgs84, lat, lng);
+    Some(MapLocation {
+        lat: format!("{:.6}", lat),
+        lng: format!("{:.6}", lng),
+        name: name.filter(|n| !n.is_empty()).map(str::to_string),
+    })
+}
+
+/// Parses a `<lat>,<lng>` pair.
+fn lat_lng(value: &str) -> Option<(f64, f64)> {
+    let (lat, lng) = value.split_once(',')?;
+    let lng = lng.split(',').next()?;
+    Some((lat.trim().parse().ok()?, lng.trim().parse().ok()?))
+}
+
+/// Parses a `<lng>,<lat>` pair, the order Amap uses.
+fn lng_lat(value: &str) -> Option<(f64, f64)> {
+    let (lat, lng) = lat_lng(value)?;
+    Some((lng, lat))
+}
@@ -0,0 +1,203 @@
+use crate::config::MetricsConfig;
+use crate::history::{RunOutcome, RunRecord};
+use crate::httpd;
+use log::{info, warn};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::net::{TcpListener, TcpStream};
+use std::sync::{LazyLock, Mutex};
+
+/// Most scrapes served at once.
+const MAX_CONNECTIONS: usize = 4;
+
+/// Upper bounds of the request latency histogram buckets, in seconds.
+const LATENCY_BUCKETS: &[f64] = &[0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+/// Counters and histograms exported to Prometheus.
+static METRICS: LazyLock<Mutex<Metrics>> = LazyLock::new(|| Mutex::new(Metrics::default()));
+
+/// Collected metric values.
+#[derive(Default)]
+struct Metrics {
+    /// Runs by outcome.
+    runs: BTreeMap<&'static str, u64>,
+    /// Sign attempts by result.
+    signs: BTreeMap<&'static str, u64>,
+    /// Request latencies by request kind.
+    latency: BTreeMap<&'static str, Histogram>,
+    /// Notifications that could not be delivered.
+    notification_errors: u64,
+}
+
+/// A cumulative latency histogram.
+#[derive(Default)]
+struct Histogram {
+    /// Observations per bucket of `LATENCY_BUCKETS`, not yet cumulative.
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    /// Number of observations.
+    count: u64,
+    /// Sum of the observed values in seconds.
+    sum: f64,
+}
+
+impl Histogram {
+    /// Adds an observation in milliseconds.
+    fn observe(&mut self, ms: u64) {
+        let secs = ms as f64 / 1000.0;
+        if let Some(i) = LATENCY_BUCKETS.iter().position(|bound| secs <= *bound) {
+            self.buckets[i] += 1;
+        }
+        self.count += 1;
+        self.sum += secs;
+    }
+}
+
+/// The label value of a run outcome, matching its serialized name.
+pub fn outcome_label(outcome: RunOutcome) -> &'static str {
+    match outcome {
+        RunOutcome::Success => "success",
+        RunOutcome::Failure => "failure",
+        RunOutcome::NoSession => "no_session",
+        RunOutcome::Error => "error",
+        RunOutcome::CookieExpired => "cookie_expired",
+        RunOutcome::Blocked => "blocked",
+        RunOutcome::Offline => "offline",
+    }
+}
+
+/// Counts a finished run and its request latencies.
+///
+/// # Arguments
+///
+/// * `record` - The recorded run.
+pub fn record_run(record: &RunRecord) {
+    let mut metrics = METRICS.lock().unwrap();
+    *metrics
+        .runs
+        .entry(outcome_label(record.outcome))
+        .or_insert(0) += 1;
+    for sign in &record.signs {
+        let result = if sign.success { "success" } else { "failure" };
+        *metrics.signs.entry(result).or_insert(0) += 1;
+        if let Some(ms) = sign.request_ms {
+            metrics.latency.entry("sign").or_default().observe(ms);
+        }
+    }
+    if let Some(ms) = record.timings.fetch_ms {
+        metrics.latency.entry("punch_list").or_default().observe(ms);
+    }
+}
+
+/// Counts a notification that could not be delivered.
+pub fn notification_failed() {
+    METRICS.lock().unwrap().notification_errors += 1;
+}
+
+/// Renders the metrics in the Prometheus text exposition format.
+///
+/// # Returns
+///
+/// * `String` - The exposition text.
+pub fn render() -> String {
+    let metrics = METRICS.lock().unwrap();
+    let mut out = String::new();
+
+    out.push_str("# HELP autocheckin_runs_total Task runs by outcome.\n");
+    out.push_str("# TYPE autocheckin_runs_total counter\n");
+    for (outcome, count) in &metrics.runs {
+        let _ = writeln!(
+            out,
+            "autocheckin_runs_total{{outcome=\"{}\"}} {}",
+            outcome, count
+        );
+    }
+
+    out.push_str("# HELP autocheckin_signs_total Sign attempts by result.\n");
+    out.push_str("# TYPE autocheckin_signs_total counter\n");
+    for (result, count) in &metrics.signs {
+        let _ = writeln!(
+            out,
+            "autocheckin_signs_total{{result=\"{}\"}} {}",
+            result, count
+        );
+    }
+
+    out.push_str("# HELP autocheckin_request_duration_seconds Latency of site requests.\n");
+    out.push_str("# TYPE autocheckin_request_duration_seconds histogram\n");
+    for (kind, histogram) in &metrics.latency {
+        let mut cumulative = 0;
+        for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+            cumulative += count;
+            let _ = writeln!(
+                out,
+                "autocheckin_request_duration_seconds_bucket{{kind=\"{}\",le=\"{}\"}} {}",
+                kind, bound, cumulative
+            );
+        }
+        let _ = writeln!(
+            out,
+            "autocheckin_request_duration_seconds_bucket{{kind=\"{}\",le=\"+Inf\"}} {}",
+            kind, histogram.count
+        );
+        let _ = writeln!(
+            out,
+            "autocheckin_request_duration_seconds_sum{{kind=\"{}\"}} {}",
+            kind, histogram.sum
+        );
+        let _ = writeln!(
+            out,
+            "autocheckin_request_duration_seconds_count{{kind=\"{}\"}} {}",
+            kind, histogram.count
+        );
+    }
+
+    out.push_str(
+        "# HELP autocheckin_notification_errors_total Notifications that failed to send.\n",
+    );
+    out.push_str("# TYPE autocheckin_notification_errors_total counter\n");
+    let _ = writeln!(
+        out,
+        "autocheckin_notification_errors_total {}",
+        metrics.notification_errors
+    );
+    out
+}
+
+/// Starts serving `GET /metrics` in a background thread.
+///
+/// # Arguments
+///
+/// * `config` - The endpoint settings.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Ok once listening, or an error message if the address cannot
+///   be bound.
+pub fn serve(config: &MetricsConfig) -> Result<(), String> {
+    let listener = TcpListener::bind((config.bind_address.as_str(), config.port))
+        .map_err(|e| e.to_string())?;
+    info!(
+        "Serving metrics on http://{}:{}/metrics",
+        config.bind_address, config.port
+    );
+    httpd::spawn(listener, MAX_CONNECTIONS, |stream| {
+        if let Err(e) = respond(stream) {
+            warn!("Metrics request failed: {}", e);
+        }
+    });
+    Ok(())
+}
+
+/// Answers one HTTP request on the metrics port.
+///
+/// The headers are read to the end before answering, so the client does not see a reset
+/// while still sending them.
+fn respond(stream: TcpStream) -> std::io::Result<()> {
+    let request = httpd::read_request(&stream, 0)?;
+    let (status, body) = if request.method == "GET" && request.path_and_query().0 == "/metrics" {
+        ("200 OK", render())
+    } else {
+        ("404 Not Found", "Not found\n".to_string())
+    };
+    httpd::write_response(&stream, status, "text/plain; version=0.0.4", &body)
+}
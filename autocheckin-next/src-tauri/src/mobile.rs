@@ -0,0 +1,106 @@
+use crate::config::Task;
+use tauri::AppHandle;
+
+#[cfg(target_os = "ios")]
+tauri::ios_plugin_binding!(init_plugin_background);
+
+/// Registers the native background plugin on mobile platforms.
+///
+/// On Android, this starts a foreground service with a persistent notification so the
+/// process, and with it the scheduler loop, survives while the app is in the background.
+/// On iOS, where apps are suspended shortly after leaving the foreground, it registers a
+/// background refresh task that briefly resumes the process near scheduled runs.
+///
+/// # Returns
+///
+/// * `TauriPlugin<Wry>` - The plugin to add to the app.
+#[cfg(mobile)]
+pub fn init() -> tauri::plugin::TauriPlugin<tauri::Wry> {
+    tauri::plugin::Builder::new("background")
+        .setup(|app, api| {
+            use tauri::Manager;
+            #[cfg(target_os = "android")]
+            let handle = {
+                let handle =
+                    api.register_android_plugin("com.autocheckin.next", "BackgroundPlugin")?;
+                if let Err(e) = handle.run_mobile_plugin::<()>("startService", ()) {
+                    log::warn!("Failed to start the background service: {}", e);
+                }
+                handle
+            };
+            #[cfg(target_os = "ios")]
+            let handle = api.register_ios_plugin(init_plugin_background)?;
+            app.manage(native::Background(handle));
+            Ok(())
+        })
+        .build()
+}
+
+/// Asks the OS to wake the app shortly before the next scheduled run.
+///
+/// On Android, the foreground service keeps the process alive, but Doze can still suspend
+/// it; an exact alarm before the next run brings it back in time. On iOS, a background
+/// refresh is requested for the same time. iOS decides on its own when refreshes actually
+/// run, so a local notification at the run time asks the user to open the app if it has
+/// not been woken by then. Does nothing on desktop.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `tasks` - The configured tasks.
+pub fn schedule_next_wake(app_handle: &AppHandle, tasks: &[Task]) {
+    #[cfg(mobile)]
+    native::schedule_next_wake(app_handle, tasks);
+    #[cfg(not(mobile))]
+    let _ = (app_handle, tasks);
+}
+
+#[cfg(mobile)]
+mod native {
+    use crate::config::Task;
+    use crate::i18n::{t, tf, Msg};
+    use crate::scheduler::next_run;
+    use chrono::{Local, TimeZone};
+    use serde_json::json;
+    use std::sync::Mutex;
+    use tauri::plugin::PluginHandle;
+    use tauri::{AppHandle, Manager, Wry};
+
+    /// How long before a run the wake-up is requested, leaving time for the network to
+    /// come up.
+    const WAKE_LEAD_SECS: i64 = 60;
+
+    /// Handle to the native `BackgroundPlugin`.
+    pub struct Background(pub PluginHandle<Wry>);
+
+    /// The last wake-up sent to the plugin, so the scheduler tick does not re-submit an
+    /// unchanged request every time.
+    static LAST_WAKE: Mutex<Option<i64>> = Mutex::new(None);
+
+    pub fn schedule_next_wake(app_handle: &AppHandle, tasks: &[Task]) {
+        let Some(background) = app_handle.try_state::<Background>() else {
+            return;
+        };
+        let Some((task, at)) = next_run(tasks, Local::now().naive_local()) else {
+            return;
+        };
+        let Some(at) = Local.from_local_datetime(&at).earliest() else {
+            return;
+        };
+        let run_at = at.timestamp_millis();
+        let mut last = LAST_WAKE.lock().unwrap();
+        if *last == Some(run_at) {
+            return;
+        }
+        let args = json!({
+            "at": run_at - WAKE_LEAD_SECS * 1000,
+            "runAt": run_at,
+            "reminderTitle": tf(Msg::RunReminderTitle, &[&task.name]),
+            "reminderBody": t(Msg::RunReminderBody),
+        });
+        match background.0.run_mobile_plugin::<()>("scheduleWake", args) {
+            Ok(()) => *last = Some(run_at),
+            Err(e) => log::warn!("Failed to schedule a wake-up: {}", e),
+        }
+    }
+}
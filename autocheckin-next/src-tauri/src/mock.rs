@@ -0,0 +1,389 @@
+pub use crate::auth::{AuthHandler, LoginSession, LoginState};
+pub use crate::config::{AppConfig, Task, TimeoutConfig};
+pub use crate::geo::CoordSystem;
+pub use crate::history::{RunOutcome, RunRecord};
+pub use crate::scheduler::Clock;
+pub use crate::task::TaskExecutor;
+use chrono::Local;
+use log::{debug, warn};
+use reqwest::Url;
+use serde_json::json;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Login page with the script the QR code parameters are read from.
+const LOGIN_QR_PAGE: &str = include_str!("mock/login_qr.html");
+
+/// Page the site shows instead of a course page without a valid cookie.
+const LOGIN_REQUIRED_PAGE: &str = include_str!("mock/login_required.html");
+
+/// Student home page the login lands on; `{courses}` is replaced with the course links.
+const STUDENT_PAGE: &str = include_str!("mock/student.html");
+
+/// Link to an enrolled course on the student home page.
+const COURSE_LINK: &str = include_str!("mock/course_link.html");
+
+/// Course home page, fetched by the warm-up navigation.
+const COURSE_PAGE: &str = include_str!("mock/course.html");
+
+/// Punch listing; `{cards}` is replaced with one card per open session.
+const PUNCHS_PAGE: &str = include_str!("mock/punchs.html");
+
+/// Card of an open GPS session on the punch listing.
+const PUNCH_CARD: &str = include_str!("mock/punch_card.html");
+
+/// Card of an open password-protected session on the punch listing.
+const PUNCH_PWD_CARD: &str = include_str!("mock/punch_pwd_card.html");
+
+/// Response to an accepted sign.
+const SIGN_SUCCESS_PAGE: &str = include_str!("mock/sign_success.html");
+
+/// Response to a sign for a session that is not open.
+const SIGN_CLOSED_PAGE: &str = include_str!("mock/sign_closed.html");
+
+/// Path of the QR login page, relative to the mock's base URL.
+const QR_PATH: &str = "/qr/weixin/student/2";
+
+/// The session cookie the mock site sets on login and expects afterwards.
+pub const MOCK_COOKIE: &str =
+    "remember_student_59ba36addc2b2f9401580f014c7f58ea4e30989d=mock-session";
+
+/// Session cookie of the login server, set with the QR page and needed to poll it.
+const QR_SESSION_COOKIE: &str = "PHPSESSID=mock-qr";
+
+/// How long a client may take to send its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Most header lines read from one request.
+const MAX_HEADERS: usize = 64;
+
+/// Largest request body accepted.
+const MAX_BODY: usize = 64 * 1024;
+
+/// A sign form received by the mock site.
+#[derive(Debug, Clone)]
+pub struct MockSign {
+    /// Class ID from the form target.
+    pub class_id: String,
+    /// Session ID from the form target.
+    pub sign_id: String,
+    /// Whether the session was open and the sign was accepted.
+    pub accepted: bool,
+    /// The submitted form fields.
+    pub form: HashMap<String, String>,
+}
+
+/// What the mock site knows and has received.
+#[derive(Debug, Default)]
+struct MockState {
+    /// Whether the QR code has been scanned, so login polls from the client that loaded
+    /// the QR page succeed.
+    scanned: bool,
+    /// Class IDs of the courses listed on the student home page.
+    courses: Vec<String>,
+    /// Open session IDs per class ID.
+    open: HashMap<String, Vec<String>>,
+    /// Passwords of the password-protected sessions, by session ID.
+    passwords: HashMap<String, String>,
+    /// Every sign form received, in order.
+    signs: Vec<MockSign>,
+}
+
+/// A local stand-in for the check-in and login sites, serving recorded responses.
+///
+/// Point `endpoints.base_url` at `base_url()` and create the `AuthHandler` with
+/// `AuthHandler::with_urls(timeouts, &site.qr_url(), site.base_url())` to develop or test
+/// the protocol without touching the real site. Course pages need `MOCK_COOKIE`; the
+/// cookie is set by the login flow.
+pub struct MockSite {
+    base_url: String,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockSite {
+    /// Starts the mock site on a free local port in a background thread.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<MockSite, String>` - The running site, or an error message if no port
+    ///   could be bound.
+    pub fn start() -> Result<Self, String> {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).map_err(|e| e.to_string())?;
+        let addr = listener.local_addr().map_err(|e| e.to_string())?;
+        let state = Arc::new(Mutex::new(MockState::default()));
+        let shared = state.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                if let Err(e) = respond(&shared, stream) {
+                    warn!("Mock site request failed: {}", e);
+                }
+            }
+        });
+        Ok(Self {
+            base_url: format!("http://{}", addr),
+            state,
+        })
+    }
+
+    /// The base URL of the mock site, without a trailing slash.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// The URL of the QR login page, as used by `AuthHandler`.
+    pub fn qr_url(&self) -> String {
+        format!("{}{}", self.base_url, QR_PATH)
+    }
+
+    /// Marks the QR code as scanned, so the next login poll succeeds.
+    pub fn scan_qr(&self) {
+        self.state.lock().unwrap().scanned = true;
+    }
+
+    /// Lists a course on the student home page the login lands on.
+    ///
+    /// # Arguments
+    ///
+    /// * `class_id` - The class ID.
+    pub fn enroll(&self, class_id: &str) {
+        self.state
+            .lock()
+            .unwrap()
+            .courses
+            .push(class_id.to_string());
+    }
+
+    /// Opens a GPS check-in session on a class's punch listing.
+    ///
+    /// # Arguments
+    ///
+    /// * `class_id` - The class ID.
+    /// * `sign_id` - The session ID shown on the card.
+    pub fn open_session(&self, class_id: &str, sign_id: &str) {
+        self.state
+            .lock()
+            .unwrap()
+            .open
+            .entry(class_id.to_string())
+            .or_default()
+            .push(sign_id.to_string());
+    }
+
+    /// Opens a password-protected check-in session on a class's punch listing.
+    ///
+    /// # Arguments
+    ///
+    /// * `class_id` - The class ID.
+    /// * `sign_id` - The session ID shown on the card.
+    /// * `password` - The password a sign must send as `pwd`.
+    pub fn open_password_session(&self, class_id: &str, sign_id: &str, password: &str) {
+        self.open_session(class_id, sign_id);
+        self.state
+            .lock()
+            .unwrap()
+            .passwords
+            .insert(sign_id.to_string(), password.to_string());
+    }
+
+    /// The sign forms received so far, in order.
+    pub fn signs(&self) -> Vec<MockSign> {
+        self.state.lock().unwrap().signs.clone()
+    }
+}
+
+/// Reads one request and writes the response.
+fn respond(state: &Mutex<MockState>, stream: TcpStream) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut content_length = 0;
+    let mut cookie = String::new();
+    for _ in 0..MAX_HEADERS {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            let name = name.trim();
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            } else if name.eq_ignore_ascii_case("cookie") {
+                cookie = value.trim().to_string();
+            }
+        }
+    }
+    let mut body = vec![0; content_length.min(MAX_BODY)];
+    reader.read_exact(&mut body)?;
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("");
+    let target = parts.next().unwrap_or("");
+    debug!("Mock site: {} {}", method, target);
+    let response = match Url::parse(&format!("http://localhost{}", target)) {
+        Ok(url) => route(state, method, &url, &cookie, &body),
+        Err(_) => Response::text("400 Bad Request", ""),
+    };
+
+    let mut stream = &stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n",
+        response.status,
+        response.content_type,
+        response.body.len()
+    )?;
+    for header in &response.headers {
+        write!(stream, "{}\r\n", header)?;
+    }
+    write!(stream, "Connection: close\r\n\r\n{}", response.body)
+}
+
+/// A response of the mock site.
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    headers: Vec<String>,
+    body: String,
+}
+
+impl Response {
+    /// An HTML page.
+    fn html(body: String) -> Self {
+        Self {
+            status: "200 OK",
+            content_type: "text/html; charset=utf-8",
+            headers: vec![],
+            body,
+        }
+    }
+
+    /// A plain text response with the given status.
+    fn text(status: &'static str, body: &str) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            headers: vec![],
+            body: body.to_string(),
+        }
+    }
+}
+
+/// Answers a request the way the real sites do.
+fn route(state: &Mutex<MockState>, method: &str, url: &Url, cookie: &str, body: &[u8]) -> Response {
+    let segments: Vec<&str> = url.path().trim_matches('/').split('/').collect();
+    let logged_in = cookie.split(';').any(|c| c.trim() == MOCK_COOKIE);
+    let mut state = state.lock().unwrap();
+    match (method, segments.as_slice()) {
+        ("GET", _) if url.path() == QR_PATH => {
+            if url
+                .query_pairs()
+                .any(|(k, v)| k == "op" && v == "checklogin")
+            {
+                let polled_by_viewer = cookie.split(';').any(|c| c.trim() == QR_SESSION_COOKIE);
+                let reply = if state.scanned && polled_by_viewer {
+                    json!({
+                        "status": 1,
+                        "url": "https://bj.k8n.cn/student/uidlogin?uid=10001&sign=mock",
+                    })
+                } else {
+                    json!({ "status": 0 })
+                };
+                Response {
+                    content_type: "application/json",
+                    ..Response::html(reply.to_string())
+                }
+            } else {
+                Response {
+                    headers: vec![format!("Set-Cookie: {}; Path=/qr", QR_SESSION_COOKIE)],
+                    ..Response::html(LOGIN_QR_PAGE.to_string())
+                }
+            }
+        }
+        ("GET", ["student", "uidlogin"]) => Response {
+            status: "302 Found",
+            headers: vec![
+                format!("Set-Cookie: {}; Path=/; HttpOnly", MOCK_COOKIE),
+                "Location: /student".to_string(),
+            ],
+            ..Response::html(String::new())
+        },
+        ("GET", ["student"]) if logged_in => {
+            let courses: String = state
+                .courses
+                .iter()
+                .map(|id| COURSE_LINK.replace("{class}", id))
+                .collect();
+            Response::html(STUDENT_PAGE.replace("{courses}", &courses))
+        }
+        ("GET", ["student"]) | ("GET", [""]) => Response::html(String::new()),
+        ("GET", ["student", "course", ..]) if !logged_in => {
+            Response::html(LOGIN_REQUIRED_PAGE.to_string())
+        }
+        ("GET", ["student", "course", class_id]) => {
+            Response::html(COURSE_PAGE.replace("{class}", class_id))
+        }
+        ("GET", ["student", "course", class_id, "punchs"]) => {
+            let cards: String = state
+                .open
+                .get(*class_id)
+                .into_iter()
+                .flatten()
+                .map(|id| match state.passwords.contains_key(id) {
+                    true => PUNCH_PWD_CARD.replace("{id}", id),
+                    false => PUNCH_CARD.replace("{id}", id),
+                })
+                .collect();
+            Response::html(
+                PUNCHS_PAGE
+                    .replace("{class}", class_id)
+                    .replace("{cards}", &cards),
+            )
+        }
+        ("POST", ["student", "punchs", "course", ..]) if !logged_in => {
+            Response::html(LOGIN_REQUIRED_PAGE.to_string())
+        }
+        ("POST", ["student", "punchs", "course", class_id, sign_id]) => {
+            // The body is URL-encoded like a query string, so parse it as one.
+            let form: HashMap<String, String> = Url::parse(&format!(
+                "http://localhost/?{}",
+                String::from_utf8_lossy(body)
+            ))
+            .map(|form| form.query_pairs().into_owned().collect())
+            .unwrap_or_default();
+            let password_ok = state
+                .passwords
+                .get(*sign_id)
+                .is_none_or(|password| form.get("pwd") == Some(password));
+            let open = state.open.entry(class_id.to_string()).or_default();
+            let accepted = match open
+                .iter()
+                .position(|id| id == sign_id)
+                .filter(|_| password_ok)
+            {
+                Some(index) => {
+                    open.remove(index);
+                    true
+                }
+                None => false,
+            };
+            state.signs.push(MockSign {
+                class_id: class_id.to_string(),
+                sign_id: sign_id.to_string(),
+                accepted,
+                form,
+            });
+            if accepted {
+                let time = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+                Response::html(SIGN_SUCCESS_PAGE.replace("{time}", &time))
+            } else {
+                Response::html(SIGN_CLOSED_PAGE.to_string())
+            }
+        }
+        _ => Response::text("404 Not Found", "not found"),
+    }
+}
@@ -0,0 +1,377 @@
+use crate::config::{ConfigState, MqttConfig, Task};
+use crate::events::{self, Event};
+use crate::history::{load_history, RunRecord};
+use crate::scheduler::run_task_now;
+use crate::tray::set_task_enabled;
+use log::{debug, info, warn};
+use serde_json::{json, Value};
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::sync::mpsc::RecvTimeoutError;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+/// Keep-alive interval announced to the broker; a ping is sent at this interval.
+const KEEP_ALIVE: Duration = Duration::from_secs(60);
+
+/// Delay before reconnecting after the connection drops.
+const RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// Timeout for connecting and for the broker's CONNACK.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// MQTT control packet types (upper nibble of the first byte).
+const CONNECT: u8 = 0x10;
+const CONNACK: u8 = 0x20;
+const PUBLISH: u8 = 0x30;
+const SUBSCRIBE: u8 = 0x82;
+const PINGREQ: u8 = 0xC0;
+
+/// Starts the MQTT connection in a background thread, reconnecting when it drops.
+///
+/// Each task is announced to Home Assistant through MQTT discovery as a sensor with the
+/// last run result, a switch for the enable flag and a button that runs it now.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `config` - The MQTT settings.
+pub fn start(app_handle: AppHandle, config: MqttConfig) {
+    if config.host.trim().is_empty() {
+        warn!("MQTT is enabled but no broker host is set");
+        return;
+    }
+    thread::spawn(move || loop {
+        match run_session(&app_handle, &config) {
+            Ok(()) => return,
+            Err(e) => warn!(
+                "MQTT connection to {} lost: {}; retrying in {} s",
+                config.host,
+                e,
+                RECONNECT_DELAY.as_secs()
+            ),
+        }
+        thread::sleep(RECONNECT_DELAY);
+    });
+}
+
+/// Connects once, publishes discovery and states, then forwards events and commands
+/// until the connection fails.
+fn run_session(app_handle: &AppHandle, config: &MqttConfig) -> io::Result<()> {
+    let mut stream = TcpStream::connect((config.host.as_str(), config.port))?;
+    stream.set_read_timeout(Some(CONNECT_TIMEOUT))?;
+    stream.write_all(&connect_packet(config))?;
+    let (header, body) = read_packet(&mut stream)?;
+    if header & 0xF0 != CONNACK || body.get(1) != Some(&0) {
+        return Err(io::Error::other(format!(
+            "broker refused the connection (code {:?})",
+            body.get(1)
+        )));
+    }
+    stream.set_read_timeout(None)?;
+    info!("Connected to MQTT broker {}:{}", config.host, config.port);
+
+    let base = config.base_topic.trim_end_matches('/');
+    publish(&mut stream, &availability_topic(base), b"online", true)?;
+    stream.write_all(&subscribe_packet(&[
+        &format!("{}/+/enable/set", base),
+        &format!("{}/+/run", base),
+    ]))?;
+    publish_all(&mut stream, app_handle, config)?;
+
+    // Commands are read on their own thread; events and pings are written on this one.
+    let reader = stream.try_clone()?;
+    let commands_app = app_handle.clone();
+    let commands_base = base.to_string();
+    thread::spawn(move || {
+        if let Err(e) = read_commands(reader, &commands_app, &commands_base) {
+            debug!("MQTT reader stopped: {}", e);
+        }
+    });
+
+    let result = forward_events(&mut stream, app_handle, config);
+    // Closes the reader's handle too, so its thread ends.
+    let _ = stream.shutdown(Shutdown::Both);
+    result
+}
+
+/// Publishes run results and refreshed discovery until writing fails, pinging the broker
+/// whenever nothing was sent for a keep-alive interval.
+fn forward_events(
+    stream: &mut TcpStream,
+    app_handle: &AppHandle,
+    config: &MqttConfig,
+) -> io::Result<()> {
+    let base = config.base_topic.trim_end_matches('/');
+    let receiver = events::subscribe();
+    let mut last_sent = Instant::now();
+    loop {
+        let wait = KEEP_ALIVE.saturating_sub(last_sent.elapsed());
+        match receiver.recv_timeout(wait) {
+            Ok(Event {
+                kind: "run_finished",
+                data,
+            }) => {
+                if let Some(task_id) = data["task_id"].as_str() {
+                    publish(
+                        stream,
+                        &format!("{}/{}/state", base, task_id),
+                        data.to_string().as_bytes(),
+                        true,
+                    )?;
+                    last_sent = Instant::now();
+                }
+            }
+            Ok(Event {
+                kind: "config_changed",
+                ..
+            }) => {
+                publish_all(stream, app_handle, config)?;
+                last_sent = Instant::now();
+            }
+            Ok(_) => {}
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+        if last_sent.elapsed() >= KEEP_ALIVE {
+            stream.write_all(&[PINGREQ, 0])?;
+            last_sent = Instant::now();
+        }
+    }
+}
+
+/// Publishes discovery configs, enable states and last results of all tasks.
+fn publish_all(
+    stream: &mut TcpStream,
+    app_handle: &AppHandle,
+    config: &MqttConfig,
+) -> io::Result<()> {
+    let tasks = app_handle
+        .state::<ConfigState>()
+        .0
+        .lock()
+        .unwrap()
+        .tasks
+        .clone();
+    let history = load_history(app_handle);
+    let base = config.base_topic.trim_end_matches('/');
+    for task in &tasks {
+        for (topic, payload) in discovery_configs(config, task) {
+            publish(stream, &topic, payload.to_string().as_bytes(), true)?;
+        }
+        publish(
+            stream,
+            &format!("{}/{}/enable", base, task.id),
+            if task.enable { b"ON" } else { b"OFF" },
+            true,
+        )?;
+        if let Some(record) = history.iter().rev().find(|r| r.task_id == task.id) {
+            publish(
+                stream,
+                &format!("{}/{}/state", base, task.id),
+                state_payload(record).to_string().as_bytes(),
+                true,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Builds the Home Assistant discovery topics and payloads for a task.
+fn discovery_configs(config: &MqttConfig, task: &Task) -> Vec<(String, Value)> {
+    let base = config.base_topic.trim_end_matches('/');
+    let prefix = config.discovery_prefix.trim_end_matches('/');
+    let object_id = format!("autocheckin_{}", task.id.replace('-', "_"));
+    let device = json!({
+        "identifiers": ["autocheckin_next"],
+        "name": "AutoCheckin-Next",
+        "sw_version": env!("CARGO_PKG_VERSION"),
+    });
+    let availability = availability_topic(base);
+    vec![
+        (
+            format!("{}/sensor/{}/last_result/config", prefix, object_id),
+            json!({
+                "name": format!("{} last result", task.name),
+                "unique_id": format!("{}_last_result", object_id),
+                "state_topic": format!("{}/{}/state", base, task.id),
+                "value_template": "{{ value_json.outcome }}",
+                "json_attributes_topic": format!("{}/{}/state", base, task.id),
+                "availability_topic": availability,
+                "icon": "mdi:calendar-check",
+                "device": device,
+            }),
+        ),
+        (
+            format!("{}/switch/{}/enabled/config", prefix, object_id),
+            json!({
+                "name": format!("{} enabled", task.name),
+                "unique_id": format!("{}_enabled", object_id),
+                "state_topic": format!("{}/{}/enable", base, task.id),
+                "command_topic": format!("{}/{}/enable/set", base, task.id),
+                "availability_topic": availability,
+                "device": device,
+            }),
+        ),
+        (
+            format!("{}/button/{}/run/config", prefix, object_id),
+            json!({
+                "name": format!("Run {} now", task.name),
+                "unique_id": format!("{}_run", object_id),
+                "command_topic": format!("{}/{}/run", base, task.id),
+                "availability_topic": availability,
+                "device": device,
+            }),
+        ),
+    ]
+}
+
+/// The state payload of a finished run, matching the `run_finished` event data.
+fn state_payload(record: &RunRecord) -> Value {
+    json!({
+        "task_id": record.task_id,
+        "task_name": record.task_name,
+        "outcome": record.outcome,
+        "message": record.message,
+        "timestamp": record.finished_at.to_rfc3339(),
+    })
+}
+
+/// Topic carrying `online`/`offline` for the whole app.
+fn availability_topic(base: &str) -> String {
+    format!("{}/status", base)
+}
+
+/// Handles enable and run commands published by Home Assistant.
+fn read_commands(mut stream: TcpStream, app_handle: &AppHandle, base: &str) -> io::Result<()> {
+    loop {
+        let (header, body) = read_packet(&mut stream)?;
+        if header & 0xF0 != PUBLISH {
+            continue;
+        }
+        let Some((topic, payload)) = parse_publish(header, &body) else {
+            continue;
+        };
+        let Some(rest) = topic.strip_prefix(base).and_then(|t| t.strip_prefix('/')) else {
+            continue;
+        };
+        let result = match rest.split('/').collect::<Vec<_>>().as_slice() {
+            [task_id, "enable", "set"] => {
+                let enable = payload.trim().eq_ignore_ascii_case("ON");
+                set_task_enabled(app_handle, task_id, Some(enable))
+            }
+            [task_id, "run"] => run_task_now(app_handle, task_id),
+            _ => continue,
+        };
+        if let Err(e) = result {
+            warn!("MQTT command on {} failed: {}", topic, e);
+        }
+    }
+}
+
+/// Splits a received PUBLISH packet into topic and payload.
+fn parse_publish(header: u8, body: &[u8]) -> Option<(String, String)> {
+    let topic_len = u16::from_be_bytes([*body.first()?, *body.get(1)?]) as usize;
+    let topic = String::from_utf8(body.get(2..2 + topic_len)?.to_vec()).ok()?;
+    // QoS 1 and 2 messages carry a packet identifier before the payload.
+    let offset = 2 + topic_len + if header & 0x06 != 0 { 2 } else { 0 };
+    let payload = String::from_utf8_lossy(body.get(offset..)?).into_owned();
+    Some((topic, payload))
+}
+
+/// Sends a QoS 0 PUBLISH packet.
+fn publish(stream: &mut TcpStream, topic: &str, payload: &[u8], retain: bool) -> io::Result<()> {
+    let mut body = encode_str(topic);
+    body.extend_from_slice(payload);
+    stream.write_all(&packet(PUBLISH | u8::from(retain), &body))
+}
+
+/// Builds the CONNECT packet, with an `offline` last will on the availability topic.
+fn connect_packet(config: &MqttConfig) -> Vec<u8> {
+    let mut flags = 0x02 | 0x04 | 0x20; // clean session, will, retained will
+    if !config.username.is_empty() {
+        flags |= 0x80;
+        if !config.password.is_empty() {
+            flags |= 0x40;
+        }
+    }
+    let mut body = encode_str("MQTT");
+    body.push(4); // protocol level 3.1.1
+    body.push(flags);
+    body.extend_from_slice(&(KEEP_ALIVE.as_secs() as u16 * 2).to_be_bytes());
+    body.extend(encode_str(&format!(
+        "autocheckin-next-{:08x}",
+        rand::random::<u32>()
+    )));
+    body.extend(encode_str(&availability_topic(
+        config.base_topic.trim_end_matches('/'),
+    )));
+    body.extend(encode_str("offline"));
+    if !config.username.is_empty() {
+        body.extend(encode_str(&config.username));
+        if !config.password.is_empty() {
+            body.extend(encode_str(&config.password));
+        }
+    }
+    packet(CONNECT, &body)
+}
+
+/// Builds a SUBSCRIBE packet for QoS 0 topic filters.
+fn subscribe_packet(filters: &[&str]) -> Vec<u8> {
+    let mut body = 1u16.to_be_bytes().to_vec();
+    for filter in filters {
+        body.extend(encode_str(filter));
+        body.push(0);
+    }
+    packet(SUBSCRIBE, &body)
+}
+
+/// Prefixes a packet body with its fixed header.
+fn packet(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut out = vec![header];
+    let mut len = body.len();
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        out.push(byte);
+        if len == 0 {
+            break;
+        }
+    }
+    out.extend_from_slice(body);
+    out
+}
+
+/// Encodes a length-prefixed UTF-8 string.
+fn encode_str(value: &str) -> Vec<u8> {
+    let mut out = (value.len() as u16).to_be_bytes().to_vec();
+    out.extend_from_slice(value.as_bytes());
+    out
+}
+
+/// Reads one packet, returning its first byte and body.
+fn read_packet(stream: &mut TcpStream) -> io::Result<(u8, Vec<u8>)> {
+    let mut header = [0u8; 1];
+    stream.read_exact(&mut header)?;
+    let mut len = 0usize;
+    let mut shift = 0;
+    loop {
+        let mut byte = [0u8; 1];
+        stream.read_exact(&mut byte)?;
+        len |= ((byte[0] & 0x7F) as usize) << shift;
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+        if shift > 21 {
+            return Err(io::Error::other("malformed packet length"));
+        }
+    }
+    let mut body = vec![0u8; len];
+    stream.read_exact(&mut body)?;
+    Ok((header[0], body))
+}
@@ -0,0 +1,88 @@
+use crate::config::{Location, Task};
+use crate::geo::validate_coordinates;
+use crate::i18n::{t, Msg};
+use base64::engine::general_purpose;
+use base64::Engine as _;
+use serde::{Deserialize, Serialize};
+
+/// Prefix identifying a location share code and its format version.
+const SHARE_CODE_PREFIX: &str = "ACN-LOC1:";
+
+/// A shareable location preset. Contains no cookies or other secrets.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LocationPreset {
+    /// Name of the place, e.g. "Teaching Building 3, Room 201".
+    pub name: String,
+    /// The measured location.
+    pub location: Location,
+    /// Suggested drift radius in meters.
+    #[serde(default)]
+    pub radius_m: Option<f64>,
+}
+
+impl LocationPreset {
+    /// Creates a preset from a task's name, location and drift radius.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - The task to share the location of.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The preset.
+    pub fn from_task(task: &Task) -> Self {
+        Self {
+            name: task.name.clone(),
+            location: task.location.clone(),
+            radius_m: task.drift_radius_m,
+        }
+    }
+}
+
+/// Encodes a preset as a share code that can be pasted into chat.
+///
+/// # Arguments
+///
+/// * `preset` - The preset to share.
+///
+/// # Returns
+///
+/// * `Result<String, String>` - The share code, or an error message if the location is invalid.
+pub fn encode_share_code(preset: &LocationPreset) -> Result<String, String> {
+    validate_coordinates(&preset.location.lat, &preset.location.lng)?;
+    let json = serde_json::to_vec(preset).map_err(|e| e.to_string())?;
+    Ok(format!(
+        "{}{}",
+        SHARE_CODE_PREFIX,
+        general_purpose::URL_SAFE_NO_PAD.encode(json)
+    ))
+}
+
+/// Decodes and validates a share code.
+///
+/// Surrounding text (e.g. a chat message) is ignored as long as the code itself is intact.
+///
+/// # Arguments
+///
+/// * `code` - The share code.
+///
+/// # Returns
+///
+/// * `Result<LocationPreset, String>` - The preset, or an error message if the code is
+///   malformed or its location is invalid.
+pub fn decode_share_code(code: &str) -> Result<LocationPreset, String> {
+    let start = code
+        .find(SHARE_CODE_PREFIX)
+        .ok_or_else(|| t(Msg::InvalidShareCode).to_string())?;
+    let payload: String = code[start + SHARE_CODE_PREFIX.len()..]
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .collect();
+    let json = general_purpose::URL_SAFE_NO_PAD
+        .decode(payload)
+        .map_err(|_| t(Msg::InvalidShareCode).to_string())?;
+    let preset: LocationPreset =
+        serde_json::from_slice(&json).map_err(|_| t(Msg::InvalidShareCode).to_string())?;
+    validate_coordinates(&preset.location.lat, &preset.location.lng)?;
+    Ok(preset)
+}
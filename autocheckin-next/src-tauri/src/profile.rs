@@ -0,0 +1,730 @@
+use crate::config::{ConfigState, SiteProfileConfig};
+use crate::geo::validate_coordinates;
+use crate::history::AttendanceStatus;
+use crate::httplog;
+use crate::i18n::{t, tf, Msg};
+use crate::task::SiteLocation;
+use crate::tls;
+use chrono::NaiveDate;
+use log::{info, warn};
+use minisign_verify::{PublicKey, Signature};
+use regex::Regex;
+use reqwest::header::USER_AGENT;
+use scraper::{Html, Selector};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, LazyLock, RwLock};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// Timeout for downloading the profile and its signature.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Extension appended to the profile URL to get its minisign signature.
+const SIGNATURE_SUFFIX: &str = ".minisig";
+
+/// Selects the named form fields inside a check-in card.
+static FIELD_SELECTOR: LazyLock<Selector> =
+    LazyLock::new(|| Selector::parse("input[name], select[name], textarea[name]").unwrap());
+
+/// The rules currently used to parse site responses.
+static ACTIVE: LazyLock<RwLock<Arc<Parser>>> = LazyLock::new(|| {
+    RwLock::new(Arc::new(
+        Parser::compile(&SiteProfile::default()).expect("built-in site profile is invalid"),
+    ))
+});
+
+/// Selectors, patterns and keywords used to read the check-in site, versioned so a site
+/// redesign can be handled by shipping a new profile instead of a new binary.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct SiteProfile {
+    /// Profile version; a downloaded profile only replaces an older one.
+    pub version: u32,
+    /// Oldest app version that understands this profile, if any.
+    pub min_app_version: Option<String>,
+    /// CSS selector of a check-in card on the punch listing.
+    pub card_selector: String,
+    /// CSS selector of the session title inside a card.
+    pub title_selector: String,
+    /// Text marking a card as already signed.
+    pub signed_markers: Vec<String>,
+    /// Text marking a response as the login page.
+    pub login_markers: Vec<String>,
+    /// URL path fragment of the login page the site redirects to.
+    pub login_path: String,
+    /// Patterns whose first group is a check-in session ID.
+    pub sign_id_patterns: Vec<String>,
+    /// Pattern whose first group is the latitude required by a card.
+    pub site_lat_pattern: String,
+    /// Pattern whose first group is the longitude required by a card.
+    pub site_lng_pattern: String,
+    /// Pattern whose first group is the allowed radius of a card in meters.
+    pub site_radius_pattern: String,
+    /// Text in a sign response that means it succeeded.
+    pub success_keywords: Vec<String>,
+    /// Text in a sign result that means the site reported an error.
+    pub error_keywords: Vec<String>,
+    /// Pattern whose first group is the date of a card on the punch history
+    /// (`YYYY-MM-DD`, `YYYY/MM/DD` or `YYYY年M月D日`).
+    pub record_date_pattern: String,
+    /// Text marking a past card as signed late; checked before `signed_markers`.
+    pub late_markers: Vec<String>,
+    /// Text marking a past card as missed.
+    pub absent_markers: Vec<String>,
+    /// Pattern whose first group is the name of whoever signed a card, if shown.
+    pub signer_pattern: String,
+    /// Text in a failed sign result meaning the location was too far away.
+    pub out_of_range_keywords: Vec<String>,
+    /// Text in a failed sign result meaning a password is needed or was wrong.
+    pub password_keywords: Vec<String>,
+    /// Text marking a card as a password-protected session.
+    pub password_markers: Vec<String>,
+    /// CSS selectors every punch listing matches, with or without open sessions.
+    pub layout_selectors: Vec<String>,
+    /// Names of the form fields known to appear inside check-in cards.
+    pub form_fields: Vec<String>,
+    /// Pattern whose first group is the logged-in student's name on a course page.
+    pub student_name_pattern: String,
+}
+
+impl Default for SiteProfile {
+    /// Creates the built-in profile, matching the site at the time of the build.
+    fn default() -> Self {
+        let strings = |items: &[&str]| items.iter().map(|s| s.to_string()).collect();
+        Self {
+            version: 1,
+            min_app_version: None,
+            card_selector: "div.card-body".to_string(),
+            title_selector: "h4, h5, .card-title".to_string(),
+            signed_markers: strings(&["已签"]),
+            login_markers: strings(&["请先登录"]),
+            login_path: "login".to_string(),
+            sign_id_patterns: strings(&[
+                r"punchcard_(\d+)",
+                r"punch_pwd_frm_(\d+)",
+                r"punch_gps\((\d+)\)",
+            ]),
+            site_lat_pattern: r#"(?i)\blat(?:itude)?["']?\s*[:=]\s*["']?(-?\d{1,2}\.\d+)"#
+                .to_string(),
+            site_lng_pattern:
+                r#"(?i)\b(?:lng|lon|longitude)["']?\s*[:=]\s*["']?(-?\d{1,3}\.\d+)"#.to_string(),
+            site_radius_pattern:
+                r#"(?i)(?:\b(?:radius|range|distance)["']?\s*[:=]\s*["']?|范围[:：]?\s*)(\d+(?:\.\d+)?)"#
+                    .to_string(),
+            success_keywords: strings(&["成功", "Success"]),
+            error_keywords: strings(&["出错", "Error"]),
+            record_date_pattern: r"(\d{4}[-/年]\d{1,2}[-/月]\d{1,2}日?)".to_string(),
+            late_markers: strings(&["迟到"]),
+            absent_markers: strings(&["未签", "缺勤", "缺席"]),
+            signer_pattern: r"(?:签到人|代签人?|操作人)[:：]\s*([^<\s]+)".to_string(),
+            out_of_range_keywords: strings(&["范围", "距离", "太远"]),
+            password_keywords: strings(&["密码", "口令", "password"]),
+            password_markers: strings(&["punch_pwd_frm_"]),
+            layout_selectors: strings(&[
+                "nav.navbar",
+                "div.container",
+                r#"a[href*="/student/course/"]"#,
+            ]),
+            form_fields: strings(&["id", "lat", "lng", "acc", "res", "gps_addr", "pwd"]),
+            student_name_pattern: r"(?:姓名|学生|欢迎)[:：,，]?\s*([^<\s]+)".to_string(),
+        }
+    }
+}
+
+/// The structure of a punch listing, compared against the profile to notice a redesign.
+#[derive(Debug, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct PageFingerprint {
+    /// Layout selectors of the profile that matched nothing.
+    pub missing_selectors: Vec<String>,
+    /// Number of elements matching the card selector.
+    pub cards: usize,
+    /// Cards not marked as signed.
+    pub open_cards: usize,
+    /// Open cards a session ID was read from.
+    pub readable_cards: usize,
+    /// Session IDs matched anywhere in the page.
+    pub page_sign_ids: usize,
+    /// Names of the form fields inside cards.
+    pub form_fields: BTreeSet<String>,
+}
+
+/// A compiled `SiteProfile`.
+pub struct Parser {
+    /// Version of the profile this was compiled from.
+    pub version: u32,
+    card_selector: Selector,
+    title_selector: Selector,
+    signed_markers: Vec<String>,
+    login_markers: Vec<String>,
+    login_path: String,
+    sign_id_patterns: Vec<Regex>,
+    site_lat: Regex,
+    site_lng: Regex,
+    site_radius: Regex,
+    success_keywords: Vec<String>,
+    error_keywords: Vec<String>,
+    record_date: Regex,
+    late_markers: Vec<String>,
+    absent_markers: Vec<String>,
+    signer: Regex,
+    out_of_range_keywords: Vec<String>,
+    password_keywords: Vec<String>,
+    password_markers: Vec<String>,
+    layout_selectors: Vec<(String, Selector)>,
+    form_fields: BTreeSet<String>,
+    student_name: Regex,
+}
+
+impl Parser {
+    /// Compiles the selectors and patterns of a profile.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The profile to compile.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Parser, String>` - The parser, or an error naming the invalid field.
+    pub fn compile(profile: &SiteProfile) -> Result<Self, String> {
+        let regex = |field: &str, pattern: &str| {
+            Regex::new(pattern).map_err(|e| format!("{}: {}", field, e))
+        };
+        Ok(Self {
+            version: profile.version,
+            card_selector: Selector::parse(&profile.card_selector)
+                .map_err(|e| format!("card_selector: {}", e))?,
+            title_selector: Selector::parse(&profile.title_selector)
+                .map_err(|e| format!("title_selector: {}", e))?,
+            signed_markers: profile.signed_markers.clone(),
+            login_markers: profile.login_markers.clone(),
+            login_path: profile.login_path.clone(),
+            sign_id_patterns: profile
+                .sign_id_patterns
+                .iter()
+                .map(|pattern| regex("sign_id_patterns", pattern))
+                .collect::<Result<_, _>>()?,
+            site_lat: regex("site_lat_pattern", &profile.site_lat_pattern)?,
+            site_lng: regex("site_lng_pattern", &profile.site_lng_pattern)?,
+            site_radius: regex("site_radius_pattern", &profile.site_radius_pattern)?,
+            success_keywords: profile.success_keywords.clone(),
+            error_keywords: profile.error_keywords.clone(),
+            record_date: regex("record_date_pattern", &profile.record_date_pattern)?,
+            late_markers: profile.late_markers.clone(),
+            absent_markers: profile.absent_markers.clone(),
+            signer: regex("signer_pattern", &profile.signer_pattern)?,
+            out_of_range_keywords: profile.out_of_range_keywords.clone(),
+            password_keywords: profile.password_keywords.clone(),
+            password_markers: profile.password_markers.clone(),
+            layout_selectors: profile
+                .layout_selectors
+                .iter()
+                .map(|selector| {
+                    Selector::parse(selector)
+                        .map(|parsed| (selector.clone(), parsed))
+                        .map_err(|e| format!("layout_selectors: {}", e))
+                })
+                .collect::<Result<_, _>>()?,
+            form_fields: profile.form_fields.iter().cloned().collect(),
+            student_name: regex("student_name_pattern", &profile.student_name_pattern)?,
+        })
+    }
+
+    /// Selects the check-in cards on the punch listing.
+    pub fn card_selector(&self) -> &Selector {
+        &self.card_selector
+    }
+
+    /// The session title shown on a card, if any.
+    pub fn title(&self, card_html: &str) -> Option<String> {
+        let fragment = Html::parse_fragment(card_html);
+        let title = fragment
+            .select(&self.title_selector)
+            .next()?
+            .text()
+            .collect::<String>();
+        let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+        (!title.is_empty()).then_some(title)
+    }
+
+    /// Whether a card has already been signed.
+    pub fn is_signed(&self, card_html: &str) -> bool {
+        contains_any(card_html, &self.signed_markers)
+    }
+
+    /// Whether a response is the login page, by its final URL path or its body.
+    pub fn is_login_page(&self, path: &str, body: &str) -> bool {
+        (!self.login_path.is_empty() && path.contains(&self.login_path))
+            || contains_any(body, &self.login_markers)
+    }
+
+    /// The logged-in student's name shown on a page, if any.
+    pub fn student_name(&self, body: &str) -> Option<String> {
+        self.student_name
+            .captures(body)
+            .and_then(|cap| cap.get(1))
+            .map(|name| name.as_str().trim().to_string())
+            .filter(|name| !name.is_empty())
+    }
+
+    /// The check-in session IDs in a card.
+    pub fn sign_ids(&self, card_html: &str) -> Vec<String> {
+        self.sign_id_patterns
+            .iter()
+            .flat_map(|re| re.captures_iter(card_html))
+            .filter_map(|cap| cap.get(1).map(|id| id.as_str().to_string()))
+            .collect()
+    }
+
+    /// Reads the required coordinates and radius embedded in a check-in card, if any.
+    ///
+    /// Cards that enforce a location carry them in attributes or inline script, e.g.
+    /// `data-lat="39.9"`, `lng: 116.4` or `范围：100米`.
+    ///
+    /// # Arguments
+    ///
+    /// * `card_html` - The HTML of one check-in card.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<SiteLocation>` - The required location, or `None` if the card has none.
+    pub fn site_location(&self, card_html: &str) -> Option<SiteLocation> {
+        let number = |re: &Regex| {
+            re.captures(card_html)
+                .and_then(|cap| cap.get(1)?.as_str().parse::<f64>().ok())
+        };
+        let lat = number(&self.site_lat)?;
+        let lng = number(&self.site_lng)?;
+        let radius_m = number(&self.site_radius);
+        validate_coordinates(&lat.to_string(), &lng.to_string()).ok()?;
+        Some(SiteLocation { lat, lng, radius_m })
+    }
+
+    /// Whether the text of a sign response reports success.
+    pub fn is_success(&self, text: &str) -> bool {
+        contains_any(text, &self.success_keywords)
+    }
+
+    /// Whether a sign result is an error reported by the site.
+    pub fn is_error(&self, text: &str) -> bool {
+        contains_any(text, &self.error_keywords)
+    }
+
+    /// Whether a failed sign result says the location was out of range.
+    pub fn is_out_of_range(&self, text: &str) -> bool {
+        contains_any(text, &self.out_of_range_keywords)
+    }
+
+    /// Whether a card is a password-protected session.
+    pub fn needs_password(&self, card_html: &str) -> bool {
+        contains_any(card_html, &self.password_markers)
+    }
+
+    /// Whether a failed sign result asks for a (correct) password.
+    pub fn is_password_error(&self, text: &str) -> bool {
+        contains_any(text, &self.password_keywords)
+    }
+
+    /// Records which parts of the profile a punch listing still matches.
+    ///
+    /// # Arguments
+    ///
+    /// * `document` - The parsed punch listing.
+    /// * `text` - The raw HTML of the listing.
+    ///
+    /// # Returns
+    ///
+    /// * `PageFingerprint` - The structure of the page.
+    pub fn fingerprint(&self, document: &Html, text: &str) -> PageFingerprint {
+        let mut fingerprint = PageFingerprint {
+            missing_selectors: self
+                .layout_selectors
+                .iter()
+                .filter(|(_, selector)| document.select(selector).next().is_none())
+                .map(|(source, _)| source.clone())
+                .collect(),
+            page_sign_ids: self.sign_ids(text).len(),
+            ..Default::default()
+        };
+        for card in document.select(&self.card_selector) {
+            fingerprint.cards += 1;
+            let card_html = card.html();
+            if !self.is_signed(&card_html) {
+                fingerprint.open_cards += 1;
+                if !self.sign_ids(&card_html).is_empty() {
+                    fingerprint.readable_cards += 1;
+                }
+            }
+            fingerprint.form_fields.extend(
+                card.select(&FIELD_SELECTOR)
+                    .filter_map(|field| field.value().attr("name"))
+                    .map(str::to_string),
+            );
+        }
+        fingerprint
+    }
+
+    /// Describes how a punch listing deviates from what the profile expects.
+    ///
+    /// # Arguments
+    ///
+    /// * `fingerprint` - The structure of the page.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<String>` - One line per deviation; empty if the page matches the profile.
+    pub fn layout_changes(&self, fingerprint: &PageFingerprint) -> Vec<String> {
+        let mut changes: Vec<String> = fingerprint
+            .missing_selectors
+            .iter()
+            .map(|selector| format!("no element matches {}", selector))
+            .collect();
+        if fingerprint.cards == 0 && fingerprint.page_sign_ids > 0 {
+            changes.push(format!(
+                "{} session IDs outside any check-in card",
+                fingerprint.page_sign_ids
+            ));
+        }
+        if fingerprint.open_cards > 0 && fingerprint.readable_cards == 0 {
+            changes.push(format!(
+                "no session ID in {} open cards",
+                fingerprint.open_cards
+            ));
+        }
+        let unknown: Vec<&str> = fingerprint
+            .form_fields
+            .iter()
+            .filter(|name| !self.form_fields.contains(*name))
+            .map(String::as_str)
+            .collect();
+        if !unknown.is_empty() {
+            changes.push(format!("unknown form fields: {}", unknown.join(", ")));
+        }
+        changes
+    }
+
+    /// Reads the date, status and signer of a card on the punch history.
+    ///
+    /// # Arguments
+    ///
+    /// * `card_html` - The HTML of one check-in card.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<(NaiveDate, AttendanceStatus, Option<String>)>` - The record, or `None` if
+    ///   the card has no date or is still open.
+    pub fn attendance(
+        &self,
+        card_html: &str,
+    ) -> Option<(NaiveDate, AttendanceStatus, Option<String>)> {
+        let date = self.record_date.captures(card_html)?.get(1)?.as_str();
+        let date = ["%Y-%m-%d", "%Y/%m/%d", "%Y年%m月%d日", "%Y年%m月%d"]
+            .iter()
+            .find_map(|format| NaiveDate::parse_from_str(date, format).ok())?;
+        let status = if contains_any(card_html, &self.late_markers) {
+            AttendanceStatus::Late
+        } else if self.is_signed(card_html) {
+            AttendanceStatus::Present
+        } else if contains_any(card_html, &self.absent_markers) {
+            AttendanceStatus::Absent
+        } else {
+            return None;
+        };
+        let signer = self
+            .signer
+            .captures(card_html)
+            .and_then(|cap| cap.get(1))
+            .map(|name| name.as_str().to_string());
+        Some((date, status, signer))
+    }
+}
+
+/// Whether `text` contains any of the non-empty `needles`.
+fn contains_any(text: &str, needles: &[String]) -> bool {
+    needles
+        .iter()
+        .any(|needle| !needle.is_empty() && text.contains(needle.as_str()))
+}
+
+/// Returns the rules currently used to parse site responses.
+///
+/// # Returns
+///
+/// * `Arc<Parser>` - The active parser.
+pub fn current() -> Arc<Parser> {
+    ACTIVE.read().unwrap().clone()
+}
+
+/// Path of the last downloaded profile in the app's data directory.
+fn cached_path(app_handle: &AppHandle) -> Option<PathBuf> {
+    app_handle
+        .path()
+        .app_data_dir()
+        .ok()
+        .map(|dir| dir.join("site_profile.json"))
+}
+
+/// Activates the last downloaded profile, if it is newer than the built-in one.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+pub fn load_cached(app_handle: &AppHandle) {
+    let Some(path) = cached_path(app_handle) else {
+        return;
+    };
+    let Ok(content) = fs::read_to_string(&path) else {
+        return;
+    };
+    let result = serde_json::from_str::<SiteProfile>(&content)
+        .map_err(|e| e.to_string())
+        .and_then(|profile| activate(app_handle, &profile));
+    match result {
+        Ok(true) => info!("Using cached site profile v{}", current().version),
+        Ok(false) => {}
+        Err(e) => warn!("Ignoring cached site profile {}: {}", path.display(), e),
+    }
+}
+
+/// Downloads the profile, verifies its signature and activates it if it is newer.
+///
+/// The signature is fetched from the profile URL with `.minisig` appended and must be
+/// made with the key matching `public_key`. An accepted profile is cached and used
+/// again after a restart.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `config` - The profile source settings.
+///
+/// # Returns
+///
+/// * `Result<u32, String>` - The version in use afterwards, or an error message if the
+///   source is not configured, the download fails or the profile is rejected.
+pub fn refresh(app_handle: &AppHandle, config: &SiteProfileConfig) -> Result<u32, String> {
+    if config.url.trim().is_empty() || config.public_key.trim().is_empty() {
+        return Err(t(Msg::ProfileSourceMissing).to_string());
+    }
+    let body = fetch_signed(app_handle, &config.url, &config.public_key)?;
+    let profile: SiteProfile = serde_json::from_slice(&body).map_err(|e| e.to_string())?;
+    if activate(app_handle, &profile)? {
+        if let Some(path) = cached_path(app_handle) {
+            if let Err(e) = fs::write(&path, &body) {
+                warn!("Failed to cache site profile: {}", e);
+            }
+        }
+        info!("Site profile updated to v{}", profile.version);
+    }
+    Ok(current().version)
+}
+
+/// Makes a profile the active one if it is newer and this app version supports it.
+///
+/// # Returns
+///
+/// * `Result<bool, String>` - Whether the profile was activated, or an error message if it
+///   is invalid or requires a newer app.
+fn activate(app_handle: &AppHandle, profile: &SiteProfile) -> Result<bool, String> {
+    if profile.version <= current().version {
+        return Ok(false);
+    }
+    if let Some(min) = &profile.min_app_version {
+        let min = Version::parse(min).map_err(|e| tf(Msg::ProfileInvalidMinVersion, &[&e]))?;
+        let running = &app_handle.package_info().version;
+        if *running < min {
+            return Err(tf(Msg::ProfileNeedsNewerApp, &[&min]));
+        }
+    }
+    let parser = Parser::compile(profile)?;
+    *ACTIVE.write().unwrap() = Arc::new(parser);
+    Ok(true)
+}
+
+/// Downloads a URL and returns its body.
+fn fetch(app_handle: &AppHandle, url: &str) -> Result<Vec<u8>, String> {
+    let client = tls::client_builder()
+        .timeout(FETCH_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let user_agent = format!("AutoCheckin-Next/{}", app_handle.package_info().version);
+    httplog::send(client.get(url).header(USER_AGENT, user_agent))
+        .and_then(|r| r.error_for_status())
+        .and_then(|r| r.bytes())
+        .map(|bytes| bytes.to_vec())
+        .map_err(|e| e.to_string())
+}
+
+/// Downloads a file and checks it against its minisign signature at `<url>.minisig`.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `url` - The file to download.
+/// * `public_key` - The minisign public key, with or without its comment line.
+///
+/// # Returns
+///
+/// * `Result<Vec<u8>, String>` - The verified body, or an error message if the key or
+///   signature is invalid, the download fails or the signature does not match.
+pub fn fetch_signed(
+    app_handle: &AppHandle,
+    url: &str,
+    public_key: &str,
+) -> Result<Vec<u8>, String> {
+    let public_key = PublicKey::from_base64(last_line(public_key))
+        .map_err(|e| tf(Msg::InvalidPublicKey, &[&e]))?;
+    let body = fetch(app_handle, url)?;
+    let signature = fetch(app_handle, &format!("{}{}", url, SIGNATURE_SUFFIX))?;
+    let signature = Signature::decode(&String::from_utf8_lossy(&signature))
+        .map_err(|e| tf(Msg::InvalidSignature, &[&e]))?;
+    public_key
+        .verify(&body, &signature, false)
+        .map_err(|e| tf(Msg::SignatureMismatch, &[&e]))?;
+    Ok(body)
+}
+
+/// The key line of a minisign public key, which may be pasted with its comment line.
+fn last_line(key: &str) -> &str {
+    key.lines()
+        .map(str::trim)
+        .rfind(|line| !line.is_empty())
+        .unwrap_or_default()
+}
+
+/// Refreshes the profile in the background, at startup and then at the configured
+/// interval. Does nothing while no URL is configured.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+pub fn start(app_handle: AppHandle) {
+    thread::spawn(move || loop {
+        let config = app_handle
+            .state::<ConfigState>()
+            .0
+            .lock()
+            .unwrap()
+            .global
+            .site_profile
+            .clone();
+        if !config.url.trim().is_empty() {
+            if let Err(e) = refresh(&app_handle, &config) {
+                warn!("Site profile refresh failed: {}", e);
+            }
+        }
+        thread::sleep(Duration::from_secs(config.refresh_hours.max(1) * 3600));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A punch listing with an open GPS card, a password card and a signed card.
+    const PUNCH_LISTING: &str = include_str!("profile/punchs.html");
+
+    /// The page shown instead of a listing when the session has expired.
+    const LOGIN_PAGE: &str = include_str!("profile/login.html");
+
+    /// The built-in parser and the cards of the punch listing fixture.
+    fn cards() -> (Parser, Vec<String>) {
+        let parser = Parser::compile(&SiteProfile::default()).unwrap();
+        let document = Html::parse_document(PUNCH_LISTING);
+        let cards = document
+            .select(parser.card_selector())
+            .map(|card| card.html())
+            .collect();
+        (parser, cards)
+    }
+
+    #[test]
+    fn listing_has_three_cards() {
+        assert_eq!(cards().1.len(), 3);
+    }
+
+    #[test]
+    fn sign_ids_are_read_by_every_pattern() {
+        let (parser, cards) = cards();
+        // `punchcard_` and `punch_gps(` both carry the ID of the GPS card.
+        assert_eq!(parser.sign_ids(&cards[0]), ["4012345", "4012345"]);
+        assert_eq!(parser.sign_ids(&cards[1]), ["4012346"]);
+        assert!(parser.sign_ids(&cards[2]).is_empty());
+    }
+
+    #[test]
+    fn signed_and_password_cards_are_recognised() {
+        let (parser, cards) = cards();
+        assert!(!parser.is_signed(&cards[0]));
+        assert!(!parser.is_signed(&cards[1]));
+        assert!(parser.is_signed(&cards[2]));
+        assert!(!parser.needs_password(&cards[0]));
+        assert!(parser.needs_password(&cards[1]));
+    }
+
+    #[test]
+    fn titles_collapse_whitespace() {
+        let (parser, cards) = cards();
+        let titles: Vec<_> = cards.iter().map(|card| parser.title(card)).collect();
+        assert_eq!(
+            titles,
+            [
+                Some("高等数学 第七周签到".to_string()),
+                Some("线性代数".to_string()),
+                Some("第一周签到".to_string()),
+            ]
+        );
+        assert_eq!(
+            parser.title("<div class=\"card-body\"><p>签到</p></div>"),
+            None
+        );
+    }
+
+    #[test]
+    fn site_location_from_attributes_and_script() {
+        let (parser, cards) = cards();
+
+        let required = parser.site_location(&cards[0]).unwrap();
+        assert_eq!((required.lat, required.lng), (39.908823, 116.397470));
+        assert_eq!(required.radius_m, Some(150.0));
+
+        let required = parser.site_location(&cards[1]).unwrap();
+        assert_eq!((required.lat, required.lng), (39.9, 116.4));
+        assert_eq!(required.radius_m, None);
+
+        assert!(parser.site_location(&cards[2]).is_none());
+    }
+
+    #[test]
+    fn login_page_is_recognised_by_body_or_path() {
+        let parser = Parser::compile(&SiteProfile::default()).unwrap();
+        assert!(parser.is_login_page("/student/course/123456/punchs", LOGIN_PAGE));
+        assert!(parser.is_login_page("/student/login", ""));
+        assert!(!parser.is_login_page("/student/course/123456/punchs", PUNCH_LISTING));
+    }
+
+    #[test]
+    fn listing_matches_the_built_in_layout() {
+        let parser = Parser::compile(&SiteProfile::default()).unwrap();
+        let document = Html::parse_document(PUNCH_LISTING);
+        let fingerprint = parser.fingerprint(&document, PUNCH_LISTING);
+        assert!(fingerprint.missing_selectors.is_empty());
+        assert_eq!(fingerprint.form_fields, BTreeSet::from(["pwd".to_string()]));
+        assert!(parser.layout_changes(&fingerprint).is_empty());
+    }
+
+    #[test]
+    fn redesigned_listing_reports_missing_layout() {
+        let parser = Parser::compile(&SiteProfile::default()).unwrap();
+        let page = "<html><body><main><section>签到</section></main></body></html>";
+        let fingerprint = parser.fingerprint(&Html::parse_document(page), page);
+        assert_eq!(
+            fingerprint.missing_selectors,
+            SiteProfile::default().layout_selectors
+        );
+        assert_eq!(parser.layout_changes(&fingerprint).len(), 3);
+    }
+}
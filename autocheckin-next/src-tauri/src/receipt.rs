@@ -0,0 +1,130 @@
+use crate::i18n::{tf, Msg};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+/// Characters of the server response kept in a receipt.
+const SNIPPET_CHARS: usize = 300;
+
+/// Evidence of one successful sign, kept in case the attendance record is disputed.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Receipt {
+    /// ID of the run the sign belongs to.
+    pub run_id: String,
+    /// ID of the task.
+    pub task_id: String,
+    /// Name of the task at the time of the sign.
+    pub task_name: String,
+    /// Class ID of the course.
+    pub class_id: String,
+    /// The check-in session ID.
+    pub sign_id: String,
+    /// Title of the session as shown on its card, if any.
+    pub session_title: Option<String>,
+    /// Start of the text the site returned for the sign.
+    pub response_snippet: String,
+    /// When the site accepted the sign.
+    pub signed_at: DateTime<Local>,
+    /// The submitted latitude.
+    pub lat: String,
+    /// The submitted longitude.
+    pub lng: String,
+    /// Path of the saved HTML response, if snapshots are enabled.
+    pub snapshot: Option<String>,
+}
+
+/// Shortens the text of a sign response to the part kept in a receipt.
+///
+/// # Arguments
+///
+/// * `text` - The visible text of the response page.
+///
+/// # Returns
+///
+/// * `String` - The text with whitespace collapsed, cut to a few hundred characters.
+pub fn snippet(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(SNIPPET_CHARS)
+        .collect()
+}
+
+/// Retrieves the directory receipts are stored in.
+fn receipts_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    app_handle
+        .path()
+        .app_data_dir()
+        .map(|dir| dir.join("receipts"))
+        .map_err(|e| e.to_string())
+}
+
+/// Saves the HTML page returned for a sign next to the receipts.
+///
+/// # Arguments
+///
+/// * `app_handle` - Handle to the Tauri application.
+/// * `run_id` - The run the sign belongs to.
+/// * `sign_id` - The check-in session ID.
+/// * `html` - The response body.
+///
+/// # Returns
+///
+/// * `Result<String, String>` - The path of the snapshot, or an error message.
+pub fn save_snapshot(
+    app_handle: &AppHandle,
+    run_id: &str,
+    sign_id: &str,
+    html: &str,
+) -> Result<String, String> {
+    let dir = receipts_dir(app_handle)?;
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let safe_id: String = sign_id
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect();
+    let path = dir.join(format!("{}_{}.html", run_id, safe_id));
+    fs::write(&path, html).map_err(|e| e.to_string())?;
+    Ok(path.display().to_string())
+}
+
+/// Stores the receipts of a run.
+///
+/// # Arguments
+///
+/// * `app_handle` - Handle to the Tauri application.
+/// * `run_id` - The run the receipts belong to.
+/// * `receipts` - The receipts of the run's successful signs.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Ok if successful, or an error message string on failure.
+pub fn save(app_handle: &AppHandle, run_id: &str, receipts: &[Receipt]) -> Result<(), String> {
+    let dir = receipts_dir(app_handle)?;
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let content = serde_json::to_string_pretty(receipts).map_err(|e| e.to_string())?;
+    fs::write(dir.join(format!("{}.json", run_id)), content).map_err(|e| e.to_string())
+}
+
+/// Loads the receipts of a run.
+///
+/// # Arguments
+///
+/// * `app_handle` - Handle to the Tauri application.
+/// * `run_id` - The ID of the run.
+///
+/// # Returns
+///
+/// * `Result<Vec<Receipt>, String>` - The receipts, or an error message if the run has none.
+pub fn load(app_handle: &AppHandle, run_id: &str) -> Result<Vec<Receipt>, String> {
+    if run_id.is_empty() || !run_id.chars().all(|c| c.is_ascii_hexdigit() || c == '-') {
+        return Err(tf(Msg::InvalidRunId, &[&run_id]));
+    }
+    let path = receipts_dir(app_handle)?.join(format!("{}.json", run_id));
+    let content =
+        fs::read_to_string(&path).map_err(|_| format!("No receipt for run {}", run_id))?;
+    serde_json::from_str(&content).map_err(|e| e.to_string())
+}
@@ -0,0 +1,81 @@
+use crate::httplog;
+use crate::i18n::{tf, Msg};
+use crate::tls;
+use reqwest::header::{ACCEPT, USER_AGENT};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::AppHandle;
+
+/// GitHub API endpoint for the latest published release.
+const LATEST_RELEASE_URL: &str =
+    "https://api.github.com/repos/K2LinTeams/AutoCheckin-Next/releases/latest";
+
+/// The subset of the GitHub release payload used by the version check.
+#[derive(Debug, Deserialize)]
+struct GitHubRelease {
+    tag_name: String,
+    body: Option<String>,
+    html_url: String,
+    published_at: Option<String>,
+}
+
+/// Result of comparing the running version with the latest GitHub release.
+#[derive(Debug, Serialize, Clone)]
+pub struct ReleaseInfo {
+    /// The version of the latest release.
+    pub latest_version: String,
+    /// The version currently running.
+    pub current_version: String,
+    /// Whether the latest release is newer than the running version.
+    pub update_available: bool,
+    /// Release notes in Markdown.
+    pub notes: Option<String>,
+    /// URL of the release page.
+    pub url: String,
+    /// Publication date in RFC 3339 format.
+    pub published_at: Option<String>,
+}
+
+/// Queries GitHub for the latest release and compares it with the running version.
+///
+/// The release tag may carry a leading `v` (e.g. `v1.2.0`).
+///
+/// # Arguments
+///
+/// * `app_handle` - Handle to the Tauri application.
+///
+/// # Returns
+///
+/// * `Result<ReleaseInfo, String>` - The latest release details, or an error message if the
+///   request fails or the tag is not a valid semantic version.
+pub fn check_latest_version(app_handle: &AppHandle) -> Result<ReleaseInfo, String> {
+    let current = app_handle.package_info().version.clone();
+    let client = tls::client_builder()
+        .timeout(Duration::from_secs(15))
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let release: GitHubRelease = httplog::json(
+        httplog::send(
+            client
+                .get(LATEST_RELEASE_URL)
+                .header(USER_AGENT, format!("AutoCheckin-Next/{}", current))
+                .header(ACCEPT, "application/vnd.github+json"),
+        )
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| e.to_string())?,
+    )?;
+
+    let latest = Version::parse(release.tag_name.trim_start_matches('v'))
+        .map_err(|e| tf(Msg::InvalidReleaseTag, &[&release.tag_name, &e]))?;
+
+    Ok(ReleaseInfo {
+        update_available: latest > current,
+        latest_version: latest.to_string(),
+        current_version: current.to_string(),
+        notes: release.body,
+        url: release.html_url,
+        published_at: release.published_at,
+    })
+}
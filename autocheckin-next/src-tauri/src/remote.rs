@@ -0,0 +1,275 @@
+use crate::config::{ConfigState, Task};
+use crate::history::load_history;
+use crate::i18n::{t, tf, Msg};
+use crate::logs::recent_logs;
+use crate::scheduler::{next_run, run_task_now};
+use crate::tray::set_task_enabled;
+use chrono::Local;
+use log::warn;
+use tauri::{AppHandle, Manager};
+
+/// Log lines returned by `lastlog` when no count is given.
+const LOG_LINES: usize = 15;
+
+/// Most log lines `lastlog` returns.
+const MAX_LOG_LINES: usize = 50;
+
+/// A text command received through a chat integration (Telegram, WeCom).
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command<'a> {
+    /// Show each task's state and last result.
+    Status,
+    /// Run the named task now.
+    Run(&'a str),
+    /// Disable the named task, or all tasks if empty.
+    Pause(&'a str),
+    /// Enable the named task, or all tasks if empty.
+    Resume(&'a str),
+    /// Show the recent log lines; the argument is an optional line count.
+    LastLog(&'a str),
+}
+
+/// Reads a command from its first word and the rest of the message.
+///
+/// Words are matched ignoring case, in English or Chinese (`状态`, `运行`/`签到`, `暂停`,
+/// `恢复`, `日志`).
+///
+/// # Arguments
+///
+/// * `word` - The command word, without any prefix such as `/`.
+/// * `argument` - The rest of the message.
+///
+/// # Returns
+///
+/// * `Option<Command>` - The command, or `None` for an unknown word.
+pub fn parse<'a>(word: &str, argument: &'a str) -> Option<Command<'a>> {
+    let argument = argument.trim();
+    match word.to_lowercase().as_str() {
+        "status" | "状态" => Some(Command::Status),
+        "run" | "运行" | "签到" => Some(Command::Run(argument)),
+        "pause" | "暂停" => Some(Command::Pause(argument)),
+        "resume" | "恢复" => Some(Command::Resume(argument)),
+        "lastlog" | "log" | "日志" => Some(Command::LastLog(argument)),
+        _ => None,
+    }
+}
+
+/// Lists the commands, each written with `prefix` (e.g. `/` for Telegram).
+///
+/// # Arguments
+///
+/// * `prefix` - Text written before each command word.
+///
+/// # Returns
+///
+/// * `String` - The command list.
+pub fn help(prefix: &str) -> String {
+    [
+        ("status", Msg::RemoteHelpStatus),
+        ("run <task>", Msg::RemoteHelpRun),
+        ("pause [task]", Msg::RemoteHelpPause),
+        ("resume [task]", Msg::RemoteHelpResume),
+        ("lastlog [lines]", Msg::RemoteHelpLastLog),
+    ]
+    .iter()
+    .map(|(usage, description)| format!("{}{} - {}", prefix, usage, t(*description)))
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+/// Runs a command and returns the reply text.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `command` - The command to run.
+///
+/// # Returns
+///
+/// * `String` - The reply, including error messages.
+pub fn execute(app_handle: &AppHandle, command: Command) -> String {
+    match command {
+        Command::Status => status(app_handle),
+        Command::Run("") => t(Msg::RemoteRunUsage).to_string(),
+        Command::Run(query) => match find_task(app_handle, query) {
+            Ok(task) => match run_task_now(app_handle, &task.id) {
+                Ok(()) => tf(Msg::RemoteStarted, &[&task.name]),
+                Err(e) => e,
+            },
+            Err(e) => e,
+        },
+        Command::Pause(query) => set_enabled(app_handle, query, false),
+        Command::Resume(query) => set_enabled(app_handle, query, true),
+        Command::LastLog(count) => last_log(count),
+    }
+}
+
+/// Keeps the last `max_chars` characters of a reply, so long logs keep their newest lines.
+///
+/// # Arguments
+///
+/// * `text` - The reply.
+/// * `max_chars` - The longest message the chat accepts.
+///
+/// # Returns
+///
+/// * `String` - The reply, shortened from the start if needed.
+pub fn tail(text: &str, max_chars: usize) -> String {
+    let count = text.chars().count();
+    text.chars().skip(count.saturating_sub(max_chars)).collect()
+}
+
+/// Lists each task with its state and last result, followed by the next run.
+fn status(app_handle: &AppHandle) -> String {
+    let tasks = configured_tasks(app_handle);
+    if tasks.is_empty() {
+        return t(Msg::RemoteNoTasks).to_string();
+    }
+    let history = load_history(app_handle);
+    let mut lines: Vec<String> = tasks
+        .iter()
+        .map(|task| {
+            let last = history
+                .iter()
+                .rev()
+                .find(|r| r.task_id == task.id)
+                .map(|r| {
+                    tf(
+                        Msg::RemoteLastRun,
+                        &[
+                            &format!("{:?}", r.outcome),
+                            &r.finished_at.format("%m-%d %H:%M"),
+                        ],
+                    )
+                })
+                .unwrap_or_else(|| t(Msg::RemoteNeverRun).to_string());
+            format!(
+                "{} {} ({}): {}",
+                t(if task.enable {
+                    Msg::RemoteTaskOn
+                } else {
+                    Msg::RemoteTaskPaused
+                }),
+                task.name,
+                task.schedule_label(),
+                last
+            )
+        })
+        .collect();
+    if let Some((task, at)) = next_run(&tasks, Local::now().naive_local()) {
+        lines.push(tf(
+            Msg::RemoteNextRun,
+            &[&task.name, &at.format("%m-%d %H:%M")],
+        ));
+    }
+    lines.join("\n")
+}
+
+/// Enables or disables the named task, or every task when no name is given.
+fn set_enabled(app_handle: &AppHandle, query: &str, enable: bool) -> String {
+    let done = if enable {
+        Msg::RemoteResumed
+    } else {
+        Msg::RemotePaused
+    };
+    if !query.is_empty() {
+        return match find_task(app_handle, query)
+            .and_then(|task| set_task_enabled(app_handle, &task.id, Some(enable)).map(|_| task))
+        {
+            Ok(task) => tf(done, &[&task.name]),
+            Err(e) => e,
+        };
+    }
+    let changed: Vec<String> = configured_tasks(app_handle)
+        .into_iter()
+        .filter(|task| task.enable != enable)
+        .filter_map(
+            |task| match set_task_enabled(app_handle, &task.id, Some(enable)) {
+                Ok(()) => Some(task.name),
+                Err(e) => {
+                    warn!("Failed to update {}: {}", task.name, e);
+                    None
+                }
+            },
+        )
+        .collect();
+    if changed.is_empty() {
+        t(if enable {
+            Msg::RemoteNothingToResume
+        } else {
+            Msg::RemoteNothingToPause
+        })
+        .to_string()
+    } else {
+        tf(done, &[&changed.join(", ")])
+    }
+}
+
+/// Returns the most recent log lines.
+fn last_log(count: &str) -> String {
+    let limit = count
+        .parse::<usize>()
+        .unwrap_or(LOG_LINES)
+        .clamp(1, MAX_LOG_LINES);
+    let lines = match recent_logs(None, limit) {
+        Ok(lines) => lines,
+        Err(e) => return e,
+    };
+    if lines.is_empty() {
+        return t(Msg::RemoteNoLogs).to_string();
+    }
+    lines
+        .iter()
+        .map(|line| {
+            format!(
+                "{} {} {}",
+                line.timestamp.get(11..19).unwrap_or(&line.timestamp),
+                line.level,
+                line.message
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Finds a task by ID, by name ignoring case, or by a part of its name matching one task.
+fn find_task(app_handle: &AppHandle, query: &str) -> Result<Task, String> {
+    let tasks = configured_tasks(app_handle);
+    let query_lower = query.to_lowercase();
+    if let Some(task) = tasks
+        .iter()
+        .find(|t| t.id == query || t.name.to_lowercase() == query_lower)
+    {
+        return Ok(task.clone());
+    }
+    let matches: Vec<&Task> = tasks
+        .iter()
+        .filter(|t| t.name.to_lowercase().contains(&query_lower))
+        .collect();
+    match matches.as_slice() {
+        [task] => Ok((*task).clone()),
+        [] => Err(tf(Msg::RemoteNoTaskMatch, &[&query])),
+        _ => Err(tf(
+            Msg::RemoteAmbiguousTask,
+            &[
+                &query,
+                &matches
+                    .iter()
+                    .map(|t| t.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ],
+        )),
+    }
+}
+
+/// A copy of the configured tasks.
+fn configured_tasks(app_handle: &AppHandle) -> Vec<Task> {
+    app_handle
+        .state::<ConfigState>()
+        .0
+        .lock()
+        .unwrap()
+        .tasks
+        .clone()
+}
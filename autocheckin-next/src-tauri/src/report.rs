@@ -0,0 +1,291 @@
+use crate::config::{AppConfig, ConfigState};
+use crate::history::{load_history, RunRecord};
+use crate::i18n::{t, Msg};
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Manager, Url};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_opener::OpenerExt;
+
+/// GitHub page for filing a new issue.
+const NEW_ISSUE_URL: &str = "https://github.com/K2LinTeams/AutoCheckin-Next/issues/new";
+
+/// Longest response snippet included in a report, in characters.
+const SNIPPET_LIMIT: usize = 1000;
+
+/// Longest report body passed in the new-issue URL; longer reports are only copied.
+const URL_BODY_LIMIT: usize = 6000;
+
+/// Global settings included in a report. Other sections (notifications, API, MQTT,
+/// Telegram, webhooks, heartbeats, geocoding...) hold credentials and are left out.
+const REPORTED_SETTINGS: &[&str] = &[
+    "debug",
+    "endpoints",
+    "logging",
+    "update_endpoint",
+    "site_coord_system",
+    "term_start",
+    "ip_location_fallback",
+    "device",
+    "drift",
+    "network_wait_minutes",
+    "timeouts",
+    "throttle",
+    "warm_up",
+    "tls",
+    "receipts",
+    "anomalies",
+    "success_rate_alert",
+    "site_profile",
+    "header_profile",
+    "locale",
+];
+
+/// Configuration fields replaced with a placeholder before a report is generated.
+const SECRET_FIELDS: &[&str] = &[
+    "cookie",
+    "secret",
+    "corpid",
+    "touser",
+    "headers",
+    "location",
+    "password",
+    "token",
+    "bot_token",
+    "allowed_chat_ids",
+    "encoding_aes_key",
+    "allowed_users",
+    "form_params",
+    "anchors",
+    "username",
+    "host",
+];
+
+/// Parts of field names that mark a credential, redacted wherever they appear.
+const SECRET_NAME_PARTS: &[&str] = &["token", "key", "secret", "password", "cookie", "chat_id"];
+
+/// A generated bug report.
+#[derive(Debug, Serialize, Clone)]
+pub struct IssueReport {
+    /// Suggested issue title.
+    pub title: String,
+    /// The report in Markdown.
+    pub body: String,
+    /// The GitHub new-issue URL, pre-filled with the report when it fits.
+    pub url: String,
+}
+
+/// Builds a bug report with the app version, OS, a redacted config excerpt and the
+/// response of the most recent failed run.
+///
+/// # Arguments
+///
+/// * `app_handle` - Handle to the Tauri application.
+/// * `task_id` - The task the report is about, or `None` for a general report.
+///
+/// # Returns
+///
+/// * `Result<IssueReport, String>` - The report, or an error message if the task is unknown.
+pub fn generate_issue_report(
+    app_handle: &AppHandle,
+    task_id: Option<&str>,
+) -> Result<IssueReport, String> {
+    let config = app_handle.state::<ConfigState>().0.lock().unwrap().clone();
+    let excerpt = redacted_excerpt(&config, task_id)?;
+    let failed_run = task_id.and_then(|id| {
+        load_history(app_handle)
+            .into_iter()
+            .rev()
+            .find(|r| r.task_id == id && r.outcome.is_failure())
+    });
+
+    let title = match &failed_run {
+        Some(run) => format!("Check-in failed: {}", truncate(&run.message, 60)),
+        None => "Bug report".to_string(),
+    };
+
+    let mut body = format!(
+        "## Description\n\n<!-- What happened and what did you expect? -->\n\n\
+         ## Environment\n\n\
+         - App version: {}\n\
+         - OS: {} ({})\n\n\
+         ## Configuration (redacted)\n\n```json\n{}\n```\n",
+        app_handle.package_info().version,
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        serde_json::to_string_pretty(&excerpt).map_err(|e| e.to_string())?
+    );
+    if let Some(run) = &failed_run {
+        body.push_str(&failure_section(run));
+    }
+
+    let body_param = if body.len() <= URL_BODY_LIMIT {
+        body.as_str()
+    } else {
+        "<!-- The report was copied to the clipboard. Please paste it here. -->"
+    };
+    let url = Url::parse_with_params(
+        NEW_ISSUE_URL,
+        &[("title", title.as_str()), ("body", body_param)],
+    )
+    .map_err(|e| e.to_string())?
+    .to_string();
+
+    Ok(IssueReport { title, body, url })
+}
+
+/// Copies a report to the clipboard and opens its new-issue URL in the browser.
+///
+/// # Arguments
+///
+/// * `app_handle` - Handle to the Tauri application.
+/// * `report` - The report to submit.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Ok on success, or an error message.
+pub fn submit_issue_report(app_handle: &AppHandle, report: &IssueReport) -> Result<(), String> {
+    app_handle
+        .clipboard()
+        .write_text(report.body.clone())
+        .map_err(|e| e.to_string())?;
+    app_handle
+        .opener()
+        .open_url(&report.url, None::<&str>)
+        .map_err(|e| e.to_string())
+}
+
+/// Serializes the global settings and the selected task with secrets replaced.
+fn redacted_excerpt(config: &AppConfig, task_id: Option<&str>) -> Result<Value, String> {
+    let global = serde_json::to_value(&config.global).map_err(|e| e.to_string())?;
+    let global: serde_json::Map<String, Value> = global
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(key, _)| REPORTED_SETTINGS.contains(&key.as_str()))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    let mut excerpt = serde_json::json!({ "global": global });
+    if let Some(id) = task_id {
+        let task = config
+            .tasks
+            .iter()
+            .find(|t| t.id == id)
+            .ok_or(t(Msg::TaskNotFound))?;
+        excerpt["task"] = serde_json::to_value(task).map_err(|e| e.to_string())?;
+    }
+    redact(&mut excerpt);
+    Ok(excerpt)
+}
+
+/// Recursively replaces the values of secret fields with `<redacted>`.
+fn redact(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, v) in map.iter_mut() {
+                if is_secret(key) {
+                    *v = Value::String("<redacted>".to_string());
+                } else {
+                    redact(v);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+/// Whether a field holds a credential or personal data, by its name.
+///
+/// Any URL counts, since ping, webhook and task list URLs embed their access keys.
+fn is_secret(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    SECRET_FIELDS.contains(&key.as_str())
+        || SECRET_NAME_PARTS.iter().any(|part| key.contains(part))
+        || key == "url"
+        || key.ends_with("_url")
+}
+
+/// Formats the failing run's message and per-sign responses.
+fn failure_section(run: &RunRecord) -> String {
+    let mut section = format!(
+        "\n## Failing run\n\n- Started: {}\n- Outcome: {:?}\n\n```\n{}\n",
+        run.started_at,
+        run.outcome,
+        truncate(&run.message, SNIPPET_LIMIT)
+    );
+    for sign in run.signs.iter().filter(|s| !s.success) {
+        section.push_str(&format!(
+            "[{}] {}\n",
+            sign.sign_id,
+            truncate(&sign.message, SNIPPET_LIMIT)
+        ));
+    }
+    section.push_str("```\n");
+    section
+}
+
+/// Shortens text to at most `limit` characters, marking the cut with an ellipsis.
+fn truncate(text: &str, limit: usize) -> String {
+    match text.char_indices().nth(limit) {
+        Some((idx, _)) => format!("{}…", &text[..idx]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Task, WebhookConfig};
+
+    #[test]
+    fn excerpt_leaves_out_every_secret() {
+        let mut config = AppConfig::default();
+        let global = &mut config.global;
+        global.wecom.corpid = "leak-corpid".to_string();
+        global.wecom.secret = "leak-wecom-secret".to_string();
+        global.wecom.touser = "leak-touser".to_string();
+        global.wecom.callback.token = "leak-callback-token".to_string();
+        global.wecom.callback.encoding_aes_key = "leak-aes-key".to_string();
+        global.wecom.callback.allowed_users = vec!["leak-wecom-user".to_string()];
+        global.api.token = "leak-api-token".to_string();
+        global.telegram.bot_token = "leak-bot-token".to_string();
+        global.telegram.allowed_chat_ids = vec![424242424242];
+        global.geocoding.api_key = "leak-geocoding-key".to_string();
+        global.heartbeat.cycle_url = "https://hc.example/leak-cycle".to_string();
+        global.heartbeat.run_success_url = "https://hc.example/leak-success".to_string();
+        global.heartbeat.run_failure_url = "https://hc.example/leak-failure".to_string();
+        global.webhooks.push(WebhookConfig {
+            url: "https://hooks.example/leak-webhook".to_string(),
+            secret: "leak-webhook-secret".to_string(),
+            events: vec![],
+        });
+        global.mqtt.host = "leak-mqtt-host".to_string();
+        global.mqtt.username = "leak-mqtt-user".to_string();
+        global.mqtt.password = "leak-mqtt-password".to_string();
+        global.managed_tasks.url = "https://tasks.example/leak-managed".to_string();
+        global.site_profile.url = "https://profiles.example/leak-profile".to_string();
+        global.site_profile.public_key = "leak-public-key".to_string();
+
+        let mut task = Task {
+            id: "task".to_string(),
+            cookie: "leak-cookie".to_string(),
+            password: Some("leak-password".to_string()),
+            ..Default::default()
+        };
+        task.location.lat = "39.424242".to_string();
+        task.location.lng = "116.424242".to_string();
+        task.headers
+            .insert("X-Auth".to_string(), "leak-header".to_string());
+        task.form_params
+            .insert("pwd".to_string(), "leak-form-param".to_string());
+        config.tasks.push(task);
+
+        let excerpt = redacted_excerpt(&config, Some("task")).unwrap().to_string();
+
+        assert!(!excerpt.contains("leak-"), "{}", excerpt);
+        assert!(!excerpt.contains("424242"), "{}", excerpt);
+        assert!(excerpt.contains("\"timeouts\""), "{}", excerpt);
+        assert!(excerpt.contains("\"task\""), "{}", excerpt);
+    }
+}
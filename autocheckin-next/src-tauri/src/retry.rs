@@ -0,0 +1,106 @@
+use crate::devtime;
+use crate::httplog;
+use log::warn;
+use reqwest::blocking::{RequestBuilder, Response};
+use reqwest::StatusCode;
+use std::thread;
+use std::time::Duration;
+
+/// When and how often a failed request is sent again.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one.
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled for each further retry.
+    pub base_delay: Duration,
+    /// Upper bound for the delay between attempts.
+    pub max_delay: Duration,
+    /// Whether to retry after the request may have reached the server (timeouts,
+    /// 5xx and 429 responses). Only safe for requests without side effects.
+    pub retry_after_send: bool,
+}
+
+impl RetryPolicy {
+    /// Policy for idempotent requests such as the punch listing and the WeCom token.
+    pub const IDEMPOTENT: RetryPolicy = RetryPolicy {
+        max_attempts: 3,
+        base_delay: Duration::from_millis(500),
+        max_delay: Duration::from_secs(5),
+        retry_after_send: true,
+    };
+
+    /// Policy for requests with side effects such as the sign POST.
+    ///
+    /// Only retries when the connection could not be established, so a sign that may
+    /// have been recorded is never submitted twice.
+    pub const CONSERVATIVE: RetryPolicy = RetryPolicy {
+        max_attempts: 2,
+        base_delay: Duration::from_secs(1),
+        max_delay: Duration::from_secs(1),
+        retry_after_send: false,
+    };
+
+    /// Returns the delay before the given retry, with jitter to spread out retries.
+    fn delay(&self, retry: u32) -> Duration {
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_delay);
+        backoff.mul_f64(0.5 + rand::random::<f64>() * 0.5)
+    }
+}
+
+/// Sends a request, retrying transient failures according to a policy.
+///
+/// Requests whose body cannot be cloned are sent once.
+///
+/// # Arguments
+///
+/// * `policy` - The retry policy.
+/// * `request` - The request to send.
+/// * `before_retry` - Called after the backoff and before each retry, such as to take
+///   the retry from the account's request budget.
+///
+/// # Returns
+///
+/// * `reqwest::Result<Response>` - The first response that is not retried, or the last
+///   error.
+pub fn send(
+    policy: &RetryPolicy,
+    request: RequestBuilder,
+    mut before_retry: impl FnMut(),
+) -> reqwest::Result<Response> {
+    let mut retry = 0;
+    loop {
+        let attempt = match request.try_clone() {
+            Some(attempt) if retry + 1 < policy.max_attempts => attempt,
+            _ => return httplog::send(request),
+        };
+        let reason = match httplog::send(attempt) {
+            Ok(resp) if policy.retry_after_send && is_transient_status(resp.status()) => {
+                format!("status {}", resp.status())
+            }
+            Ok(resp) => return Ok(resp),
+            Err(e) if e.is_connect() || (policy.retry_after_send && e.is_timeout()) => {
+                e.to_string()
+            }
+            Err(e) => return Err(e),
+        };
+        let delay = policy.delay(retry);
+        retry += 1;
+        warn!(
+            "Request failed ({}), retrying in {} ms (attempt {}/{})",
+            reason,
+            delay.as_millis(),
+            retry + 1,
+            policy.max_attempts
+        );
+        thread::sleep(devtime::scaled(delay));
+        before_retry();
+    }
+}
+
+/// Returns whether a response status indicates a temporary server-side problem.
+fn is_transient_status(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
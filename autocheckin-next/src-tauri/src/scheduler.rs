@@ -1,57 +1,1189 @@
-use crate::config::{load_config, AppConfig, Task};
-use crate::task::TaskExecutor;
-use chrono::Local;
-use log::info;
-use std::sync::Arc;
+use crate::anomaly::{self, AnomalyKind};
+use crate::config::{
+    load_config, save_config, AppConfig, ConfigState, GlobalConfig, SuccessRateAlertConfig, Task,
+    WebhookConfig,
+};
+use crate::events;
+use crate::heartbeat;
+use crate::history::{
+    append_history, compute_stats, compute_streak, consecutive_failures, get_artifacts_dir,
+    load_history, RunOutcome, RunRecord, TaskStats,
+};
+use crate::hooks::{self, PreRun};
+use crate::i18n::{t, tf, Msg};
+use crate::metrics;
+use crate::mobile;
+use crate::spans::{self, SpanContext, SpanKind};
+use crate::task::{TaskExecutor, COOKIE_EXPIRED};
+use crate::tray::{refresh_tray_menu, run_finished, run_started};
+use crate::webhooks;
+use chrono::{Days, Local, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use log::{debug, error, info, warn};
+use serde_json::{json, Value};
+use std::any::Any;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
 use std::time::Duration;
-use tauri::AppHandle;
+use tauri::{AppHandle, Manager};
 use tokio::time::sleep;
 
+/// How often the scheduler wakes up to check the clock.
+const TICK: Duration = Duration::from_secs(5);
+
+/// A wall-clock gap between ticks larger than this is treated as a system sleep.
+const RESUME_GAP_SECS: i64 = 30;
+
+/// Longest period of missed runs caught up after a sleep.
+const MAX_CATCH_UP_HOURS: i64 = 24;
+
+/// Longest time quitting waits for in-flight runs to finish.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Set once the app started quitting; no new runs are started afterwards.
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+
+/// Number of runs currently executing, past the network wait.
+static IN_FLIGHT: Mutex<usize> = Mutex::new(0);
+
+/// Signalled whenever a run finishes executing.
+static RUN_DONE: Condvar = Condvar::new();
+
+/// Marks a run as executing until dropped, so quitting can wait for it.
+struct InFlight;
+
+impl InFlight {
+    /// Registers an executing run, unless the app is quitting.
+    fn enter() -> Option<InFlight> {
+        let mut count = IN_FLIGHT.lock().unwrap();
+        if SHUTTING_DOWN.load(Ordering::SeqCst) {
+            return None;
+        }
+        *count += 1;
+        Some(InFlight)
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        *IN_FLIGHT.lock().unwrap() -= 1;
+        RUN_DONE.notify_all();
+    }
+}
+
+/// Stops the scheduler and refuses new runs.
+///
+/// # Returns
+///
+/// * `bool` - `true` if this call started the shutdown, `false` if it had already begun.
+pub fn begin_shutdown() -> bool {
+    let _count = IN_FLIGHT.lock().unwrap();
+    !SHUTTING_DOWN.swap(true, Ordering::SeqCst)
+}
+
+/// Blocks until every executing run has recorded its history and sent its notifications,
+/// or the shutdown timeout passes.
+///
+/// Runs still waiting for the network are abandoned, since they have not contacted the
+/// site yet.
+///
+/// # Returns
+///
+/// * `bool` - `true` if all runs finished, `false` if the timeout passed.
+pub fn wait_for_runs() -> bool {
+    let count = IN_FLIGHT.lock().unwrap();
+    if *count > 0 {
+        info!("Waiting for {} run(s) to finish before quitting", *count);
+    }
+    let (count, result) = RUN_DONE
+        .wait_timeout_while(count, SHUTDOWN_TIMEOUT, |count| *count > 0)
+        .unwrap();
+    if result.timed_out() {
+        warn!("Quitting with {} run(s) still in flight", *count);
+        return false;
+    }
+    true
+}
+
+/// The executor shared by all runs, together with the settings it was built from.
+///
+/// Reusing one executor keeps its HTTP connection pool and cached WeCom token alive
+/// between runs. It is rebuilt when the global settings change.
+#[derive(Default)]
+pub struct ExecutorState(Mutex<Option<(Value, Arc<TaskExecutor>)>>);
+
+/// Returns the shared executor, building a new one if the global settings changed.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `global` - The current global settings.
+///
+/// # Returns
+///
+/// * `Arc<TaskExecutor>` - The executor to run tasks with.
+pub fn shared_executor(app_handle: &AppHandle, global: &GlobalConfig) -> Arc<TaskExecutor> {
+    let settings = serde_json::to_value(global).unwrap_or_default();
+    let state = app_handle.state::<ExecutorState>();
+    let mut slot = state.0.lock().unwrap();
+    match slot.as_ref() {
+        Some((built_from, executor)) if *built_from == settings => executor.clone(),
+        _ => {
+            debug!("Building task executor");
+            let executor = Arc::new(
+                TaskExecutor::new(global, get_artifacts_dir(app_handle))
+                    .with_app_handle(app_handle.clone()),
+            );
+            *slot = Some((settings, executor.clone()));
+            executor
+        }
+    }
+}
+
+/// Source of the current time and of waiting, between ticks and within runs.
+///
+/// The scheduler and the executor only read time and wait through this trait, so the
+/// catch-up, resume and deduplication logic and the pauses of a run can be driven by a
+/// simulated clock.
+pub trait Clock: Send + Sync + 'static {
+    /// The current local time.
+    fn now(&self) -> NaiveDateTime;
+
+    /// Waits for the given duration.
+    fn sleep(&self, duration: Duration) -> impl Future<Output = ()> + Send
+    where
+        Self: Sized;
+
+    /// Blocks the calling thread for the given duration, as between the signs of a run.
+    fn pause(&self, duration: Duration);
+}
+
+/// The system clock, waiting with tokio timers.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> NaiveDateTime {
+        Local::now().naive_local()
+    }
+
+    fn sleep(&self, duration: Duration) -> impl Future<Output = ()> + Send {
+        sleep(duration)
+    }
+
+    fn pause(&self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+/// A minute the scheduler has to handle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tick {
+    /// The last minute already handled; tasks after it are due.
+    pub since: NaiveDateTime,
+    /// The current minute, the last one the tick covers.
+    pub minute: NaiveDateTime,
+    /// Whether the clock jumped forward since the previous check, as after a system sleep.
+    pub resumed: bool,
+}
+
+impl Tick {
+    /// Selects the enabled tasks scheduled in the minutes the tick covers.
+    ///
+    /// # Arguments
+    ///
+    /// * `tasks` - The configured tasks.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Task>` - The tasks that are due, each at most once.
+    pub fn due_tasks(&self, tasks: &[Task]) -> Vec<Task> {
+        due_tasks(tasks, self.since, self.minute)
+    }
+}
+
+/// The minutes the scheduler has handled and when it last checked the clock.
+///
+/// Holds no reference to the real time: each check passes the current time in, so the
+/// same sequence of times always produces the same ticks.
+#[derive(Debug, Clone, Copy)]
+pub struct ScheduleState {
+    /// When the clock was last checked.
+    last_check: NaiveDateTime,
+    /// The last minute whose tasks were started.
+    last_minute: NaiveDateTime,
+}
+
+impl ScheduleState {
+    /// Creates the state of a scheduler starting at `now`.
+    ///
+    /// Starts one minute back so tasks scheduled for the current minute still run.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The current local time.
+    pub fn new(now: NaiveDateTime) -> Self {
+        Self {
+            last_check: now,
+            last_minute: truncate_to_minute(now) - chrono::Duration::minutes(1),
+        }
+    }
+
+    /// Checks the clock and returns the minutes that became due since the last check.
+    ///
+    /// A gap of more than 30 seconds between checks is reported as a resume. Minutes
+    /// missed during a gap are caught up, at most 24 hours of them. When the clock is set
+    /// back, the scheduler starts over from the current minute without repeating runs.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The current local time.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Tick>` - The minutes to handle, or `None` if still in the handled minute.
+    pub fn advance(&mut self, now: NaiveDateTime) -> Option<Tick> {
+        let minute = truncate_to_minute(now);
+        let gap = (now - self.last_check).num_seconds();
+        let resumed = gap > RESUME_GAP_SECS;
+        if resumed {
+            info!("System resumed after {}s, re-evaluating schedule", gap);
+        }
+        self.last_check = now;
+
+        if minute < self.last_minute {
+            // The clock was set back; start over from the current minute.
+            self.last_minute = minute;
+        }
+        if minute <= self.last_minute {
+            return None;
+        }
+        let since = self
+            .last_minute
+            .max(minute - chrono::Duration::hours(MAX_CATCH_UP_HOURS));
+        self.last_minute = minute;
+        Some(Tick {
+            since,
+            minute,
+            resumed,
+        })
+    }
+}
+
 /// Starts the task scheduler loop.
 ///
-/// This function runs indefinitely, checking every minute if there are any enabled tasks
-/// scheduled for the current time. If matching tasks are found, they are executed in
+/// This function runs indefinitely, waking every few seconds and running enabled tasks
+/// whose scheduled minute has passed since the previous check. Runs are executed in
 /// separate threads using `tokio::task::spawn_blocking`.
 ///
+/// Tauri does not expose OS power events, so a system sleep is detected as a jump in
+/// wall-clock time between ticks. On resume, tasks missed during the sleep are run
+/// immediately and the cookies of the remaining enabled tasks are re-validated.
+///
 /// # Arguments
 ///
 /// * `app_handle` - The Tauri application handle, used to load the configuration.
-pub async fn start_scheduler(app_handle: AppHandle) {
+/// * `clock` - The time source; `SystemClock` outside of simulations.
+pub async fn start_scheduler<C: Clock>(app_handle: AppHandle, clock: C) {
     info!("Scheduler started");
-    loop {
-        // Run check every minute
-        let now = Local::now();
-        let current_time = now.format("%H:%M").to_string();
+    run_ticks(
+        &clock,
+        || SHUTTING_DOWN.load(Ordering::SeqCst),
+        |tick| handle_tick(&app_handle, tick),
+    )
+    .await;
+    info!("Scheduler stopped");
+}
 
-        info!("Scheduler tick: {}", current_time);
+/// Checks the clock every `TICK` and hands each new tick to `on_tick`, until `stopped`
+/// returns `true`.
+///
+/// # Arguments
+///
+/// * `clock` - The time source.
+/// * `stopped` - Checked before every tick; ends the loop once it returns `true`.
+/// * `on_tick` - Handles the minutes that became due.
+async fn run_ticks<C: Clock>(clock: &C, stopped: impl Fn() -> bool, mut on_tick: impl FnMut(Tick)) {
+    let mut state = ScheduleState::new(clock.now());
+    while !stopped() {
+        if let Some(tick) = state.advance(clock.now()) {
+            on_tick(tick);
+        }
+        clock.sleep(TICK).await;
+    }
+}
 
-        let config: AppConfig = load_config(&app_handle);
+/// Starts the runs of a tick, and re-validates the other sessions after a resume.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle, used to load the configuration.
+/// * `tick` - The minutes to handle.
+fn handle_tick(app_handle: &AppHandle, tick: Tick) {
+    let minute = tick.minute;
+    let resumed = tick.resumed;
+    debug!("Scheduler tick: {}", minute.format("%H:%M"));
+    let mut tick_span = spans::start("scheduler.tick", SpanKind::Internal);
+    let config: AppConfig = load_config(app_handle);
+    let tasks_to_run = tick.due_tasks(&config.tasks);
+    tick_span.set("scheduler.due_tasks", tasks_to_run.len());
+    tick_span.set("scheduler.resumed", resumed);
+
+    let executor = shared_executor(app_handle, &config.global);
 
-        // Find tasks scheduled for now
-        let tasks_to_run: Vec<Task> = config
+    if !tasks_to_run.is_empty() {
+        info!("Found {} tasks to run.", tasks_to_run.len());
+    }
+    if resumed {
+        let to_check: Vec<Task> = config
             .tasks
-            .into_iter()
-            .filter(|t| t.enable && t.time == current_time)
+            .iter()
+            .filter(|t| t.enable && !tasks_to_run.iter().any(|r| r.id == t.id))
+            .cloned()
             .collect();
+        revalidate_sessions(
+            app_handle,
+            executor.clone(),
+            to_check,
+            config.global.webhooks.clone(),
+        );
+    }
+    events::publish(
+        "scheduler_tick",
+        json!({
+            "minute": minute.format("%H:%M").to_string(),
+            "due_tasks": tasks_to_run.iter().map(|t| &t.id).collect::<Vec<_>>(),
+            "resumed": resumed,
+        }),
+    );
+    for task in tasks_to_run {
+        spawn_run(app_handle, executor.clone(), task);
+    }
+    heartbeat::cycle(&config.global.heartbeat);
+    mobile::schedule_next_wake(app_handle, &config.tasks);
+}
+
+/// Drops the seconds and sub-second part of a time.
+fn truncate_to_minute(time: NaiveDateTime) -> NaiveDateTime {
+    time.date()
+        .and_hms_opt(time.hour(), time.minute(), 0)
+        .unwrap_or(time)
+}
+
+/// Selects the enabled tasks scheduled in the interval `(after, until]`.
+///
+/// Each task is returned at most once, even if the interval spans several days.
+///
+/// # Arguments
+///
+/// * `tasks` - The configured tasks.
+/// * `after` - The last minute already handled.
+/// * `until` - The current minute.
+///
+/// # Returns
+///
+/// * `Vec<Task>` - The tasks that are due.
+fn due_tasks(tasks: &[Task], after: NaiveDateTime, until: NaiveDateTime) -> Vec<Task> {
+    tasks
+        .iter()
+        .filter(|t| t.enable)
+        .filter(|t| {
+            occurrences(t, after.date(), until.date())
+                .into_iter()
+                .any(|at| at > after && at <= until)
+        })
+        .cloned()
+        .collect()
+}
+
+/// Lists when a task is scheduled to run on the days from `from` to `to`, inclusive.
+///
+/// Follows the task's cron schedule if it has one, else runs at its time on each of its
+/// days. The task's enable flag is not checked.
+///
+/// # Arguments
+///
+/// * `task` - The task.
+/// * `from` - The first day.
+/// * `to` - The last day.
+///
+/// # Returns
+///
+/// * `Vec<NaiveDateTime>` - The scheduled times in order; empty if the time or schedule
+///   is invalid.
+pub fn occurrences(task: &Task, from: NaiveDate, to: NaiveDate) -> Vec<NaiveDateTime> {
+    let days = from.iter_days().take_while(|d| *d <= to);
+    match task.cron() {
+        Some(Ok(schedule)) => days.flat_map(|d| schedule.times_on(d)).collect(),
+        Some(Err(_)) => vec![],
+        None => match NaiveTime::parse_from_str(&task.time, "%H:%M") {
+            Ok(time) => days
+                .filter(|d| task.runs_on(*d))
+                .map(|d| d.and_time(time))
+                .collect(),
+            Err(_) => vec![],
+        },
+    }
+}
+
+/// Finds when a task runs next.
+///
+/// A run in the current minute counts as already done, matching the scheduler's
+/// once-per-minute check.
+///
+/// # Arguments
+///
+/// * `task` - The task.
+/// * `now` - The current local time.
+///
+/// # Returns
+///
+/// * `Option<NaiveDateTime>` - The next run, or `None` if the time or schedule is invalid.
+fn next_occurrence(task: &Task, now: NaiveDateTime) -> Option<NaiveDateTime> {
+    let minute = truncate_to_minute(now);
+    match task.cron() {
+        Some(schedule) => schedule.ok()?.next_after(minute),
+        None => occurrences(task, minute.date(), minute.date() + Days::new(7))
+            .into_iter()
+            .find(|at| *at > minute),
+    }
+}
+
+/// Marks the account of a task whose cookie the site rejected as expired, and alerts once.
+///
+/// Logging in again needs a QR code scan, so the session cannot be renewed unattended.
+/// Instead the account keeps its expiry until it gets a new cookie or a run gets through,
+/// the user is notified once per account, and `session_expired` is published so event
+/// stream clients can prompt for the QR login.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `executor` - The executor used for the notification.
+/// * `task` - The task whose cookie expired.
+fn session_expired(app_handle: &AppHandle, executor: &TaskExecutor, task: &Task) {
+    let state = app_handle.state::<ConfigState>();
+    let mut config = state.0.lock().unwrap();
+    let account = task
+        .account_id
+        .as_ref()
+        .and_then(|id| config.accounts.iter_mut().find(|a| &a.id == id));
+    let name = match account {
+        Some(account) if account.is_expired() => return,
+        Some(account) => {
+            account.expires_at = Some(Local::now());
+            account.name.clone()
+        }
+        None => task.name.clone(),
+    };
+    let task_ids: Vec<String> = config
+        .tasks
+        .iter()
+        .filter(|t| t.id == task.id || (t.account_id.is_some() && t.account_id == task.account_id))
+        .map(|t| t.id.clone())
+        .collect();
+    if task.account_id.is_some() {
+        if let Err(e) = save_config(app_handle, &config) {
+            warn!("Failed to mark the session of {} as expired: {}", name, e);
+        }
+    }
+    drop(config);
+
+    warn!("Session of {} expired; a new QR login is needed", name);
+    let _ = executor.send_wecom_notification(
+        &tf(Msg::CookieExpiredTitle, &[&name]),
+        &tf(Msg::CookieExpiredBody, &[&name]),
+    );
+    events::publish(
+        "session_expired",
+        json!({
+            "account_id": task.account_id,
+            "account_name": name,
+            "task_ids": task_ids,
+        }),
+    );
+}
+
+/// Clears the expiry of a task's account once a run got through with its cookie.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `task` - The task whose run reached the session list.
+fn session_valid(app_handle: &AppHandle, task: &Task) {
+    let state = app_handle.state::<ConfigState>();
+    let mut config = state.0.lock().unwrap();
+    let Some(account) = task
+        .account_id
+        .as_ref()
+        .and_then(|id| config.accounts.iter_mut().find(|a| &a.id == id))
+        .filter(|a| a.is_expired())
+    else {
+        return;
+    };
+    account.expires_at = None;
+    info!("Session of {} is valid again", account.name);
+    if let Err(e) = save_config(app_handle, &config) {
+        warn!("Failed to clear the expiry of {}: {}", task.name, e);
+    }
+}
+
+/// Checks the sessions of the given tasks in the background and reports expired cookies.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle, for marking expired accounts.
+/// * `executor` - The executor used for the checks and notifications.
+/// * `tasks` - The tasks to check.
+/// * `hooks` - The webhooks notified of expired cookies.
+fn revalidate_sessions(
+    app_handle: &AppHandle,
+    executor: Arc<TaskExecutor>,
+    tasks: Vec<Task>,
+    hooks: Vec<WebhookConfig>,
+) {
+    if tasks.is_empty() {
+        return;
+    }
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        for task in tasks {
+            match executor.check_session(&task) {
+                Ok(()) => debug!("Session for {} is still valid", task.name),
+                Err(e) if e == COOKIE_EXPIRED => {
+                    warn!("Cookie for {} expired during sleep", task.name);
+                    session_expired(&app_handle, &executor, &task);
+                    webhooks::cookie_expired(&hooks, &task);
+                }
+                Err(e) => warn!("Could not validate session for {}: {}", task.name, e),
+            }
+        }
+    });
+}
+
+/// Finds the enabled task that the scheduler will run next.
+///
+/// A task due in the current minute counts as already run, matching the scheduler's
+/// once-per-minute check.
+///
+/// # Arguments
+///
+/// * `tasks` - The configured tasks.
+/// * `now` - The current local time.
+///
+/// # Returns
+///
+/// * `Option<(&Task, NaiveDateTime)>` - The next task and when it runs, or `None` if no
+///   enabled task has a valid time or schedule.
+pub fn next_run(tasks: &[Task], now: NaiveDateTime) -> Option<(&Task, NaiveDateTime)> {
+    tasks
+        .iter()
+        .filter(|t| t.enable)
+        .filter_map(|t| Some((t, next_occurrence(t, now)?)))
+        .min_by_key(|(_, at)| *at)
+}
+
+/// Runs a task on a blocking worker thread and records the result.
+///
+/// Since `TaskExecutor` uses blocking reqwest, the run is moved off the async runtime.
+/// If the site is unreachable, the run is deferred until the network comes back or the
+/// configured wait window expires. A panic during the run is recorded as an errored run
+/// and the shared executor is rebuilt for later runs, in case it left a lock poisoned.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `executor` - The shared executor.
+/// * `task` - The task to execute.
+fn spawn_run(app_handle: &AppHandle, executor: Arc<TaskExecutor>, task: Task) {
+    let app_handle = app_handle.clone();
+    let parent = spans::current();
+    run_started(&app_handle);
+    tauri::async_runtime::spawn_blocking(move || {
+        let outcome = execute_run(&app_handle, &executor, &task, parent);
+        run_finished(&app_handle, outcome);
+    });
+}
+
+/// Executes a task on the current thread and records the result.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `executor` - The shared executor.
+/// * `task` - The task to execute.
+/// * `parent` - The span to record the run under, if any.
+///
+/// # Returns
+///
+/// * `Option<RunOutcome>` - The outcome, or `None` if the run was skipped because the app
+///   is quitting, the task is disabled or the pre-run hook vetoed it.
+fn execute_run(
+    app_handle: &AppHandle,
+    executor: &TaskExecutor,
+    task: &Task,
+    parent: Option<SpanContext>,
+) -> Option<RunOutcome> {
+    let mut span = spans::start_with_parent("task.run", SpanKind::Internal, parent);
+    span.set("task.id", task.id.as_str());
+    span.set("task.name", task.name.as_str());
+    let waited = executor.wait_for_network();
+    let Some(_in_flight) = InFlight::enter() else {
+        info!("Skipping run of {}: the app is quitting", task.name);
+        return None;
+    };
+    let waited = match waited {
+        Ok(waited) => waited,
+        Err(waited) => {
+            record_run(
+                app_handle,
+                executor,
+                task,
+                executor.offline_record(task, waited),
+            );
+            return Some(RunOutcome::Offline);
+        }
+    };
+    let global = current_global(app_handle);
+    let task = match hooks::pre_run(&global.scripting, task) {
+        PreRun::Run(task) => task,
+        PreRun::Veto => return None,
+    };
+    let task = &*task;
+    webhooks::task_started(&global.webhooks, task);
+    events::publish(
+        "run_started",
+        json!({ "task_id": task.id, "task_name": task.name }),
+    );
+    let started_at = Local::now();
+    let result = panic::catch_unwind(AssertUnwindSafe(|| executor.execute(task)));
+    let result = result.unwrap_or_else(|payload| {
+        app_handle.state::<ExecutorState>().0.lock().unwrap().take();
+        Some(executor.panicked_record(task, started_at, &panic_message(&*payload)))
+    });
+    result.map(|mut record| {
+        if !waited.is_zero() {
+            record.deferred_secs = Some(waited.as_secs());
+        }
+        let outcome = record.outcome;
+        span.set("task.outcome", metrics::outcome_label(outcome));
+        if outcome.is_failure() {
+            span.fail(&record.message);
+        }
+        record_run(app_handle, executor, task, record);
+        outcome
+    })
+}
+
+/// Extracts the message of a caught panic.
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+/// Runs a task immediately, outside its schedule.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `task_id` - The ID of the task to run.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Ok once the run is started, error message if the task is
+///   missing or disabled.
+pub fn run_task_now(app_handle: &AppHandle, task_id: &str) -> Result<(), String> {
+    let (global, task) = runnable_task(app_handle, task_id)?;
+    info!("Running task {} now", task.name);
+    let executor = shared_executor(app_handle, &global);
+    spawn_run(app_handle, executor, task);
+    Ok(())
+}
 
-        if !tasks_to_run.is_empty() {
-            info!("Found {} tasks to run.", tasks_to_run.len());
+/// Runs a task immediately on the current thread and waits for it to finish.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `task_id` - The ID of the task to run.
+///
+/// # Returns
+///
+/// * `Result<Option<RunOutcome>, String>` - The outcome (`None` if the run was skipped),
+///   or an error message if the task is missing or disabled.
+pub fn run_task_blocking(
+    app_handle: &AppHandle,
+    task_id: &str,
+) -> Result<Option<RunOutcome>, String> {
+    let (global, task) = runnable_task(app_handle, task_id)?;
+    info!("Running task {} now", task.name);
+    let executor = shared_executor(app_handle, &global);
+    Ok(execute_run(app_handle, &executor, &task, None))
+}
 
-            let wecom_config = config.global.wecom.clone();
-            let executor = Arc::new(TaskExecutor::new(wecom_config));
+/// Looks up a task that may be run now, with the global settings to run it with.
+fn runnable_task(app_handle: &AppHandle, task_id: &str) -> Result<(GlobalConfig, Task), String> {
+    let config = app_handle.state::<ConfigState>().0.lock().unwrap().clone();
+    let task = config
+        .tasks
+        .into_iter()
+        .find(|t| t.id == task_id)
+        .ok_or(t(Msg::TaskNotFound))?;
+    if !task.enable {
+        return Err(t(Msg::TaskDisabled).to_string());
+    }
+    if SHUTTING_DOWN.load(Ordering::SeqCst) {
+        return Err(t(Msg::ShuttingDown).to_string());
+    }
+    Ok((config.global, task))
+}
 
-            for task in tasks_to_run {
-                let executor_clone = executor.clone();
-                // Spawn a blocking thread for each task to avoid blocking the async loop?
-                // Since `TaskExecutor` uses blocking reqwest, we should use `spawn_blocking`.
+/// Runs every enabled task immediately through the same path as `run_task_now`.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+///
+/// # Returns
+///
+/// * `usize` - The number of runs started.
+pub fn run_all_now(app_handle: &AppHandle) -> usize {
+    let task_ids: Vec<String> = app_handle
+        .state::<ConfigState>()
+        .0
+        .lock()
+        .unwrap()
+        .tasks
+        .iter()
+        .filter(|t| t.enable)
+        .map(|t| t.id.clone())
+        .collect();
 
-                tokio::task::spawn_blocking(move || {
-                    executor_clone.execute(&task);
-                });
+    task_ids
+        .iter()
+        .filter(|id| match run_task_now(app_handle, id) {
+            Ok(()) => true,
+            Err(e) => {
+                error!("Failed to run task {}: {}", id, e);
+                false
             }
+        })
+        .count()
+}
+
+/// Stores a finished run in the history and sends follow-up alerts.
+///
+/// Notifies via WeCom when the run breaks the task's streak of successful days, drops its
+/// rolling success rate below the alert threshold or reveals a change in the class's
+/// check-in pattern, and disables the task once it reaches its
+/// consecutive failure limit.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `executor` - The executor used to send notifications.
+/// * `task` - The task that was executed.
+/// * `record` - The finished run.
+fn record_run(app_handle: &AppHandle, executor: &TaskExecutor, task: &Task, record: RunRecord) {
+    let previous = load_history(app_handle);
+    let (previous_streak, _) = compute_streak(&previous, &task.id);
+
+    metrics::record_run(&record);
+    let global = current_global(app_handle);
+    heartbeat::run_finished(&global.heartbeat, &record);
+    webhooks::run_finished(&global.webhooks, task, &record);
+    hooks::post_run(&global.scripting, task, &record);
+    match record.outcome {
+        RunOutcome::CookieExpired => session_expired(app_handle, executor, task),
+        RunOutcome::Success | RunOutcome::Failure | RunOutcome::NoSession => {
+            session_valid(app_handle, task)
         }
+        _ => {}
+    }
+    events::publish(
+        "run_finished",
+        json!({
+            "task_id": task.id,
+            "task_name": task.name,
+            "outcome": record.outcome,
+            "message": record.message,
+        }),
+    );
+    if let Err(e) = append_history(app_handle, record.clone()) {
+        error!("Failed to record history for {}: {}", task.name, e);
+        return;
+    }
+
+    let history = load_history(app_handle);
+    let tasks = app_handle
+        .state::<ConfigState>()
+        .0
+        .lock()
+        .unwrap()
+        .tasks
+        .clone();
+    for found in anomaly::check_run(&tasks, &history, task, &record, &global.anomalies) {
+        warn!(
+            "Check-in pattern of {} changed: {:?}",
+            task.name, found.kind
+        );
+        events::publish("anomaly_detected", json!(found));
+        let (title, mut body) = match found.kind {
+            AnomalyKind::MissingSessions => (
+                tf(Msg::SessionsMissingTitle, &[&task.name]),
+                tf(Msg::SessionsMissingBody, &[&task.name, &found.count]),
+            ),
+            AnomalyKind::UnusualTime => (
+                tf(Msg::UnusualSessionTitle, &[&task.name]),
+                tf(
+                    Msg::UnusualSessionBody,
+                    &[&task.name, &record.started_at.format("%m-%d %H:%M")],
+                ),
+            ),
+        };
+        if let Some(usual) = found.usual_time {
+            body.push(' ');
+            body.push_str(&tf(Msg::UsualSessionTime, &[&usual.format("%H:%M")]));
+        }
+        let _ = executor.send_wecom_notification(&title, &body);
+    }
+
+    let (current_streak, _) = compute_streak(&history, &task.id);
+    if previous_streak > 0 && current_streak == 0 {
+        let _ = executor.send_wecom_notification(
+            &tf(Msg::StreakBrokenTitle, &[&task.name]),
+            &tf(Msg::StreakBrokenBody, &[&previous_streak, &task.name]),
+        );
+    }
+
+    let alert = &global.success_rate_alert;
+    if alert.enable {
+        let threshold = alert.threshold_percent as f64 / 100.0;
+        let was_low = rolling_stats(&previous, &task.id, alert)
+            .and_then(|s| s.success_rate)
+            .is_some_and(|rate| rate < threshold);
+        let dropped = rolling_stats(&history, &task.id, alert)
+            .filter(|s| s.success_rate.is_some_and(|rate| rate < threshold))
+            .filter(|_| !was_low);
+        if let Some(stats) = dropped {
+            let rate = stats.success_rate.unwrap_or_default();
+            let percent = format!("{:.0}", rate * 100.0);
+            warn!(
+                "Success rate of {} dropped to {}% over {} days",
+                task.name, percent, alert.window_days
+            );
+            webhooks::success_rate_low(&global.webhooks, task, rate, alert.window_days);
+            events::publish(
+                "success_rate_low",
+                json!({
+                    "task_id": task.id,
+                    "task_name": task.name,
+                    "success_rate": rate,
+                    "window_days": alert.window_days,
+                }),
+            );
+            let _ = executor.send_wecom_notification(
+                &tf(Msg::SuccessRateLowTitle, &[&task.name]),
+                &tf(
+                    Msg::SuccessRateLowBody,
+                    &[
+                        &task.name,
+                        &alert.window_days,
+                        &percent,
+                        &stats.successes,
+                        &(stats.successes + stats.failures),
+                        &alert.threshold_percent,
+                    ],
+                ),
+            );
+        }
+    }
+
+    let failures = consecutive_failures(&history, &task.id, task.enabled_at);
+    if task.disable_after_failures > 0 && failures >= task.disable_after_failures {
+        match disable_task(app_handle, &task.id) {
+            Ok(()) => {
+                warn!("Task {} disabled after {} failures", task.name, failures);
+                let _ = executor.send_wecom_notification(
+                    &tf(Msg::TaskDisabledTitle, &[&task.name]),
+                    &tf(Msg::TaskDisabledBody, &[&task.name, &failures]),
+                );
+            }
+            Err(e) => error!("Failed to disable task {}: {}", task.name, e),
+        }
+    }
+}
+
+/// The task's statistics over the success rate alert window, if enough runs were attempted
+/// in it to judge the rate.
+fn rolling_stats(
+    records: &[RunRecord],
+    task_id: &str,
+    config: &SuccessRateAlertConfig,
+) -> Option<TaskStats> {
+    let stats = compute_stats(records, task_id, Some(config.window_days));
+    (stats.successes + stats.failures >= config.min_attempts.max(1)).then_some(stats)
+}
+
+/// Returns a copy of the current global settings.
+fn current_global(app_handle: &AppHandle) -> GlobalConfig {
+    app_handle
+        .state::<ConfigState>()
+        .0
+        .lock()
+        .unwrap()
+        .global
+        .clone()
+}
+
+/// Disables a task in the managed configuration, persists the change and refreshes the tray.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `task_id` - The ID of the task to disable.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Ok on success, error message if the task is missing or saving fails.
+fn disable_task(app_handle: &AppHandle, task_id: &str) -> Result<(), String> {
+    let state = app_handle.state::<ConfigState>();
+    let mut config = state.0.lock().unwrap();
+    let task = config
+        .tasks
+        .iter_mut()
+        .find(|t| t.id == task_id)
+        .ok_or(t(Msg::TaskNotFound))?;
+    task.enable = false;
+    save_config(app_handle, &config)?;
+    refresh_tray_menu(app_handle, &config.tasks);
+    events::publish("config_changed", json!({}));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A time on the given day of October 2026.
+    fn at(day: u32, hour: u32, minute: u32, second: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, second)
+            .unwrap()
+    }
+
+    /// An enabled task running daily at `time`.
+    fn task(id: &str, time: &str) -> Task {
+        Task {
+            id: id.to_string(),
+            time: time.to_string(),
+            enable: true,
+            ..Default::default()
+        }
+    }
+
+    /// The IDs of the tasks, in order.
+    fn ids(tasks: Vec<Task>) -> Vec<String> {
+        tasks.into_iter().map(|t| t.id).collect()
+    }
+
+    /// A clock that only moves when the scheduler sleeps, by the requested duration, and
+    /// jumps to a new time once it passes a scripted point, like a system sleep or a clock
+    /// change.
+    struct FakeClock {
+        now: Mutex<NaiveDateTime>,
+        jumps: Mutex<Vec<(NaiveDateTime, NaiveDateTime)>>,
+    }
+
+    impl FakeClock {
+        fn new(start: NaiveDateTime) -> Self {
+            Self {
+                now: Mutex::new(start),
+                jumps: Mutex::new(vec![]),
+            }
+        }
+
+        /// Moves the clock to `to` once it reaches `when`.
+        fn jump_at(self, when: NaiveDateTime, to: NaiveDateTime) -> Self {
+            self.jumps.lock().unwrap().push((when, to));
+            self
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> NaiveDateTime {
+            *self.now.lock().unwrap()
+        }
+
+        fn sleep(&self, duration: Duration) -> impl Future<Output = ()> + Send {
+            self.pause(duration);
+            std::future::ready(())
+        }
+
+        fn pause(&self, duration: Duration) {
+            let mut now = self.now.lock().unwrap();
+            *now += duration;
+            let mut jumps = self.jumps.lock().unwrap();
+            if let Some(i) = jumps.iter().position(|(when, _)| *now >= *when) {
+                *now = jumps.remove(i).1;
+            }
+        }
+    }
+
+    /// Runs the scheduler loop on `clock` until it reaches `until`, and lists the runs it
+    /// started: task ID, tick minute and whether the tick was a resume.
+    fn simulate(
+        clock: &FakeClock,
+        tasks: &[Task],
+        until: NaiveDateTime,
+    ) -> Vec<(String, NaiveDateTime, bool)> {
+        let mut runs = Vec::new();
+        tauri::async_runtime::block_on(run_ticks(
+            clock,
+            || clock.now() >= until,
+            |tick| {
+                for id in ids(tick.due_tasks(tasks)) {
+                    runs.push((id, tick.minute, tick.resumed));
+                }
+            },
+        ));
+        runs
+    }
+
+    #[test]
+    fn loop_runs_a_task_once_in_its_minute() {
+        let tasks = [task("eight", "08:00"), task("nine", "09:00")];
+        let clock = FakeClock::new(at(12, 7, 58, 30));
+        let runs = simulate(&clock, &tasks, at(12, 8, 5, 0));
+        assert_eq!(runs, [("eight".to_string(), at(12, 8, 0, 0), false)]);
+    }
+
+    #[test]
+    fn loop_catches_up_once_after_a_system_sleep() {
+        let tasks = [
+            task("eight", "08:00"),
+            task("nine", "09:00"),
+            task("ten", "10:00"),
+        ];
+        let clock = FakeClock::new(at(12, 7, 30, 0)).jump_at(at(12, 7, 30, 10), at(12, 9, 30, 2));
+        let runs = simulate(&clock, &tasks, at(12, 9, 40, 0));
+        assert_eq!(
+            runs,
+            [
+                ("eight".to_string(), at(12, 9, 30, 0), true),
+                ("nine".to_string(), at(12, 9, 30, 0), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn loop_resumes_the_normal_schedule_after_a_long_sleep() {
+        let tasks = [task("six", "06:00"), task("eight", "08:00")];
+        let clock = FakeClock::new(at(12, 7, 0, 0)).jump_at(at(12, 7, 0, 10), at(15, 7, 0, 2));
+        let runs = simulate(&clock, &tasks, at(15, 8, 1, 0));
+        assert_eq!(
+            runs,
+            [
+                ("six".to_string(), at(15, 7, 0, 0), true),
+                ("eight".to_string(), at(15, 7, 0, 0), true),
+                ("eight".to_string(), at(15, 8, 0, 0), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn loop_does_not_repeat_runs_after_the_clock_is_set_back() {
+        let tasks = [task("eight", "08:00"), task("eight-thirty", "08:30")];
+        let clock = FakeClock::new(at(12, 7, 59, 50)).jump_at(at(12, 8, 20, 0), at(12, 8, 5, 0));
+        let runs = simulate(&clock, &tasks, at(12, 8, 31, 0));
+        assert_eq!(
+            runs,
+            [
+                ("eight".to_string(), at(12, 8, 0, 0), false),
+                ("eight-thirty".to_string(), at(12, 8, 30, 0), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn current_minute_runs_once() {
+        let tasks = [task("eight", "08:00"), task("nine", "09:00")];
+        let mut state = ScheduleState::new(at(12, 8, 0, 10));
+
+        let tick = state.advance(at(12, 8, 0, 15)).unwrap();
+        assert_eq!(tick.minute, at(12, 8, 0, 0));
+        assert!(!tick.resumed);
+        assert_eq!(ids(tick.due_tasks(&tasks)), ["eight"]);
+
+        assert_eq!(state.advance(at(12, 8, 0, 20)), None);
+        assert_eq!(state.advance(at(12, 8, 0, 45)), None);
+
+        let tick = state.advance(at(12, 8, 1, 5)).unwrap();
+        assert!(tick.due_tasks(&tasks).is_empty());
+    }
+
+    #[test]
+    fn gap_is_caught_up_and_reported_as_resume() {
+        let tasks = [
+            task("eight", "08:00"),
+            task("nine", "09:00"),
+            task("ten", "10:00"),
+        ];
+        let mut state = ScheduleState::new(at(12, 7, 30, 0));
+        state.advance(at(12, 7, 30, 5));
+
+        let tick = state.advance(at(12, 9, 30, 0)).unwrap();
+        assert!(tick.resumed);
+        assert_eq!(tick.since, at(12, 7, 30, 0));
+        assert_eq!(ids(tick.due_tasks(&tasks)), ["eight", "nine"]);
+    }
+
+    #[test]
+    fn short_pauses_are_not_resumes() {
+        let mut state = ScheduleState::new(at(12, 8, 0, 0));
+        assert!(!state.advance(at(12, 8, 0, 5)).unwrap().resumed);
+        assert_eq!(state.advance(at(12, 8, 0, 35)), None);
+        assert!(!state.advance(at(12, 8, 1, 5)).unwrap().resumed);
+        assert!(state.advance(at(12, 8, 2, 0)).unwrap().resumed);
+    }
+
+    #[test]
+    fn catch_up_is_capped_at_a_day() {
+        let tasks = [task("six", "06:00"), task("eight", "08:00")];
+        let mut state = ScheduleState::new(at(12, 7, 0, 0));
+        state.advance(at(12, 7, 0, 5));
+
+        let tick = state.advance(at(15, 7, 0, 0)).unwrap();
+        assert!(tick.resumed);
+        assert_eq!(tick.since, at(14, 7, 0, 0));
+        // Each task runs once, however many of its times were missed.
+        assert_eq!(ids(tick.due_tasks(&tasks)), ["six", "eight"]);
+    }
+
+    #[test]
+    fn clock_set_back_does_not_repeat_runs() {
+        let tasks = [task("eight", "08:00"), task("eight-thirty", "08:30")];
+        let mut state = ScheduleState::new(at(12, 9, 0, 0));
+        state.advance(at(12, 9, 0, 5));
+
+        assert_eq!(state.advance(at(12, 8, 0, 0)), None);
+        let tick = state.advance(at(12, 8, 1, 0)).unwrap();
+        assert!(tick.due_tasks(&tasks).is_empty());
+
+        let tick = state.advance(at(12, 8, 30, 0)).unwrap();
+        assert_eq!(ids(tick.due_tasks(&tasks)), ["eight-thirty"]);
+    }
 
-        // Sleep for 60 seconds
-        sleep(Duration::from_secs(60)).await;
+    #[test]
+    fn disabled_tasks_are_never_due() {
+        let mut paused = task("paused", "08:00");
+        paused.enable = false;
+        let mut state = ScheduleState::new(at(12, 8, 0, 0));
+        let tick = state.advance(at(12, 8, 0, 5)).unwrap();
+        assert!(tick.due_tasks(&[paused]).is_empty());
     }
 }
@@ -0,0 +1,319 @@
+use std::process::Command;
+
+/// Name of the registered background service.
+#[cfg_attr(not(any(target_os = "windows", target_os = "macos")), allow(dead_code))]
+const SERVICE_NAME: &str = "AutoCheckin-Next";
+
+/// Registers the app to start in the background: at boot on Windows and Linux, at login
+/// on macOS.
+///
+/// # Arguments
+///
+/// * `headless` - Start without window or tray. Only honored on macOS; Windows and Linux
+///   start before any desktop session and always run headless.
+///
+/// # Returns
+///
+/// * `Result<String, String>` - A description of what was installed, or an error message
+///   if registration failed or the platform is not supported.
+pub fn install_service(headless: bool) -> Result<String, String> {
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    platform::install(&exe, headless)
+}
+
+/// Removes the registration made by `install_service`.
+///
+/// # Returns
+///
+/// * `Result<String, String>` - A description of what was removed, or an error message.
+pub fn uninstall_service() -> Result<String, String> {
+    platform::uninstall()
+}
+
+/// Runs a system tool and turns a non-zero exit into an error with its output.
+fn run_tool(command: &mut Command) -> Result<(), String> {
+    let output = command.output().map_err(|e| e.to_string())?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Err(format!(
+        "{:?} failed: {}",
+        command.get_program(),
+        if stderr.trim().is_empty() {
+            stdout
+        } else {
+            stderr
+        }
+        .trim()
+    ))
+}
+
+/// Windows: a Task Scheduler task that starts at boot under the current user.
+///
+/// The app hosts a webview, so it cannot implement the service control protocol that
+/// the Service Control Manager expects. A boot task with `/NP` runs without anyone
+/// logged in, as the current user, so the usual config and history are used.
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::{run_tool, SERVICE_NAME};
+    use std::path::Path;
+    use std::process::Command;
+
+    pub fn install(exe: &Path, _headless: bool) -> Result<String, String> {
+        let user = std::env::var("USERNAME").map_err(|e| e.to_string())?;
+        let action = format!("\"{}\" --headless", exe.display());
+        run_tool(Command::new("schtasks").args([
+            "/Create",
+            "/F",
+            "/TN",
+            SERVICE_NAME,
+            "/TR",
+            &action,
+            "/SC",
+            "ONSTARTUP",
+            "/RU",
+            &user,
+            "/NP",
+        ]))?;
+        Ok(format!(
+            "Scheduled task \"{}\" starts at boot as {}",
+            SERVICE_NAME, user
+        ))
+    }
+
+    pub fn uninstall() -> Result<String, String> {
+        let _ = Command::new("schtasks")
+            .args(["/End", "/TN", SERVICE_NAME])
+            .output();
+        run_tool(Command::new("schtasks").args(["/Delete", "/F", "/TN", SERVICE_NAME]))?;
+        Ok(format!("Scheduled task \"{}\" removed", SERVICE_NAME))
+    }
+}
+
+/// Linux: a systemd user unit running the app headless, started at boot through lingering.
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::run_tool;
+    use crate::i18n::{t, Msg};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+
+    /// File name of the generated unit.
+    const UNIT_NAME: &str = "autocheckin-next.service";
+
+    pub fn install(exe: &Path, _headless: bool) -> Result<String, String> {
+        let path = unit_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        fs::write(&path, unit_file(exe)).map_err(|e| e.to_string())?;
+        run_tool(Command::new("systemctl").args(["--user", "daemon-reload"]))?;
+        run_tool(Command::new("systemctl").args(["--user", "enable", "--now", UNIT_NAME]))?;
+
+        // Without lingering, user units only start once the user logs in.
+        let user = std::env::var("USER").unwrap_or_default();
+        let linger = run_tool(Command::new("loginctl").args(["enable-linger", &user]));
+        let mut message = format!("Installed and started {}", path.display());
+        if let Err(e) = linger {
+            message.push_str(&format!(
+                "\nCould not enable lingering ({}); the service starts at login instead of boot. \
+                 Run `sudo loginctl enable-linger {}` to start it at boot.",
+                e, user
+            ));
+        }
+        Ok(message)
+    }
+
+    pub fn uninstall() -> Result<String, String> {
+        let path = unit_path()?;
+        let _ = Command::new("systemctl")
+            .args(["--user", "disable", "--now", UNIT_NAME])
+            .output();
+        if path.exists() {
+            fs::remove_file(&path).map_err(|e| e.to_string())?;
+        }
+        run_tool(Command::new("systemctl").args(["--user", "daemon-reload"]))?;
+        Ok(format!("Removed {}", path.display()))
+    }
+
+    /// Location of the unit under the user's systemd configuration directory.
+    fn unit_path() -> Result<PathBuf, String> {
+        let config_home = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+            .ok_or_else(|| t(Msg::HomeNotSet).to_string())?;
+        Ok(config_home.join("systemd/user").join(UNIT_NAME))
+    }
+
+    /// Renders the unit. The webview toolkit still needs a display, so on machines without
+    /// one the app runs under `xvfb-run` when it is installed.
+    fn unit_file(exe: &Path) -> String {
+        let has_display = ["DISPLAY", "WAYLAND_DISPLAY"]
+            .iter()
+            .any(|var| std::env::var_os(var).is_some());
+        let xvfb = (!has_display)
+            .then(|| which("xvfb-run"))
+            .flatten()
+            .map(|path| format!("{} -a ", path.display()))
+            .unwrap_or_default();
+        let mut environment = String::new();
+        for var in ["DISPLAY", "WAYLAND_DISPLAY", "LANG"] {
+            if let Ok(value) = std::env::var(var) {
+                environment.push_str(&format!("Environment={}={}\n", var, value));
+            }
+        }
+        format!(
+            "[Unit]\n\
+             Description=AutoCheckin-Next scheduler\n\
+             \n\
+             [Service]\n\
+             Type=simple\n\
+             ExecStart={}\"{}\" --headless\n\
+             {}\
+             Restart=on-failure\n\
+             RestartSec=30\n\
+             TimeoutStopSec=120\n\
+             \n\
+             [Install]\n\
+             WantedBy=default.target\n",
+            xvfb,
+            exe.display(),
+            environment
+        )
+    }
+
+    /// Finds an executable on the `PATH`.
+    fn which(name: &str) -> Option<PathBuf> {
+        std::env::var_os("PATH")?
+            .to_str()?
+            .split(':')
+            .map(|dir| Path::new(dir).join(name))
+            .find(|path| path.is_file())
+    }
+}
+
+/// macOS: a LaunchAgent that starts the app at login and restarts it if it crashes.
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{run_tool, SERVICE_NAME};
+    use crate::i18n::{t, Msg};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+
+    /// Label of the agent, matching the bundle identifier.
+    const LABEL: &str = "com.autocheckin.next";
+
+    pub fn install(exe: &Path, headless: bool) -> Result<String, String> {
+        let path = plist_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        // Replace a loaded older version of the agent.
+        let _ = Command::new("launchctl").arg("unload").arg(&path).output();
+        fs::write(&path, plist(exe, headless)?).map_err(|e| e.to_string())?;
+        run_tool(Command::new("launchctl").args(["load", "-w"]).arg(&path))?;
+        Ok(format!("Installed and loaded {}", path.display()))
+    }
+
+    pub fn uninstall() -> Result<String, String> {
+        let path = plist_path()?;
+        if !path.exists() {
+            return Ok(format!("{} is not installed", LABEL));
+        }
+        run_tool(Command::new("launchctl").args(["unload", "-w"]).arg(&path))?;
+        fs::remove_file(&path).map_err(|e| e.to_string())?;
+        Ok(format!("Removed {}", path.display()))
+    }
+
+    /// Location of the agent in the user's LaunchAgents directory.
+    fn plist_path() -> Result<PathBuf, String> {
+        Ok(home()?
+            .join("Library/LaunchAgents")
+            .join(format!("{}.plist", LABEL)))
+    }
+
+    /// The user's home directory.
+    fn home() -> Result<PathBuf, String> {
+        std::env::var_os("HOME")
+            .map(PathBuf::from)
+            .ok_or_else(|| t(Msg::HomeNotSet).to_string())
+    }
+
+    /// Renders the agent. A clean exit (quitting from the tray) is not restarted.
+    fn plist(exe: &Path, headless: bool) -> Result<String, String> {
+        let log = home()?
+            .join("Library/Logs")
+            .join(format!("{}.log", SERVICE_NAME));
+        let mut arguments = format!(
+            "        <string>{}</string>\n",
+            escape(&exe.display().to_string())
+        );
+        if headless {
+            arguments.push_str("        <string>--headless</string>\n");
+        }
+        Ok(format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+{arguments}    </array>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <dict>
+        <key>SuccessfulExit</key>
+        <false/>
+    </dict>
+    <key>ThrottleInterval</key>
+    <integer>30</integer>
+    <key>ProcessType</key>
+    <string>{process_type}</string>
+    <key>StandardOutPath</key>
+    <string>{log}</string>
+    <key>StandardErrorPath</key>
+    <string>{log}</string>
+</dict>
+</plist>
+"#,
+            label = LABEL,
+            arguments = arguments,
+            process_type = if headless {
+                "Background"
+            } else {
+                "Interactive"
+            },
+            log = escape(&log.display().to_string()),
+        ))
+    }
+
+    /// Escapes text for an XML element.
+    fn escape(value: &str) -> String {
+        value
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+    }
+}
+
+/// Other platforms have no background service support.
+#[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+mod platform {
+    use crate::i18n::{t, Msg};
+    use std::path::Path;
+
+    pub fn install(_exe: &Path, _headless: bool) -> Result<String, String> {
+        Err(t(Msg::ServiceUnsupported).to_string())
+    }
+
+    pub fn uninstall() -> Result<String, String> {
+        Err(t(Msg::ServiceUnsupported).to_string())
+    }
+}
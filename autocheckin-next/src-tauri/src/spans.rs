@@ -0,0 +1,301 @@
+use crate::config::TracingConfig;
+use crate::logs::{with_context, ContextGuard};
+use crate::tls;
+use log::{debug, warn};
+use serde_json::{json, Value};
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, Once};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How often finished spans are sent to the collector.
+const EXPORT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Timeout for one export request.
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Finished spans kept while the collector is unreachable; older ones are dropped.
+const MAX_BUFFERED: usize = 2000;
+
+/// Whether spans are recorded.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// The collector settings.
+static CONFIG: Mutex<Option<TracingConfig>> = Mutex::new(None);
+
+/// Finished spans waiting to be exported, in OTLP JSON form.
+static BUFFER: Mutex<Vec<Value>> = Mutex::new(Vec::new());
+
+/// Starts the export thread once.
+static EXPORTER: Once = Once::new();
+
+thread_local! {
+    /// Spans open on the current thread, innermost last.
+    static OPEN: RefCell<Vec<SpanContext>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Identifies a span so work on another thread can continue its trace.
+#[derive(Debug, Clone)]
+pub struct SpanContext {
+    /// 32 hex digits shared by all spans of a trace.
+    trace_id: String,
+    /// 16 hex digits identifying the span.
+    span_id: String,
+}
+
+/// Whether a span describes an outbound request or internal work.
+#[derive(Debug, Clone, Copy)]
+pub enum SpanKind {
+    /// Internal work such as a scheduler tick or task run.
+    Internal,
+    /// An outbound HTTP request.
+    Client,
+}
+
+/// A timed operation, exported when dropped.
+///
+/// Spans must be dropped on the thread that started them, innermost first. While a
+/// span is open, its trace and span IDs are attached to log lines on the thread.
+pub struct Span {
+    /// The span data, or `None` while tracing is disabled.
+    data: Option<SpanData>,
+}
+
+/// Data of a recording span.
+struct SpanData {
+    /// IDs of the span.
+    context: SpanContext,
+    /// ID of the parent span, if any.
+    parent_span_id: Option<String>,
+    /// The operation name.
+    name: String,
+    /// Whether the span is an outbound request.
+    kind: SpanKind,
+    /// Start time in nanoseconds since the Unix epoch.
+    start_ns: u128,
+    /// Attributes added while the span was open.
+    attributes: Vec<(String, Value)>,
+    /// The error description if the operation failed.
+    error: Option<String>,
+    /// Attaches the trace and span IDs to log lines while the span is open.
+    _log_context: ContextGuard,
+}
+
+/// Applies the tracing settings.
+///
+/// # Arguments
+///
+/// * `config` - The tracing settings.
+pub fn configure(config: &TracingConfig) {
+    ENABLED.store(config.enable, Ordering::Relaxed);
+    *CONFIG.lock().unwrap() = Some(config.clone());
+    if config.enable {
+        EXPORTER.call_once(|| {
+            thread::spawn(|| loop {
+                thread::sleep(EXPORT_INTERVAL);
+                export();
+            });
+        });
+    }
+}
+
+/// Returns whether spans are recorded.
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Starts a span as a child of the innermost span open on this thread.
+///
+/// # Arguments
+///
+/// * `name` - The operation name, e.g. `task.run`.
+/// * `kind` - Whether the span is an outbound request.
+///
+/// # Returns
+///
+/// * `Span` - The open span; a no-op while tracing is disabled.
+pub fn start(name: &str, kind: SpanKind) -> Span {
+    let parent = OPEN.with(|open| open.borrow().last().cloned());
+    start_with_parent(name, kind, parent)
+}
+
+/// Starts a span under an explicit parent, e.g. one captured on another thread.
+///
+/// # Arguments
+///
+/// * `name` - The operation name.
+/// * `kind` - Whether the span is an outbound request.
+/// * `parent` - The parent span, or `None` to start a new trace.
+///
+/// # Returns
+///
+/// * `Span` - The open span; a no-op while tracing is disabled.
+pub fn start_with_parent(name: &str, kind: SpanKind, parent: Option<SpanContext>) -> Span {
+    if !enabled() {
+        return Span { data: None };
+    }
+    let context = SpanContext {
+        trace_id: parent
+            .as_ref()
+            .map(|p| p.trace_id.clone())
+            .unwrap_or_else(|| format!("{:032x}", rand::random::<u128>())),
+        span_id: format!("{:016x}", rand::random::<u64>()),
+    };
+    OPEN.with(|open| open.borrow_mut().push(context.clone()));
+    let log_context = with_context([
+        ("trace_id", context.trace_id.clone().into()),
+        ("span_id", context.span_id.clone().into()),
+    ]);
+    Span {
+        data: Some(SpanData {
+            context,
+            parent_span_id: parent.map(|p| p.span_id),
+            name: name.to_string(),
+            kind,
+            start_ns: now_ns(),
+            attributes: Vec::new(),
+            error: None,
+            _log_context: log_context,
+        }),
+    }
+}
+
+/// Returns the innermost span open on this thread, to pass to another thread.
+///
+/// # Returns
+///
+/// * `Option<SpanContext>` - The span, or `None` if none is open.
+pub fn current() -> Option<SpanContext> {
+    OPEN.with(|open| open.borrow().last().cloned())
+}
+
+impl Span {
+    /// Adds an attribute to the span.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The attribute name, e.g. `http.response.status_code`.
+    /// * `value` - A string, number or boolean.
+    pub fn set(&mut self, key: &str, value: impl Into<Value>) {
+        if let Some(data) = &mut self.data {
+            data.attributes.push((key.to_string(), value.into()));
+        }
+    }
+
+    /// Marks the span as failed.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The error description.
+    pub fn fail(&mut self, message: &str) {
+        if let Some(data) = &mut self.data {
+            data.error = Some(message.to_string());
+        }
+    }
+}
+
+impl Drop for Span {
+    /// Ends the span and queues it for export.
+    fn drop(&mut self) {
+        let Some(data) = self.data.take() else {
+            return;
+        };
+        OPEN.with(|open| {
+            let mut open = open.borrow_mut();
+            if let Some(i) = open.iter().rposition(|c| c.span_id == data.context.span_id) {
+                open.remove(i);
+            }
+        });
+
+        let attributes: Vec<Value> = data
+            .attributes
+            .iter()
+            .map(|(key, value)| json!({ "key": key, "value": otlp_value(value) }))
+            .collect();
+        let status = match &data.error {
+            Some(message) => json!({ "code": 2, "message": message }),
+            None => json!({ "code": 1 }),
+        };
+        let mut span = json!({
+            "traceId": data.context.trace_id,
+            "spanId": data.context.span_id,
+            "name": data.name,
+            "kind": match data.kind {
+                SpanKind::Internal => 1,
+                SpanKind::Client => 3,
+            },
+            "startTimeUnixNano": data.start_ns.to_string(),
+            "endTimeUnixNano": now_ns().to_string(),
+            "attributes": attributes,
+            "status": status,
+        });
+        if let Some(parent) = data.parent_span_id {
+            span["parentSpanId"] = json!(parent);
+        }
+
+        let mut buffer = BUFFER.lock().unwrap();
+        if buffer.len() >= MAX_BUFFERED {
+            buffer.remove(0);
+        }
+        buffer.push(span);
+    }
+}
+
+/// Sends the buffered spans to the OTLP/HTTP collector as JSON.
+fn export() {
+    let Some(config) = CONFIG.lock().unwrap().clone() else {
+        return;
+    };
+    let spans = std::mem::take(&mut *BUFFER.lock().unwrap());
+    if !config.enable || spans.is_empty() {
+        return;
+    }
+    let count = spans.len();
+    let body = json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [
+                    { "key": "service.name", "value": { "stringValue": config.service_name } },
+                    {
+                        "key": "service.version",
+                        "value": { "stringValue": env!("CARGO_PKG_VERSION") }
+                    },
+                ]
+            },
+            "scopeSpans": [{
+                "scope": { "name": "autocheckin-next" },
+                "spans": spans,
+            }]
+        }]
+    });
+    // Sent without `httplog` so exports do not create spans of their own.
+    match tls::client_builder()
+        .timeout(EXPORT_TIMEOUT)
+        .build()
+        .and_then(|client| client.post(&config.endpoint).json(&body).send())
+        .and_then(|r| r.error_for_status())
+    {
+        Ok(_) => debug!("Exported {} span(s)", count),
+        Err(e) => warn!("Failed to export {} span(s): {}", count, e),
+    }
+}
+
+/// Converts a JSON value into an OTLP `AnyValue`.
+fn otlp_value(value: &Value) -> Value {
+    match value {
+        Value::Bool(b) => json!({ "boolValue": b }),
+        Value::Number(n) if n.is_i64() || n.is_u64() => json!({ "intValue": n.to_string() }),
+        Value::Number(n) => json!({ "doubleValue": n.as_f64() }),
+        Value::String(s) => json!({ "stringValue": s }),
+        other => json!({ "stringValue": other.to_string() }),
+    }
+}
+
+/// Nanoseconds since the Unix epoch.
+fn now_ns() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default()
+}
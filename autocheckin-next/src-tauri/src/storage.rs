@@ -0,0 +1,202 @@
+use crate::crash::get_crash_dir;
+use crate::geocode;
+use crate::history::{clear_history, get_artifacts_dir, get_attendance_path, get_history_path};
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+/// A kind of data the app accumulates on disk.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageCategory {
+    /// Rotated log files; the log files in use are never purged.
+    Logs,
+    /// The run history and the attendance synced from the site.
+    History,
+    /// Debug artifacts: pages of failed runs, anti-bot pages and HAR captures.
+    Artifacts,
+    /// Receipts and HTML snapshots of successful signs.
+    Receipts,
+    /// Crash reports of previous sessions.
+    CrashReports,
+    /// The reverse-geocoding cache.
+    Cache,
+}
+
+/// Disk usage of one category.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StorageUsage {
+    /// The category.
+    pub category: StorageCategory,
+    /// Where the category is stored.
+    pub paths: Vec<String>,
+    /// Total size in bytes.
+    pub bytes: u64,
+    /// Number of files.
+    pub files: usize,
+}
+
+/// Disk usage of everything the app stores.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StorageReport {
+    /// Total size in bytes.
+    pub total_bytes: u64,
+    /// Usage per category, largest first.
+    pub categories: Vec<StorageUsage>,
+}
+
+/// All categories, in the order they are measured.
+const CATEGORIES: [StorageCategory; 6] = [
+    StorageCategory::Logs,
+    StorageCategory::History,
+    StorageCategory::Artifacts,
+    StorageCategory::Receipts,
+    StorageCategory::CrashReports,
+    StorageCategory::Cache,
+];
+
+/// Measures the disk usage of each kind of stored data.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+///
+/// # Returns
+///
+/// * `StorageReport` - Sizes and file counts per category; missing paths count as empty.
+pub fn storage_report(app_handle: &AppHandle) -> StorageReport {
+    let mut categories: Vec<StorageUsage> = CATEGORIES
+        .iter()
+        .map(|&category| {
+            let paths = category_paths(app_handle, category);
+            let (mut bytes, mut files) = (0, 0);
+            for path in &paths {
+                let (b, f) = measure(path);
+                bytes += b;
+                files += f;
+            }
+            if category == StorageCategory::Logs {
+                for path in active_logs(app_handle) {
+                    if let Ok(meta) = fs::metadata(path) {
+                        bytes += meta.len();
+                        files += 1;
+                    }
+                }
+            }
+            StorageUsage {
+                category,
+                paths: paths.iter().map(|p| p.display().to_string()).collect(),
+                bytes,
+                files,
+            }
+        })
+        .collect();
+    categories.sort_by_key(|c| std::cmp::Reverse(c.bytes));
+    StorageReport {
+        total_bytes: categories.iter().map(|c| c.bytes).sum(),
+        categories,
+    }
+}
+
+/// Deletes the stored data of one category.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `category` - The category to purge.
+///
+/// # Returns
+///
+/// * `Result<u64, String>` - The number of bytes freed, or an error message if a file
+///   cannot be deleted.
+pub fn purge(app_handle: &AppHandle, category: StorageCategory) -> Result<u64, String> {
+    let paths = category_paths(app_handle, category);
+    let freed = paths.iter().map(|p| measure(p).0).sum();
+    match category {
+        StorageCategory::History => clear_history(app_handle)?,
+        StorageCategory::Cache => geocode::clear_cache()?,
+        _ => {
+            for path in &paths {
+                remove(path)?;
+            }
+        }
+    }
+    info!("Purged {:?}: {} bytes freed", category, freed);
+    Ok(freed)
+}
+
+/// The files and directories a category consists of.
+///
+/// For logs, these are the rotated copies only, so the files being written stay intact.
+fn category_paths(app_handle: &AppHandle, category: StorageCategory) -> Vec<PathBuf> {
+    let data_dir = app_handle.path().app_data_dir().unwrap_or_default();
+    match category {
+        StorageCategory::Logs => {
+            let active = active_logs(app_handle);
+            app_handle
+                .path()
+                .app_log_dir()
+                .ok()
+                .and_then(|dir| fs::read_dir(dir).ok())
+                .into_iter()
+                .flatten()
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.is_file() && !active.contains(p))
+                .collect()
+        }
+        StorageCategory::History => vec![
+            get_history_path(app_handle),
+            get_attendance_path(app_handle),
+        ],
+        StorageCategory::Artifacts => vec![get_artifacts_dir(app_handle)],
+        StorageCategory::Receipts => vec![data_dir.join("receipts")],
+        StorageCategory::CrashReports => vec![get_crash_dir(app_handle)],
+        StorageCategory::Cache => vec![data_dir.join("geocode_cache.json")],
+    }
+}
+
+/// The log files currently written to.
+fn active_logs(app_handle: &AppHandle) -> Vec<PathBuf> {
+    let name = &app_handle.package_info().name;
+    app_handle
+        .path()
+        .app_log_dir()
+        .map(|dir| {
+            ["log", "json"]
+                .iter()
+                .map(|ext| dir.join(format!("{}.{}", name, ext)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The total size and number of files under a path.
+fn measure(path: &Path) -> (u64, usize) {
+    let Ok(meta) = fs::metadata(path) else {
+        return (0, 0);
+    };
+    if meta.is_file() {
+        return (meta.len(), 1);
+    }
+    fs::read_dir(path)
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .map(|e| measure(&e.path()))
+        .fold((0, 0), |(b, f), (eb, ef)| (b + eb, f + ef))
+}
+
+/// Deletes a file or a directory tree, ignoring paths that do not exist.
+fn remove(path: &Path) -> Result<(), String> {
+    let result = if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else if path.exists() {
+        fs::remove_file(path)
+    } else {
+        Ok(())
+    };
+    result.map_err(|e| format!("{}: {}", path.display(), e))
+}
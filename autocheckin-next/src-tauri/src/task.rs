@@ -1,17 +1,169 @@
-use crate::config::{Task, WeComConfig};
-use chrono::Local;
-use log::{error, info};
-use regex::Regex;
+use crate::anchor::{select_anchor, teaching_week};
+use crate::config::{
+    save_config, ConfigState, DeviceConfig, DriftConfig, EndpointConfig, GeocodingConfig,
+    GlobalConfig, Location, ReceiptConfig, Task, ThrottleConfig, TimeoutConfig, WarmUpConfig,
+    WeComConfig,
+};
+use crate::cookie::{normalize_cookie, parse_cookie};
+use crate::devtime;
+use crate::drift::{make_fix, next_offset, Fix};
+use crate::events;
+use crate::geo::{distance_m, offset_by_meters, validate_coordinates, CoordSystem};
+use crate::geocode::{locate_by_ip, reverse_geocode};
+use crate::har::{self, Exchange};
+use crate::headers::{HeaderProfile, WECHAT_ANDROID_UA};
+use crate::history::{RunOutcome, RunRecord, RunTimings, SignRecord, SiteAttendance};
+use crate::httplog;
+use crate::i18n::{t, tf, Msg};
+use crate::logs::with_context;
+use crate::metrics;
+use crate::profile;
+use crate::receipt::{self, Receipt};
+use crate::retry::{self, RetryPolicy};
+use crate::scheduler::Clock;
+use crate::throttle;
+use crate::tls;
+use chrono::{DateTime, Local, NaiveDate};
+use log::{debug, error, info, warn};
 use reqwest::blocking::Client;
-use reqwest::header::{HeaderMap, HeaderValue, COOKIE, REFERER, USER_AGENT};
-use scraper::{Html, Selector};
-use serde_json::Value;
-use std::collections::HashSet;
+use reqwest::cookie::{CookieStore, Jar};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, COOKIE, REFERER, USER_AGENT};
+use reqwest::Url;
+use scraper::Html;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, PoisonError};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
 
-/// User Agent string used for requests to simulate a mobile WeChat browser.
-const UA: &str = "Mozilla/5.0 (Linux; Android 12; PAL-AL00 Build/HUAWEIPAL-AL00; wv) AppleWebKit/537.36 (KHTML, like Gecko) Version/4.0 Chrome/116.0.0.0 Mobile Safari/537.36 XWEB/1160065 MMWEBSDK/20231202 MMWEBID/1136 MicroMessenger/8.0.47.2560(0x28002F35) WeChat/arm64 Weixin NetType/4G Language/zh_CN ABI/arm64";
+/// Most punch history pages read by one attendance sync.
+const MAX_HISTORY_PAGES: u32 = 30;
+
+/// Maximum number of body characters written to debug logs.
+const DEBUG_BODY_LIMIT: usize = 2000;
+
+/// Error message returned when the site redirects to its login page.
+pub const COOKIE_EXPIRED: &str = "Cookie expired, please log in again";
+
+/// Prefix of the error returned when the site shows a captcha, WAF or rate-limit page.
+pub const SITE_BLOCKED: &str = "Blocked by anti-bot page";
+
+/// Prefix of the error returned when the punch listing no longer matches the site profile.
+pub const SITE_LAYOUT_CHANGED: &str = "Site layout changed";
+
+/// How long a task pauses after hitting an anti-bot page.
+const BLOCK_BACKOFF: Duration = Duration::from_secs(30 * 60);
+
+/// Drift radius used when a task does not set one, roughly the old fixed ±0.00015°.
+const DEFAULT_DRIFT_RADIUS_M: f64 = 15.0;
+
+/// Timeout for each connectivity probe.
+const NETWORK_PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Delay between connectivity probes while a run is deferred.
+const NETWORK_RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long before its reported expiry a cached WeCom token is refreshed.
+const WECOM_TOKEN_MARGIN: Duration = Duration::from_secs(300);
+
+/// A location required by a check-in card, in the site's coordinate system.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SiteLocation {
+    /// Required latitude.
+    pub lat: f64,
+    /// Required longitude.
+    pub lng: f64,
+    /// Allowed distance from the location in meters, if the card states it.
+    pub radius_m: Option<f64>,
+}
+
+/// Payload of the `location-mismatch` event.
+#[derive(Debug, Serialize, Clone)]
+pub struct LocationMismatch {
+    /// ID of the affected task.
+    pub task_id: String,
+    /// Name of the affected task.
+    pub task_name: String,
+    /// The check-in session ID.
+    pub sign_id: String,
+    /// Distance between the configured and the required location in meters.
+    pub distance_m: f64,
+    /// Allowed distance in meters.
+    pub radius_m: f64,
+    /// Required latitude, in the site's coordinate system.
+    pub required_lat: f64,
+    /// Required longitude, in the site's coordinate system.
+    pub required_lng: f64,
+}
+
+/// Kinds of interstitial pages that stop automated requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockKind {
+    /// A captcha or slider verification.
+    Captcha,
+    /// A web application firewall challenge or block page.
+    Waf,
+    /// A rate-limit page.
+    RateLimit,
+}
+
+impl BlockKind {
+    /// The localized name of the page kind.
+    fn label(self) -> &'static str {
+        match self {
+            BlockKind::Captcha => t(Msg::BlockCaptcha),
+            BlockKind::Waf => t(Msg::BlockWaf),
+            BlockKind::RateLimit => t(Msg::BlockRateLimit),
+        }
+    }
+}
+
+/// An open check-in session listed on the punch list.
+struct OpenCard {
+    /// The location the card requires, if it lists one.
+    required: Option<SiteLocation>,
+    /// The session title shown on the card, if any.
+    title: Option<String>,
+    /// Whether the session is password-protected.
+    needs_password: bool,
+}
+
+/// Per-run connection state for one task.
+struct SiteSession {
+    /// The site base URL.
+    base: String,
+    /// The HTTP headers to send, without the cookie.
+    headers: HeaderMap,
+    /// The request timeout.
+    timeout: Duration,
+    /// Client bound to the account's cookie jar.
+    client: Client,
+    /// Cookies of the task's account, updated from `Set-Cookie` responses.
+    jar: Arc<Jar>,
+    /// HAR entries recorded during the run in debug mode.
+    har: Mutex<Vec<Value>>,
+    /// Throttling key of the account: the task's account ID, or a digest of its cookie
+    /// for tasks with a cookie of their own.
+    account: String,
+    /// Time spent in each phase of the run.
+    timings: Mutex<RunTimings>,
+    /// How the punch listing deviated from the site profile, if it did.
+    layout_changes: Mutex<Vec<String>>,
+}
+
+/// Whether the site still accepts a session cookie.
+#[derive(Debug, Serialize, Clone)]
+pub struct SessionStatus {
+    /// Whether the site accepted the cookie.
+    pub valid: bool,
+    /// The logged-in student's name, if the page shows it.
+    pub student_name: Option<String>,
+}
 
 /// Executes check-in tasks.
 ///
@@ -22,8 +174,55 @@ pub struct TaskExecutor {
     client: Client,
     /// The base URL of the target website.
     base_url: String,
+    /// URL path templates for the target website.
+    endpoints: EndpointConfig,
     /// WeCom configuration for sending notifications.
     wecom: WeComConfig,
+    /// Whether to log request/response details and keep raw HTML artifacts.
+    debug: bool,
+    /// Directory where raw HTML artifacts are stored in debug mode.
+    artifact_dir: PathBuf,
+    /// How long a run waits for the network to come back before trying anyway.
+    network_wait: Duration,
+    /// The coordinate system the site expects.
+    site_coord_system: CoordSystem,
+    /// Optional GPS readings the sign form accepts.
+    device: DeviceConfig,
+    /// Shape of the coordinate drift.
+    drift: DriftConfig,
+    /// Whether tasks without a location use an approximate IP-based one.
+    ip_location_fallback: bool,
+    /// First day of the term, used by odd/even week anchor rules.
+    term_start: Option<NaiveDate>,
+    /// Geocoding settings used to fill in the sign address.
+    geocoding: GeocodingConfig,
+    /// Default timeout for a whole request, overridable per task.
+    request_timeout: Duration,
+    /// Browser headers sent by default.
+    header_profile: HeaderProfile,
+    /// Connect and request timeouts used to build clients.
+    timeouts: TimeoutConfig,
+    /// Request rate ceiling per account.
+    throttle: ThrottleConfig,
+    /// Navigation performed before fetching the punch list.
+    warm_up: WarmUpConfig,
+    /// Evidence kept for successful signs.
+    receipts: ReceiptConfig,
+    /// Cookie jar and client of each account, keyed by account ID, or by the cookie's
+    /// throttling key for tasks without a linked account.
+    sessions: Mutex<HashMap<String, (Arc<Jar>, Client)>>,
+    /// Until when each task pauses after an anti-bot page, keyed by task ID.
+    blocked_until: Mutex<HashMap<String, Instant>>,
+    /// The layout changes last reported for each task, keyed by task ID.
+    layout_alerts: Mutex<HashMap<String, Vec<String>>>,
+    /// Base URL that worked for each task, keyed by task ID.
+    site_bases: Mutex<HashMap<String, String>>,
+    /// Cached WeCom access token and when it expires.
+    wecom_token: Mutex<Option<(String, Instant)>>,
+    /// Handle used to emit events to the frontend, if attached.
+    app_handle: Option<AppHandle>,
+    /// Clock the pauses of a run wait on.
+    clock: Arc<dyn Clock>,
 }
 
 impl TaskExecutor {
@@ -31,16 +230,436 @@ impl TaskExecutor {
     ///
     /// # Arguments
     ///
-    /// * `wecom` - The WeCom configuration.
+    /// * `global` - The global configuration (endpoints, WeCom and debug settings).
+    /// * `artifact_dir` - Directory where raw HTML artifacts are stored in debug mode.
     ///
     /// # Returns
     ///
     /// * `Self` - A new instance of `TaskExecutor`.
-    pub fn new(wecom: WeComConfig) -> Self {
+    pub fn new(global: &GlobalConfig, artifact_dir: PathBuf) -> Self {
         Self {
-            client: Client::builder().user_agent(UA).build().unwrap(),
-            base_url: "http://k8n.cn".to_string(),
-            wecom,
+            client: build_client(&global.timeouts, None),
+            base_url: global.endpoints.base_url.trim_end_matches('/').to_string(),
+            endpoints: global.endpoints.clone(),
+            wecom: global.wecom.clone(),
+            debug: global.debug,
+            artifact_dir,
+            network_wait: Duration::from_secs(global.network_wait_minutes * 60),
+            site_coord_system: global.site_coord_system,
+            device: global.device.clone(),
+            drift: global.drift,
+            ip_location_fallback: global.ip_location_fallback,
+            term_start: global.term_start,
+            geocoding: global.geocoding.clone(),
+            request_timeout: Duration::from_secs(global.timeouts.request_secs),
+            header_profile: global.header_profile,
+            timeouts: global.timeouts,
+            throttle: global.throttle,
+            warm_up: global.warm_up,
+            receipts: global.receipts.clone(),
+            sessions: Mutex::new(HashMap::new()),
+            blocked_until: Mutex::new(HashMap::new()),
+            layout_alerts: Mutex::new(HashMap::new()),
+            site_bases: Mutex::new(HashMap::new()),
+            wecom_token: Mutex::new(None),
+            app_handle: None,
+            clock: devtime::clock(),
+        }
+    }
+
+    /// Replaces the clock the pauses of a run wait on, such as the jitter between signs.
+    ///
+    /// # Arguments
+    ///
+    /// * `clock` - The clock.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The executor waiting on `clock`.
+    #[cfg_attr(not(feature = "mock-site"), allow(dead_code))]
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Attaches the app handle so the executor can emit events to the frontend.
+    ///
+    /// # Arguments
+    ///
+    /// * `app_handle` - The Tauri application handle.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The executor with events enabled.
+    pub fn with_app_handle(mut self, app_handle: AppHandle) -> Self {
+        self.app_handle = Some(app_handle);
+        self
+    }
+
+    /// Checks whether the check-in site can be reached.
+    ///
+    /// Resolves the site's host and opens a TCP connection with a short timeout.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - `true` if a connection could be established.
+    pub fn is_online(&self) -> bool {
+        let Ok(url) = reqwest::Url::parse(&self.base_url) else {
+            return false;
+        };
+        let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+            return false;
+        };
+        let Ok(addrs) = (host, port).to_socket_addrs() else {
+            return false;
+        };
+        addrs
+            .into_iter()
+            .any(|addr| TcpStream::connect_timeout(&addr, NETWORK_PROBE_TIMEOUT).is_ok())
+    }
+
+    /// Blocks until the site is reachable or the configured wait window has passed.
+    ///
+    /// The site is probed again every minute while the run is parked.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Duration, Duration>` - How long the run waited: `Ok` if the site is
+    ///   reachable, `Err` if the window expired.
+    pub fn wait_for_network(&self) -> Result<Duration, Duration> {
+        let started = Instant::now();
+        let deadline = started + self.network_wait;
+        if self.is_online() {
+            return Ok(Duration::ZERO);
+        }
+        warn!(
+            "Network unavailable, deferring run for up to {} minute(s)",
+            self.network_wait.as_secs() / 60
+        );
+        while Instant::now() < deadline {
+            thread::sleep(NETWORK_RETRY_INTERVAL.min(deadline - Instant::now()));
+            if self.is_online() {
+                info!("Network is back, resuming run");
+                return Ok(started.elapsed());
+            }
+        }
+        Err(started.elapsed())
+    }
+
+    /// Builds the record of a run skipped because the network did not come back in time.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - The task that was due.
+    /// * `waited` - How long the run was deferred.
+    ///
+    /// # Returns
+    ///
+    /// * `RunRecord` - A record with the `Offline` outcome.
+    pub fn offline_record(&self, task: &Task, waited: Duration) -> RunRecord {
+        let finished_at = Local::now();
+        let message = format!(
+            "Network unavailable for {} min; run skipped",
+            waited.as_secs().div_ceil(60)
+        );
+        warn!("[{}] {}", task.name, message);
+        let started_at = finished_at - chrono::Duration::from_std(waited).unwrap_or_default();
+        RunRecord {
+            deferred_secs: Some(waited.as_secs()),
+            ..aborted_record(task, started_at, RunOutcome::Offline, message)
+        }
+    }
+
+    /// Records a run that panicked and notifies the user.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - The task that was run.
+    /// * `started_at` - When the run started.
+    /// * `panic` - The panic message.
+    ///
+    /// # Returns
+    ///
+    /// * `RunRecord` - A record with the `Error` outcome.
+    pub fn panicked_record(
+        &self,
+        task: &Task,
+        started_at: DateTime<Local>,
+        panic: &str,
+    ) -> RunRecord {
+        let message = format!("Run crashed: {}", panic);
+        error!("[{}] {}", task.name, message);
+        let _ = self.send_wecom_notification(
+            &tf(Msg::RunCrashedTitle, &[&task.name]),
+            &tf(Msg::RunCrashedBody, &[&task.name, &panic]),
+        );
+        aborted_record(task, started_at, RunOutcome::Error, message)
+    }
+
+    /// Logs a request/response exchange, records it for the run's HAR file and saves the
+    /// body as an artifact in debug mode.
+    ///
+    /// The Cookie header is redacted and the logged body is truncated. Artifacts are
+    /// written to `<artifact_dir>/<date>/<time>_<label>.html`.
+    ///
+    /// # Arguments
+    ///
+    /// * `session` - The session the request was sent in.
+    /// * `exchange` - The request and response.
+    /// * `label` - Short name used in the artifact file name.
+    fn trace_exchange(&self, session: &SiteSession, exchange: &Exchange, label: &str) {
+        if !self.debug {
+            return;
+        }
+
+        let redacted: Vec<String> = exchange
+            .request_headers
+            .iter()
+            .map(|(name, value)| {
+                if name == COOKIE {
+                    format!("{}: <redacted>", name)
+                } else {
+                    format!("{}: {}", name, value.to_str().unwrap_or("<binary>"))
+                }
+            })
+            .collect();
+        let truncated: String = exchange.body.chars().take(DEBUG_BODY_LIMIT).collect();
+        debug!(
+            "{} {} -> {}\nRequest headers: {}\nResponse body ({} bytes): {}",
+            exchange.method,
+            exchange.url,
+            exchange.status,
+            redacted.join("; "),
+            exchange.body.len(),
+            truncated
+        );
+
+        session.har.lock().unwrap().push(har::entry(exchange));
+        self.save_artifact(label, exchange.body);
+    }
+
+    /// Writes the HAR entries recorded during a run to the task's HAR directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - The task that was run.
+    /// * `session` - The session holding the recorded entries.
+    fn save_har(&self, task: &Task, session: &SiteSession) {
+        let entries = std::mem::take(&mut *session.har.lock().unwrap());
+        if entries.is_empty() {
+            return;
+        }
+        match har::write_har(&har::har_dir(&self.artifact_dir), &task.id, entries) {
+            Ok(path) => debug!("[{}] HAR written to {}", task.name, path.display()),
+            Err(e) => warn!("[{}] Failed to write HAR: {}", task.name, e),
+        }
+    }
+
+    /// Saves a response body to `<artifact_dir>/<date>/<time>_<label>.html`.
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - Short name used in the file name.
+    /// * `body` - The response body.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<PathBuf>` - The path of the saved file, or `None` if it could not be written.
+    fn save_artifact(&self, label: &str, body: &str) -> Option<PathBuf> {
+        let now = Local::now();
+        let dir = self.artifact_dir.join(now.format("%Y-%m-%d").to_string());
+        let path = dir.join(format!("{}_{}.html", now.format("%H%M%S%3f"), label));
+        match fs::create_dir_all(&dir).and_then(|_| fs::write(&path, body)) {
+            Ok(()) => Some(path),
+            Err(e) => {
+                warn!("Failed to save artifact {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Fails with `SITE_BLOCKED` if a response is a captcha, WAF or rate-limit page.
+    ///
+    /// The page is always saved as an artifact so the user can see what the site showed.
+    ///
+    /// # Arguments
+    ///
+    /// * `status` - The response status code.
+    /// * `body` - The response body.
+    /// * `label` - Short name used in the artifact file name.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - Ok for a regular page, or an error starting with
+    ///   `SITE_BLOCKED` that names the page kind and the artifact path.
+    fn check_blocked(&self, status: u16, body: &str, label: &str) -> Result<(), String> {
+        let Some(kind) = detect_block(status, body) else {
+            return Ok(());
+        };
+        let artifact = self
+            .save_artifact(label, body)
+            .map(|path| path.display().to_string())
+            .unwrap_or_default();
+        Err(format!("{}: {} ({})", SITE_BLOCKED, kind.label(), artifact))
+    }
+
+    /// Alerts the user when the punch listing of a task stops matching the site profile.
+    ///
+    /// Each distinct set of changes is reported once per task; a listing that matches the
+    /// profile again clears the report.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - The task whose listing was read.
+    /// * `session` - The run's connection state, holding the changes found.
+    fn report_layout_changes(&self, task: &Task, session: &SiteSession) {
+        let changes = session.layout_changes.lock().unwrap().clone();
+        let mut reported = self.layout_alerts.lock().unwrap();
+        if changes.is_empty() {
+            reported.remove(&task.id);
+            return;
+        }
+        if reported.get(&task.id) == Some(&changes) {
+            return;
+        }
+        reported.insert(task.id.clone(), changes.clone());
+        drop(reported);
+        let details = changes.join("; ");
+        warn!("[{}] {}: {}", task.name, SITE_LAYOUT_CHANGED, details);
+        events::publish(
+            "layout_changed",
+            json!({
+                "task_id": task.id,
+                "profile_version": profile::current().version,
+                "changes": changes,
+            }),
+        );
+        let _ = self.send_wecom_notification(
+            &tf(Msg::LayoutChangedTitle, &[&task.name]),
+            &tf(Msg::LayoutChangedBody, &[&task.name, &details]),
+        );
+    }
+
+    /// Returns how long a task still pauses after an anti-bot page, if it does.
+    fn block_remaining(&self, task: &Task) -> Option<Duration> {
+        let blocked_until = self.blocked_until.lock().unwrap();
+        blocked_until
+            .get(&task.id)
+            .and_then(|until| until.checked_duration_since(Instant::now()))
+    }
+
+    /// Pauses a task after an anti-bot page and asks the user to step in.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - The blocked task.
+    /// * `error` - The `SITE_BLOCKED` error, including the page kind and artifact path.
+    fn handle_block(&self, task: &Task, error: &str) {
+        warn!("[{}] {}", task.name, error);
+        self.blocked_until
+            .lock()
+            .unwrap()
+            .insert(task.id.clone(), Instant::now() + BLOCK_BACKOFF);
+        let details = error
+            .strip_prefix(SITE_BLOCKED)
+            .unwrap_or(error)
+            .trim_start_matches(':')
+            .trim();
+        let _ = self.send_wecom_notification(
+            &tf(Msg::SiteBlockedTitle, &[&task.name]),
+            &tf(
+                Msg::SiteBlockedBody,
+                &[&task.name, &details, &(BLOCK_BACKOFF.as_secs() / 60)],
+            ),
+        );
+    }
+
+    /// Returns the base URL to reach the site with for a task.
+    ///
+    /// The scheme that worked last time is reused. Otherwise the configured base URL is
+    /// probed: if the site redirects HTTPS to plain HTTP, or HTTPS cannot be reached and
+    /// the fallback is allowed, the plain HTTP URL is used and a warning is logged, since
+    /// the session cookie is then sent unencrypted.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - The task being run.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - The base URL, without a trailing slash.
+    fn site_base(&self, task: &Task) -> String {
+        if let Some(base) = self.site_bases.lock().unwrap().get(&task.id) {
+            return base.clone();
+        }
+        let Some(plain) = self.base_url.strip_prefix("https://") else {
+            return self.base_url.clone();
+        };
+        let http_base = format!("http://{}", plain);
+
+        let base = match httplog::send(
+            self.client
+                .get(&self.base_url)
+                .timeout(NETWORK_PROBE_TIMEOUT * 2),
+        ) {
+            Ok(resp) if resp.url().scheme() == "http" => {
+                warn!(
+                    "[{}] {} redirects to plain HTTP; the cookie is sent unencrypted",
+                    task.name, self.base_url
+                );
+                http_base
+            }
+            Ok(_) => self.base_url.clone(),
+            Err(e) if self.endpoints.allow_http_fallback && (e.is_connect() || e.is_timeout()) => {
+                match httplog::send(
+                    self.client
+                        .get(&http_base)
+                        .timeout(NETWORK_PROBE_TIMEOUT * 2),
+                ) {
+                    Ok(_) => {
+                        warn!(
+                            "[{}] HTTPS unavailable ({}), falling back to {}; the cookie is sent unencrypted",
+                            task.name, e, http_base
+                        );
+                        http_base
+                    }
+                    // Neither works right now; try again on the next run.
+                    Err(_) => return self.base_url.clone(),
+                }
+            }
+            Err(e) => {
+                debug!("[{}] HTTPS probe failed: {}", task.name, e);
+                return self.base_url.clone();
+            }
+        };
+        info!("[{}] Using {}", task.name, base);
+        self.site_bases
+            .lock()
+            .unwrap()
+            .insert(task.id.clone(), base.clone());
+        base
+    }
+
+    /// Renders an endpoint template into a full URL.
+    ///
+    /// # Arguments
+    ///
+    /// * `base` - The scheme and host to resolve relative templates against.
+    /// * `template` - The path template containing `{class}` and `{id}` placeholders.
+    /// * `class_id` - The class ID.
+    /// * `sign_id` - The check-in session ID (empty if not applicable).
+    ///
+    /// # Returns
+    ///
+    /// * `String` - The resolved URL.
+    fn endpoint_url(&self, base: &str, template: &str, class_id: &str, sign_id: &str) -> String {
+        let path = template
+            .replace("{class}", class_id)
+            .replace("{id}", sign_id);
+        if path.starts_with("http") {
+            path
+        } else {
+            format!("{}/{}", base, path.trim_start_matches('/'))
         }
     }
 
@@ -53,136 +672,849 @@ impl TaskExecutor {
     /// # Arguments
     ///
     /// * `task` - The task to execute.
-    pub fn execute(&self, task: &Task) {
+    ///
+    /// # Returns
+    ///
+    /// * `Option<RunRecord>` - The record of the run, or `None` if the task is disabled.
+    pub fn execute(&self, task: &Task) -> Option<RunRecord> {
         if !task.enable {
-            return;
+            return None;
         }
 
+        let _log_ctx = with_context([("task_id", task.id.clone().into())]);
         info!(">>> Starting task: {} <<<", task.name);
+        let resolved;
+        let task = match self.resolve_location(task) {
+            Some(location) => {
+                resolved = Task {
+                    location,
+                    ..task.clone()
+                };
+                &resolved
+            }
+            None => task,
+        };
+        let started_at = Local::now();
+        let run_id = uuid::Uuid::new_v4().to_string();
+        let session = self.open_session(task);
+        let scheme = Url::parse(&session.base)
+            .ok()
+            .map(|url| url.scheme().to_string());
+        let finish = |outcome: RunOutcome, message: String, signs: Vec<SignRecord>| RunRecord {
+            id: run_id.clone(),
+            task_id: task.id.clone(),
+            task_name: task.name.clone(),
+            class_id: task.class_id.clone(),
+            started_at,
+            finished_at: Local::now(),
+            outcome,
+            message,
+            signs,
+            scheme: scheme.clone(),
+            deferred_secs: None,
+            timings: *session.timings.lock().unwrap(),
+            receipt: false,
+        };
+        let finish = |outcome: RunOutcome, message: String, signs: Vec<SignRecord>| {
+            self.save_har(task, &session);
+            finish(outcome, message, signs)
+        };
+
+        if let Err(e) = validate_coordinates(&task.location.lat, &task.location.lng) {
+            error!("Invalid location for {}: {}", task.name, e);
+            return Some(finish(RunOutcome::Error, e, vec![]));
+        }
+
+        if let Some(remaining) = self.block_remaining(task) {
+            let message = format!(
+                "{}; paused for another {} min",
+                SITE_BLOCKED,
+                remaining.as_secs().div_ceil(60)
+            );
+            warn!("[{}] {}", task.name, message);
+            return Some(finish(RunOutcome::Blocked, message, vec![]));
+        }
 
-        let headers = self.build_headers(&task.cookie, &task.class_id);
+        if task.warm_up.unwrap_or(self.warm_up.enable) {
+            let warm_up_started = Instant::now();
+            if let Err(e) = self.warm_up(&session, &task.class_id) {
+                warn!("[{}] Warm-up navigation failed: {}", task.name, e);
+            }
+            session.timings.lock().unwrap().warm_up_ms =
+                Some(warm_up_started.elapsed().as_millis() as u64);
+        }
 
         // Fetch active tasks
-        let active_ids = match self.get_active_tasks(&headers, &task.class_id) {
+        let active_ids = self.get_active_tasks(&session, &task.class_id);
+        self.report_layout_changes(task, &session);
+        let active_ids = match active_ids {
             Ok(ids) => ids,
             Err(e) => {
                 error!("Failed to get active tasks for {}: {}", task.name, e);
-                return;
+                let outcome = if e == COOKIE_EXPIRED {
+                    RunOutcome::CookieExpired
+                } else if e.starts_with(SITE_BLOCKED) {
+                    self.handle_block(task, &e);
+                    RunOutcome::Blocked
+                } else {
+                    RunOutcome::Error
+                };
+                return Some(finish(outcome, e, vec![]));
             }
         };
+        let discovered_at = Instant::now();
+
+        self.persist_rotated_cookie(task, &session);
 
         if active_ids.is_empty() {
             info!("[{}] No active check-in tasks.", task.name);
-            return;
+            return Some(finish(
+                RunOutcome::NoSession,
+                "No active check-in tasks".to_string(),
+                vec![],
+            ));
         }
 
-        for sign_id in active_ids {
-            thread::sleep(Duration::from_secs_f64(rand::random::<f64>() * 4.0 + 1.0));
+        events::publish(
+            "sessions_found",
+            json!({
+                "task_id": task.id,
+                "sign_ids": active_ids.keys().collect::<Vec<_>>(),
+            }),
+        );
+        let parser = profile::current();
+        let mut signs = Vec::new();
+        let mut receipts = Vec::new();
+        let mut blocked = false;
+        for (sign_id, card) in active_ids {
+            let required = card.required;
+            self.clock
+                .pause(Duration::from_secs_f64(rand::random::<f64>() * 4.0 + 1.0));
 
-            let (lat, lng) = self.random_coordinate(&task.location.lat, &task.location.lng);
+            let anchor = required.filter(|_| task.use_site_location);
+            if let (Some(site), None) = (required, anchor) {
+                self.check_distance(task, &sign_id, site);
+            }
+            if let Some(site) = anchor {
+                info!(
+                    "[{}] Using location from check-in card: {},{} (radius {:?} m)",
+                    task.name, site.lat, site.lng, site.radius_m
+                );
+            }
+            let (lat, lng) = self.random_coordinate(task, anchor);
 
-            let result = self.perform_sign(&headers, &task.class_id, &sign_id, &lat, &lng);
-            let msg = match &result {
-                Ok(msg) => msg.clone(),
-                Err(e) => e.clone(),
+            let mut fix = make_fix(&self.device, lat.clone(), lng.clone());
+            if self.geocoding.fill_gps_addr {
+                fix.address = self.lookup_address(&lat, &lng);
+            }
+            let sign_started = Instant::now();
+            let password = task.password.as_deref().unwrap_or_default();
+            let result = if card.needs_password
+                && password.is_empty()
+                && !task.form_params.contains_key("pwd")
+            {
+                warn!(
+                    "[{}] Session {} is password-protected but the task has no password",
+                    task.name, sign_id
+                );
+                Err(t(Msg::PasswordMissing).to_string())
+            } else {
+                self.perform_sign(
+                    &session,
+                    &task.class_id,
+                    &sign_id,
+                    &fix,
+                    password,
+                    &task.form_params,
+                )
+            };
+            let request_ms = Some(sign_started.elapsed().as_millis() as u64);
+            let latency_ms = discovered_at.elapsed().as_millis() as u64;
+            let (msg, body) = match result {
+                Ok((msg, body)) => (msg, Some(body)),
+                Err(e) => (e, None),
             };
 
             let log_msg = format!(
                 "Task [{}] Result: {} (Loc: {},{})",
                 task.name, msg, lat, lng
             );
-            info!("{}", log_msg);
-
-            let success = result.is_ok() && (msg.contains("成功") || msg.contains("Success"));
+            let notify_msg = tf(Msg::SignResultBody, &[&task.name, &msg, &lat, &lng]);
+            let success = body.is_some() && parser.is_success(&msg);
+            events::publish(
+                "sign_attempted",
+                json!({
+                    "task_id": task.id,
+                    "sign_id": sign_id,
+                    "success": success,
+                    "message": msg,
+                }),
+            );
+            if !success && msg.starts_with(SITE_BLOCKED) {
+                self.handle_block(task, &msg);
+                signs.push(SignRecord {
+                    sign_id,
+                    success,
+                    message: msg,
+                    lat,
+                    lng,
+                    latency_ms,
+                    request_ms,
+                });
+                blocked = true;
+                break;
+            }
+            {
+                let _sign_ctx = with_context([
+                    ("sign_id", sign_id.clone().into()),
+                    ("duration_ms", latency_ms.into()),
+                    (
+                        "outcome",
+                        if success { "success" } else { "failure" }.into(),
+                    ),
+                ]);
+                info!("{}", log_msg);
+            }
 
-            if success || msg.contains("出错") || msg.contains("Error") {
+            let notify_started = Instant::now();
+            if success || parser.is_error(&msg) {
                 let _ = self
-                    .send_wecom_notification(&format!("{} Check-in Result", task.name), &log_msg);
+                    .send_wecom_notification(&tf(Msg::SignResultTitle, &[&task.name]), &notify_msg);
             } else {
                 let _ = self
-                    .send_wecom_notification(&format!("{} Check-in Failed", task.name), &log_msg);
+                    .send_wecom_notification(&tf(Msg::SignFailedTitle, &[&task.name]), &notify_msg);
+            }
+            if self.wecom.enable {
+                let mut timings = session.timings.lock().unwrap();
+                timings.notify_ms = Some(
+                    timings.notify_ms.unwrap_or(0) + notify_started.elapsed().as_millis() as u64,
+                );
+            }
+
+            if let Some(body) = body.filter(|_| success && self.receipts.enable) {
+                receipts.push(self.make_receipt(&run_id, task, &sign_id, card.title, &body, &fix));
+            }
+
+            signs.push(SignRecord {
+                sign_id,
+                success,
+                message: msg,
+                lat,
+                lng,
+                latency_ms,
+                request_ms,
+            });
+        }
+
+        self.persist_rotated_cookie(task, &session);
+
+        let failed = signs.iter().filter(|s| !s.success).count();
+        let (outcome, message) = if blocked {
+            (RunOutcome::Blocked, SITE_BLOCKED.to_string())
+        } else if failed == 0 {
+            (
+                RunOutcome::Success,
+                format!("Signed {} session(s)", signs.len()),
+            )
+        } else {
+            (
+                RunOutcome::Failure,
+                format!("{} of {} sign(s) failed", failed, signs.len()),
+            )
+        };
+        let mut record = finish(outcome, message, signs);
+        if let (Some(app_handle), false) = (&self.app_handle, receipts.is_empty()) {
+            match receipt::save(app_handle, &run_id, &receipts) {
+                Ok(()) => record.receipt = true,
+                Err(e) => warn!("[{}] Failed to save receipt: {}", task.name, e),
+            }
+        }
+        let _run_ctx = with_context([
+            (
+                "duration_ms",
+                (record.finished_at - record.started_at)
+                    .num_milliseconds()
+                    .into(),
+            ),
+            ("outcome", serde_json::to_value(record.outcome).unwrap()),
+        ]);
+        info!("<<< Finished task: {} ({}) >>>", task.name, record.message);
+        Some(record)
+    }
+
+    /// Builds the receipt of a successful sign, saving the response page if snapshots are on.
+    fn make_receipt(
+        &self,
+        run_id: &str,
+        task: &Task,
+        sign_id: &str,
+        title: Option<String>,
+        body: &str,
+        fix: &Fix,
+    ) -> Receipt {
+        let text = Html::parse_document(body)
+            .root_element()
+            .text()
+            .collect::<String>();
+        let snapshot = match (&self.app_handle, self.receipts.save_snapshot) {
+            (Some(app_handle), true) => receipt::save_snapshot(app_handle, run_id, sign_id, body)
+                .map_err(|e| warn!("[{}] Failed to save sign snapshot: {}", task.name, e))
+                .ok(),
+            _ => None,
+        };
+        Receipt {
+            run_id: run_id.to_string(),
+            task_id: task.id.clone(),
+            task_name: task.name.clone(),
+            class_id: task.class_id.clone(),
+            sign_id: sign_id.to_string(),
+            session_title: title,
+            response_snippet: receipt::snippet(&text),
+            signed_at: Local::now(),
+            lat: fix.lat.clone(),
+            lng: fix.lng.clone(),
+            snapshot,
+        }
+    }
+
+    /// Determines the location to sign at today when it differs from `task.location`.
+    ///
+    /// The first anchor whose rotation rule matches today wins. A task without any location
+    /// uses a low-accuracy IP location if the fallback is enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - The task being run.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Location>` - The location to use, or `None` to keep the configured one.
+    fn resolve_location(&self, task: &Task) -> Option<Location> {
+        let today = Local::now().date_naive();
+        if let Some(anchor) = select_anchor(&task.anchors, today, self.term_start) {
+            info!(
+                "[{}] Using anchor {} (week {})",
+                task.name,
+                anchor.name,
+                teaching_week(today, self.term_start)
+            );
+            return Some(anchor.location.clone());
+        }
+
+        if !self.ip_location_fallback || !task.location.is_unset() {
+            return None;
+        }
+        match locate_by_ip() {
+            Ok(ip) => {
+                warn!(
+                    "[{}] No location configured, using low-accuracy IP location {},{} ({})",
+                    task.name,
+                    ip.lat,
+                    ip.lng,
+                    ip.place.as_deref().unwrap_or("unknown")
+                );
+                Some(Location {
+                    lat: ip.lat,
+                    lng: ip.lng,
+                    acc: format!("{:.0}", ip.accuracy_m),
+                    coord_system: CoordSystem::Wgs84,
+                })
+            }
+            Err(e) => {
+                error!("[{}] IP location lookup failed: {}", task.name, e);
+                None
             }
         }
     }
 
+    /// Looks up the street address of a sign position through the geocoding cache.
+    ///
+    /// # Arguments
+    ///
+    /// * `lat` - The latitude, in the site's coordinate system.
+    /// * `lng` - The longitude, in the site's coordinate system.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<String>` - The address, or `None` if the lookup failed.
+    fn lookup_address(&self, lat: &str, lng: &str) -> Option<String> {
+        let (lat, lng) = (lat.parse().ok()?, lng.parse().ok()?);
+        reverse_geocode(&self.geocoding, lat, lng, self.site_coord_system)
+            .map_err(|e| warn!("Reverse geocoding failed: {}", e))
+            .ok()
+    }
+
+    /// Checks whether the site still accepts a task's cookie.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - The task whose session is checked.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - Ok if the session is valid, or an error message
+    ///   (`COOKIE_EXPIRED` if the site asked for a login).
+    pub fn check_session(&self, task: &Task) -> Result<(), String> {
+        let session = self.open_session(task);
+        let result = self.get_active_tasks(&session, &task.class_id).map(|_| ());
+        self.persist_rotated_cookie(task, &session);
+        result
+    }
+
+    /// Checks a task's cookie with a single request for its course page.
+    ///
+    /// Lighter than `check_session`, which reads and parses the punch listing.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - The task whose course and cookie are used.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<SessionStatus, String>` - Whether the site accepted the cookie and the
+    ///   student's name, or an error message if the site could not be reached.
+    pub fn validate_session(&self, task: &Task) -> Result<SessionStatus, String> {
+        let session = self.open_session(task);
+        let url = self.endpoint_url(&session.base, &self.endpoints.course, &task.class_id, "");
+        let request = session
+            .client
+            .get(&url)
+            .headers(session.headers.clone())
+            .timeout(session.timeout);
+        self.throttle(&session);
+        let resp = retry::send(&RetryPolicy::IDEMPOTENT, request, || {
+            self.throttle(&session)
+        })
+        .map_err(|e| e.to_string())?;
+        let final_path = resp.url().path().to_string();
+        let status = resp.status().as_u16();
+        let text = resp.text().map_err(|e| e.to_string())?;
+        httplog::log_body(&url, &text);
+        self.check_blocked(status, &text, &format!("blocked_course_{}", task.class_id))?;
+        let parser = profile::current();
+        if parser.is_login_page(&final_path, &text) {
+            return Ok(SessionStatus {
+                valid: false,
+                student_name: None,
+            });
+        }
+        self.persist_rotated_cookie(task, &session);
+        Ok(SessionStatus {
+            valid: true,
+            student_name: parser.student_name(&text),
+        })
+    }
+
+    /// Reads the course's punch history from the site.
+    ///
+    /// Pages are read until one shows no card that was not on an earlier page, which also
+    /// stops after one page if the site ignores the page number. Cards that are still open
+    /// or have no date are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - The task whose course and cookie are used.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<SiteAttendance>, String>` - The past sessions, or an error message
+    ///   (`COOKIE_EXPIRED` if the site asked for a login).
+    pub fn fetch_attendance(&self, task: &Task) -> Result<Vec<SiteAttendance>, String> {
+        if let Some(remaining) = self.block_remaining(task) {
+            return Err(format!(
+                "{}; paused for another {} min",
+                SITE_BLOCKED,
+                remaining.as_secs().div_ceil(60)
+            ));
+        }
+        let session = self.open_session(task);
+        let result = self.read_punch_history(&session, task);
+        self.persist_rotated_cookie(task, &session);
+        if let Err(e) = &result {
+            if e.starts_with(SITE_BLOCKED) {
+                self.handle_block(task, e);
+            }
+        }
+        result
+    }
+
+    /// Reads the punch history pages of a task's course.
+    fn read_punch_history(
+        &self,
+        session: &SiteSession,
+        task: &Task,
+    ) -> Result<Vec<SiteAttendance>, String> {
+        let parser = profile::current();
+        let synced_at = Local::now();
+        let mut seen = HashSet::new();
+        let mut records = Vec::new();
+        for page in 1..=MAX_HISTORY_PAGES {
+            let template = self
+                .endpoints
+                .punch_history
+                .replace("{page}", &page.to_string());
+            let url = self.endpoint_url(&session.base, &template, &task.class_id, "");
+            let request = session
+                .client
+                .get(&url)
+                .headers(session.headers.clone())
+                .timeout(session.timeout);
+            self.throttle(session);
+            let started_at = Local::now();
+            let sent = Instant::now();
+            let resp = retry::send(&RetryPolicy::IDEMPOTENT, request, || self.throttle(session))
+                .map_err(|e| e.to_string())?;
+            let final_path = resp.url().path().to_string();
+            let status = resp.status().as_u16();
+            let response_headers = resp.headers().clone();
+            let text = resp.text().map_err(|e| e.to_string())?;
+            let exchange = Exchange {
+                method: "GET",
+                url: &url,
+                request_headers: &session.headers,
+                form: None,
+                status,
+                response_headers: &response_headers,
+                body: &text,
+                started_at,
+                elapsed: sent.elapsed(),
+            };
+            let label = format!("history_{}_{}", task.class_id, page);
+            self.trace_exchange(session, &exchange, &label);
+            self.check_blocked(status, &text, &format!("blocked_{}", label))?;
+            if parser.is_login_page(&final_path, &text) {
+                return Err(COOKIE_EXPIRED.to_string());
+            }
+
+            let document = Html::parse_document(&text);
+            let mut new_cards = 0;
+            for card in document.select(parser.card_selector()) {
+                let card_html = card.html();
+                if !seen.insert(card_html.clone()) {
+                    continue;
+                }
+                new_cards += 1;
+                let Some((date, status, signed_by)) = parser.attendance(&card_html) else {
+                    continue;
+                };
+                records.push(SiteAttendance {
+                    class_id: task.class_id.clone(),
+                    sign_id: parser.sign_ids(&card_html).into_iter().next(),
+                    date,
+                    status,
+                    signed_by,
+                    synced_at,
+                });
+            }
+            if new_cards == 0 {
+                break;
+            }
+        }
+        info!(
+            "[{}] Read {} past sessions from the punch history",
+            task.name,
+            records.len()
+        );
+        Ok(records)
+    }
+
+    /// Prepares the base URL, headers and cookie jar for a task's requests.
+    ///
+    /// Each account keeps its own jar for the executor's lifetime, shared by all of its
+    /// tasks, so cookies the site sets (session rotation, anti-bot cookies) are sent with
+    /// later requests. The configured cookie is added to the jar on every run, overriding
+    /// stale values of the same name.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - The task being run.
+    ///
+    /// # Returns
+    ///
+    /// * `SiteSession` - The connection state for the run.
+    fn open_session(&self, task: &Task) -> SiteSession {
+        let base = self.site_base(task);
+        let profile = task.header_profile.unwrap_or(self.header_profile);
+        let headers = self.build_headers(&base, &task.class_id, profile, &task.headers);
+        let account = task
+            .account_id
+            .clone()
+            .unwrap_or_else(|| throttle::account_key(&task.cookie));
+        let (jar, client) = self
+            .sessions
+            .lock()
+            .unwrap()
+            .entry(account.clone())
+            .or_insert_with(|| {
+                let jar = Arc::new(Jar::default());
+                (jar.clone(), build_client(&self.timeouts, Some(jar)))
+            })
+            .clone();
+        if let Ok(url) = Url::parse(&base) {
+            for (name, value) in parse_cookie(&task.cookie) {
+                jar.add_cookie_str(&format!("{}={}; Path=/", name, value), &url);
+            }
+        }
+        SiteSession {
+            base,
+            headers,
+            timeout: self.timeout_for(task),
+            client,
+            jar,
+            har: Mutex::new(Vec::new()),
+            account,
+            layout_changes: Mutex::new(Vec::new()),
+            timings: Mutex::new(RunTimings::default()),
+        }
+    }
+
+    /// Waits until the session's account may send another request.
+    fn throttle(&self, session: &SiteSession) {
+        let waited = throttle::acquire(&session.account, &self.throttle);
+        if !waited.is_zero() {
+            debug!("Throttled request for {} ms", waited.as_millis());
+        }
+    }
+
+    /// Saves cookies the site rotated during a run back to the task's account, for every
+    /// task of the account.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - The task that was run.
+    /// * `session` - The session whose jar may hold updated cookies.
+    fn persist_rotated_cookie(&self, task: &Task, session: &SiteSession) {
+        let Some(app_handle) = &self.app_handle else {
+            return;
+        };
+        let Some(current) = Url::parse(&session.base)
+            .ok()
+            .and_then(|url| session.jar.cookies(&url))
+            .and_then(|value| value.to_str().map(normalize_cookie).ok())
+        else {
+            return;
+        };
+        let sorted = |cookie: &str| {
+            let mut pairs = parse_cookie(cookie);
+            pairs.sort();
+            pairs
+        };
+        if current.is_empty() || sorted(&current) == sorted(&task.cookie) {
+            return;
+        }
+
+        let state = app_handle.state::<ConfigState>();
+        let mut config = state.0.lock().unwrap();
+        let account_id = config
+            .tasks
+            .iter()
+            .find(|t| t.id == task.id)
+            .and_then(|t| t.account_id.clone());
+        match account_id.and_then(|id| config.accounts.iter_mut().find(|a| a.id == id)) {
+            Some(account) => account.set_cookie(current),
+            None => match config.tasks.iter_mut().find(|t| t.id == task.id) {
+                Some(stored) => stored.cookie = current,
+                None => return,
+            },
+        }
+        config.link_accounts();
+        match save_config(app_handle, &config) {
+            Ok(()) => info!("[{}] Saved cookie rotated by the site", task.name),
+            Err(e) => warn!("[{}] Failed to save rotated cookie: {}", task.name, e),
+        }
+    }
+
+    /// Returns the request timeout for a task: its own override or the global one.
+    fn timeout_for(&self, task: &Task) -> Duration {
+        task.request_timeout_secs
+            .map(Duration::from_secs)
+            .unwrap_or(self.request_timeout)
+    }
+
     /// Builds the HTTP headers required for requests.
     ///
-    /// Sets the User-Agent, Referer, and Cookie headers.
+    /// Sets the User-Agent, Referer and the header profile's browser headers, then merges
+    /// in the task's extra headers. The cookie is sent from the account's jar. Extra
+    /// headers override the defaults; invalid names or values are skipped with a warning.
     ///
     /// # Arguments
     ///
-    /// * `cookie` - The session cookie.
+    /// * `base` - The site base URL, used for the Referer header.
     /// * `class_id` - The class ID, used for the Referer header.
+    /// * `profile` - The browser whose headers are imitated.
+    /// * `extra` - Additional headers configured on the task.
     ///
     /// # Returns
     ///
     /// * `HeaderMap` - The constructed headers.
-    fn build_headers(&self, cookie: &str, class_id: &str) -> HeaderMap {
+    fn build_headers(
+        &self,
+        base: &str,
+        class_id: &str,
+        profile: HeaderProfile,
+        extra: &HashMap<String, String>,
+    ) -> HeaderMap {
         let mut headers = HeaderMap::new();
-        headers.insert(USER_AGENT, HeaderValue::from_static(UA));
-        // headers.insert(X_REQUESTED_WITH, HeaderValue::from_static("com.tencent.mm"));
+        headers.insert(USER_AGENT, HeaderValue::from_static(profile.user_agent()));
+        for (name, value) in profile.headers() {
+            headers.insert(*name, HeaderValue::from_static(value));
+        }
 
-        let referer = format!("{}/student/course/{}", self.base_url, class_id);
+        let referer = self.endpoint_url(base, &self.endpoints.course, class_id, "");
         if let Ok(val) = HeaderValue::from_str(&referer) {
             headers.insert(REFERER, val);
         }
 
-        let real_cookie = cookie.replace("username=", ""); // Simplified cleaning
-        if let Ok(val) = HeaderValue::from_str(&real_cookie) {
-            headers.insert(COOKIE, val);
+        for (name, value) in extra {
+            match (
+                HeaderName::from_bytes(name.trim().as_bytes()),
+                HeaderValue::from_str(value.trim()),
+            ) {
+                (Ok(name), Ok(value)) => {
+                    headers.insert(name, value);
+                }
+                _ => warn!("Skipping invalid extra header: {}", name),
+            }
         }
 
         headers
     }
 
+    /// Opens the course page and pauses like a user reading it before the punch list.
+    ///
+    /// # Arguments
+    ///
+    /// * `session` - The task's connection state.
+    /// * `class_id` - The class ID whose course page is opened.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - Ok once the page was loaded and the pause is over.
+    fn warm_up(&self, session: &SiteSession, class_id: &str) -> Result<(), String> {
+        let url = self.endpoint_url(&session.base, &self.endpoints.course, class_id, "");
+        let request = session
+            .client
+            .get(&url)
+            .headers(session.headers.clone())
+            .timeout(session.timeout);
+        self.throttle(session);
+        let started_at = Local::now();
+        let sent = Instant::now();
+        let resp = retry::send(&RetryPolicy::IDEMPOTENT, request, || self.throttle(session))
+            .map_err(|e| e.to_string())?;
+        let status = resp.status().as_u16();
+        let response_headers = resp.headers().clone();
+        let text = resp.text().map_err(|e| e.to_string())?;
+        let exchange = Exchange {
+            method: "GET",
+            url: &url,
+            request_headers: &session.headers,
+            form: None,
+            status,
+            response_headers: &response_headers,
+            body: &text,
+            started_at,
+            elapsed: sent.elapsed(),
+        };
+        self.trace_exchange(session, &exchange, &format!("course_{}", class_id));
+
+        let min = self.warm_up.min_delay_ms;
+        let max = self.warm_up.max_delay_ms.max(min);
+        let delay = Duration::from_millis(rand::random_range(min..=max));
+        debug!(
+            "Warm-up: loaded course page, pausing {} ms",
+            delay.as_millis()
+        );
+        self.clock.pause(delay);
+        Ok(())
+    }
+
     /// Fetches the list of active check-in session IDs.
     ///
     /// Parses the course page to find active check-in elements.
     ///
     /// # Arguments
     ///
-    /// * `headers` - The HTTP headers to use for the request.
+    /// * `session` - The task's connection state.
     /// * `class_id` - The class ID to check.
     ///
     /// # Returns
     ///
-    /// * `Result<HashSet<String>, String>` - A set of active check-in IDs, or an error message.
+    /// * `Result<HashMap<String, OpenCard>, String>` - Active check-in IDs with the location
+    ///   required by their card and its title, or an error message (`COOKIE_EXPIRED` if
+    ///   the site asked for a login).
     fn get_active_tasks(
         &self,
-        headers: &HeaderMap,
+        session: &SiteSession,
         class_id: &str,
-    ) -> Result<HashSet<String>, String> {
-        let url = format!("{}/student/course/{}/punchs", self.base_url, class_id);
-        let resp = self
+    ) -> Result<HashMap<String, OpenCard>, String> {
+        let url = self.endpoint_url(&session.base, &self.endpoints.punch_list, class_id, "");
+        let request = session
             .client
             .get(&url)
-            .headers(headers.clone())
-            .send()
+            .headers(session.headers.clone())
+            .timeout(session.timeout);
+        self.throttle(session);
+        let started_at = Local::now();
+        let sent = Instant::now();
+        let resp = retry::send(&RetryPolicy::IDEMPOTENT, request, || self.throttle(session))
             .map_err(|e| e.to_string())?;
+        let final_path = resp.url().path().to_string();
+        let status = resp.status().as_u16();
+        let response_headers = resp.headers().clone();
         let text = resp.text().map_err(|e| e.to_string())?;
+        let exchange = Exchange {
+            method: "GET",
+            url: &url,
+            request_headers: &session.headers,
+            form: None,
+            status,
+            response_headers: &response_headers,
+            body: &text,
+            started_at,
+            elapsed: sent.elapsed(),
+        };
+        session.timings.lock().unwrap().fetch_ms = Some(exchange.elapsed.as_millis() as u64);
+        self.trace_exchange(session, &exchange, &format!("punchs_{}", class_id));
+        self.check_blocked(status, &text, &format!("blocked_punchs_{}", class_id))?;
+        let parser = profile::current();
+        if parser.is_login_page(&final_path, &text) {
+            return Err(COOKIE_EXPIRED.to_string());
+        }
 
+        let parse_started = Instant::now();
         let document = Html::parse_document(&text);
-        let card_selector = Selector::parse("div.card-body").unwrap();
 
-        let mut active_ids = HashSet::new();
-        let re1 = Regex::new(r"punchcard_(\d+)").unwrap();
-        let re2 = Regex::new(r"punch_pwd_frm_(\d+)").unwrap();
-        let re3 = Regex::new(r"punch_gps\((\d+)\)").unwrap();
+        let mut active_ids = HashMap::new();
 
-        for card in document.select(&card_selector) {
+        for card in document.select(parser.card_selector()) {
             let card_html = card.html();
-            if card_html.contains("已签") {
+            if parser.is_signed(&card_html) {
                 continue;
             }
 
-            for cap in re1.captures_iter(&card_html) {
-                active_ids.insert(cap[1].to_string());
-            }
-            for cap in re2.captures_iter(&card_html) {
-                active_ids.insert(cap[1].to_string());
+            let required = parser.site_location(&card_html);
+            let title = parser.title(&card_html);
+            let needs_password = parser.needs_password(&card_html);
+            for sign_id in parser.sign_ids(&card_html) {
+                active_ids.insert(
+                    sign_id,
+                    OpenCard {
+                        required,
+                        title: title.clone(),
+                        needs_password,
+                    },
+                );
             }
-            for cap in re3.captures_iter(&card_html) {
-                active_ids.insert(cap[1].to_string());
+        }
+
+        let changes = parser.layout_changes(&parser.fingerprint(&document, &text));
+        session.timings.lock().unwrap().parse_ms = Some(parse_started.elapsed().as_millis() as u64);
+        if !changes.is_empty() {
+            self.save_artifact(&format!("layout_{}", class_id), &text);
+            *session.layout_changes.lock().unwrap() = changes.clone();
+            if active_ids.is_empty() {
+                return Err(format!("{}: {}", SITE_LAYOUT_CHANGED, changes.join("; ")));
             }
         }
 
@@ -191,84 +1523,248 @@ impl TaskExecutor {
 
     /// Performs the sign-in request for a specific session.
     ///
+    /// The default form fields are sent unless the task overrides them. Overrides for
+    /// `id`, `lat` and `lng` are ignored since those are computed per sign; unknown keys
+    /// are appended as extra fields. Altitude and speed are only sent when configured.
+    ///
     /// # Arguments
     ///
-    /// * `headers` - The HTTP headers to use.
+    /// * `session` - The task's connection state.
     /// * `class_id` - The class ID.
     /// * `sign_id` - The check-in session ID.
-    /// * `lat` - The latitude to report.
-    /// * `lng` - The longitude to report.
+    /// * `fix` - The position and GPS readings to report.
+    /// * `password` - The session password; empty for sessions without one.
+    /// * `overrides` - Form fields configured on the task.
     ///
     /// # Returns
     ///
-    /// * `Result<String, String>` - A success message or an error message based on the response content.
+    /// * `Result<(String, String), String>` - A success message and the response body, or an
+    ///   error message based on the response content.
     fn perform_sign(
         &self,
-        headers: &HeaderMap,
+        session: &SiteSession,
         class_id: &str,
         sign_id: &str,
-        lat: &str,
-        lng: &str,
-    ) -> Result<String, String> {
-        let url = format!(
-            "{}/student/punchs/course/{}/{}",
-            self.base_url, class_id, sign_id
-        );
-        let params = [
+        fix: &Fix,
+        password: &str,
+        overrides: &HashMap<String, String>,
+    ) -> Result<(String, String), String> {
+        let url = self.endpoint_url(&session.base, &self.endpoints.sign, class_id, sign_id);
+        let acc = format!("{:.1}", fix.accuracy_m);
+        let mut params: Vec<(String, String)> = [
             ("id", sign_id),
-            ("lat", lat),
-            ("lng", lng),
-            ("acc", "10.0"),
+            ("lat", fix.lat.as_str()),
+            ("lng", fix.lng.as_str()),
+            ("acc", acc.as_str()),
             ("res", ""),
-            ("gps_addr", ""),
-            ("pwd", ""),
-        ];
+            ("gps_addr", fix.address.as_deref().unwrap_or_default()),
+            ("pwd", password),
+        ]
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        if let Some(altitude) = fix.altitude_m {
+            params.push(("altitude".to_string(), format!("{:.1}", altitude)));
+        }
+        if let Some(speed) = fix.speed_mps {
+            params.push(("speed".to_string(), format!("{:.2}", speed)));
+        }
+
+        for (key, value) in overrides {
+            if matches!(key.as_str(), "id" | "lat" | "lng") {
+                continue;
+            }
+            match params.iter_mut().find(|(k, _)| k == key) {
+                Some(entry) => entry.1 = value.clone(),
+                None => params.push((key.clone(), value.clone())),
+            }
+        }
 
-        let resp = self
+        let request = session
             .client
             .post(&url)
-            .headers(headers.clone())
-            .form(&params)
-            .send()
-            .map_err(|e| e.to_string())?;
+            .headers(session.headers.clone())
+            .timeout(session.timeout)
+            .form(&params);
+        self.throttle(session);
+        let started_at = Local::now();
+        let sent = Instant::now();
+        let resp = retry::send(&RetryPolicy::CONSERVATIVE, request, || {
+            self.throttle(session)
+        })
+        .map_err(|e| e.to_string())?;
+        let status = resp.status().as_u16();
+        let response_headers = resp.headers().clone();
         let text = resp.text().map_err(|e| e.to_string())?;
+        let exchange = Exchange {
+            method: "POST",
+            url: &url,
+            request_headers: &session.headers,
+            form: Some(&params),
+            status,
+            response_headers: &response_headers,
+            body: &text,
+            started_at,
+            elapsed: sent.elapsed(),
+        };
+        self.trace_exchange(session, &exchange, &format!("sign_{}", sign_id));
+        self.check_blocked(status, &text, &format!("blocked_sign_{}", sign_id))?;
 
         let document = Html::parse_document(&text);
         let res_text = document.root_element().text().collect::<Vec<_>>().join("");
 
-        if res_text.contains("成功") || res_text.contains("Success") {
-            Ok("签到成功".to_string())
+        if profile::current().is_success(&res_text) {
+            Ok(("签到成功".to_string(), text))
         } else {
             Err(res_text.trim().chars().take(50).collect())
         }
     }
 
-    /// Generates a randomized coordinate within a small radius of the target location.
+    /// Warns when the configured location is outside the radius required by a check-in card.
+    ///
+    /// Emits a `location-mismatch` event and sends a WeCom notification, so the user can
+    /// fix the coordinates before the sign fails.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - The task being signed.
+    /// * `sign_id` - The check-in session ID.
+    /// * `site` - The location required by the check-in card.
+    fn check_distance(&self, task: &Task, sign_id: &str, site: SiteLocation) {
+        let Some(radius_m) = site.radius_m else {
+            return;
+        };
+        let Ok((lat, lng)) = validate_coordinates(&task.location.lat, &task.location.lng) else {
+            return;
+        };
+        let (lat, lng) = task
+            .location
+            .coord_system
+            .convert(self.site_coord_system, lat, lng);
+        let distance = distance_m(lat, lng, site.lat, site.lng);
+        if distance <= radius_m {
+            return;
+        }
+
+        warn!(
+            "[{}] Configured location is {:.0} m from the required location (allowed {:.0} m)",
+            task.name, distance, radius_m
+        );
+        if let Some(app_handle) = &self.app_handle {
+            let _ = app_handle.emit(
+                "location-mismatch",
+                LocationMismatch {
+                    task_id: task.id.clone(),
+                    task_name: task.name.clone(),
+                    sign_id: sign_id.to_string(),
+                    distance_m: distance,
+                    radius_m,
+                    required_lat: site.lat,
+                    required_lng: site.lng,
+                },
+            );
+        }
+        let _ = self.send_wecom_notification(
+            &tf(Msg::LocationMismatchTitle, &[&task.name]),
+            &tf(
+                Msg::LocationMismatchBody,
+                &[
+                    &task.name,
+                    &format!("{:.0}", distance),
+                    &format!("{:.0}", radius_m),
+                ],
+            ),
+        );
+    }
+
+    /// Generates a randomized coordinate within the task's drift radius.
     ///
     /// Helps to simulate natural GPS drift and avoid detection of static coordinates.
+    /// The configured location is first converted to the coordinate system the site
+    /// expects, then offset along the task's daily random walk (see `drift::next_offset`).
+    /// A location taken from the check-in card is already in the site's system; its
+    /// drift is limited to half the card's radius.
     ///
     /// # Arguments
     ///
-    /// * `lat` - The base latitude.
-    /// * `lng` - The base longitude.
+    /// * `task` - The task being signed.
+    /// * `site` - The location required by the check-in card, used instead of the
+    ///   configured one when given.
     ///
     /// # Returns
     ///
     /// * `(String, String)` - The randomized latitude and longitude.
-    fn random_coordinate(&self, lat: &str, lng: &str) -> (String, String) {
-        let lat_val = lat.parse::<f64>().unwrap_or(0.0);
-        let lng_val = lng.parse::<f64>().unwrap_or(0.0);
+    fn random_coordinate(&self, task: &Task, site: Option<SiteLocation>) -> (String, String) {
+        let mut radius_m = task.drift_radius_m.unwrap_or(DEFAULT_DRIFT_RADIUS_M);
+        let (lat_val, lng_val) = match site {
+            Some(site) => {
+                if let Some(site_radius) = site.radius_m {
+                    radius_m = radius_m.min(site_radius / 2.0);
+                }
+                (site.lat, site.lng)
+            }
+            None => {
+                let location = &task.location;
+                location.coord_system.convert(
+                    self.site_coord_system,
+                    location.lat.parse::<f64>().unwrap_or(0.0),
+                    location.lng.parse::<f64>().unwrap_or(0.0),
+                )
+            }
+        };
 
-        let offset = 0.00015;
-        let r_lat = lat_val + (rand::random::<f64>() * 2.0 - 1.0) * offset;
-        let r_lng = lng_val + (rand::random::<f64>() * 2.0 - 1.0) * offset;
+        let (north_m, east_m) = next_offset(&task.id, radius_m, &self.drift);
+        let (r_lat, r_lng) = offset_by_meters(lat_val, lng_val, north_m, east_m);
 
         (format!("{:.6}", r_lat), format!("{:.6}", r_lng))
     }
 
+    /// Returns a WeCom access token, fetching a new one when the cached one has expired.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<String, String>` - The access token, or an error message.
+    fn wecom_access_token(&self) -> Result<String, String> {
+        // A cached token stays valid even if a crashed run poisoned the lock, and the
+        // crash notification itself goes through here.
+        let mut cached = self
+            .wecom_token
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some((token, expires_at)) = cached.as_ref() {
+            if Instant::now() < *expires_at {
+                return Ok(token.clone());
+            }
+        }
+
+        let token_url = format!(
+            "https://qyapi.weixin.qq.com/cgi-bin/gettoken?corpid={}&corpsecret={}",
+            self.wecom.corpid, self.wecom.secret
+        );
+        let token_resp: Value = httplog::json(
+            retry::send(&RetryPolicy::IDEMPOTENT, self.client.get(&token_url), || {})
+                .map_err(|e| e.to_string())?,
+        )?;
+
+        let token = token_resp
+            .get("access_token")
+            .and_then(|v| v.as_str())
+            .ok_or("Failed to get access token")?
+            .to_string();
+        let lifetime = token_resp
+            .get("expires_in")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(7200);
+        let expires_at =
+            Instant::now() + Duration::from_secs(lifetime).saturating_sub(WECOM_TOKEN_MARGIN);
+        *cached = Some((token.clone(), expires_at));
+        Ok(token)
+    }
+
     /// Sends a notification via WeCom (Enterprise WeChat).
     ///
-    /// Retrieves an access token and then sends a text message to the configured user.
+    /// Uses the cached access token (fetching one if needed) and then sends a text message
+    /// to the configured user.
     ///
     /// # Arguments
     ///
@@ -278,37 +1774,32 @@ impl TaskExecutor {
     /// # Returns
     ///
     /// * `Result<(), String>` - Ok on success, or an error message on failure.
-    fn send_wecom_notification(&self, title: &str, content: &str) -> Result<(), String> {
+    pub fn send_wecom_notification(&self, title: &str, content: &str) -> Result<(), String> {
         if !self.wecom.enable {
             return Ok(());
         }
+        let result = self.deliver_wecom(title, content);
+        if let Err(e) = &result {
+            warn!("Failed to send WeCom notification: {}", e);
+            metrics::notification_failed();
+        }
+        result
+    }
 
-        let token_url = format!(
-            "https://qyapi.weixin.qq.com/cgi-bin/gettoken?corpid={}&corpsecret={}",
-            self.wecom.corpid, self.wecom.secret
-        );
-        let token_resp: Value = self
-            .client
-            .get(&token_url)
-            .send()
-            .map_err(|e| e.to_string())?
-            .json()
-            .map_err(|e| e.to_string())?;
-
-        let token = token_resp
-            .get("access_token")
-            .and_then(|v| v.as_str())
-            .ok_or("Failed to get access token")?;
+    /// Formats and posts a WeCom text message.
+    fn deliver_wecom(&self, title: &str, content: &str) -> Result<(), String> {
+        let token = self.wecom_access_token()?;
 
         let msg_url = format!(
             "https://qyapi.weixin.qq.com/cgi-bin/message/send?access_token={}",
             token
         );
         let full_content = format!(
-            "【Checkin Magic】\n{}\n----------------\n{}\nTime: {}",
+            "{}\n{}\n----------------\n{}\n{}",
+            t(Msg::WeComHeader),
             title,
             content,
-            Local::now().format("%Y-%m-%d %H:%M:%S")
+            tf(Msg::WeComTime, &[&Local::now().format("%Y-%m-%d %H:%M:%S")])
         );
 
         let payload = serde_json::json!({
@@ -321,14 +1812,9 @@ impl TaskExecutor {
             "safe": 0
         });
 
-        let send_resp: Value = self
-            .client
-            .post(&msg_url)
-            .json(&payload)
-            .send()
-            .map_err(|e| e.to_string())?
-            .json()
-            .map_err(|e| e.to_string())?;
+        let send_resp: Value = httplog::json(
+            httplog::send(self.client.post(&msg_url).json(&payload)).map_err(|e| e.to_string())?,
+        )?;
 
         if send_resp.get("errcode").and_then(|v| v.as_i64()) == Some(0) {
             Ok(())
@@ -337,3 +1823,99 @@ impl TaskExecutor {
         }
     }
 }
+
+/// Recognizes captcha, WAF and rate-limit interstitials.
+///
+/// # Arguments
+///
+/// * `status` - The response status code.
+/// * `body` - The response body.
+///
+/// # Returns
+///
+/// * `Option<BlockKind>` - The kind of page, or `None` for a regular response.
+fn detect_block(status: u16, body: &str) -> Option<BlockKind> {
+    const RATE_LIMIT: &[&str] = &[
+        "访问过于频繁",
+        "请求过于频繁",
+        "操作过于频繁",
+        "too many requests",
+        "rate limit",
+    ];
+    const CAPTCHA: &[&str] = &[
+        "captcha",
+        "geetest",
+        "滑动验证",
+        "人机验证",
+        "安全验证",
+        "请完成验证",
+    ];
+    const WAF: &[&str] = &[
+        "cf-chl",
+        "just a moment...",
+        "aliyun_waf",
+        "waf.tencent",
+        "web应用防火墙",
+        "访问被拦截",
+        "请求已被拦截",
+    ];
+
+    let lower = body.to_lowercase();
+    let contains = |markers: &[&str]| markers.iter().any(|m| lower.contains(m));
+    if status == 429 || contains(RATE_LIMIT) {
+        Some(BlockKind::RateLimit)
+    } else if contains(CAPTCHA) {
+        Some(BlockKind::Captcha)
+    } else if contains(WAF) || (matches!(status, 403 | 405) && lower.contains("waf")) {
+        Some(BlockKind::Waf)
+    } else {
+        None
+    }
+}
+
+/// Builds the record of a run that ended before any request was made or completed.
+fn aborted_record(
+    task: &Task,
+    started_at: DateTime<Local>,
+    outcome: RunOutcome,
+    message: String,
+) -> RunRecord {
+    RunRecord {
+        id: uuid::Uuid::new_v4().to_string(),
+        task_id: task.id.clone(),
+        task_name: task.name.clone(),
+        class_id: task.class_id.clone(),
+        started_at,
+        finished_at: Local::now(),
+        outcome,
+        message,
+        signs: vec![],
+        scheme: None,
+        deferred_secs: None,
+        timings: RunTimings::default(),
+        receipt: false,
+    }
+}
+
+/// Builds an HTTP client for the check-in site.
+///
+/// # Arguments
+///
+/// * `timeouts` - The connect and request timeouts.
+/// * `jar` - The cookie jar to send and update, if any.
+///
+/// # Returns
+///
+/// * `Client` - The client.
+fn build_client(timeouts: &TimeoutConfig, jar: Option<Arc<Jar>>) -> Client {
+    let mut builder = tls::client_builder()
+        .user_agent(WECHAT_ANDROID_UA)
+        .gzip(true)
+        .brotli(true)
+        .connect_timeout(Duration::from_secs(timeouts.connect_secs))
+        .timeout(Duration::from_secs(timeouts.request_secs));
+    if let Some(jar) = jar {
+        builder = builder.cookie_provider(jar);
+    }
+    builder.build().unwrap()
+}
@@ -0,0 +1,179 @@
+use crate::config::TelegramConfig;
+use crate::httplog;
+use crate::remote;
+use crate::tls;
+use chrono::Local;
+use log::{debug, info, warn};
+use reqwest::blocking::Client;
+use serde::Deserialize;
+use serde_json::json;
+use std::thread;
+use std::time::Duration;
+use tauri::AppHandle;
+
+/// Base URL of the Bot API.
+const API_BASE: &str = "https://api.telegram.org";
+
+/// How long each `getUpdates` call waits for new messages.
+const POLL_TIMEOUT: Duration = Duration::from_secs(50);
+
+/// Delay before polling again after an error.
+const RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Commands older than this, e.g. sent while the app was off, are ignored.
+const STALE_SECS: i64 = 300;
+
+/// Longest message the Bot API accepts, in characters.
+const MAX_MESSAGE_CHARS: usize = 4096;
+
+/// Response of `getUpdates`.
+#[derive(Debug, Deserialize)]
+struct Updates {
+    ok: bool,
+    #[serde(default)]
+    result: Vec<Update>,
+    description: Option<String>,
+}
+
+/// One incoming update; only messages are used.
+#[derive(Debug, Deserialize)]
+struct Update {
+    update_id: i64,
+    message: Option<Message>,
+}
+
+/// A chat message.
+#[derive(Debug, Deserialize)]
+struct Message {
+    chat: Chat,
+    /// Unix time the message was sent.
+    date: i64,
+    text: Option<String>,
+}
+
+/// The chat a message was sent in.
+#[derive(Debug, Deserialize)]
+struct Chat {
+    id: i64,
+}
+
+/// Starts listening for bot commands in a background thread.
+///
+/// Messages are fetched by long polling, so no public address is needed. Only chats
+/// listed in `allowed_chat_ids` are answered; messages from other chats are logged with
+/// their chat ID so it can be added.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `config` - The Telegram settings.
+pub fn start(app_handle: AppHandle, config: TelegramConfig) {
+    if config.bot_token.trim().is_empty() {
+        warn!("Telegram bot is enabled but no bot token is set");
+        return;
+    }
+    if config.allowed_chat_ids.is_empty() {
+        warn!("Telegram bot has no allowed chat IDs; all commands will be ignored");
+    }
+    thread::spawn(move || {
+        let client = match tls::client_builder()
+            .timeout(POLL_TIMEOUT + Duration::from_secs(10))
+            .build()
+        {
+            Ok(client) => client,
+            Err(e) => {
+                warn!("Telegram bot not started: {}", e);
+                return;
+            }
+        };
+        info!("Telegram bot listening for commands");
+        let mut offset = 0;
+        loop {
+            match poll(&client, &config.bot_token, offset) {
+                Ok(updates) => {
+                    for update in updates {
+                        offset = offset.max(update.update_id + 1);
+                        if let Some(message) = update.message {
+                            handle_message(&app_handle, &client, &config, message);
+                        }
+                    }
+                }
+                Err(e) => {
+                    warn!("Telegram polling failed: {}", e);
+                    thread::sleep(RETRY_DELAY);
+                }
+            }
+        }
+    });
+}
+
+/// Fetches updates after `offset`, waiting up to `POLL_TIMEOUT` for new ones.
+fn poll(client: &Client, token: &str, offset: i64) -> Result<Vec<Update>, String> {
+    let url = format!("{}/bot{}/getUpdates", API_BASE, token);
+    let timeout = POLL_TIMEOUT.as_secs().to_string();
+    let offset = offset.to_string();
+    let resp = httplog::send(client.get(url).query(&[
+        ("timeout", timeout.as_str()),
+        ("offset", offset.as_str()),
+        ("allowed_updates", r#"["message"]"#),
+    ]))
+    .map_err(|e| e.without_url().to_string())?;
+    let updates: Updates = httplog::json(resp)?;
+    if !updates.ok {
+        return Err(updates.description.unwrap_or_default());
+    }
+    Ok(updates.result)
+}
+
+/// Answers a command from an allowed chat.
+fn handle_message(
+    app_handle: &AppHandle,
+    client: &Client,
+    config: &TelegramConfig,
+    message: Message,
+) {
+    let Some(text) = message.text else {
+        return;
+    };
+    if !config.allowed_chat_ids.contains(&message.chat.id) {
+        warn!(
+            "Ignoring Telegram message from chat {}, which is not in allowed_chat_ids",
+            message.chat.id
+        );
+        return;
+    }
+    if Local::now().timestamp() - message.date > STALE_SECS {
+        debug!("Ignoring stale Telegram command: {}", text);
+        return;
+    }
+    let text = text.trim();
+    let (word, argument) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+    // Commands in groups may be addressed to the bot, as in `/run@MyBot`.
+    let word = word
+        .trim_start_matches('/')
+        .split('@')
+        .next()
+        .unwrap_or_default();
+    info!("Telegram command: {}", text);
+    let reply = match remote::parse(word, argument) {
+        Some(command) => remote::execute(app_handle, command),
+        None => remote::help("/"),
+    };
+    let reply = remote::tail(&reply, MAX_MESSAGE_CHARS);
+    if let Err(e) = send_message(client, &config.bot_token, message.chat.id, &reply) {
+        warn!("Failed to answer Telegram command: {}", e);
+    }
+}
+
+/// Sends a plain-text message to a chat.
+fn send_message(client: &Client, token: &str, chat_id: i64, text: &str) -> Result<(), String> {
+    let url = format!("{}/bot{}/sendMessage", API_BASE, token);
+    httplog::send(
+        client
+            .post(url)
+            .json(&json!({ "chat_id": chat_id, "text": text })),
+    )
+    .and_then(|r| r.error_for_status())
+    .map(|_| ())
+    .map_err(|e| e.without_url().to_string())
+}
@@ -0,0 +1,82 @@
+use crate::config::ThrottleConfig;
+use crate::cookie::parse_cookie;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::{LazyLock, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Token buckets keyed by account.
+///
+/// Kept outside the executor so the budget survives executor rebuilds on config changes.
+static BUCKETS: LazyLock<Mutex<HashMap<String, Bucket>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Request budget of one account.
+struct Bucket {
+    /// Available requests; negative while requests are waiting for a token.
+    tokens: f64,
+    /// When the tokens were last refilled.
+    refilled: Instant,
+}
+
+/// Derives the throttling key of the account a cookie belongs to.
+///
+/// Tasks pasted with the same cookie share a key regardless of cookie order.
+///
+/// # Arguments
+///
+/// * `cookie` - The task's cookie.
+///
+/// # Returns
+///
+/// * `String` - A hex digest identifying the account.
+pub fn account_key(cookie: &str) -> String {
+    let mut pairs = parse_cookie(cookie);
+    pairs.sort();
+    let mut hasher = DefaultHasher::new();
+    pairs.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Takes one request from an account's budget, blocking until one is available.
+///
+/// # Arguments
+///
+/// * `key` - The account key from [`account_key`].
+/// * `config` - The rate ceiling.
+///
+/// # Returns
+///
+/// * `Duration` - How long the caller was held back.
+pub fn acquire(key: &str, config: &ThrottleConfig) -> Duration {
+    if config.requests_per_minute == 0 {
+        return Duration::ZERO;
+    }
+    let rate = config.requests_per_minute as f64 / 60.0;
+    let capacity = config.burst.max(1) as f64;
+
+    let wait = {
+        let mut buckets = BUCKETS.lock().unwrap();
+        let now = Instant::now();
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            refilled: now,
+        });
+        let refill = now.duration_since(bucket.refilled).as_secs_f64() * rate;
+        bucket.tokens = (bucket.tokens + refill).min(capacity);
+        bucket.refilled = now;
+        // Reserve the token now so concurrent callers queue up behind each other.
+        bucket.tokens -= 1.0;
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / rate)
+        }
+    };
+    if !wait.is_zero() {
+        thread::sleep(wait);
+    }
+    wait
+}
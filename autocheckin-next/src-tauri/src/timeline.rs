@@ -0,0 +1,221 @@
+use crate::config::Task;
+use crate::history::{RunOutcome, RunRecord};
+use crate::i18n::{t, tf, Msg};
+use crate::scheduler::occurrences;
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// How long after its scheduled time a run still counts as that scheduled run, covering
+/// network deferral and retries.
+const MATCH_WINDOW_MINUTES: i64 = 120;
+
+/// Longest range one timeline call covers.
+const MAX_RANGE_DAYS: i64 = 400;
+
+/// What a timeline entry stands for.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TimelineKind {
+    /// A scheduled run that has not happened yet.
+    Upcoming,
+    /// A scheduled run that happened.
+    Run,
+    /// A run outside the schedule (manual, tray, remote or catch-up).
+    Unscheduled,
+    /// A scheduled run that never happened, e.g. because the app was not running.
+    Missed,
+}
+
+/// One entry of the timeline.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TimelineEvent {
+    /// What the entry stands for.
+    pub kind: TimelineKind,
+    /// ID of the task.
+    pub task_id: String,
+    /// Name of the task.
+    pub task_name: String,
+    /// Class ID of the task.
+    pub class_id: String,
+    /// When the task was scheduled to run, for scheduled entries.
+    pub scheduled_at: Option<NaiveDateTime>,
+    /// When the run started, for entries with a run.
+    pub started_at: Option<DateTime<Local>>,
+    /// The run's outcome, for entries with a run.
+    pub outcome: Option<RunOutcome>,
+    /// The run's ID in the history, for entries with a run.
+    pub run_id: Option<String>,
+    /// Whether the run found an open check-in session.
+    pub found_session: bool,
+    /// The run's summary message, empty for entries without a run.
+    pub message: String,
+}
+
+/// The timeline entries of one day.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TimelineDay {
+    /// The day.
+    pub date: NaiveDate,
+    /// The entries, in time order.
+    pub events: Vec<TimelineEvent>,
+}
+
+/// Merges the schedule and the run history into one entry list per day.
+///
+/// Each run is matched to the scheduled time of its task it followed within two hours.
+/// Scheduled times in the past without a matching run are reported as missed, starting
+/// from the task's first recorded run so days before the task existed are not counted.
+///
+/// # Arguments
+///
+/// * `tasks` - The configured tasks; disabled tasks only contribute their runs.
+/// * `records` - All run records.
+/// * `from` - The first day.
+/// * `to` - The last day, inclusive.
+/// * `now` - The current local time, separating upcoming from missed runs.
+///
+/// # Returns
+///
+/// * `Result<Vec<TimelineDay>, String>` - Every day of the range, including empty ones,
+///   or an error message if the range is reversed or too long.
+pub fn build_timeline(
+    tasks: &[Task],
+    records: &[RunRecord],
+    from: NaiveDate,
+    to: NaiveDate,
+    now: NaiveDateTime,
+) -> Result<Vec<TimelineDay>, String> {
+    if to < from {
+        return Err(t(Msg::RangeReversed).to_string());
+    }
+    if (to - from).num_days() >= MAX_RANGE_DAYS {
+        return Err(tf(Msg::RangeTooLong, &[&MAX_RANGE_DAYS]));
+    }
+
+    let runs: Vec<&RunRecord> = records
+        .iter()
+        .filter(|r| {
+            let day = r.started_at.date_naive();
+            day >= from && day <= to
+        })
+        .collect();
+    let mut first_run: HashMap<&str, NaiveDate> = HashMap::new();
+    for record in records {
+        let day = record.started_at.date_naive();
+        first_run
+            .entry(record.task_id.as_str())
+            .and_modify(|d| *d = (*d).min(day))
+            .or_insert(day);
+    }
+
+    let mut days: BTreeMap<NaiveDate, Vec<TimelineEvent>> = from
+        .iter_days()
+        .take_while(|d| *d <= to)
+        .map(|d| (d, vec![]))
+        .collect();
+    let mut matched: HashSet<&str> = HashSet::new();
+    let window = Duration::minutes(MATCH_WINDOW_MINUTES);
+
+    for task in tasks.iter().filter(|t| t.enable) {
+        for at in occurrences(task, from, to) {
+            let run = runs
+                .iter()
+                .filter(|r| r.task_id == task.id && !matched.contains(r.id.as_str()))
+                .find(|r| in_slot(at, r.started_at.naive_local()));
+            let event = match run {
+                Some(run) => {
+                    matched.insert(run.id.as_str());
+                    run_event(TimelineKind::Run, Some(at), run)
+                }
+                None => {
+                    let kind = if at > now {
+                        TimelineKind::Upcoming
+                    } else if at + window > now
+                        || first_run
+                            .get(task.id.as_str())
+                            .is_none_or(|first| at.date() < *first)
+                    {
+                        continue;
+                    } else {
+                        TimelineKind::Missed
+                    };
+                    TimelineEvent {
+                        kind,
+                        task_id: task.id.clone(),
+                        task_name: task.name.clone(),
+                        class_id: task.class_id.clone(),
+                        scheduled_at: Some(at),
+                        started_at: None,
+                        outcome: None,
+                        run_id: None,
+                        found_session: false,
+                        message: String::new(),
+                    }
+                }
+            };
+            days.entry(at.date()).or_default().push(event);
+        }
+    }
+
+    for run in runs.iter().filter(|r| !matched.contains(r.id.as_str())) {
+        days.entry(run.started_at.date_naive())
+            .or_default()
+            .push(run_event(TimelineKind::Unscheduled, None, run));
+    }
+
+    Ok(days
+        .into_iter()
+        .map(|(date, mut events)| {
+            events.sort_by_key(|e| {
+                e.scheduled_at
+                    .or_else(|| e.started_at.map(|s| s.naive_local()))
+            });
+            TimelineDay { date, events }
+        })
+        .collect())
+}
+
+/// Finds the scheduled time a run of the task belongs to.
+///
+/// # Arguments
+///
+/// * `task` - The task that ran.
+/// * `started_at` - When the run started, in local time.
+///
+/// # Returns
+///
+/// * `Option<NaiveDateTime>` - The scheduled time the run followed within two hours, or
+///   `None` for a run outside the schedule.
+pub fn scheduled_slot(task: &Task, started_at: NaiveDateTime) -> Option<NaiveDateTime> {
+    let day = started_at.date();
+    occurrences(task, day.pred_opt().unwrap_or(day), day)
+        .into_iter()
+        .find(|at| in_slot(*at, started_at))
+}
+
+/// Whether a run started close enough to a scheduled time to count as that run.
+fn in_slot(at: NaiveDateTime, started_at: NaiveDateTime) -> bool {
+    started_at >= at - Duration::minutes(1)
+        && started_at <= at + Duration::minutes(MATCH_WINDOW_MINUTES)
+}
+
+/// Builds the entry of a run.
+fn run_event(
+    kind: TimelineKind,
+    scheduled_at: Option<NaiveDateTime>,
+    run: &RunRecord,
+) -> TimelineEvent {
+    TimelineEvent {
+        kind,
+        task_id: run.task_id.clone(),
+        task_name: run.task_name.clone(),
+        class_id: run.class_id.clone(),
+        scheduled_at,
+        started_at: Some(run.started_at),
+        outcome: Some(run.outcome),
+        run_id: Some(run.id.clone()),
+        found_session: !run.signs.is_empty(),
+        message: run.message.clone(),
+    }
+}
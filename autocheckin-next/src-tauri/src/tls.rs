@@ -0,0 +1,66 @@
+use crate::config::TlsConfig;
+use log::warn;
+use reqwest::blocking::{Client, ClientBuilder};
+use reqwest::Certificate;
+use std::fs;
+use std::sync::RwLock;
+
+/// The active certificate settings, applied to every client built afterwards.
+static SETTINGS: RwLock<Settings> = RwLock::new(Settings {
+    roots: Vec::new(),
+    accept_invalid_certs: false,
+});
+
+/// Parsed certificate settings.
+struct Settings {
+    /// Extra trusted root certificates.
+    roots: Vec<Certificate>,
+    /// Whether certificate validation is disabled.
+    accept_invalid_certs: bool,
+}
+
+/// Loads and applies the certificate settings.
+///
+/// # Arguments
+///
+/// * `config` - The certificate settings.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Ok if applied, or an error message if the CA bundle cannot be
+///   read; the previous settings stay active in that case.
+pub fn configure(config: &TlsConfig) -> Result<(), String> {
+    let roots = match config.ca_bundle_path.trim() {
+        "" => Vec::new(),
+        path => {
+            let pem = fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+            Certificate::from_pem_bundle(&pem).map_err(|e| format!("{}: {}", path, e))?
+        }
+    };
+    if config.accept_invalid_certs {
+        warn!(
+            "TLS certificate validation is DISABLED. Anyone on the network can read and alter \
+             traffic, including the session cookie. Only use this behind a trusted \
+             intercepting proxy."
+        );
+    }
+    *SETTINGS.write().unwrap() = Settings {
+        roots,
+        accept_invalid_certs: config.accept_invalid_certs,
+    };
+    Ok(())
+}
+
+/// Returns a client builder with the configured root certificates and validation mode.
+///
+/// # Returns
+///
+/// * `ClientBuilder` - A builder to add further options to.
+pub fn client_builder() -> ClientBuilder {
+    let settings = SETTINGS.read().unwrap();
+    let mut builder = Client::builder();
+    for root in &settings.roots {
+        builder = builder.add_root_certificate(root.clone());
+    }
+    builder.danger_accept_invalid_certs(settings.accept_invalid_certs)
+}
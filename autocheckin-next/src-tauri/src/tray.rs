@@ -0,0 +1,354 @@
+use crate::config::{save_config, ConfigState, Task};
+use crate::events;
+use crate::history::RunOutcome;
+use crate::i18n::{t, tf, Msg};
+use crate::scheduler::{next_run, run_all_now, run_task_now};
+use chrono::Local;
+use log::{error, info};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use tauri::image::Image;
+use tauri::menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::TrayIconBuilder;
+use tauri::{App, AppHandle, Emitter, Manager, Wry};
+
+/// ID of the application's tray icon.
+const TRAY_ID: &str = "main";
+
+/// Menu ID prefix for task enable toggles.
+const TOGGLE_PREFIX: &str = "toggle:";
+
+/// Menu ID prefix for run-now items.
+const RUN_PREFIX: &str = "run:";
+
+/// Number of task runs currently in flight.
+static RUNNING: AtomicUsize = AtomicUsize::new(0);
+
+/// Status shown once no runs are in flight, derived from the last finished run.
+static SETTLED_STATUS: Mutex<TrayStatus> = Mutex::new(TrayStatus::Idle);
+
+/// Version of an available update, shown as a tray menu entry.
+static UPDATE_VERSION: Mutex<Option<String>> = Mutex::new(None);
+
+/// The state reflected by the tray icon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayStatus {
+    /// Nothing needs attention.
+    Idle,
+    /// At least one task is running.
+    Running,
+    /// The last run failed.
+    Failed,
+    /// The last run found an expired cookie.
+    CookieExpired,
+}
+
+impl TrayStatus {
+    /// The badge color drawn over the app icon, or `None` for the plain icon.
+    fn badge_color(self) -> Option<[u8; 3]> {
+        match self {
+            TrayStatus::Idle => None,
+            TrayStatus::Running => Some([0x21, 0x96, 0xf3]),
+            TrayStatus::Failed => Some([0xe5, 0x39, 0x35]),
+            TrayStatus::CookieExpired => Some([0xfb, 0x8c, 0x00]),
+        }
+    }
+}
+
+/// Marks a task run as started and shows the running state.
+///
+/// # Arguments
+///
+/// * `app` - The Tauri application handle.
+pub fn run_started(app: &AppHandle) {
+    RUNNING.fetch_add(1, Ordering::SeqCst);
+    set_tray_status(app, TrayStatus::Running);
+}
+
+/// Marks a task run as finished and updates the tray once no runs remain.
+///
+/// # Arguments
+///
+/// * `app` - The Tauri application handle.
+/// * `outcome` - The outcome of the run, or `None` if it did not execute.
+pub fn run_finished(app: &AppHandle, outcome: Option<RunOutcome>) {
+    let mut settled = SETTLED_STATUS.lock().unwrap();
+    match outcome {
+        Some(RunOutcome::CookieExpired) => *settled = TrayStatus::CookieExpired,
+        Some(o) if o.is_failure() => *settled = TrayStatus::Failed,
+        Some(_) => *settled = TrayStatus::Idle,
+        None => {}
+    }
+    if RUNNING.fetch_sub(1, Ordering::SeqCst) == 1 {
+        set_tray_status(app, *settled);
+    }
+    drop(settled);
+
+    let tasks = app.state::<ConfigState>().0.lock().unwrap().tasks.clone();
+    refresh_tray_menu(app, &tasks);
+}
+
+/// Swaps the tray icon to reflect the given status.
+fn set_tray_status(app: &AppHandle, status: TrayStatus) {
+    let (Some(tray), Some(base)) = (app.tray_by_id(TRAY_ID), app.default_window_icon()) else {
+        return;
+    };
+    let icon = match status.badge_color() {
+        Some(color) => badge_icon(base, color),
+        None => base.clone(),
+    };
+    if let Err(e) = tray.set_icon(Some(icon)) {
+        error!("Failed to update tray icon: {}", e);
+    }
+}
+
+/// Draws a colored status dot in the bottom-right corner of the icon.
+fn badge_icon(base: &Image<'_>, color: [u8; 3]) -> Image<'static> {
+    let (width, height) = (base.width(), base.height());
+    let mut rgba = base.rgba().to_vec();
+    let radius = width.min(height) as f64 * 0.22;
+    let center_x = width as f64 - radius - 1.0;
+    let center_y = height as f64 - radius - 1.0;
+
+    for y in 0..height {
+        for x in 0..width {
+            let dx = x as f64 + 0.5 - center_x;
+            let dy = y as f64 + 0.5 - center_y;
+            if dx * dx + dy * dy <= radius * radius {
+                let i = ((y * width + x) * 4) as usize;
+                rgba[i..i + 3].copy_from_slice(&color);
+                rgba[i + 3] = 0xff;
+            }
+        }
+    }
+    Image::new(&rgba, width, height).to_owned()
+}
+
+/// Creates the system tray icon with a menu built from the current tasks.
+///
+/// # Arguments
+///
+/// * `app` - The Tauri application being set up.
+/// * `tasks` - The configured tasks.
+///
+/// # Returns
+///
+/// * `tauri::Result<()>` - Ok on success, or the error raised while building the tray.
+pub fn create_tray(app: &App, tasks: &[Task]) -> tauri::Result<()> {
+    let menu = build_menu(app.handle(), tasks)?;
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .menu(&menu)
+        .tooltip(next_run_label(tasks))
+        .show_menu_on_left_click(false)
+        .on_menu_event(|app, event| handle_menu_event(app, event.id.as_ref()));
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+    Ok(())
+}
+
+/// Rebuilds the tray menu and tooltip after the task list has changed or a run finished.
+///
+/// # Arguments
+///
+/// * `app` - The Tauri application handle.
+/// * `tasks` - The configured tasks.
+pub fn refresh_tray_menu(app: &AppHandle, tasks: &[Task]) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    match build_menu(app, tasks) {
+        Ok(menu) => {
+            if let Err(e) = tray.set_menu(Some(menu)) {
+                error!("Failed to update tray menu: {}", e);
+            }
+        }
+        Err(e) => error!("Failed to build tray menu: {}", e),
+    }
+    if let Err(e) = tray.set_tooltip(Some(next_run_label(tasks))) {
+        error!("Failed to update tray tooltip: {}", e);
+    }
+}
+
+/// Describes the next scheduled run, e.g. "Next: 高数 at 08:50".
+///
+/// Runs on a later day include the date.
+fn next_run_label(tasks: &[Task]) -> String {
+    let now = Local::now().naive_local();
+    match next_run(tasks, now) {
+        Some((task, at)) => {
+            let when = if at.date() == now.date() {
+                at.format("%H:%M").to_string()
+            } else {
+                at.format("%m-%d %H:%M").to_string()
+            };
+            tf(Msg::TrayNextRun, &[&task.name, &when])
+        }
+        None => t(Msg::TrayNoUpcomingRun).to_string(),
+    }
+}
+
+/// Records that an update is available and adds an install entry to the tray menu.
+///
+/// # Arguments
+///
+/// * `app` - The Tauri application handle.
+/// * `version` - The version that can be installed.
+pub fn set_update_available(app: &AppHandle, version: &str) {
+    *UPDATE_VERSION.lock().unwrap() = Some(version.to_string());
+    let tasks = app.state::<ConfigState>().0.lock().unwrap().tasks.clone();
+    refresh_tray_menu(app, &tasks);
+}
+
+/// Builds the tray menu: a disabled next-run header, Show, one enable toggle per task,
+/// run-now actions and Quit.
+///
+/// An "update available" entry is added at the top once an update has been found.
+fn build_menu(app: &AppHandle, tasks: &[Task]) -> tauri::Result<Menu<Wry>> {
+    let menu = Menu::new(app)?;
+    menu.append(&MenuItem::with_id(
+        app,
+        "next_run",
+        next_run_label(tasks),
+        false,
+        None::<&str>,
+    )?)?;
+    menu.append(&PredefinedMenuItem::separator(app)?)?;
+    if let Some(version) = UPDATE_VERSION.lock().unwrap().as_ref() {
+        menu.append(&MenuItem::with_id(
+            app,
+            "install_update",
+            tf(Msg::TrayUpdateAvailable, &[version]),
+            true,
+            None::<&str>,
+        )?)?;
+        menu.append(&PredefinedMenuItem::separator(app)?)?;
+    }
+    menu.append(&MenuItem::with_id(
+        app,
+        "show",
+        t(Msg::TrayShow),
+        true,
+        None::<&str>,
+    )?)?;
+
+    if !tasks.is_empty() {
+        menu.append(&PredefinedMenuItem::separator(app)?)?;
+
+        let run_menu = Submenu::with_id(app, "run_menu", t(Msg::TrayRunNow), true)?;
+        for task in tasks {
+            menu.append(&CheckMenuItem::with_id(
+                app,
+                format!("{}{}", TOGGLE_PREFIX, task.id),
+                &task.name,
+                true,
+                task.enable,
+                None::<&str>,
+            )?)?;
+            run_menu.append(&MenuItem::with_id(
+                app,
+                format!("{}{}", RUN_PREFIX, task.id),
+                &task.name,
+                task.enable,
+                None::<&str>,
+            )?)?;
+        }
+        menu.append(&run_menu)?;
+        menu.append(&MenuItem::with_id(
+            app,
+            "run_all",
+            t(Msg::TrayRunAll),
+            tasks.iter().any(|t| t.enable),
+            None::<&str>,
+        )?)?;
+    }
+
+    menu.append(&PredefinedMenuItem::separator(app)?)?;
+    menu.append(&MenuItem::with_id(
+        app,
+        "quit",
+        t(Msg::TrayQuit),
+        true,
+        None::<&str>,
+    )?)?;
+    Ok(menu)
+}
+
+/// Dispatches a tray menu click.
+fn handle_menu_event(app: &AppHandle, id: &str) {
+    match id {
+        "quit" => {
+            app.exit(0);
+        }
+        "show" => {
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.show();
+                let _ = window.set_focus();
+            }
+        }
+        #[cfg(desktop)]
+        "install_update" => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = crate::updater::install_update(&app).await {
+                    error!("Failed to install update: {}", e);
+                }
+            });
+        }
+        "run_all" => {
+            info!("Started {} task(s) from tray", run_all_now(app));
+        }
+        _ => {
+            if let Some(task_id) = id.strip_prefix(TOGGLE_PREFIX) {
+                if let Err(e) = toggle_task(app, task_id) {
+                    error!("Failed to toggle task {}: {}", task_id, e);
+                }
+            } else if let Some(task_id) = id.strip_prefix(RUN_PREFIX) {
+                if let Err(e) = run_task_now(app, task_id) {
+                    error!("Failed to run task {}: {}", task_id, e);
+                }
+            }
+        }
+    }
+}
+
+/// Flips a task's enable flag, persists it, and notifies the frontend.
+fn toggle_task(app: &AppHandle, task_id: &str) -> Result<(), String> {
+    set_task_enabled(app, task_id, None)
+}
+
+/// Sets a task's enable flag, persists it, and notifies the frontend.
+///
+/// Also works for tasks from a managed task list, which keep the flag locally.
+///
+/// # Arguments
+///
+/// * `app` - The Tauri application handle.
+/// * `task_id` - The ID of the task.
+/// * `enable` - The new flag, or `None` to flip it.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Ok on success, error message if the task is missing or the
+///   config cannot be saved.
+pub fn set_task_enabled(
+    app: &AppHandle,
+    task_id: &str,
+    enable: Option<bool>,
+) -> Result<(), String> {
+    let state = app.state::<ConfigState>();
+    let mut config = state.0.lock().unwrap();
+    let task = config
+        .tasks
+        .iter_mut()
+        .find(|t| t.id == task_id)
+        .ok_or(t(Msg::TaskNotFound))?;
+    let enable = enable.unwrap_or(!task.enable);
+    task.set_enable(enable);
+    save_config(app, &config)?;
+    refresh_tray_menu(app, &config.tasks);
+    events::publish("config_changed", serde_json::json!({}));
+    let _ = app.emit("config-changed", ());
+    Ok(())
+}
@@ -0,0 +1,116 @@
+use crate::config::ConfigState;
+use crate::i18n::{t, Msg};
+use crate::tray::set_update_available;
+use log::info;
+use serde::Serialize;
+use tauri::{AppHandle, Manager, Url};
+use tauri_plugin_updater::{Update, UpdaterExt};
+
+/// Details about an available update.
+#[derive(Debug, Serialize, Clone)]
+pub struct UpdateInfo {
+    /// The version that can be installed.
+    pub version: String,
+    /// The version currently running.
+    pub current_version: String,
+    /// Release notes, if provided by the update manifest.
+    pub notes: Option<String>,
+    /// Publication date, if provided by the update manifest.
+    pub date: Option<String>,
+}
+
+/// Returns whether a public key for verifying update packages is set in `tauri.conf.json`.
+///
+/// Without one, no update can be installed, so updates are neither checked for nor offered.
+///
+/// # Arguments
+///
+/// * `app` - The Tauri application handle.
+pub fn is_configured(app: &AppHandle) -> bool {
+    app.config()
+        .plugins
+        .0
+        .get("updater")
+        .and_then(|updater| updater.get("pubkey"))
+        .and_then(|pubkey| pubkey.as_str())
+        .is_some_and(|pubkey| !pubkey.trim().is_empty())
+}
+
+/// Queries the configured update endpoint.
+///
+/// # Arguments
+///
+/// * `app` - The Tauri application handle.
+///
+/// # Returns
+///
+/// * `Result<Option<Update>, String>` - The update if a newer version exists, or an error
+///   message, also when no public key is configured.
+async fn find_update(app: &AppHandle) -> Result<Option<Update>, String> {
+    if !is_configured(app) {
+        return Err(t(Msg::UpdaterNotConfigured).to_string());
+    }
+    let endpoint = app
+        .state::<ConfigState>()
+        .0
+        .lock()
+        .unwrap()
+        .global
+        .update_endpoint
+        .clone();
+    let url = Url::parse(&endpoint).map_err(|e| e.to_string())?;
+    app.updater_builder()
+        .endpoints(vec![url])
+        .map_err(|e| e.to_string())?
+        .build()
+        .map_err(|e| e.to_string())?
+        .check()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Checks for a newer version and flags it in the tray menu.
+///
+/// # Arguments
+///
+/// * `app` - The Tauri application handle.
+///
+/// # Returns
+///
+/// * `Result<Option<UpdateInfo>, String>` - Details of the update, `None` if up to date,
+///   or an error message.
+pub async fn check_for_updates(app: &AppHandle) -> Result<Option<UpdateInfo>, String> {
+    let Some(update) = find_update(app).await? else {
+        return Ok(None);
+    };
+
+    info!("Update available: {}", update.version);
+    set_update_available(app, &update.version);
+    Ok(Some(UpdateInfo {
+        version: update.version.clone(),
+        current_version: update.current_version.clone(),
+        notes: update.body.clone(),
+        date: update.date.map(|d| d.to_string()),
+    }))
+}
+
+/// Downloads and installs the available update, then restarts the app.
+///
+/// The package signature is verified against the public key in `tauri.conf.json`.
+///
+/// # Arguments
+///
+/// * `app` - The Tauri application handle.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - An error message if there is no update or installation fails.
+pub async fn install_update(app: &AppHandle) -> Result<(), String> {
+    let update = find_update(app).await?.ok_or(t(Msg::NoUpdateAvailable))?;
+    info!("Installing update {}", update.version);
+    update
+        .download_and_install(|_, _| {}, || {})
+        .await
+        .map_err(|e| e.to_string())?;
+    app.restart();
+}
@@ -0,0 +1,178 @@
+use crate::config::{Task, WebhookConfig, WebhookEvent};
+use crate::history::{RunOutcome, RunRecord};
+use crate::httplog;
+use crate::tls;
+use chrono::Local;
+use log::{debug, warn};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::fmt::Write as _;
+use std::thread;
+use std::time::Duration;
+
+/// Timeout for a single webhook delivery.
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Block size of SHA-256, used for the HMAC key padding.
+const SHA256_BLOCK: usize = 64;
+
+/// Header carrying the event name.
+const EVENT_HEADER: &str = "X-AutoCheckin-Event";
+
+/// Header carrying the HMAC-SHA256 signature of the body.
+const SIGNATURE_HEADER: &str = "X-AutoCheckin-Signature";
+
+/// Fires the `task_started` event.
+///
+/// # Arguments
+///
+/// * `hooks` - The configured webhooks.
+/// * `task` - The task about to run.
+pub fn task_started(hooks: &[WebhookConfig], task: &Task) {
+    fire(
+        hooks,
+        WebhookEvent::TaskStarted,
+        task,
+        json!({ "run": null }),
+    );
+}
+
+/// Fires the events for a finished run: `task_succeeded` or `task_failed`, plus
+/// `cookie_expired` when the run found an expired cookie.
+///
+/// Runs that found no session or were skipped while offline fire nothing.
+///
+/// # Arguments
+///
+/// * `hooks` - The configured webhooks.
+/// * `task` - The task that ran.
+/// * `record` - The finished run.
+pub fn run_finished(hooks: &[WebhookConfig], task: &Task, record: &RunRecord) {
+    let run = json!({ "run": record });
+    match record.outcome {
+        RunOutcome::Success => fire(hooks, WebhookEvent::TaskSucceeded, task, run),
+        outcome if outcome.is_failure() => {
+            fire(hooks, WebhookEvent::TaskFailed, task, run.clone());
+            if outcome == RunOutcome::CookieExpired {
+                fire(hooks, WebhookEvent::CookieExpired, task, run);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Fires the `cookie_expired` event for an expiry found outside a run.
+///
+/// # Arguments
+///
+/// * `hooks` - The configured webhooks.
+/// * `task` - The task whose cookie expired.
+pub fn cookie_expired(hooks: &[WebhookConfig], task: &Task) {
+    fire(
+        hooks,
+        WebhookEvent::CookieExpired,
+        task,
+        json!({ "run": null }),
+    );
+}
+
+/// Fires the `success_rate_low` event.
+///
+/// # Arguments
+///
+/// * `hooks` - The configured webhooks.
+/// * `task` - The task whose success rate dropped.
+/// * `success_rate` - The rolling success rate, between 0 and 1.
+/// * `window_days` - The number of days the rate covers.
+pub fn success_rate_low(hooks: &[WebhookConfig], task: &Task, success_rate: f64, window_days: u32) {
+    fire(
+        hooks,
+        WebhookEvent::SuccessRateLow,
+        task,
+        json!({ "success_rate": success_rate, "window_days": window_days }),
+    );
+}
+
+/// Posts an event to every webhook subscribed to it, in the background.
+///
+/// The fields of `data` are added to the payload next to the event and task.
+fn fire(hooks: &[WebhookConfig], event: WebhookEvent, task: &Task, data: Value) {
+    let targets: Vec<WebhookConfig> = hooks
+        .iter()
+        .filter(|hook| !hook.url.is_empty())
+        .filter(|hook| hook.events.is_empty() || hook.events.contains(&event))
+        .cloned()
+        .collect();
+    if targets.is_empty() {
+        return;
+    }
+    let mut payload = json!({
+        "event": event,
+        "timestamp": Local::now().to_rfc3339(),
+        "task": { "id": task.id, "name": task.name, "class_id": task.class_id },
+    });
+    if let (Some(payload), Value::Object(data)) = (payload.as_object_mut(), data) {
+        payload.extend(data);
+    }
+    let payload = payload.to_string();
+    let event_name = serde_json::to_value(event)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default();
+    thread::spawn(move || {
+        for hook in targets {
+            deliver(&hook, &event_name, &payload);
+        }
+    });
+}
+
+/// Posts one payload, signed with the webhook's secret if it has one.
+fn deliver(hook: &WebhookConfig, event: &str, payload: &str) {
+    let client = match tls::client_builder().timeout(DELIVERY_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => return warn!("Failed to send webhook {}: {}", event, e),
+    };
+    let mut request = client
+        .post(&hook.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(EVENT_HEADER, event)
+        .body(payload.to_string());
+    if !hook.secret.is_empty() {
+        let signature = hmac_sha256(hook.secret.as_bytes(), payload.as_bytes());
+        request = request.header(SIGNATURE_HEADER, format!("sha256={}", hex(&signature)));
+    }
+    match httplog::send(request) {
+        Ok(resp) if resp.status().is_success() => debug!("Webhook {} delivered", event),
+        Ok(resp) => warn!("Webhook {} rejected with status {}", event, resp.status()),
+        Err(e) => warn!("Failed to send webhook {}: {}", event, e),
+    }
+}
+
+/// Computes the HMAC-SHA256 of a message (RFC 2104).
+fn hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut block = [0u8; SHA256_BLOCK];
+    if key.len() > SHA256_BLOCK {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let inner_pad: Vec<u8> = block.iter().map(|b| b ^ 0x36).collect();
+    let outer_pad: Vec<u8> = block.iter().map(|b| b ^ 0x5c).collect();
+    let inner = Sha256::new()
+        .chain_update(&inner_pad)
+        .chain_update(message)
+        .finalize();
+    Sha256::new()
+        .chain_update(&outer_pad)
+        .chain_update(inner)
+        .finalize()
+        .to_vec()
+}
+
+/// Formats bytes as lowercase hex.
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut out, b| {
+        let _ = write!(out, "{:02x}", b);
+        out
+    })
+}
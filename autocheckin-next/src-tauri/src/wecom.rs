@@ -0,0 +1,375 @@
+use crate::config::{WeComCallbackConfig, WeComConfig};
+use crate::httpd;
+use crate::i18n::{t, Msg};
+use crate::remote;
+use aes::Aes256;
+use base64::alphabet;
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig, STANDARD};
+use base64::Engine;
+use cbc::cipher::block_padding::NoPadding;
+use cbc::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use chrono::Local;
+use log::{debug, info, warn};
+use reqwest::Url;
+use sha1::{Digest, Sha1};
+use std::fmt::Write as _;
+use std::net::{TcpListener, TcpStream};
+use tauri::AppHandle;
+
+/// Path WeCom posts messages to.
+const CALLBACK_PATH: &str = "/wecom/callback";
+
+/// Largest request body accepted.
+const MAX_BODY: usize = 64 * 1024;
+
+/// Most callbacks handled at once.
+const MAX_CONNECTIONS: usize = 8;
+
+/// Callbacks whose timestamp is further than this from the local clock are rejected, so a
+/// captured request cannot be replayed later.
+const STALE_SECS: i64 = 300;
+
+/// Padding block size of WeCom's message encryption.
+const PAD_BLOCK: usize = 32;
+
+/// Longest text message WeCom delivers, in characters (2048 bytes of mostly CJK text).
+const MAX_REPLY_CHARS: usize = 680;
+
+/// Decodes the EncodingAESKey, whose last character WeCom does not keep canonical.
+const KEY_ENGINE: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_allow_trailing_bits(true),
+);
+
+type Decryptor = cbc::Decryptor<Aes256>;
+type Encryptor = cbc::Encryptor<Aes256>;
+
+/// Verifies, decrypts and encrypts callback messages.
+struct Crypto {
+    token: String,
+    key: [u8; 32],
+    corpid: String,
+}
+
+/// Starts the receiver for WeCom application callbacks in a background thread.
+///
+/// Set the callback URL of the WeCom application to `http(s)://<host>/wecom/callback`
+/// with the same token and EncodingAESKey. WeCom must be able to reach the receiver, so
+/// it usually runs behind a reverse proxy or tunnel. Text messages such as `run 高数` or
+/// `状态`, and menu buttons whose key is such a text, run the matching command; the
+/// result is sent back as the reply.
+///
+/// # Arguments
+///
+/// * `app_handle` - The Tauri application handle.
+/// * `config` - The WeCom settings, for the CorpID and the callback settings.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Ok once listening, or an error message if the token or key
+///   is invalid or the address cannot be bound.
+pub fn serve(app_handle: AppHandle, config: &WeComConfig) -> Result<(), String> {
+    let callback = config.callback.clone();
+    if callback.token.trim().is_empty() {
+        return Err(t(Msg::WeComTokenEmpty).to_string());
+    }
+    let crypto = Crypto::new(&callback, &config.corpid)?;
+    let listener = TcpListener::bind((callback.bind_address.as_str(), callback.port))
+        .map_err(|e| e.to_string())?;
+    info!(
+        "Receiving WeCom callbacks on http://{}:{}{}",
+        callback.bind_address, callback.port, CALLBACK_PATH
+    );
+    httpd::spawn(listener, MAX_CONNECTIONS, move |stream| {
+        if let Err(e) = respond(&app_handle, &callback, &crypto, stream) {
+            warn!("WeCom callback failed: {}", e);
+        }
+    });
+    Ok(())
+}
+
+/// Reads one request and writes the response.
+fn respond(
+    app_handle: &AppHandle,
+    config: &WeComCallbackConfig,
+    crypto: &Crypto,
+    stream: TcpStream,
+) -> std::io::Result<()> {
+    let request = httpd::read_request(&stream, MAX_BODY)?;
+    let method = request.method.as_str();
+    let (status, reply) = match Url::parse(&format!("http://localhost{}", request.target)) {
+        Ok(url) if url.path() == CALLBACK_PATH => {
+            let param = |key: &str| {
+                url.query_pairs()
+                    .find(|(k, _)| k == key)
+                    .map(|(_, v)| v.into_owned())
+                    .unwrap_or_default()
+            };
+            let (signature, timestamp, nonce) =
+                (param("msg_signature"), param("timestamp"), param("nonce"));
+            let result = match method {
+                _ if !is_fresh(&timestamp, Local::now().timestamp()) => {
+                    Err(format!("stale timestamp {}", timestamp))
+                }
+                // URL verification when the callback is saved in the admin console.
+                "GET" => crypto.open(&signature, &timestamp, &nonce, &param("echostr")),
+                "POST" => handle_message(
+                    app_handle,
+                    config,
+                    crypto,
+                    &signature,
+                    &timestamp,
+                    &nonce,
+                    &String::from_utf8_lossy(&request.body),
+                ),
+                _ => Err("method not allowed".to_string()),
+            };
+            match result {
+                Ok(reply) => ("200 OK", reply),
+                Err(e) => {
+                    warn!("Rejected WeCom callback: {}", e);
+                    ("403 Forbidden", String::new())
+                }
+            }
+        }
+        _ => ("404 Not Found", String::new()),
+    };
+
+    httpd::write_response(&stream, status, "text/plain; charset=utf-8", &reply)
+}
+
+/// Decrypts a received message, runs its command and returns the encrypted reply, or
+/// an empty body when there is nothing to answer.
+fn handle_message(
+    app_handle: &AppHandle,
+    config: &WeComCallbackConfig,
+    crypto: &Crypto,
+    signature: &str,
+    timestamp: &str,
+    nonce: &str,
+    body: &str,
+) -> Result<String, String> {
+    let encrypted = xml_field(body, "Encrypt").ok_or("the message has no Encrypt field")?;
+    let message = crypto.open(signature, timestamp, nonce, &encrypted)?;
+    let user = xml_field(&message, "FromUserName").unwrap_or_default();
+    let text = match xml_field(&message, "MsgType").as_deref() {
+        Some("text") => xml_field(&message, "Content"),
+        Some("event") if xml_field(&message, "Event").as_deref() == Some("click") => {
+            xml_field(&message, "EventKey")
+        }
+        _ => None,
+    };
+    let Some(text) = text else {
+        debug!("Ignoring WeCom message without a command");
+        return Ok(String::new());
+    };
+    if !config.allowed_users.is_empty() && !config.allowed_users.contains(&user) {
+        warn!(
+            "Ignoring WeCom command from {}, who is not in allowed_users",
+            user
+        );
+        return Ok(String::new());
+    }
+    info!("WeCom command from {}: {}", user, text);
+    let text = text.trim();
+    // Menu keys cannot hold spaces in every client, so `run:高数` works as well.
+    let (word, argument) = text
+        .split_once(|c: char| c.is_whitespace() || c == ':')
+        .unwrap_or((text, ""));
+    let reply = match remote::parse(word, argument) {
+        Some(command) => remote::execute(app_handle, command),
+        None => remote::help(""),
+    };
+    crypto.seal_reply(&user, &remote::tail(&reply, MAX_REPLY_CHARS), nonce)
+}
+
+impl Crypto {
+    /// Decodes the 43-character EncodingAESKey.
+    fn new(config: &WeComCallbackConfig, corpid: &str) -> Result<Self, String> {
+        let key = KEY_ENGINE
+            .decode(format!("{}=", config.encoding_aes_key.trim()))
+            .ok()
+            .and_then(|key| <[u8; 32]>::try_from(key).ok())
+            .ok_or_else(|| t(Msg::WeComAesKeyInvalid).to_string())?;
+        Ok(Self {
+            token: config.token.trim().to_string(),
+            key,
+            corpid: corpid.trim().to_string(),
+        })
+    }
+
+    /// The SHA-1 signature WeCom puts over the token, timestamp, nonce and ciphertext.
+    fn signature(&self, timestamp: &str, nonce: &str, encrypted: &str) -> String {
+        let mut parts = [self.token.as_str(), timestamp, nonce, encrypted];
+        parts.sort_unstable();
+        let digest = Sha1::digest(parts.concat().as_bytes());
+        let mut hex = String::with_capacity(40);
+        for byte in digest {
+            let _ = write!(hex, "{:02x}", byte);
+        }
+        hex
+    }
+
+    /// Checks the signature and decrypts a message.
+    ///
+    /// The plaintext is 16 random bytes, the message length as a big-endian `u32`, the
+    /// message and the CorpID it was sent to.
+    fn open(
+        &self,
+        signature: &str,
+        timestamp: &str,
+        nonce: &str,
+        encrypted: &str,
+    ) -> Result<String, String> {
+        if self.signature(timestamp, nonce, encrypted) != signature {
+            return Err("signature mismatch".to_string());
+        }
+        let mut data = STANDARD.decode(encrypted).map_err(|e| e.to_string())?;
+        let plain = Decryptor::new(&self.key.into(), self.key[..16].into())
+            .decrypt_padded_mut::<NoPadding>(&mut data)
+            .map_err(|_| "ciphertext is not a whole number of blocks")?;
+        let pad = *plain.last().ok_or("empty message")? as usize;
+        let plain = plain
+            .get(..plain.len().saturating_sub(pad))
+            .filter(|_| (1..=PAD_BLOCK).contains(&pad))
+            .ok_or("invalid padding")?;
+        let length = plain
+            .get(16..20)
+            .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize)
+            .ok_or("message too short")?;
+        let message = plain.get(20..20 + length).ok_or("invalid message length")?;
+        let receiver = &plain[20 + length..];
+        if !self.corpid.is_empty() && receiver != self.corpid.as_bytes() {
+            return Err("message was sent to another CorpID".to_string());
+        }
+        String::from_utf8(message.to_vec()).map_err(|e| e.to_string())
+    }
+
+    /// Encrypts a text reply to `user` into the passive reply envelope.
+    fn seal_reply(&self, user: &str, text: &str, nonce: &str) -> Result<String, String> {
+        let timestamp = Local::now().timestamp().to_string();
+        let message = format!(
+            "<xml><ToUserName>{}</ToUserName><FromUserName>{}</FromUserName>\
+             <CreateTime>{}</CreateTime><MsgType><![CDATA[text]]></MsgType>\
+             <Content>{}</Content></xml>",
+            cdata(user),
+            cdata(&self.corpid),
+            timestamp,
+            cdata(text)
+        );
+        let mut plain = rand::random::<[u8; 16]>().to_vec();
+        plain.extend_from_slice(&(message.len() as u32).to_be_bytes());
+        plain.extend_from_slice(message.as_bytes());
+        plain.extend_from_slice(self.corpid.as_bytes());
+        let pad = PAD_BLOCK - plain.len() % PAD_BLOCK;
+        plain.extend(std::iter::repeat_n(pad as u8, pad));
+        let length = plain.len();
+        let sealed = Encryptor::new(&self.key.into(), self.key[..16].into())
+            .encrypt_padded_mut::<NoPadding>(&mut plain, length)
+            .map_err(|_| "failed to encrypt the reply")?;
+        let encrypted = STANDARD.encode(sealed);
+        Ok(format!(
+            "<xml><Encrypt>{}</Encrypt><MsgSignature>{}</MsgSignature>\
+             <TimeStamp>{}</TimeStamp><Nonce>{}</Nonce></xml>",
+            cdata(&encrypted),
+            cdata(&self.signature(&timestamp, nonce, &encrypted)),
+            timestamp,
+            cdata(nonce)
+        ))
+    }
+}
+
+/// Whether a callback's timestamp is within `STALE_SECS` of `now`.
+///
+/// # Arguments
+///
+/// * `timestamp` - The `timestamp` query parameter, in seconds since the Unix epoch.
+/// * `now` - The current time, in seconds since the Unix epoch.
+fn is_fresh(timestamp: &str, now: i64) -> bool {
+    timestamp
+        .parse::<i64>()
+        .is_ok_and(|at| (now - at).abs() <= STALE_SECS)
+}
+
+/// Reads the text of the first `<tag>` element, with or without a CDATA section.
+fn xml_field(xml: &str, tag: &str) -> Option<String> {
+    let open = format!("<{}>", tag);
+    let start = xml.find(&open)? + open.len();
+    let end = start + xml[start..].find(&format!("</{}>", tag))?;
+    let value = &xml[start..end];
+    Some(
+        value
+            .strip_prefix("<![CDATA[")
+            .and_then(|v| v.strip_suffix("]]>"))
+            .unwrap_or(value)
+            .to_string(),
+    )
+}
+
+/// Wraps text in a CDATA section, splitting any `]]>` inside it.
+fn cdata(text: &str) -> String {
+    format!("<![CDATA[{}]]>", text.replace("]]>", "]]]]><![CDATA[>"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Token, EncodingAESKey and CorpID of the sample in WeCom's callback documentation.
+    fn sample_crypto() -> Crypto {
+        let config = WeComCallbackConfig {
+            token: "QDG6eK".to_string(),
+            encoding_aes_key: "jWmYm7qr5nMoAUwZRjGtBxmz3KA1tkAj3ykkR6q2B2C".to_string(),
+            ..WeComCallbackConfig::default()
+        };
+        Crypto::new(&config, "wx5823bf96d3bd56c7").unwrap()
+    }
+
+    #[test]
+    fn published_sample_url_verification_decrypts() {
+        let crypto = sample_crypto();
+        let echo = "P9nAzCzyDtyTWESHep1vC5X9xho/qYX3Zpb4yKa9SKld1DsH3Iyt3tP3zNdtp+4RPcs8TgAE7OaBO+FZXvnaqQ==";
+        let plain = crypto.open(
+            "5c45ff5e21c57e6ad56bac8758b79b1d9ac89fd3",
+            "1409659589",
+            "263014780",
+            echo,
+        );
+        assert_eq!(plain.unwrap(), "1616140317555161061");
+        assert!(crypto
+            .open("0".repeat(40).as_str(), "1409659589", "263014780", echo)
+            .is_err());
+    }
+
+    #[test]
+    fn sealed_reply_opens_to_the_same_text() {
+        let crypto = sample_crypto();
+        let sealed = crypto
+            .seal_reply("zhangsan", "已签到 ]]> 高数", "1372623149")
+            .unwrap();
+        let field = |tag| xml_field(&sealed, tag).unwrap();
+        let message = crypto
+            .open(
+                &field("MsgSignature"),
+                &field("TimeStamp"),
+                &field("Nonce"),
+                &field("Encrypt"),
+            )
+            .unwrap();
+        assert_eq!(xml_field(&message, "ToUserName").unwrap(), "zhangsan");
+        assert_eq!(
+            xml_field(&message, "Content").unwrap(),
+            "已签到 ]]]]><![CDATA[> 高数"
+        );
+    }
+
+    #[test]
+    fn stale_timestamps_are_rejected() {
+        let now = 1_760_500_000;
+        assert!(is_fresh(&now.to_string(), now));
+        assert!(is_fresh(&(now - STALE_SECS).to_string(), now));
+        assert!(!is_fresh(&(now - STALE_SECS - 1).to_string(), now));
+        assert!(!is_fresh(&(now + STALE_SECS + 1).to_string(), now));
+        assert!(!is_fresh("", now));
+    }
+}
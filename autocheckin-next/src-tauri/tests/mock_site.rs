@@ -0,0 +1,219 @@
+#![cfg(feature = "mock-site")]
+
+use autocheckin_next_lib::mock::{
+    AppConfig, AuthHandler, Clock, CoordSystem, LoginState, MockSite, RunOutcome, Task,
+    TaskExecutor, TimeoutConfig, MOCK_COOKIE,
+};
+use chrono::{Local, NaiveDateTime};
+use serde_json::json;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const CLASS_ID: &str = "123456";
+
+/// A clock that records the pauses of a run instead of waiting them out.
+#[derive(Default)]
+struct PauseRecorder(Mutex<Vec<Duration>>);
+
+impl PauseRecorder {
+    fn pauses(&self) -> Vec<Duration> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+impl Clock for PauseRecorder {
+    fn now(&self) -> NaiveDateTime {
+        Local::now().naive_local()
+    }
+
+    fn sleep(&self, duration: Duration) -> impl Future<Output = ()> + Send {
+        self.pause(duration);
+        std::future::ready(())
+    }
+
+    fn pause(&self, duration: Duration) {
+        self.0.lock().unwrap().push(duration);
+    }
+}
+
+/// An executor pointed at the mock site, keeping artifacts in a temporary directory and
+/// recording its pauses on `clock`.
+fn executor_with_clock(site: &MockSite, clock: Arc<PauseRecorder>) -> TaskExecutor {
+    let mut config = AppConfig::default();
+    config.global.endpoints.base_url = site.base_url().to_string();
+    config.global.endpoints.allow_http_fallback = false;
+    let artifacts: PathBuf = std::env::temp_dir().join("autocheckin-mock-site");
+    TaskExecutor::new(&config.global, artifacts).with_clock(clock)
+}
+
+/// An executor pointed at the mock site that does not wait between signs.
+fn executor(site: &MockSite) -> TaskExecutor {
+    executor_with_clock(site, Arc::default())
+}
+
+/// An enabled task for the mock class with the given cookie.
+fn task(cookie: &str) -> Task {
+    serde_json::from_value(json!({
+        "id": "mock-task",
+        "name": "Mock course",
+        "time": "08:00",
+        "class_id": CLASS_ID,
+        "cookie": cookie,
+        "location": { "lat": "39.908823", "lng": "116.397470", "acc": "20" },
+        "enable": true,
+    }))
+    .unwrap()
+}
+
+#[test]
+fn login_completes_after_the_qr_code_is_scanned() {
+    let site = MockSite::start().unwrap();
+    site.enroll(CLASS_ID);
+    let auth = AuthHandler::with_urls(&TimeoutConfig::default(), &site.qr_url(), site.base_url());
+
+    let (image, check_url) = auth.get_qr_code().unwrap();
+    assert!(!image.is_empty());
+    assert_eq!(auth.check_login(&check_url).unwrap(), None);
+
+    site.scan_qr();
+    let session = auth.check_login(&check_url).unwrap().unwrap();
+    assert_eq!(session.cookie, MOCK_COOKIE);
+    assert_eq!(session.class_ids, vec![CLASS_ID.to_string()]);
+}
+
+#[test]
+fn login_polls_reuse_the_handler_that_fetched_the_qr_code() {
+    let site = MockSite::start().unwrap();
+    site.enroll(CLASS_ID);
+    let handler =
+        || AuthHandler::with_urls(&TimeoutConfig::default(), &site.qr_url(), site.base_url());
+    let login = LoginState::default();
+
+    // Like the commands: the QR code and each poll arrive as separate calls.
+    let (_, check_url) = login.start(handler()).unwrap();
+    site.scan_qr();
+
+    // A fresh handler has no login-server session, so the scan never shows up for it.
+    assert_eq!(handler().check_login(&check_url).unwrap(), None);
+
+    let session = login.check(&check_url).unwrap().unwrap();
+    assert_eq!(session.cookie, MOCK_COOKIE);
+    assert!(login.check(&check_url).is_err());
+}
+
+#[test]
+fn open_session_is_signed_near_the_task_location() {
+    let site = MockSite::start().unwrap();
+    site.open_session(CLASS_ID, "9876");
+
+    let record = executor(&site).execute(&task(MOCK_COOKIE)).unwrap();
+
+    assert_eq!(record.outcome, RunOutcome::Success, "{}", record.message);
+    let signs = site.signs();
+    assert_eq!(signs.len(), 1);
+    assert!(signs[0].accepted);
+    assert_eq!(signs[0].sign_id, "9876");
+    let lat: f64 = signs[0].form["lat"].parse().unwrap();
+    let lng: f64 = signs[0].form["lng"].parse().unwrap();
+    assert!((lat - 39.908823).abs() < 0.001);
+    assert!((lng - 116.397470).abs() < 0.001);
+}
+
+#[test]
+fn sign_is_preceded_by_a_jitter_pause() {
+    let site = MockSite::start().unwrap();
+    site.open_session(CLASS_ID, "9878");
+    let clock = Arc::new(PauseRecorder::default());
+
+    let record = executor_with_clock(&site, clock.clone())
+        .execute(&task(MOCK_COOKIE))
+        .unwrap();
+
+    assert_eq!(record.outcome, RunOutcome::Success, "{}", record.message);
+    assert!(clock
+        .pauses()
+        .iter()
+        .any(|p| (Duration::from_secs(1)..=Duration::from_secs(5)).contains(p)));
+}
+
+#[test]
+fn wgs84_location_is_signed_in_gcj02() {
+    let site = MockSite::start().unwrap();
+    site.open_session(CLASS_ID, "9877");
+    let mut task = task(MOCK_COOKIE);
+    task.location.coord_system = CoordSystem::Wgs84;
+    task.drift_radius_m = Some(0.0);
+
+    let record = executor(&site).execute(&task).unwrap();
+
+    assert_eq!(record.outcome, RunOutcome::Success, "{}", record.message);
+    let form = &site.signs()[0].form;
+    let lat: f64 = form["lat"].parse().unwrap();
+    let lng: f64 = form["lng"].parse().unwrap();
+    let (gcj_lat, gcj_lng) = CoordSystem::Wgs84.convert(CoordSystem::Gcj02, 39.908823, 116.397470);
+    assert!((lat - gcj_lat).abs() < 1e-5, "{} vs {}", lat, gcj_lat);
+    assert!((lng - gcj_lng).abs() < 1e-5, "{} vs {}", lng, gcj_lng);
+    // Beijing is offset by hundreds of meters between the two systems.
+    assert!((lng - 116.397470).abs() > 0.003);
+}
+
+#[test]
+fn listing_without_open_sessions_is_no_session() {
+    let site = MockSite::start().unwrap();
+
+    let record = executor(&site).execute(&task(MOCK_COOKIE)).unwrap();
+
+    assert_eq!(record.outcome, RunOutcome::NoSession, "{}", record.message);
+    assert!(site.signs().is_empty());
+}
+
+#[test]
+fn invalid_cookie_is_reported_as_expired() {
+    let site = MockSite::start().unwrap();
+    site.open_session(CLASS_ID, "9876");
+
+    let record = executor(&site)
+        .execute(&task("remember_student_0=stale"))
+        .unwrap();
+
+    assert_eq!(record.outcome, RunOutcome::CookieExpired);
+    assert!(site.signs().is_empty());
+}
+
+#[test]
+fn session_check_reports_validity_and_student_name() {
+    let site = MockSite::start().unwrap();
+
+    let status = executor(&site)
+        .validate_session(&task(MOCK_COOKIE))
+        .unwrap();
+    assert!(status.valid);
+    assert_eq!(status.student_name.as_deref(), Some("张三"));
+
+    // A fresh executor, since sessions keep the cookies of earlier requests.
+    let status = executor(&site)
+        .validate_session(&task("remember_student_0=stale"))
+        .unwrap();
+    assert!(!status.valid);
+}
+
+#[test]
+fn password_protected_session_is_signed_with_the_task_password() {
+    let site = MockSite::start().unwrap();
+    site.open_password_session(CLASS_ID, "9876", "4321");
+
+    let record = executor(&site).execute(&task(MOCK_COOKIE)).unwrap();
+    assert_eq!(record.outcome, RunOutcome::Failure, "{}", record.message);
+    assert!(site.signs().is_empty());
+
+    let mut task = task(MOCK_COOKIE);
+    task.password = Some("4321".to_string());
+    let record = executor(&site).execute(&task).unwrap();
+    assert_eq!(record.outcome, RunOutcome::Success, "{}", record.message);
+    let signs = site.signs();
+    assert_eq!(signs.len(), 1);
+    assert!(signs[0].accepted);
+    assert_eq!(signs[0].form["pwd"], "4321");
+}
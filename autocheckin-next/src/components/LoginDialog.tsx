@@ -56,10 +56,10 @@ const LoginDialog: React.FC<LoginDialogProps> = ({ open, onClose, onSuccess }) =
     if (checkUrl) {
       interval = setInterval(async () => {
         try {
-          const result: [string, string] | null = await invoke("check_login_status", { url: checkUrl });
+          const result: { cookie: string; class_ids: string[] } | null = await invoke("check_login_status", { url: checkUrl });
           if (result) {
             clearInterval(interval);
-            onSuccess(result[0], result[1]);
+            onSuccess(result.cookie, result.class_ids[0] ?? "");
             onClose();
           }
         } catch (e) {
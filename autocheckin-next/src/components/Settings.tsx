@@ -58,10 +58,13 @@ const Settings: React.FC = () => {
 
   /**
    * Saves the current configuration to the backend.
+   * Only the global settings are taken from this page; tasks and accounts are reloaded
+   * first so that changes made elsewhere since the page opened are kept.
    * Displays an alert upon successful save.
    */
   const handleSave = async () => {
-      await invoke("update_config", { newConfig: config });
+      const current: any = await invoke("get_config");
+      await invoke("update_config", { newConfig: { ...current, global: config.global } });
       alert(t("Saved"));
   };
 
@@ -1,7 +1,7 @@
 import React, { useState, useEffect } from "react";
 import {
   Dialog, DialogTitle, DialogContent, DialogActions, Button, TextField,
-  Grid2, Box, IconButton, InputAdornment
+  Grid2, Box, IconButton, InputAdornment, MenuItem, ToggleButton, ToggleButtonGroup
 } from "@mui/material";
 import { useTranslation } from "react-i18next";
 import { invoke } from "@tauri-apps/api/core";
@@ -10,27 +10,46 @@ import QrCodeScannerIcon from '@mui/icons-material/QrCodeScanner';
 import MapIcon from '@mui/icons-material/Map';
 import LocationDialog from "./LocationDialog";
 
+/**
+ * Weekday values as stored by the backend, from Monday.
+ */
+const WEEKDAYS = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/**
+ * Geodetic datum of a location; the backend converts it to the one the site expects.
+ */
+type CoordSystem = "wgs84" | "gcj02" | "bd09";
+
 /**
  * Interface representing a task object.
  * @interface Task
  * @property {string} [id] - The unique identifier of the task (optional).
  * @property {string} name - The name of the task.
  * @property {string} time - The time the task is scheduled for (HH:mm format).
+ * @property {string | null} [schedule] - Cron expression run instead of the daily time.
+ * @property {string[]} [days] - Weekdays the time applies to (e.g. "Tue"); empty for every day.
  * @property {string} class_id - The ID of the class associated with the task.
- * @property {string} cookie - The authentication cookie for the task.
+ * @property {string} cookie - A new authentication cookie; the backend never sends the saved one.
+ * @property {boolean} [has_cookie] - Whether the backend has a cookie for the task.
+ * @property {string | null} [password] - Password for password-protected check-ins.
  * @property {object} location - The location details for the task.
  * @property {string} location.lat - Latitude.
  * @property {string} location.lng - Longitude.
  * @property {string} location.acc - Accuracy.
+ * @property {CoordSystem} [location.coord_system] - The datum the coordinates are given in.
  * @property {boolean} enable - Whether the task is enabled.
  */
 interface Task {
   id?: string;
   name: string;
   time: string;
+  schedule?: string | null;
+  days?: string[];
   class_id: string;
   cookie: string;
-  location: { lat: string; lng: string; acc: string };
+  has_cookie?: boolean;
+  password?: string | null;
+  location: { lat: string; lng: string; acc: string; coord_system?: CoordSystem };
   enable: boolean;
 }
 
@@ -63,7 +82,7 @@ const TaskDialog: React.FC<TaskDialogProps> = ({ open, onClose, onSave, task })
     time: "08:00",
     class_id: "",
     cookie: "",
-    location: { lat: "", lng: "", acc: "10.0" },
+    location: { lat: "", lng: "", acc: "10.0", coord_system: "gcj02" },
     enable: true
   });
   const [loginOpen, setLoginOpen] = useState(false);
@@ -83,7 +102,7 @@ const TaskDialog: React.FC<TaskDialogProps> = ({ open, onClose, onSave, task })
         time: "08:00",
         class_id: "",
         cookie: "",
-        location: { lat: "", lng: "", acc: "10.0" },
+        location: { lat: "", lng: "", acc: "10.0", coord_system: "gcj02" },
         enable: true
       });
     }
@@ -146,6 +165,27 @@ const TaskDialog: React.FC<TaskDialogProps> = ({ open, onClose, onSave, task })
             value={formData.time}
             onChange={(e) => handleChange("time", e.target.value)}
             InputLabelProps={{ shrink: true }}
+            disabled={!!formData.schedule}
+          />
+          <ToggleButtonGroup
+            size="small"
+            fullWidth
+            value={formData.days ?? []}
+            onChange={(_, days: string[]) => handleChange("days", days)}
+            disabled={!!formData.schedule}
+          >
+            {WEEKDAYS.map((day) => (
+              <ToggleButton key={day} value={day}>{t(day)}</ToggleButton>
+            ))}
+          </ToggleButtonGroup>
+          <TextField
+            margin="normal"
+            fullWidth
+            label={t("Cron Schedule")}
+            placeholder="0 30 8 * * MON-FRI"
+            helperText={t("Overrides the time when set")}
+            value={formData.schedule ?? ""}
+            onChange={(e) => handleChange("schedule", e.target.value || null)}
           />
           <TextField
             margin="normal"
@@ -158,6 +198,7 @@ const TaskDialog: React.FC<TaskDialogProps> = ({ open, onClose, onSave, task })
             margin="normal"
             fullWidth
             label="Cookie"
+            helperText={formData.has_cookie ? t("Cookie saved; leave empty to keep it") : undefined}
             value={formData.cookie}
             onChange={(e) => handleChange("cookie", e.target.value)}
             InputProps={{
@@ -170,6 +211,13 @@ const TaskDialog: React.FC<TaskDialogProps> = ({ open, onClose, onSave, task })
               ),
             }}
           />
+          <TextField
+            margin="normal"
+            fullWidth
+            label={t("Check-in Password")}
+            value={formData.password ?? ""}
+            onChange={(e) => handleChange("password", e.target.value || null)}
+          />
           <Grid2 container spacing={2}>
             <Grid2 size={5}>
               <TextField
@@ -195,6 +243,18 @@ const TaskDialog: React.FC<TaskDialogProps> = ({ open, onClose, onSave, task })
                  </IconButton>
             </Grid2>
           </Grid2>
+          <TextField
+            select
+            margin="normal"
+            fullWidth
+            label={t("Coordinate System")}
+            value={formData.location.coord_system ?? "gcj02"}
+            onChange={(e) => handleLocationChange("coord_system", e.target.value)}
+          >
+            <MenuItem value="wgs84">{t("WGS-84 (GPS, Google Maps)")}</MenuItem>
+            <MenuItem value="gcj02">{t("GCJ-02 (Amap, Tencent Maps, WeChat)")}</MenuItem>
+            <MenuItem value="bd09">{t("BD-09 (Baidu Maps)")}</MenuItem>
+          </TextField>
         </Box>
       </DialogContent>
       <DialogActions>
@@ -216,7 +276,8 @@ const TaskDialog: React.FC<TaskDialogProps> = ({ open, onClose, onSave, task })
         onSelect={(lat, lng) => {
             setFormData({
                 ...formData,
-                location: { ...formData.location, lat, lng }
+                // The map shows OpenStreetMap tiles, which use WGS-84.
+                location: { ...formData.location, lat, lng, coord_system: "wgs84" }
             });
         }}
         initialLat={formData.location.lat}
@@ -8,6 +8,7 @@ import EditIcon from '@mui/icons-material/Edit';
 import DeleteIcon from '@mui/icons-material/Delete';
 import { useTranslation } from "react-i18next";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import TaskDialog from "./TaskDialog";
 
 /**
@@ -16,8 +17,11 @@ import TaskDialog from "./TaskDialog";
  * @property {string} id - The unique identifier of the task.
  * @property {string} name - The name of the task.
  * @property {string} time - The scheduled time for the task.
+ * @property {string | null} [schedule] - Cron expression run instead of the daily time.
+ * @property {string[]} [days] - Weekdays the time applies to; empty for every day.
  * @property {string} class_id - The class ID associated with the task.
- * @property {string} cookie - The authentication cookie for the task.
+ * @property {string} cookie - Always empty; the backend keeps cookies to itself.
+ * @property {boolean} [has_cookie] - Whether the backend has a cookie for the task.
  * @property {object} location - The location details.
  * @property {string} location.lat - Latitude.
  * @property {string} location.lng - Longitude.
@@ -28,8 +32,11 @@ interface Task {
   id: string;
   name: string;
   time: string;
+  schedule?: string | null;
+  days?: string[];
   class_id: string;
   cookie: string;
+  has_cookie?: boolean;
   location: { lat: string; lng: string; acc: string };
   enable: boolean;
 }
@@ -44,13 +51,19 @@ const Tasks: React.FC = () => {
   const { t } = useTranslation();
   const [tasks, setTasks] = useState<Task[]>([]);
   const [openDialog, setOpenDialog] = useState(false);
-  const [currentTask, setCurrentTask] = useState<Task | null>(null);
+  const [currentTask, setCurrentTask] = useState<Task | Omit<Task, "id"> | null>(null);
 
   /**
-   * Effect to load tasks from the backend when the component mounts.
+   * Effect to load tasks from the backend when the component mounts, and to open
+   * tasks shared through autocheckin://add-task links.
    */
   useEffect(() => {
     loadTasks();
+    openTaskDrafts();
+    const unlisten = listen("task-drafts", openTaskDrafts);
+    return () => {
+      unlisten.then((f) => f());
+    };
   }, []);
 
   /**
@@ -66,6 +79,21 @@ const Tasks: React.FC = () => {
     }
   };
 
+  /**
+   * Opens the task dialog pre-filled with the latest task received from a link.
+   */
+  const openTaskDrafts = async () => {
+    try {
+      const drafts: Omit<Task, "id">[] = await invoke("take_task_drafts");
+      if (drafts.length > 0) {
+        setCurrentTask(drafts[drafts.length - 1]);
+        setOpenDialog(true);
+      }
+    } catch (e) {
+      console.error(e);
+    }
+  };
+
   /**
    * Opens the task dialog for creating a new task.
    */
@@ -85,15 +113,15 @@ const Tasks: React.FC = () => {
   };
 
   /**
-   * Deletes a task after user confirmation.
+   * Deletes a task after the user confirms in the native dialog shown by the backend.
    *
    * @param {string} id - The ID of the task to delete.
    */
   const handleDelete = async (id: string) => {
-    if (confirm(t("Are you sure?"))) {
-        await invoke("delete_task", { taskId: id });
-        loadTasks();
-    }
+    const confirmToken = await invoke<string | null>("request_confirmation", { action: "delete_task" });
+    if (!confirmToken) return;
+    await invoke("delete_task", { taskId: id, confirmToken });
+    loadTasks();
   };
 
   /**
@@ -124,7 +152,9 @@ const Tasks: React.FC = () => {
             {tasks.map((task) => (
               <TableRow key={task.id}>
                 <TableCell>{task.name}</TableCell>
-                <TableCell>{task.time}</TableCell>
+                <TableCell>
+                  {task.schedule || [task.time, ...(task.days ?? []).map((day) => t(day))].join(" ")}
+                </TableCell>
                 <TableCell>{task.class_id}</TableCell>
                 <TableCell>
                   <Switch
@@ -18,6 +18,17 @@ const resources = {
       "Scan the QR code to login": "Scan the QR code to login",
       "Logged in as": "Logged in as",
       "Class ID": "Class ID",
+      "Check-in Password": "Check-in Password",
+      "Cookie saved; leave empty to keep it": "Cookie saved; leave empty to keep it",
+      "Cron Schedule": "Cron Schedule",
+      "Overrides the time when set": "Overrides the time when set",
+      "Mon": "Mon",
+      "Tue": "Tue",
+      "Wed": "Wed",
+      "Thu": "Thu",
+      "Fri": "Fri",
+      "Sat": "Sat",
+      "Sun": "Sun",
       "Name": "Name",
       "Time": "Time",
       "Status": "Status",
@@ -28,6 +39,10 @@ const resources = {
       "Cancel": "Cancel",
       "Latitude": "Latitude",
       "Longitude": "Longitude",
+      "Coordinate System": "Coordinate System",
+      "WGS-84 (GPS, Google Maps)": "WGS-84 (GPS, Google Maps)",
+      "GCJ-02 (Amap, Tencent Maps, WeChat)": "GCJ-02 (Amap, Tencent Maps, WeChat)",
+      "BD-09 (Baidu Maps)": "BD-09 (Baidu Maps)",
       "WeCom Settings": "WeCom Settings",
       "Enable": "Enable",
       "CorpID": "CorpID",
@@ -48,6 +63,17 @@ const resources = {
       "Scan the QR code to login": "请扫描二维码登录",
       "Logged in as": "已登录用户",
       "Class ID": "班级ID",
+      "Check-in Password": "签到密码",
+      "Cookie saved; leave empty to keep it": "Cookie 已保存，留空则保持不变",
+      "Cron Schedule": "Cron 定时表达式",
+      "Overrides the time when set": "填写后将代替执行时间",
+      "Mon": "周一",
+      "Tue": "周二",
+      "Wed": "周三",
+      "Thu": "周四",
+      "Fri": "周五",
+      "Sat": "周六",
+      "Sun": "周日",
       "Name": "任务名称",
       "Time": "执行时间",
       "Status": "状态",
@@ -58,6 +84,10 @@ const resources = {
       "Cancel": "取消",
       "Latitude": "纬度",
       "Longitude": "经度",
+      "Coordinate System": "坐标系",
+      "WGS-84 (GPS, Google Maps)": "WGS-84（GPS、谷歌地图）",
+      "GCJ-02 (Amap, Tencent Maps, WeChat)": "GCJ-02（高德、腾讯地图、微信）",
+      "BD-09 (Baidu Maps)": "BD-09（百度地图）",
       "WeCom Settings": "企业微信设置",
       "Enable": "启用",
       "CorpID": "企业ID",